@@ -1,10 +1,11 @@
+use crate::commands::packfile;
 use crate::commands::protocol_utils;
 use crate::commands::structs::Head;
-use crate::commands::git_commands::{Command, PackObjects, PathHandler};
+use crate::commands::git_commands::{PackObjects, PathHandler};
 use crate::constants::{IP_LOCALHOST, NAK_RESPONSE, REQUEST_DELIMITER_DONE, REQUEST_LENGTH_CERO, WANT_REQUEST, ZERO_HASH};
 
 use std::{
-    error::Error, fs, io::Read, io::Write, net::Shutdown, net::TcpStream, str, thread,
+    error::Error, io::Read, io::Write, net::Shutdown, net::TcpStream, str, thread,
     time::Duration,
 };
 use crate::commands::helpers::get_client_current_working_repo;
@@ -27,7 +28,9 @@ impl ClientProtocol {
         Ok(TcpStream::connect(address)?)
     }
 
-    pub fn receive_pack(&mut self, remote_url: String, path_handler: &PathHandler) -> Result<(), Box<dyn Error>> {
+    /// Pushes the current branch and returns the refs the remote reports as updated. Fails if the remote could
+    /// not unpack the objects or rejected the ref.
+    pub fn receive_pack(&mut self, remote_url: String, path_handler: &PathHandler) -> Result<Vec<String>, Box<dyn Error>> {
         let mut stream = ClientProtocol::connect(&remote_url)?;
         // println!("connect complete");
         let current_repo = get_client_current_working_repo()?;
@@ -87,23 +90,47 @@ impl ClientProtocol {
         let _ = stream.write_all(REQUEST_LENGTH_CERO.as_bytes());
         // println!("sent 0000");
 
-        let pack_checksum = PackObjects::new().execute(Some(vec![&last_commit_hash]), path_handler)?;
-        let pack_file_path = format!(".git/pack/pack-{}.pack", pack_checksum);
-        // println!("{}", pack_file_path);
-        let mut pack_file = fs::File::open(pack_file_path)?;
-        let mut buffer = Vec::new();
-        pack_file.read_to_end(&mut buffer)?;
-        // println!("buffer: {:?}", buffer);
+        let pack = PackObjects::new().create_pack(vec![&last_commit_hash], path_handler)?;
 
         thread::sleep(Duration::from_millis(500));
-        stream.write_all(&buffer)?;
+        stream.write_all(&pack)?;
 
         // println!("sending pack file");
         stream.flush()?;
 
         stream.shutdown(Shutdown::Write)?;
 
-        Ok(())
+        let report: Vec<String> = protocol_utils::read_until(&mut reader, REQUEST_LENGTH_CERO, true)?;
+        protocol_utils::parse_report_status(&report)
+    }
+
+    /// Asks a remote for the refs it has, as (hash, ref name) pairs, without fetching any object
+    pub fn list_remote_refs(&mut self, remote_url: String) -> Result<Vec<(String, String)>, Box<dyn Error>> {
+        let mut stream = ClientProtocol::connect(&remote_url)?;
+
+        let current_repo = get_client_current_working_repo()?;
+        let request = protocol_utils::format_line_to_send(
+            format!("git-upload-pack /{}/.git\0host={}\0", current_repo, IP_LOCALHOST).to_string(),
+        );
+        stream.write_all(request.as_bytes())?;
+        stream.flush()?;
+
+        let mut reader = std::io::BufReader::new(stream.try_clone()?);
+        let response_received: Vec<String> =
+            protocol_utils::read_until(&mut reader, REQUEST_DELIMITER_DONE, true)?;
+        let mut refs_in_remote: Vec<(String, String)> = Vec::new();
+        for line in response_received {
+            if let [remote_hash, ref_name, ..] = line.split_whitespace().collect::<Vec<&str>>().as_slice() {
+                let ref_name = ref_name.split('\0').next().unwrap_or_default();
+                refs_in_remote.push((remote_hash.to_string(), ref_name.to_string()));
+            }
+        }
+
+        // nothing is wanted, so the conversation ends with a flush
+        let _ = stream.write_all(REQUEST_LENGTH_CERO.as_bytes());
+        stream.shutdown(Shutdown::Both)?;
+
+        Ok(refs_in_remote)
     }
 
     pub fn fetch_from_remote_with_our_server(
@@ -172,11 +199,7 @@ impl ClientProtocol {
         let mut buffer = Vec::new();
         stream.read_to_end(&mut buffer)?;
         // println!("buffer received: {:?}", buffer);
-        // ACA PODRIA HACER EL CHECKSUM: asi ya verifica apenas me llega que esta bien y sino lanzo error
-        let mut file = fs::File::create(path_handler.get_relative_path(
-            ".git/pack/received_pack_file.pack",
-        ))?;
-        file.write_all(&buffer)?;
+        packfile::store_received_pack(&buffer, path_handler)?;
 
         // println!("pack file received");
 
@@ -27,9 +27,17 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             }
             "add" => Add::new().execute(parse_arguments(&args[2..]), &path_handler)?,
             "rm" => Rm::new().execute(parse_arguments(&args[2..]), &path_handler)?,
+            "mv" => Mv::new().execute(parse_arguments(&args[2..]), &path_handler)?,
             "commit" => Commit::new().execute(parse_arguments(&args[2..]), &path_handler)?,
             "status" => Status::new().execute(None, &path_handler)?,
             "log" => Log::new().execute(parse_arguments(&args[2..]), &path_handler)?,
+            "diff" => Diff::new().execute(parse_arguments(&args[2..]), &path_handler)?,
+            "blame" => Blame::new().execute(parse_arguments(&args[2..]), &path_handler)?,
+            "grep" => Grep::new().execute(parse_arguments(&args[2..]), &path_handler)?,
+            "bisect" => Bisect::new().execute(parse_arguments(&args[2..]), &path_handler)?,
+            "reflog" => Reflog::new().execute(parse_arguments(&args[2..]), &path_handler)?,
+            "config" => ConfigCommand::new().execute(parse_arguments(&args[2..]), &path_handler)?,
+            "pack-refs" => PackRefs::new().execute(parse_arguments(&args[2..]), &path_handler)?,
             "remote" => Remote::new().execute(parse_arguments(&args[2..]), &path_handler)?,
             "pack-objects" => {
                 PackObjects::new().execute(parse_arguments(&args[2..]), &path_handler)?
@@ -46,10 +54,21 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 CheckIgnore::new().execute(parse_arguments(&args[2..]), &path_handler)?
             }
             "show-ref" => ShowRef::new().execute(parse_arguments(&args[2..]), &path_handler)?,
+            "for-each-ref" => {
+                ForEachRef::new().execute(parse_arguments(&args[2..]), &path_handler)?
+            }
+            "describe" => Describe::new().execute(parse_arguments(&args[2..]), &path_handler)?,
+            "verify-commit" => {
+                VerifyCommit::new().execute(parse_arguments(&args[2..]), &path_handler)?
+            }
+            "verify-tag" => VerifyTag::new().execute(parse_arguments(&args[2..]), &path_handler)?,
             "unpack-objects" => {
                 UnpackObjects::new().execute(parse_arguments(&args[2..]), &path_handler)?
             }
             "rebase" => Rebase::new().execute(parse_arguments(&args[2..]), &path_handler)?,
+            "repack" => Repack::new().execute(parse_arguments(&args[2..]), &path_handler)?,
+            "prune" => Prune::new().execute(parse_arguments(&args[2..]), &path_handler)?,
+            "gc" => Gc::new().execute(parse_arguments(&args[2..]), &path_handler)?,
 
             _ => {
                 return Err(Box::new(io::Error::new(
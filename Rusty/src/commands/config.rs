@@ -0,0 +1,792 @@
+use std::{env, error::Error, fs, io, io::Write, path::Path};
+
+use super::git_commands::PathHandler;
+use super::helpers;
+use crate::constants::{CONFIG_FILE, LOCK_FILE_EXTENSION, MAX_CONFIG_INCLUDE_DEPTH};
+
+/// The config files that are read, from lowest to highest priority
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConfigScope {
+    System,
+    Global,
+    Local,
+}
+
+impl ConfigScope {
+    /// Returns the path of the config file of the scope. The system file is `$GIT_CONFIG_SYSTEM` or `/etc/gitconfig`
+    /// unless `$GIT_CONFIG_NOSYSTEM` is set, and the global one `$GIT_CONFIG_GLOBAL` or `~/.gitconfig`.
+    pub fn get_path(&self, path_handler: &PathHandler) -> Option<String> {
+        match self {
+            ConfigScope::System if env::var_os("GIT_CONFIG_NOSYSTEM").is_some() => None,
+            ConfigScope::System => Some(env::var("GIT_CONFIG_SYSTEM").unwrap_or_else(|_| String::from("/etc/gitconfig"))),
+            ConfigScope::Global => env::var("GIT_CONFIG_GLOBAL")
+                .ok()
+                .or_else(|| env::var("HOME").ok().map(|home| format!("{}/.gitconfig", home))),
+            ConfigScope::Local => Some(path_handler.get_relative_path(CONFIG_FILE)),
+        }
+    }
+}
+
+/// Makes the tests read only the config file of their repository, so the system and global files of whoever
+/// runs them can't change the results
+#[cfg(test)]
+pub fn ignore_user_config() {
+    env::set_var("GIT_CONFIG_NOSYSTEM", "1");
+    env::set_var("GIT_CONFIG_GLOBAL", "/dev/null");
+}
+
+/// A variable read from a config file
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigEntry {
+    /// Full name of the variable with the section and key in lowercase, e.g. `remote.origin.url`
+    pub name: String,
+    /// None when the key was written without `=`, which means true
+    pub value: Option<String>,
+}
+
+/// What a line of a config file holds. Entries remember the last line they span, since values can be
+/// continued on the next line with a trailing backslash.
+#[derive(Debug, PartialEq)]
+enum ConfigItem {
+    Section { section: String, subsection: Option<String> },
+    Entry { entry: ConfigEntry, last_line: usize },
+}
+
+/// Splits a variable name such as `remote.origin.url` into its section, subsection and key, validating them.
+/// The subsection is everything between the first and the last dot.
+fn split_name(name: &str) -> Result<(String, Option<String>, String), Box<dyn Error>> {
+    let (section, rest) = name.split_once('.').ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidInput, format!("error: key does not contain a section: {}", name))
+    })?;
+    let (subsection, key) = match rest.rsplit_once('.') {
+        Some((subsection, key)) => (Some(subsection.to_string()), key),
+        None => (None, rest),
+    };
+    if !is_valid_section(section) || !is_valid_key(key) {
+        return Err(Box::new(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("error: invalid key: {}", name),
+        )));
+    }
+    Ok((section.to_lowercase(), subsection, key.to_lowercase()))
+}
+
+/// Returns the canonical form of a variable name, with the section and the key in lowercase
+fn canonical_name(section: &str, subsection: Option<&str>, key: &str) -> String {
+    match subsection {
+        Some(subsection) => format!("{}.{}.{}", section.to_lowercase(), subsection, key.to_lowercase()),
+        None => format!("{}.{}", section.to_lowercase(), key.to_lowercase()),
+    }
+}
+
+fn is_valid_section(section: &str) -> bool {
+    !section.is_empty() && section.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.')
+}
+
+fn is_valid_key(key: &str) -> bool {
+    key.starts_with(|c: char| c.is_ascii_alphabetic()) && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
+
+fn bad_config_line(line_number: usize, origin: &str) -> Box<dyn Error> {
+    Box::new(io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("fatal: bad config line {} in file {}", line_number + 1, origin),
+    ))
+}
+
+/// Parses a section header such as `[core]`, `[remote "origin"]` or the deprecated `[branch.main]`. The header
+/// `[remote 'origin']` that older versions of this program wrote is also accepted. Returns the section, the
+/// subsection and the length of the header, as the line can go on with a comment or an entry, like `[core] bare`.
+fn parse_section_header(line: &str) -> Option<(String, Option<String>, usize)> {
+    // the header ends at the first ']' outside quotes
+    let mut in_quotes = false;
+    let mut escaped = false;
+    let end = line.char_indices().skip(1).find_map(|(index, c)| {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' => in_quotes = !in_quotes,
+            ']' if !in_quotes => return Some(index),
+            _ => {}
+        }
+        None
+    })?;
+    let header = line[1..end].trim();
+    let header_length = end + 1;
+    let (section, subsection) = match header.split_once(char::is_whitespace) {
+        Some((section, quoted)) => (section, Some(quoted.trim())),
+        None => (header, None),
+    };
+    if !is_valid_section(section) {
+        return None;
+    }
+
+    match subsection {
+        Some(quoted) if quoted.len() >= 2 && quoted.starts_with('\'') && quoted.ends_with('\'') => {
+            Some((section.to_lowercase(), Some(quoted[1..quoted.len() - 1].to_string()), header_length))
+        }
+        Some(quoted) => {
+            let inner = quoted.strip_prefix('"')?.strip_suffix('"')?;
+            let mut subsection = String::new();
+            let mut chars = inner.chars();
+            while let Some(c) = chars.next() {
+                match c {
+                    '\\' => subsection.push(chars.next()?),
+                    '"' => return None,
+                    _ => subsection.push(c),
+                }
+            }
+            Some((section.to_lowercase(), Some(subsection), header_length))
+        }
+        None => match section.split_once('.') {
+            Some((section, subsection)) => Some((section.to_lowercase(), Some(subsection.to_lowercase()), header_length)),
+            None => Some((section.to_lowercase(), None, header_length)),
+        },
+    }
+}
+
+/// Parses the value of a variable starting at `text`, in line `*line_index` of `lines`. Whitespace outside quotes
+/// is trimmed at both ends of the value and kept as it is inside it, `#` and `;` outside quotes start a comment, `\n`, `\t`, `\b`, `\"` and `\\` are escapes
+/// and a backslash at the end of the line continues the value in the next one. `line_index` ends at the last line read.
+fn parse_value(text: &str, lines: &[&str], line_index: &mut usize, origin: &str) -> Result<String, Box<dyn Error>> {
+    let mut value = String::new();
+    let mut pending_spaces = 0;
+    let mut in_quotes = false;
+    let mut chars: Vec<char> = text.chars().collect();
+    let mut position = 0;
+
+    loop {
+        let Some(&c) = chars.get(position) else {
+            if in_quotes {
+                return Err(bad_config_line(*line_index, origin));
+            }
+            break;
+        };
+        position += 1;
+        match c {
+            '\\' => {
+                let escaped = match chars.get(position) {
+                    None => {
+                        // the value continues in the next line
+                        *line_index += 1;
+                        let next_line = lines.get(*line_index).ok_or_else(|| bad_config_line(*line_index - 1, origin))?;
+                        chars = next_line.trim_end_matches('\r').chars().collect();
+                        position = 0;
+                        continue;
+                    }
+                    Some('n') => '\n',
+                    Some('t') => '\t',
+                    Some('b') => '\u{8}',
+                    Some('"') => '"',
+                    Some('\\') => '\\',
+                    Some(_) => return Err(bad_config_line(*line_index, origin)),
+                };
+                position += 1;
+                if !value.is_empty() {
+                    value.extend(std::iter::repeat_n(' ', pending_spaces));
+                }
+                pending_spaces = 0;
+                value.push(escaped);
+            }
+            '"' => in_quotes = !in_quotes,
+            '#' | ';' if !in_quotes => break,
+            c if c.is_whitespace() && !in_quotes => pending_spaces += 1,
+            c => {
+                if !value.is_empty() {
+                    value.extend(std::iter::repeat_n(' ', pending_spaces));
+                }
+                pending_spaces = 0;
+                value.push(c);
+            }
+        }
+    }
+    Ok(value)
+}
+
+/// Parses the content of a config file into its section headers and entries, along with the line each one starts at
+fn parse_config(content: &str, origin: &str) -> Result<Vec<(usize, ConfigItem)>, Box<dyn Error>> {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut items = Vec::new();
+    let mut current_section: Option<(String, Option<String>)> = None;
+    let mut line_index = 0;
+
+    while line_index < lines.len() {
+        let first_line = line_index;
+        let mut line = lines[line_index].trim_end_matches('\r').trim_start();
+        if line.is_empty() || line.starts_with(['#', ';']) {
+            line_index += 1;
+            continue;
+        }
+
+        if line.starts_with('[') {
+            let (section, subsection, header_length) =
+                parse_section_header(line).ok_or_else(|| bad_config_line(line_index, origin))?;
+            current_section = Some((section.clone(), subsection.clone()));
+            items.push((first_line, ConfigItem::Section { section, subsection }));
+            // an entry can follow the header in the same line
+            line = line[header_length..].trim_start();
+            if line.is_empty() || line.starts_with(['#', ';']) {
+                line_index += 1;
+                continue;
+            }
+        }
+
+        let (section, subsection) = current_section.as_ref().ok_or_else(|| bad_config_line(line_index, origin))?;
+        let key_length = line.find(|c: char| !c.is_ascii_alphanumeric() && c != '-').unwrap_or(line.len());
+        let key = &line[..key_length];
+        if !is_valid_key(key) {
+            return Err(bad_config_line(line_index, origin));
+        }
+        let rest = line[key_length..].trim_start();
+        let value = if rest.is_empty() || rest.starts_with(['#', ';']) {
+            None
+        } else if let Some(text) = rest.strip_prefix('=') {
+            Some(parse_value(text, &lines, &mut line_index, origin)?)
+        } else {
+            return Err(bad_config_line(line_index, origin));
+        };
+
+        let name = canonical_name(section, subsection.as_deref(), key);
+        items.push((first_line, ConfigItem::Entry { entry: ConfigEntry { name, value }, last_line: line_index }));
+        line_index += 1;
+    }
+    Ok(items)
+}
+
+/// Interprets a config value as a boolean: a key without value, `true`, `yes`, `on` or a non zero number are true,
+/// and `false`, `no`, `off`, an empty value or zero are false
+pub fn parse_bool(name: &str, value: Option<&str>) -> Result<bool, Box<dyn Error>> {
+    let Some(value) = value else {
+        return Ok(true);
+    };
+    match value.to_lowercase().as_str() {
+        "true" | "yes" | "on" => Ok(true),
+        "false" | "no" | "off" | "" => Ok(false),
+        other => match other.parse::<i64>() {
+            Ok(number) => Ok(number != 0),
+            Err(_) => Err(Box::new(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("fatal: bad boolean config value '{}' for '{}'", value, name),
+            ))),
+        },
+    }
+}
+
+/// Interprets a config value as an integer, which may end in `k`, `m` or `g` to be multiplied by 1024, 1024^2 or 1024^3
+pub fn parse_int(name: &str, value: Option<&str>) -> Result<i64, Box<dyn Error>> {
+    let bad_value = || -> Box<dyn Error> {
+        Box::new(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("fatal: bad numeric config value '{}' for '{}'", value.unwrap_or_default(), name),
+        ))
+    };
+    let value = value.ok_or_else(bad_value)?.trim();
+    let (number, factor) = match value.char_indices().last() {
+        Some((index, 'k' | 'K')) => (&value[..index], 1024),
+        Some((index, 'm' | 'M')) => (&value[..index], 1024 * 1024),
+        Some((index, 'g' | 'G')) => (&value[..index], 1024 * 1024 * 1024),
+        _ => (value, 1),
+    };
+    number.parse::<i64>().ok().and_then(|number| number.checked_mul(factor)).ok_or_else(bad_value)
+}
+
+/// Expands a leading `~/` of a path with the home directory
+fn expand_path(path: &str) -> String {
+    match (path.strip_prefix("~/"), env::var("HOME")) {
+        (Some(rest), Ok(home)) => format!("{}/{}", home, rest),
+        _ => path.to_string(),
+    }
+}
+
+/// The variables of one or more config files. Later values of a variable override earlier ones, so when the
+/// system, global and repository files are read, in that order, the repository wins.
+#[derive(Debug, Default)]
+pub struct Config {
+    entries: Vec<ConfigEntry>,
+}
+
+impl Config {
+    /// Reads the system, global and repository config files. Missing files are skipped.
+    pub fn load(path_handler: &PathHandler) -> Result<Self, Box<dyn Error>> {
+        let mut config = Config::default();
+        for scope in [ConfigScope::System, ConfigScope::Global, ConfigScope::Local] {
+            if let Some(path) = scope.get_path(path_handler) {
+                config.read_file(&path, 0)?;
+            }
+        }
+        Ok(config)
+    }
+
+    /// Reads the config file of a single scope
+    pub fn load_scope(scope: ConfigScope, path_handler: &PathHandler) -> Result<Self, Box<dyn Error>> {
+        let mut config = Config::default();
+        if let Some(path) = scope.get_path(path_handler) {
+            config.read_file(&path, 0)?;
+        }
+        Ok(config)
+    }
+
+    /// Reads a config file, following its `include.path` variables. Relative include paths are relative to the
+    /// directory of the file that includes them.
+    pub fn read_file(&mut self, path: &str, depth: usize) -> Result<(), Box<dyn Error>> {
+        if depth > MAX_CONFIG_INCLUDE_DEPTH {
+            return Err(Box::new(io::Error::new(
+                io::ErrorKind::Other,
+                format!("fatal: exceeded maximum include depth ({}) while including {}", MAX_CONFIG_INCLUDE_DEPTH, path),
+            )));
+        }
+        if !Path::new(path).is_file() {
+            return Ok(());
+        }
+        let content = helpers::read_file_content(path)?;
+        for (_line, item) in parse_config(&content, path)? {
+            let ConfigItem::Entry { entry, .. } = item else {
+                continue;
+            };
+            let include_path = match (&entry.name[..], &entry.value) {
+                ("include.path", Some(include_path)) => Some(expand_path(include_path)),
+                _ => None,
+            };
+            self.entries.push(entry);
+            if let Some(include_path) = include_path {
+                let include_path = match Path::new(path).parent() {
+                    Some(directory) if !include_path.starts_with('/') => directory.join(include_path).to_string_lossy().to_string(),
+                    _ => include_path,
+                };
+                self.read_file(&include_path, depth + 1)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns every variable read, in the order they were found
+    pub fn entries(&self) -> &[ConfigEntry] {
+        &self.entries
+    }
+
+    /// Returns the entries of a variable, in the order they were found
+    pub fn get_entries(&self, name: &str) -> Vec<&ConfigEntry> {
+        let Ok((section, subsection, key)) = split_name(name) else {
+            return Vec::new();
+        };
+        let name = canonical_name(&section, subsection.as_deref(), &key);
+        self.entries.iter().filter(|entry| entry.name == name).collect()
+    }
+
+    /// Returns the value of a variable, the last one if it has several. A key without value gives an empty string.
+    pub fn get(&self, name: &str) -> Option<String> {
+        self.get_entries(name).last().map(|entry| entry.value.clone().unwrap_or_default())
+    }
+
+    /// Returns every value of a multi-valued variable
+    pub fn get_all(&self, name: &str) -> Vec<String> {
+        self.get_entries(name)
+            .into_iter()
+            .map(|entry| entry.value.clone().unwrap_or_default())
+            .collect()
+    }
+
+    pub fn get_bool(&self, name: &str) -> Result<Option<bool>, Box<dyn Error>> {
+        self.get_entries(name)
+            .last()
+            .map(|entry| parse_bool(name, entry.value.as_deref()))
+            .transpose()
+    }
+
+    pub fn get_int(&self, name: &str) -> Result<Option<i64>, Box<dyn Error>> {
+        self.get_entries(name)
+            .last()
+            .map(|entry| parse_int(name, entry.value.as_deref()))
+            .transpose()
+    }
+
+    /// Returns the value of a variable holding a path, with a leading `~/` expanded
+    pub fn get_path(&self, name: &str) -> Option<String> {
+        self.get(name).map(|path| expand_path(&path))
+    }
+
+    /// Returns the names of the subsections of a section that have at least one variable, e.g. the remotes
+    /// for the `remote` section
+    pub fn get_subsections(&self, section: &str) -> Vec<String> {
+        let prefix = format!("{}.", section.to_lowercase());
+        let mut subsections: Vec<String> = Vec::new();
+        for entry in &self.entries {
+            let subsection = entry
+                .name
+                .strip_prefix(&prefix)
+                .and_then(|rest| rest.rsplit_once('.'))
+                .map(|(subsection, _key)| subsection.to_string());
+            if let Some(subsection) = subsection {
+                if !subsections.contains(&subsection) {
+                    subsections.push(subsection);
+                }
+            }
+        }
+        subsections
+    }
+}
+
+/// Formats a value so it's read back as it is, quoting it if it has surrounding whitespace or comment characters
+fn format_value(value: &str) -> String {
+    let escaped = value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace('\t', "\\t");
+    if value.starts_with(char::is_whitespace) || value.ends_with(char::is_whitespace) || value.contains(['#', ';']) {
+        format!("\"{}\"", escaped)
+    } else {
+        escaped
+    }
+}
+
+fn format_section_header(section: &str, subsection: Option<&str>) -> String {
+    match subsection {
+        Some(subsection) => format!("[{} \"{}\"]", section, subsection.replace('\\', "\\\\").replace('"', "\\\"")),
+        None => format!("[{}]", section),
+    }
+}
+
+/// A single config file opened to be modified. Only the lines of the variables that change are rewritten, so
+/// comments and formatting of the rest of the file are kept. Like git, the file is locked by creating
+/// `<file>.lock` before it's read, and the lock is held until it's saved or dropped, so concurrent changes
+/// aren't lost.
+pub struct ConfigFile {
+    path: String,
+    lines: Vec<String>,
+    lock_path: String,
+    lock_file: Option<fs::File>,
+}
+
+impl ConfigFile {
+    /// Opens the config file of a scope. A missing file is treated as empty and created when saved.
+    pub fn open(scope: ConfigScope, path_handler: &PathHandler) -> Result<Self, Box<dyn Error>> {
+        let path = scope.get_path(path_handler).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, "fatal: the system config file is disabled")
+        })?;
+        Self::open_path(&path)
+    }
+
+    pub fn open_path(path: &str) -> Result<Self, Box<dyn Error>> {
+        if let Some(parent) = Path::new(path).parent() {
+            if !parent.as_os_str().is_empty() {
+                fs::create_dir_all(parent)?;
+            }
+        }
+        let lock_path = format!("{}{}", path, LOCK_FILE_EXTENSION);
+        let lock_file = fs::OpenOptions::new().write(true).create_new(true).open(&lock_path).map_err(|error| {
+            io::Error::new(io::ErrorKind::Other, format!("error: could not lock config file {}: {}", lock_path, error))
+        })?;
+        // from here on the lock is released when the config file is dropped, also if reading it fails
+        let mut config_file = ConfigFile {
+            path: path.to_string(),
+            lines: Vec::new(),
+            lock_path,
+            lock_file: Some(lock_file),
+        };
+        if Path::new(path).is_file() {
+            config_file.lines = helpers::read_file_content(path)?.lines().map(String::from).collect();
+        }
+        config_file.split_header_entries();
+        Ok(config_file)
+    }
+
+    /// Moves the entries written in the same line as their section header to a line of their own, so they can
+    /// be changed or removed without touching the header
+    fn split_header_entries(&mut self) {
+        let Ok(items) = self.parse() else {
+            return;
+        };
+        let header_lines: Vec<usize> = items
+            .iter()
+            .filter(|(_first_line, item)| matches!(item, ConfigItem::Section { .. }))
+            .map(|(first_line, _item)| *first_line)
+            .collect();
+        let lines_to_split: Vec<usize> = items
+            .iter()
+            .filter(|(first_line, item)| matches!(item, ConfigItem::Entry { .. }) && header_lines.contains(first_line))
+            .map(|(first_line, _item)| *first_line)
+            .collect();
+        for &line_index in lines_to_split.iter().rev() {
+            let line = self.lines[line_index].trim_start().to_string();
+            if let Some((_section, _subsection, header_length)) = parse_section_header(&line) {
+                let entry = format!("\t{}", line[header_length..].trim_start());
+                self.lines.splice(line_index..=line_index, [line[..header_length].to_string(), entry]);
+            }
+        }
+    }
+
+    fn parse(&self) -> Result<Vec<(usize, ConfigItem)>, Box<dyn Error>> {
+        parse_config(&self.lines.join("\n"), &self.path)
+    }
+
+    /// Returns the line ranges of the entries of a variable
+    fn find_entries(&self, name: &str) -> Result<Vec<(usize, usize)>, Box<dyn Error>> {
+        let (section, subsection, key) = split_name(name)?;
+        let name = canonical_name(&section, subsection.as_deref(), &key);
+        Ok(self
+            .parse()?
+            .into_iter()
+            .filter_map(|(first_line, item)| match item {
+                ConfigItem::Entry { entry, last_line } if entry.name == name => Some((first_line, last_line)),
+                _ => None,
+            })
+            .collect())
+    }
+
+    /// Inserts a new entry at the end of the last section it belongs to, adding the section if it isn't there
+    fn insert_entry(&mut self, name: &str, value: &str) -> Result<(), Box<dyn Error>> {
+        let (section, subsection, _key) = split_name(name)?;
+        let key = &name[name.rfind('.').unwrap_or_default() + 1..];
+        let entry_line = format!("\t{} = {}", key, format_value(value));
+
+        let mut insert_position = None;
+        let mut in_section = false;
+        for (first_line, item) in self.parse()? {
+            match item {
+                ConfigItem::Section { section: found_section, subsection: found_subsection } => {
+                    in_section = found_section == section && found_subsection == subsection;
+                    if in_section {
+                        insert_position = Some(first_line + 1);
+                    }
+                }
+                ConfigItem::Entry { last_line, .. } if in_section => insert_position = Some(last_line + 1),
+                ConfigItem::Entry { .. } => {}
+            }
+        }
+
+        match insert_position {
+            Some(position) => self.lines.insert(position, entry_line),
+            None => {
+                self.lines.push(format_section_header(&section, subsection.as_deref()));
+                self.lines.push(entry_line);
+            }
+        }
+        Ok(())
+    }
+
+    /// Sets the value of a variable, replacing it if it's already set. Fails if the variable has several values.
+    pub fn set(&mut self, name: &str, value: &str) -> Result<(), Box<dyn Error>> {
+        let entries = self.find_entries(name)?;
+        match entries[..] {
+            [] => self.insert_entry(name, value),
+            [(first_line, last_line)] => {
+                let key = &name[name.rfind('.').unwrap_or_default() + 1..];
+                self.lines.splice(first_line..=last_line, [format!("\t{} = {}", key, format_value(value))]);
+                Ok(())
+            }
+            _ => Err(Box::new(io::Error::new(
+                io::ErrorKind::Other,
+                format!("warning: {} has multiple values\nerror: cannot overwrite multiple values with a single value", name),
+            ))),
+        }
+    }
+
+    /// Adds a value to a variable, keeping the ones it already has
+    pub fn add(&mut self, name: &str, value: &str) -> Result<(), Box<dyn Error>> {
+        match self.find_entries(name)?.last() {
+            Some(&(_first_line, last_line)) => {
+                let key = &name[name.rfind('.').unwrap_or_default() + 1..];
+                self.lines.insert(last_line + 1, format!("\t{} = {}", key, format_value(value)));
+                Ok(())
+            }
+            None => self.insert_entry(name, value),
+        }
+    }
+
+    /// Removes a variable. Unless `all` is set, fails if it has several values.
+    pub fn unset(&mut self, name: &str, all: bool) -> Result<(), Box<dyn Error>> {
+        let entries = self.find_entries(name)?;
+        if entries.is_empty() {
+            return Err(Box::new(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("error: key '{}' is not set", name),
+            )));
+        }
+        if entries.len() > 1 && !all {
+            return Err(Box::new(io::Error::new(
+                io::ErrorKind::Other,
+                format!("warning: {} has multiple values", name),
+            )));
+        }
+        for &(first_line, last_line) in entries.iter().rev() {
+            self.lines.drain(first_line..=last_line);
+        }
+        Ok(())
+    }
+
+    /// Removes every appearance of a section, with all its variables and comments
+    pub fn remove_section(&mut self, section: &str, subsection: Option<&str>) -> Result<(), Box<dyn Error>> {
+        let section = section.to_lowercase();
+        let header_lines: Vec<(usize, bool)> = self
+            .parse()?
+            .into_iter()
+            .filter_map(|(first_line, item)| match item {
+                ConfigItem::Section { section: found_section, subsection: found_subsection } => {
+                    Some((first_line, found_section == section && found_subsection.as_deref() == subsection))
+                }
+                ConfigItem::Entry { .. } => None,
+            })
+            .collect();
+        if !header_lines.iter().any(|&(_line, matches)| matches) {
+            return Err(Box::new(io::Error::new(
+                io::ErrorKind::NotFound,
+                "fatal: no such section!",
+            )));
+        }
+
+        let mut removed_ranges = Vec::new();
+        for (index, &(first_line, matches)) in header_lines.iter().enumerate() {
+            if matches {
+                let end = header_lines.get(index + 1).map(|&(line, _)| line).unwrap_or(self.lines.len());
+                removed_ranges.push(first_line..end);
+            }
+        }
+        for range in removed_ranges.into_iter().rev() {
+            self.lines.drain(range);
+        }
+        Ok(())
+    }
+
+    /// Renames every appearance of a section with the given subsection to a new subsection
+    pub fn rename_section(&mut self, section: &str, subsection: &str, new_subsection: &str) -> Result<(), Box<dyn Error>> {
+        let section = section.to_lowercase();
+        let header_lines: Vec<usize> = self
+            .parse()?
+            .into_iter()
+            .filter_map(|(first_line, item)| match item {
+                ConfigItem::Section { section: found_section, subsection: Some(found_subsection) }
+                    if found_section == section && found_subsection == subsection =>
+                {
+                    Some(first_line)
+                }
+                _ => None,
+            })
+            .collect();
+        if header_lines.is_empty() {
+            return Err(Box::new(io::Error::new(
+                io::ErrorKind::NotFound,
+                "fatal: no such section!",
+            )));
+        }
+        for line in header_lines {
+            self.lines[line] = format_section_header(&section, Some(new_subsection));
+        }
+        Ok(())
+    }
+
+    /// Writes the file to its lock file and renames it over the file, so readers never see it half written.
+    /// The lock is released either way.
+    pub fn save(mut self) -> Result<(), Box<dyn Error>> {
+        let Some(mut lock_file) = self.lock_file.take() else {
+            return Err(Box::new(io::Error::new(io::ErrorKind::Other, "error: the config file isn't locked")));
+        };
+        let content: String = self.lines.iter().map(|line| format!("{}\n", line)).collect();
+        let written = lock_file.write_all(content.as_bytes()).and_then(|_| lock_file.sync_all());
+        if let Err(error) = written.and_then(|_| fs::rename(&self.lock_path, &self.path)) {
+            let _ = fs::remove_file(&self.lock_path);
+            return Err(Box::new(error));
+        }
+        Ok(())
+    }
+}
+
+impl Drop for ConfigFile {
+    /// Releases the lock of a config file that wasn't saved, leaving the file as it was
+    fn drop(&mut self) {
+        if self.lock_file.take().is_some() {
+            let _ = fs::remove_file(&self.lock_path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_config_parsing() {
+        ignore_user_config();
+        let temp_dir = tempdir().unwrap();
+        let temp_path = temp_dir.path().to_string_lossy().to_string();
+        fs::write(format!("{}/extra", temp_path), "[core]\n\tbigFileThreshold = 2k\n").unwrap();
+        let content = "# a comment\n\
+            [core]\n\
+            \tbare\n\
+            \tfilemode = no ; inline comment\n\
+            [remote \"my \\\"origin\\\"\"]\n\
+            \turl = https://example.com/repo.git\n\
+            \tfetch = +refs/heads/*:refs/remotes/origin/*\n\
+            \tfetch = +refs/tags/*:refs/tags/*\n\
+            [Branch.Main]\n\
+            \tdescription = \"  quoted # value \"  and\\\n   more\n\
+            [include]\n\
+            \tpath = extra\n\
+            [user] name = Jane\n";
+        let config_path = format!("{}/config", temp_path);
+        fs::write(&config_path, content).unwrap();
+
+        let mut config = Config::default();
+        config.read_file(&config_path, 0).unwrap();
+        assert_eq!(config.get_bool("core.bare").unwrap(), Some(true));
+        assert_eq!(config.get_bool("core.fileMode").unwrap(), Some(false));
+        assert_eq!(config.get("remote.my \"origin\".url").unwrap(), "https://example.com/repo.git");
+        assert_eq!(config.get_all("remote.my \"origin\".fetch").len(), 2);
+        assert_eq!(config.get("branch.main.description").unwrap(), "  quoted # value   and   more");
+        assert_eq!(config.get_int("core.bigfilethreshold").unwrap(), Some(2048));
+        assert_eq!(config.get_subsections("remote"), vec!["my \"origin\""]);
+        assert_eq!(config.get("user.name").unwrap(), "Jane");
+        assert!(parse_bool("core.bare", Some("maybe")).is_err());
+        assert_eq!(parse_int("pack.size", Some("3m")).unwrap(), 3 * 1024 * 1024);
+
+        fs::write(&config_path, "[core\n").unwrap();
+        let error = Config::default().read_file(&config_path, 0).unwrap_err();
+        assert!(error.to_string().starts_with("fatal: bad config line 1"));
+    }
+
+    #[test]
+    fn test_config_file_editing() {
+        ignore_user_config();
+        let temp_dir = tempdir().unwrap();
+        let config_path = format!("{}/config", temp_dir.path().to_string_lossy());
+        fs::write(&config_path, "; kept\n[core]\n\tbare = false\n[remote \"origin\"]\n\turl = a\n").unwrap();
+
+        let mut config_file = ConfigFile::open_path(&config_path).unwrap();
+        config_file.set("core.bare", "true").unwrap();
+        config_file.set("core.editor", " vim ").unwrap();
+        config_file.add("remote.origin.fetch", "one").unwrap();
+        config_file.add("remote.origin.fetch", "two").unwrap();
+        assert!(config_file.set("remote.origin.fetch", "three").is_err());
+        config_file.set("user.name", "Jane").unwrap();
+        config_file.save().unwrap();
+        assert_eq!(
+            fs::read_to_string(&config_path).unwrap(),
+            "; kept\n[core]\n\tbare = true\n\teditor = \" vim \"\n[remote \"origin\"]\n\turl = a\n\tfetch = one\n\tfetch = two\n[user]\n\tname = Jane\n"
+        );
+
+        // the file stays locked while it's open, and the lock is released when it's dropped without saving
+        let config_file = ConfigFile::open_path(&config_path).unwrap();
+        let error = ConfigFile::open_path(&config_path).err().unwrap();
+        assert!(error.to_string().contains("could not lock config file"));
+        drop(config_file);
+
+        let mut config_file = ConfigFile::open_path(&config_path).unwrap();
+        assert!(config_file.unset("remote.origin.fetch", false).is_err());
+        config_file.unset("remote.origin.fetch", true).unwrap();
+        config_file.remove_section("remote", Some("origin")).unwrap();
+        assert!(config_file.remove_section("remote", Some("origin")).is_err());
+        config_file.save().unwrap();
+        let mut config = Config::default();
+        config.read_file(&config_path, 0).unwrap();
+        assert_eq!(config.get("core.editor").unwrap(), " vim ");
+        assert!(config.get("remote.origin.url").is_none());
+
+        fs::write(&config_path, "[core] bare = true ; inline\n[user] name = Jane\n").unwrap();
+        let mut config_file = ConfigFile::open_path(&config_path).unwrap();
+        config_file.set("core.bare", "false").unwrap();
+        config_file.unset("user.name", false).unwrap();
+        config_file.save().unwrap();
+        assert_eq!(fs::read_to_string(&config_path).unwrap(), "[core]\n\tbare = false\n[user]\n");
+    }
+}
@@ -1,17 +1,20 @@
 use std::fmt::Write as Write_FMT;
 use std::{
-    collections::HashSet, error::Error, fs, io, io::BufRead, io::ErrorKind, io::Read,
-    io::Seek, io::SeekFrom, io::Write, str, fs::ReadDir,
+    cell::RefCell, collections::BTreeSet, collections::HashMap, collections::HashSet, collections::VecDeque, error::Error, fs, io, io::BufRead, io::Write, path::Path, str,
 };
 
-extern crate libflate;
-use libflate::zlib::Decoder;
-use crypto::{digest::Digest, sha1::Sha1};
-
 use crate::client;
 use crate::client::client_protocol::ClientProtocol;
-use crate::commands::helpers::get_file_length;
+use crate::commands::config::{self, Config, ConfigFile, ConfigScope};
 use crate::commands::helpers;
+use crate::commands::ignore::IgnoreRules;
+use crate::commands::packfile;
+use crate::commands::reflog;
+use crate::commands::refs::{self, RefTransaction, Refspec};
+use crate::commands::revision;
+use crate::commands::signing::{self, Signer};
+use regex::{Regex, RegexBuilder};
+use std::thread;
 
 use crate::commands::structs::*;
 use crate::constants::*;
@@ -23,43 +26,30 @@ pub struct PathHandler {
 
 impl PathHandler {
     pub fn new(path: String) -> Self {
-        let path = if !path.ends_with('/') {
-            // If the path doesn't end with '/', append it
-            format!("{}/", path)
-        } else {
-            // If the path already ends with '/', leave it unchanged
+        PathHandler { path: Self::with_trailing_slash(path) }
+    }
+
+    /// An empty path stays empty so paths are resolved from the current directory.
+    /// Otherwise a '/' is appended if missing.
+    fn with_trailing_slash(path: String) -> String {
+        if path.is_empty() || path.ends_with('/') {
             path
-        };
-        PathHandler { path }
+        } else {
+            format!("{}/", path)
+        }
     }
 
     pub fn get_relative_path(&self, append_path: &str) -> String {
-
-        let path = if !self.path.is_empty() {
-            // Concatenate with a const string
-            format!("{}{}", self.path, append_path)
-        } else {
+        if self.path.is_empty() {
             append_path.to_string()
-        };
-
-        if let Some(stripped) = path.strip_prefix('/') {
-            // If the input starts with '/', return a new &str without it
-            stripped.to_string()
         } else {
-            // Otherwise, return the original input as is
-            path
+            // Concatenate with a const string
+            format!("{}{}", self.path, append_path)
         }
     }
 
     pub fn set_relative_path(&mut self, path: String) {
-        let path = if !path.ends_with('/') {
-            // If the path doesn't end with '/', append it
-            format!("{}/", path)
-        } else {
-            // If the path already ends with '/', leave it unchanged
-            path
-        };
-        self.path = path;
+        self.path = Self::with_trailing_slash(path);
     }
 }
 
@@ -139,70 +129,211 @@ impl Branch {
 
     /// Creates a new branch with the specified name. Creates branch file.
     pub fn create_new_branch(&self, branch_name: &str, path_handler: &PathHandler) -> Result<(), Box<dyn Error>> {
-        let branch_path = helpers::get_branch_path(branch_name);
+        let branch_ref = format!("refs/heads/{}", branch_name);
+        Self::check_branch_name(branch_name)?;
 
-        if helpers::check_if_file_exists(&branch_path, path_handler) {
+        if refs::ref_exists(&branch_ref, path_handler) {
             return Err(Box::new(io::Error::new(
                 io::ErrorKind::Other,
                 "A branch with the specified name already exists",
             )));
         }
 
-        let mut branch_file = fs::File::create(path_handler.get_relative_path(&branch_path))?;
-
         if branch_name == DEFAULT_BRANCH_NAME {
-            write!(branch_file, "")?;
+            refs::update_ref(&branch_ref, "", Some(ZERO_HASH), None, path_handler)?;
         } else {
             let last_commit_hash = Head::get_head_commit(path_handler)?;
-            write!(branch_file, "{}", last_commit_hash)?;
+            refs::update_ref(&branch_ref, &last_commit_hash, Some(ZERO_HASH), Some("branch: Created from HEAD"), path_handler)?;
         }
 
         Ok(())
     }
 
-    pub fn delete_branch(&self, branch_name: &str, path_handler: &PathHandler) -> Result<(), Box<dyn Error>> {
-        let branch_path = helpers::get_branch_path(branch_name);
+    /// Fails if the name isn't a valid branch name according to git's ref name rules
+    fn check_branch_name(branch_name: &str) -> Result<(), Box<dyn Error>> {
+        if branch_name.starts_with('-') || !refs::check_ref_format(&format!("refs/heads/{}", branch_name)) {
+            return Err(Box::new(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("fatal: '{}' is not a valid branch name", branch_name),
+            )));
+        }
+        Ok(())
+    }
 
-        if !helpers::check_if_file_exists(&branch_path, path_handler) {
+    /// Deletes a branch. Unless `force` is set, fails if the branch has commits that aren't merged into its
+    /// upstream, or into HEAD if it has none.
+    pub fn delete_branch(&self, branch_name: &str, force: bool, path_handler: &PathHandler) -> Result<(), Box<dyn Error>> {
+        if !refs::ref_exists(&format!("refs/heads/{}", branch_name), path_handler) {
             return Err(Box::new(io::Error::new(
                 io::ErrorKind::Other,
                 "No branch with the specified name was found",
             )));
         }
 
-        if Head::get_current_branch_name(path_handler)? == branch_name {
+        if !Head::is_detached(path_handler)? && Head::get_current_branch_name(path_handler)? == branch_name {
             return Err(Box::new(io::Error::new(
                 io::ErrorKind::Other,
                 "Cannot delete current branch",
             )));
         }
 
-        fs::remove_file(path_handler.get_relative_path(&branch_path))?;
+        if !force && !Self::is_fully_merged(branch_name, path_handler)? {
+            return Err(Box::new(io::Error::new(
+                io::ErrorKind::Other,
+                format!(
+                    "error: The branch '{}' is not fully merged.\nIf you are sure you want to delete it, run 'git branch -D {}'.",
+                    branch_name, branch_name
+                ),
+            )));
+        }
+
+        refs::delete_ref(&format!("refs/heads/{}", branch_name), None, path_handler)?;
+        Self::move_branch_config(branch_name, None, false, path_handler)?;
 
         Ok(())
     }
 
-    pub fn list_all_branches(&self, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
-        let mut branches: Vec<String> = Vec::new();
+    /// Returns true if the tip of a branch is reachable from its upstream, or from HEAD if it has none, so
+    /// deleting it doesn't lose any commit. A branch without commits is always merged.
+    fn is_fully_merged(branch_name: &str, path_handler: &PathHandler) -> Result<bool, Box<dyn Error>> {
+        let Some(branch_hash) = refs::read_ref(&format!("refs/heads/{}", branch_name), path_handler) else {
+            return Ok(true);
+        };
+        let upstream_hash = helpers::get_upstream_ref(branch_name, path_handler)?
+            .and_then(|upstream_ref| refs::read_ref(&upstream_ref, path_handler));
+        let target_hash = match upstream_hash {
+            Some(upstream_hash) => upstream_hash,
+            None => Head::get_head_commit(path_handler)?,
+        };
+        if target_hash.is_empty() {
+            return Ok(false);
+        }
+        Ok(revision::get_ancestors(&[target_hash], path_handler)?.contains(&branch_hash))
+    }
+
+    /// Moves the upstream configuration of a branch to a new name, or copies it if `keep_original` is set.
+    /// With no new name the configuration is removed, as when the branch is deleted.
+    fn move_branch_config(branch_name: &str, new_name: Option<&str>, keep_original: bool, path_handler: &PathHandler) -> Result<(), Box<dyn Error>> {
+        let config = Config::load_scope(ConfigScope::Local, path_handler)?;
+        let remote = config.get(&format!("branch.{}.remote", branch_name));
+        let merge = config.get(&format!("branch.{}.merge", branch_name));
+        if !config.get_subsections("branch").iter().any(|name| name == branch_name) {
+            return Ok(());
+        }
+
+        let mut config_file = ConfigFile::open(ConfigScope::Local, path_handler)?;
+        if !keep_original {
+            config_file.remove_section("branch", Some(branch_name))?;
+        }
+        if let (Some(new_name), Some(remote), Some(merge)) = (new_name, remote, merge) {
+            config_file.set(&format!("branch.{}.remote", new_name), &remote)?;
+            config_file.set(&format!("branch.{}.merge", new_name), &merge)?;
+        }
+        config_file.save()
+    }
+
+    /// Returns the branches a listing shows
+    fn get_listed_branches(&self, filter: &BranchFilter, path_handler: &PathHandler) -> Result<Vec<ListedBranch>, Box<dyn Error>> {
+        let mut branches = Vec::new();
+        if filter.include_local {
+            for (ref_name, hash) in refs::list_refs("refs/heads/", path_handler)? {
+                branches.push((ref_name.trim_start_matches("refs/heads/").to_string(), ref_name, hash));
+            }
+        }
+        if filter.include_remotes {
+            // remote-tracking branches are shown as remotes/<remote>/<branch> when listed along with local ones
+            let display_prefix = if filter.include_local { "refs/" } else { "refs/remotes/" };
+            for (ref_name, hash) in refs::list_refs("refs/remotes/", path_handler)? {
+                branches.push((ref_name.trim_start_matches(display_prefix).to_string(), ref_name, hash));
+            }
+        }
 
-        match fs::read_dir(path_handler.get_relative_path(R_HEADS)) {
-            Ok(entries) => {
-                for entry in entries {
-                    branches.push(entry?.file_name().to_string_lossy().to_string())
+        if let Some(commit) = &filter.contains {
+            let mut containing = Vec::new();
+            for (name, ref_name, hash) in branches {
+                if !hash.is_empty() && revision::get_ancestors(std::slice::from_ref(&hash), path_handler)?.contains(commit) {
+                    containing.push((name, ref_name, hash));
                 }
             }
-            Err(_) => {
-                return Err(Box::new(io::Error::new(
-                    io::ErrorKind::Other,
-                    "Error reading branches",
-                )))
+            branches = containing;
+        }
+        if let Some((commit, merged)) = &filter.merged {
+            let merged_commits = revision::get_ancestors(std::slice::from_ref(commit), path_handler)?;
+            branches.retain(|(_name, _ref_name, hash)| !hash.is_empty() && merged_commits.contains(hash) == *merged);
+        }
+        Ok(branches)
+    }
+
+    /// Lists the branches with the commit they point to and its summary. Branches with an upstream show how far
+    /// ahead and behind it they are, and with `very_verbose` the name of the upstream too.
+    pub fn list_branches_verbose(&self, filter: &BranchFilter, very_verbose: bool, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
+        let branches = self.get_listed_branches(filter, path_handler)?;
+        let current_branch = if Head::is_detached(path_handler)? { None } else { Some(Head::get_current_branch_name(path_handler)?) };
+        let name_width = branches.iter().map(|(name, _ref_name, _hash)| name.len()).max().unwrap_or(0);
+
+        let mut lines = Vec::new();
+        for (name, ref_name, hash) in branches {
+            if hash.is_empty() {
+                continue;
+            }
+            let local_branch = ref_name.strip_prefix("refs/heads/");
+            let marker = if local_branch.is_some() && current_branch.as_deref() == local_branch { '*' } else { ' ' };
+
+            let mut tracking = String::new();
+            let upstream_ref = match local_branch {
+                Some(branch_name) => helpers::get_upstream_ref(branch_name, path_handler)?,
+                None => None,
+            };
+            if let Some(upstream_ref) = upstream_ref {
+                let upstream_name = helpers::shorten_ref_name(&upstream_ref);
+                let counts = match refs::read_ref(&upstream_ref, path_handler) {
+                    None => vec![String::from("gone")],
+                    Some(upstream_hash) => {
+                        let (ahead, behind) = revision::count_ahead_behind(&hash, &upstream_hash, path_handler)?;
+                        let mut counts = Vec::new();
+                        if ahead > 0 {
+                            counts.push(format!("ahead {}", ahead));
+                        }
+                        if behind > 0 {
+                            counts.push(format!("behind {}", behind));
+                        }
+                        counts
+                    }
+                };
+                tracking = match (very_verbose, counts.is_empty()) {
+                    (true, true) => format!("[{}] ", upstream_name),
+                    (true, false) => format!("[{}: {}] ", upstream_name, counts.join(", ")),
+                    (false, false) => format!("[{}] ", counts.join(", ")),
+                    (false, true) => String::new(),
+                };
             }
+
+            let line = format!(
+                "{} {:width$} {} {}{}",
+                marker,
+                name,
+                &hash[..ABBREVIATED_HASH_LENGTH],
+                tracking,
+                helpers::get_commit_summary(&hash, path_handler)?,
+                width = name_width
+            );
+            println!("{}", line);
+            lines.push(line);
         }
+        Ok(lines.join("\n"))
+    }
+
+    pub fn list_all_branches(&self, filter: &BranchFilter, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
+        let branches: Vec<String> = self
+            .get_listed_branches(filter, path_handler)?
+            .into_iter()
+            .map(|(name, _ref_name, _hash)| name)
+            .collect();
 
-        let current_branch = Head::get_current_branch_name(path_handler)?;
+        let current_branch = if Head::is_detached(path_handler)? { String::new() } else { Head::get_current_branch_name(path_handler)? };
 
         for branch in branches.clone() {
-            if branch == current_branch {
+            if filter.include_local && branch == current_branch {
                 print!("{}* {}\n{}", COLOR_GREEN_CODE, branch, COLOR_RESET_CODE);
             } else {
                 println!("{}", branch)
@@ -218,31 +349,112 @@ impl Branch {
         Ok(branches_in_string)
     }
 
-    pub fn rename_branch(&self, previous_name: &str, new_name: &str, path_handler: &PathHandler) -> Result<(), Box<dyn Error>> {
-        let previous_branch_path = helpers::get_branch_path(previous_name);
-
-        if !helpers::check_if_file_exists(&previous_branch_path, path_handler) {
+    /// Checks that a branch can be renamed or copied to a new name: the branch must exist, the new name must be
+    /// valid and, unless `force` is set, no other branch can have it. Even with `force`, the checked out branch
+    /// can't be replaced.
+    fn check_branch_target(previous_name: &str, new_name: &str, force: bool, path_handler: &PathHandler) -> Result<(), Box<dyn Error>> {
+        if !refs::ref_exists(&format!("refs/heads/{}", previous_name), path_handler) {
             return Err(Box::new(io::Error::new(
                 io::ErrorKind::Other,
                 "No branch with the specified name was found",
             )));
         }
+        Self::check_branch_name(new_name)?;
+        if !force && refs::ref_exists(&format!("refs/heads/{}", new_name), path_handler) {
+            return Err(Box::new(io::Error::new(
+                io::ErrorKind::Other,
+                format!("fatal: a branch named '{}' already exists", new_name),
+            )));
+        }
+        if previous_name != new_name && Head::get_current_branch_name(path_handler).ok().as_deref() == Some(new_name) {
+            return Err(Box::new(io::Error::new(
+                io::ErrorKind::Other,
+                format!("fatal: cannot force update the current branch '{}'", new_name),
+            )));
+        }
+        Ok(())
+    }
 
-        let new_branch_path = path_handler.get_relative_path(&helpers::get_branch_path(new_name));
+    /// Renames a branch along with its reflog and configuration. With `force`, an existing branch with the new
+    /// name is replaced.
+    pub fn rename_branch(&self, previous_name: &str, new_name: &str, force: bool, path_handler: &PathHandler) -> Result<(), Box<dyn Error>> {
+        let previous_branch_path = helpers::get_branch_path(previous_name);
+        Self::check_branch_target(previous_name, new_name, force, path_handler)?;
+        if previous_name == new_name {
+            return Ok(());
+        }
 
-        fs::rename(
-            path_handler.get_relative_path(&previous_branch_path),
-            new_branch_path,
-        )?;
+        let previous_ref = format!("refs/heads/{}", previous_name);
+        let new_ref = format!("refs/heads/{}", new_name);
+        let branch_hash = helpers::get_branch_last_commit(&previous_branch_path, path_handler)?;
+
+        // the reflog moves first so the deletion of the old branch doesn't remove it
+        reflog::rename(&previous_ref, &new_ref, path_handler)?;
+        let mut transaction = RefTransaction::new();
+        transaction
+            .delete(&previous_ref, Some(&branch_hash))
+            .update(&new_ref, &branch_hash, if force { None } else { Some(ZERO_HASH) }, None);
+        if let Err(error) = transaction.commit(path_handler) {
+            reflog::rename(&new_ref, &previous_ref, path_handler)?;
+            return Err(error);
+        }
 
+        Self::move_branch_config(previous_name, Some(new_name), false, path_handler)?;
+        let message = format!("Branch: renamed {} to {}", previous_ref, new_ref);
+        reflog::append(&new_ref, &branch_hash, &branch_hash, &message, path_handler)?;
         if Head::get_current_branch_name(path_handler)? == previous_name {
-            Head::change_head_branch(new_name, path_handler)?
+            Head::change_head_branch(new_name, path_handler)?;
+            reflog::append(HEAD, &branch_hash, &branch_hash, &message, path_handler)?;
+        }
+
+        Ok(())
+    }
+
+    /// Copies a branch along with its reflog and configuration. With `force`, an existing branch with the new
+    /// name is replaced.
+    pub fn copy_branch(&self, previous_name: &str, new_name: &str, force: bool, path_handler: &PathHandler) -> Result<(), Box<dyn Error>> {
+        Self::check_branch_target(previous_name, new_name, force, path_handler)?;
+        if previous_name == new_name {
+            return Ok(());
         }
 
+        let previous_ref = format!("refs/heads/{}", previous_name);
+        let new_ref = format!("refs/heads/{}", new_name);
+        let branch_hash = helpers::get_branch_last_commit(&helpers::get_branch_path(previous_name), path_handler)?;
+
+        reflog::copy(&previous_ref, &new_ref, path_handler)?;
+        refs::update_ref(&new_ref, &branch_hash, if force { None } else { Some(ZERO_HASH) }, None, path_handler)?;
+        Self::move_branch_config(previous_name, Some(new_name), true, path_handler)?;
+        let message = format!("Branch: copied {} to {}", previous_ref, new_ref);
+        reflog::append(&new_ref, &branch_hash, &branch_hash, &message, path_handler)?;
+
         Ok(())
     }
 }
 
+/// A branch shown by the branch command: the name it's shown with, its ref and the commit it points to
+type ListedBranch = (String, String, String);
+
+/// Which branches a listing of the branch command shows: local ones, remote-tracking ones or both, optionally only
+/// those that contain a commit or the ones that are (or aren't) merged into one
+pub struct BranchFilter {
+    pub include_local: bool,
+    pub include_remotes: bool,
+    pub contains: Option<String>,
+    pub merged: Option<(String, bool)>,
+}
+
+impl Default for BranchFilter {
+    fn default() -> Self {
+        BranchFilter {
+            include_local: true,
+            include_remotes: false,
+            contains: None,
+            merged: None,
+        }
+    }
+}
+
 /// Implementation of the `Command` trait for the `Branch` type.
 ///
 /// This implementation defines the behavior of the `execute` method for the `Branch` type.
@@ -258,7 +470,10 @@ impl Branch {
 ///
 /// * If no arguments are provided (`args` is `None`), it lists all branches in the repository.
 /// * If the "-d" flag is provided in the arguments, it sets the `delete_flag` to `true` and attempts to delete a branch.
-/// * If the "-m" flag is provided in the arguments, it sets the `rename_flag` to `true`.
+/// * If the "-m" flag is provided in the arguments, it sets the `rename_flag` to `true`. "-c" copies the branch instead, and "-M", "-C" and "-D" force the rename, copy or delete.
+/// * If the "-r" or "-a" flag is provided, remote-tracking branches are listed instead of or along with local ones. "--contains", "--merged" and "--no-merged" filter the listing by a commit, HEAD by default.
+/// * If the "-u" or "--set-upstream-to" flag is provided with an upstream, the given branch (or the current one) is set to track it. "--unset-upstream" removes it.
+/// * If the "-v" or "-vv" flag is provided, branches are listed with their last commit and how far ahead and behind their upstream they are.
 /// * All other arguments are treated as branch names, and the code populates `first_branch_name` and `second_branch_name` options.
 ///
 /// If `first_branch_name` is `None`, it is populated with the first encountered branch name. If `first_branch_name` is already populated, `second_branch_name` is populated with the next encountered branch name. This ensures that `second_branch_name` is only populated after `first_branch_name`.
@@ -282,18 +497,52 @@ impl Branch {
 ///
 impl Command for Branch {
     fn execute(&self, args: Option<Vec<&str>>, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
-        let list_branches_flag = args.is_none();
+        let mut list_branches_flag = args.is_none();
         let mut delete_flag = false;
         let mut rename_flag = false;
+        let mut copy_flag = false;
+        let mut force_flag = false;
+        let mut verbosity = 0;
+        let mut filter = BranchFilter::default();
+        let mut upstream: Option<String> = None;
+        let mut unset_upstream_flag = false;
         let mut first_branch_name: Option<String> = None;
         let mut second_branch_name: Option<String> = None;
-        let arg_slice = args.unwrap_or_default();
+        let mut arg_slice = args.unwrap_or_default().into_iter().peekable();
 
-        for arg in arg_slice {
+        while let Some(arg) = arg_slice.next() {
             // Note the & in for &arg
             match arg {
                 DELETE_FLAG => delete_flag = true,
+                FORCE_DELETE_FLAG => (delete_flag, force_flag) = (true, true),
                 RENAME_FLAG => rename_flag = true,
+                FORCE_RENAME_FLAG => (rename_flag, force_flag) = (true, true),
+                COPY_FLAG => copy_flag = true,
+                FORCE_COPY_FLAG => (copy_flag, force_flag) = (true, true),
+                FORCE_FLAG => force_flag = true,
+                REMOTES_FLAG => (filter.include_local, filter.include_remotes, list_branches_flag) = (false, true, true),
+                ALL_FLAG => (filter.include_local, filter.include_remotes, list_branches_flag) = (true, true, true),
+                CONTAINS_FLAG | MERGED_FLAG | NO_MERGED_FLAG => {
+                    // the commit is optional and defaults to HEAD
+                    let revision = match arg_slice.peek() {
+                        Some(next_arg) if !next_arg.starts_with('-') => arg_slice.next().unwrap_or(HEAD),
+                        _ => HEAD,
+                    };
+                    let commit = revision::resolve_commit(revision, path_handler)?;
+                    if arg == CONTAINS_FLAG {
+                        filter.contains = Some(commit);
+                    } else {
+                        filter.merged = Some((commit, arg == MERGED_FLAG));
+                    }
+                    list_branches_flag = true;
+                }
+                VERBOSE_FLAG => verbosity += 1,
+                VERY_VERBOSE_FLAG => verbosity += 2,
+                SET_UPSTREAM_FLAG | SET_UPSTREAM_TO_FLAG => upstream = arg_slice.next().map(String::from),
+                UNSET_UPSTREAM_FLAG => unset_upstream_flag = true,
+                _ if arg.starts_with(&format!("{}=", SET_UPSTREAM_TO_FLAG)) => {
+                    upstream = Some(arg[SET_UPSTREAM_TO_FLAG.len() + 1..].to_string())
+                }
                 _ => {
                     if first_branch_name.is_none() {
                         first_branch_name = Some(arg.to_string());
@@ -313,19 +562,50 @@ impl Command for Branch {
 
         let mut result = String::new();
 
+        // upstream changes apply to the given branch or to the current one
+        if upstream.is_some() || unset_upstream_flag {
+            let branch_name = match first_branch_name {
+                Some(branch_name) => branch_name,
+                None => Head::get_current_branch_name(path_handler)?,
+            };
+            if let Some(upstream) = upstream {
+                let upstream_name = helpers::set_upstream(&branch_name, &upstream, path_handler)?;
+                result = format!("branch '{}' set up to track '{}'.", branch_name, upstream_name);
+                println!("{}", result);
+            } else {
+                helpers::unset_upstream(&branch_name, path_handler)?;
+            }
+            return Ok(result);
+        }
+        if verbosity > 0 && !delete_flag && !rename_flag && !copy_flag {
+            return self.list_branches_verbose(&filter, verbosity > 1, path_handler);
+        }
+
+        // with a single name, renames and copies apply to the current branch
+        let (source_name, target_name) = match (first_branch_name, second_branch_name) {
+            (Some(new_name), None) if (rename_flag || copy_flag) && !Head::is_detached(path_handler)? => {
+                (Some(Head::get_current_branch_name(path_handler)?), Some(new_name))
+            }
+            names => names,
+        };
+
         match (
             list_branches_flag,
             delete_flag,
-            rename_flag,
-            first_branch_name,
-            second_branch_name,
+            rename_flag || copy_flag,
+            source_name,
+            target_name,
         ) {
-            (true, _, _, _, _) => result = self.list_all_branches(path_handler)?,
-            (_, true, _, Some(name), _) => self.delete_branch(&name, path_handler)?,
+            (true, _, _, _, _) => result = self.list_all_branches(&filter, path_handler)?,
+            (_, true, _, Some(name), _) => self.delete_branch(&name, force_flag, path_handler)?,
+            (_, false, true, Some(old_name), Some(new_name)) if copy_flag => {
+                self.copy_branch(&old_name, &new_name, force_flag, path_handler)?
+            }
             (_, false, true, Some(old_name), Some(new_name)) => {
-                self.rename_branch(&old_name, &new_name, path_handler)?
+                self.rename_branch(&old_name, &new_name, force_flag, path_handler)?
             }
             (false, false, false, Some(name), _) => self.create_new_branch(&name, path_handler)?,
+            (false, false, false, None, _) => result = self.list_all_branches(&filter, path_handler)?,
             _ => {}
         }
         Ok(result)
@@ -344,6 +624,90 @@ impl Checkout {
     pub fn new() -> Self {
         Checkout {}
     }
+
+    /// Returns the files of the tree of a commit with their modes and hashes, or nothing for an empty commit hash
+    fn get_commit_files(commit_hash: &str, path_handler: &PathHandler) -> Result<HashMap<String, (String, String)>, Box<dyn Error>> {
+        if commit_hash.is_empty() {
+            return Ok(HashMap::new());
+        }
+        helpers::get_tree_files(&helpers::get_commit_tree(commit_hash, path_handler)?, "", path_handler)
+    }
+
+    /// Updates the working directory and index from the tree of HEAD to the tree of the given commit, without
+    /// moving HEAD. Only files that differ between both trees are touched, so local changes to other files are kept.
+    /// Fails without changing anything if a file with local changes or an untracked file would be overwritten.
+    pub fn checkout_commit(commit_hash: &str, path_handler: &PathHandler) -> Result<(), Box<dyn Error>> {
+        let head_files = Self::get_commit_files(&Head::get_head_commit(path_handler)?, path_handler)?;
+        let target_files = Self::get_commit_files(commit_hash, path_handler)?;
+        let index_entries = helpers::read_index_entries(path_handler)?;
+
+        let changed_paths: HashSet<&String> = head_files
+            .keys()
+            .chain(target_files.keys())
+            .filter(|path| head_files.get(*path) != target_files.get(*path))
+            .collect();
+        let mut overwritten_files = Vec::new();
+        let mut untracked_files = Vec::new();
+        for path in &changed_paths {
+            let head_hash = head_files.get(*path).map(|(_mode, hash)| hash);
+            match index_entries.iter().find(|(index_path, _hash, _state, _mode)| index_path == *path) {
+                Some((_path, hash, state, _mode)) => {
+                    let working_hash = helpers::hash_working_file(path, path_handler).ok();
+                    if Some(hash) != head_hash
+                        || *state == IndexFileEntryState::Deleted.to_string()
+                        || working_hash.as_ref() != Some(hash)
+                    {
+                        overwritten_files.push(path.to_string());
+                    }
+                }
+                None if target_files.contains_key(*path) && fs::symlink_metadata(path_handler.get_relative_path(path)).is_ok() => {
+                    untracked_files.push(path.to_string())
+                }
+                None => {}
+            }
+        }
+        if !overwritten_files.is_empty() || !untracked_files.is_empty() {
+            overwritten_files.sort();
+            untracked_files.sort();
+            let mut message = String::new();
+            if !overwritten_files.is_empty() {
+                message.push_str(&format!(
+                    "error: Your local changes to the following files would be overwritten by checkout:\n\t{}\nPlease commit your changes or stash them before you switch branches.",
+                    overwritten_files.join("\n\t")
+                ));
+            }
+            if !untracked_files.is_empty() {
+                if !message.is_empty() {
+                    message.push('\n');
+                }
+                message.push_str(&format!(
+                    "error: The following untracked working tree files would be overwritten by checkout:\n\t{}\nPlease move or remove them before you switch branches.",
+                    untracked_files.join("\n\t")
+                ));
+            }
+            return Err(Box::new(io::Error::new(io::ErrorKind::Other, message)));
+        }
+
+        let mut new_index_entries: Vec<helpers::IndexEntry> = index_entries
+            .into_iter()
+            .filter(|(path, _hash, _state, _mode)| !changed_paths.contains(path))
+            .collect();
+        for path in changed_paths {
+            match target_files.get(path) {
+                Some((file_mode, file_hash)) => {
+                    let file_path = path_handler.get_relative_path(path);
+                    if let Some(parent) = Path::new(&file_path).parent() {
+                        fs::create_dir_all(parent)?;
+                    }
+                    WorkingDirectory::write_tree_entry(&file_path, file_mode, file_hash, path_handler)?;
+                    new_index_entries.push((path.clone(), file_hash.clone(), IndexFileEntryState::Cached.to_string(), file_mode.clone()));
+                }
+                None => WorkingDirectory::remove_file_and_empty_parent_directories(Path::new(&path_handler.get_relative_path(path)))?,
+            }
+        }
+        new_index_entries.sort();
+        helpers::write_index_entries(&new_index_entries, path_handler)
+    }
 }
 
 impl Command for Checkout {
@@ -354,7 +718,7 @@ impl Command for Checkout {
         match args {
             Some(args) => {
                 let branch_name = args[0];
-                if !helpers::check_if_file_exists(&helpers::get_branch_path(branch_name), path_handler) {
+                if !refs::ref_exists(&format!("refs/heads/{}", branch_name), path_handler) {
                     return Err(Box::new(io::Error::new(
                         io::ErrorKind::Other,
                         "Name did not match any known branch",
@@ -402,49 +766,119 @@ impl CatFile {
     pub fn new() -> Self {
         CatFile {}
     }
-}
-
-impl Command for CatFile {
-    /// Executes the `cat-file` command, which displays information about a Git object's type or size.
-    fn execute(&self, args: Option<Vec<&str>>, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
-        match args {
-            Some(args) => {
-                let file = fs::File::open(path_handler.get_relative_path(
-                    &helpers::get_object_path(args[1]),
-                ))?;
 
-                let mut decoder = Decoder::new(file)?;
-                let mut header = Vec::new();
+    /// Returns the type of object a tree entry with the given mode points to
+    fn get_tree_entry_type(mode: &str) -> ObjectType {
+        if helpers::is_tree_mode(mode) {
+            ObjectType::Tree
+        } else if mode == GITLINK_MODE {
+            ObjectType::Commit
+        } else {
+            ObjectType::Blob
+        }
+    }
 
-                loop {
-                    let mut byte = [0; 1];
-                    decoder.read_exact(&mut byte)?;
+    /// Returns the content of an object formatted for humans. Tree entries are listed one per line as
+    /// `mode type hash\tname`, every other type is shown as it is stored.
+    pub fn pretty_print(object_type: &ObjectType, content: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+        if *object_type != ObjectType::Tree {
+            return Ok(content.to_vec());
+        }
+        let mut output = String::new();
+        for (mode, name, hash) in helpers::parse_tree_entries(content)? {
+            output.push_str(&format!(
+                "{:0>6} {} {}\t{}\n",
+                mode,
+                CatFile::get_tree_entry_type(&mode),
+                hash,
+                name
+            ));
+        }
+        Ok(output.into_bytes())
+    }
 
-                    // Check if the byte is '\0'
-                    if byte[0] == b'\0' {
-                        break; // Exit the loop if null byte is encountered
+    /// Reads object names from `input`, one per line, and writes `<hash> <type> <size>` for each to `output`,
+    /// followed by the content of the object and a newline if `with_content` is set.
+    /// Names that can't be resolved are reported as `<name> missing`.
+    pub fn run_batch<R: BufRead, W: Write>(
+        input: R,
+        output: &mut W,
+        with_content: bool,
+        path_handler: &PathHandler
+    ) -> Result<(), Box<dyn Error>> {
+        for line in input.lines() {
+            let line = line?;
+            let object_name = line.trim();
+            if object_name.is_empty() {
+                continue;
+            }
+            let object = revision::resolve_revision(object_name, path_handler)
+                .and_then(|hash| Ok((helpers::read_object_to_bytes(hash.clone(), path_handler)?, hash)));
+            match object {
+                Ok(((object_type, content, object_size), hash)) => {
+                    writeln!(output, "{} {} {}", hash, object_type, object_size)?;
+                    if with_content {
+                        output.write_all(&content)?;
+                        writeln!(output)?;
                     }
-                    header.push(byte[0]);
-                }
-
-                let header_str = String::from_utf8(header)?;
-                let parts: Vec<&str> = header_str.trim_end().split(' ').collect();
-
-                match args[0] {
-                    TYPE_FLAG => println!("{}", parts[0]),
-                    SIZE_FLAG => println!("{}", parts[1]),
-                    _ => return Err(Box::new(io::Error::new(io::ErrorKind::Other, "Flag error"))),
                 }
+                Err(_) => writeln!(output, "{} missing", object_name)?,
             }
+            output.flush()?;
+        }
+        Ok(())
+    }
+}
+
+impl Command for CatFile {
+    /// Executes the `cat-file` command, which displays information about a Git object: its type (-t), its size (-s)
+    /// or its content (-p). With -e nothing is shown, it only fails if the object doesn't exist.
+    /// The object can be given by any revision, including abbreviated hashes.
+    /// With --batch or --batch-check object names are read from stdin instead.
+    fn execute(&self, args: Option<Vec<&str>>, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
+        let args = args.unwrap_or_default();
+        let flag = match args.first() {
+            Some(flag) => *flag,
             None => {
                 return Err(Box::new(io::Error::new(
                     io::ErrorKind::Other,
                     "No arguments received",
                 )))
             }
+        };
+
+        if flag == BATCH_FLAG || flag == BATCH_CHECK_FLAG {
+            let stdin = io::stdin();
+            CatFile::run_batch(stdin.lock(), &mut io::stdout(), flag == BATCH_FLAG, path_handler)?;
+            return Ok(String::new());
         }
 
-        Ok(String::new())
+        let object_name = match args.get(1) {
+            Some(object_name) => *object_name,
+            None => return Err(Box::new(io::Error::new(io::ErrorKind::Other, "No object provided"))),
+        };
+        let hash = revision::resolve_revision(object_name, path_handler)?;
+        let object = helpers::read_object_to_bytes(hash, path_handler);
+        if flag == EXISTS_FLAG {
+            return match object {
+                Ok(_) => Ok(String::new()),
+                Err(_) => Err(Box::new(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("Error: object {} doesn't exist", object_name),
+                ))),
+            };
+        }
+        let (object_type, content, object_size) = object?;
+
+        let output = match flag {
+            TYPE_FLAG => format!("{}\n", object_type).into_bytes(),
+            SIZE_FLAG => format!("{}\n", object_size).into_bytes(),
+            PRETTY_PRINT_FLAG => CatFile::pretty_print(&object_type, &content)?,
+            _ => return Err(Box::new(io::Error::new(io::ErrorKind::Other, "Flag error"))),
+        };
+        io::stdout().write_all(&output)?;
+
+        Ok(String::from_utf8_lossy(&output).to_string())
     }
 }
 
@@ -460,12 +894,104 @@ impl HashObject {
     pub fn new() -> Self {
         HashObject {}
     }
+
+    /// Returns an error saying the content isn't a valid object of the given type
+    fn corrupt_object(obj_type: &ObjectType, reason: &str) -> Box<dyn Error> {
+        Box::new(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Error: corrupt {}: {}", obj_type, reason),
+        ))
+    }
+
+    /// Checks that the header of a commit or tag has the required fields, in order, with the
+    /// fields that point to objects holding valid hashes
+    fn validate_header(obj_type: &ObjectType, content: &str, required_fields: &[&str], hash_fields: &[&str]) -> Result<(), Box<dyn Error>> {
+        let header: Vec<(&str, &str)> = content
+            .split("\n\n")
+            .next()
+            .unwrap_or_default()
+            .lines()
+            .map(|line| line.split_once(' ').unwrap_or((line, "")))
+            .collect();
+
+        let mut position = 0;
+        for field in required_fields {
+            while position < header.len() && header[position].0 != *field {
+                position += 1;
+            }
+            if position == header.len() {
+                return Err(HashObject::corrupt_object(obj_type, &format!("missing {} line", field)));
+            }
+        }
+        for (key, value) in header {
+            if hash_fields.contains(&key) && !helpers::is_object_hash(value) {
+                return Err(HashObject::corrupt_object(obj_type, &format!("invalid {} line", key)));
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that the content has the format git expects for objects of the given type.
+    /// Blobs can hold anything.
+    pub fn validate_object_format(obj_type: &ObjectType, content: &[u8]) -> Result<(), Box<dyn Error>> {
+        match obj_type {
+            ObjectType::Blob => Ok(()),
+            ObjectType::Tree => {
+                let entries = helpers::parse_tree_entries(content)
+                    .map_err(|_| HashObject::corrupt_object(obj_type, "invalid tree entry"))?;
+                for (mode, name, _hash) in entries {
+                    if mode.is_empty() || !mode.chars().all(|c| ('0'..='7').contains(&c)) {
+                        return Err(HashObject::corrupt_object(obj_type, &format!("invalid mode {}", mode)));
+                    }
+                    if name.is_empty() || name.contains('/') || name == "." || name == ".." {
+                        return Err(HashObject::corrupt_object(obj_type, &format!("invalid entry name '{}'", name)));
+                    }
+                }
+                Ok(())
+            }
+            ObjectType::Commit | ObjectType::Tag => {
+                let content = str::from_utf8(content)
+                    .map_err(|_| HashObject::corrupt_object(obj_type, "content is not valid utf-8"))?;
+                if *obj_type == ObjectType::Commit {
+                    HashObject::validate_header(obj_type, content, &["tree", "author", "committer"], &["tree", "parent"])
+                } else {
+                    HashObject::validate_header(obj_type, content, &["object", "type", "tag"], &["object"])?;
+                    let tagged_type = revision::get_header_field(content, "type").unwrap_or_default();
+                    match ObjectType::new(&tagged_type) {
+                        Some(_) => Ok(()),
+                        None => Err(HashObject::corrupt_object(obj_type, &format!("invalid type {}", tagged_type))),
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns the hash of an object with the given type and content. The object is only stored in the
+    /// repository if `write` is set, and its format is validated unless `literally` is set.
+    pub fn hash_object(
+        content: &[u8],
+        obj_type: &ObjectType,
+        write: bool,
+        literally: bool,
+        path_handler: &PathHandler
+    ) -> Result<String, Box<dyn Error>> {
+        if !literally {
+            HashObject::validate_object_format(obj_type, content)?;
+        }
+        if write {
+            HashObjectCreator::write_object_file_bytes(content, obj_type.clone(), content.len(), path_handler)
+        } else {
+            Ok(HashObjectCreator::generate_object_hash_from_bytes(obj_type, content))
+        }
+    }
 }
 
 impl Command for HashObject {
-    /// Executes the `hash-object` command, which calculates the hash of a given file or data.
-    /// If the write flag is specified, the object is created as a file in the objects subdirectory.
-    /// Default object type is "blob" but can be specified with type flag.
+    /// Executes the `hash-object` command, which calculates the hash of the given files, of the data read from
+    /// stdin (--stdin) or of the files whose paths are read from stdin, one per line (--stdin-paths).
+    /// If the write flag is specified, the objects are created as files in the objects subdirectory.
+    /// Default object type is "blob" but can be specified with type flag. Content of other types is
+    /// validated unless --literally is used.
     fn execute(&self, args: Option<Vec<&str>>, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
         if args.is_none() {
             return Err(Box::new(io::Error::new(
@@ -474,50 +1000,71 @@ impl Command for HashObject {
             )));
         }
         let arg_slice = args.unwrap_or_default();
-        let mut path: &str = "";
+        let mut paths: Vec<String> = Vec::new();
         let mut obj_type = ObjectType::Blob;
         let mut write = false;
-        let mut awaiting_type = false;
-        for &item in &arg_slice {
+        let mut stdin = false;
+        let mut stdin_paths = false;
+        let mut literally = false;
+        let mut arg_iter = arg_slice.iter();
+        while let Some(&item) = arg_iter.next() {
             match item {
                 TYPE_FLAG => {
-                    awaiting_type = true;
+                    let type_name = arg_iter.next().copied().unwrap_or_default();
+                    obj_type = ObjectType::new(type_name).ok_or(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("Error: Unknown object type '{}'", type_name),
+                    ))?;
                 }
                 WRITE_FLAG => write = true,
-                _ => {
-                    if awaiting_type {
-                        if let Some(new_obj_type) = ObjectType::new(item) {
-                            obj_type = new_obj_type;
-                        } else {
-                            eprintln!("Unknown object type for input: {}", item);
-                            return Ok(String::new());
-                        }
-                        awaiting_type = false;
-                    } else {
-                        path = item
-                    }
-                }
+                STDIN_FLAG => stdin = true,
+                STDIN_PATHS_FLAG => stdin_paths = true,
+                LITERALLY_FLAG => literally = true,
+                _ => paths.push(item.to_string()),
             }
         }
-        if path.is_empty() {
-            eprintln!("Please provide a file path or data to hash.");
-            return Ok(String::new());
+
+        if stdin_paths {
+            if stdin || !paths.is_empty() {
+                return Err(Box::new(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "Error: --stdin-paths can't be combined with --stdin or file paths",
+                )));
+            }
+            for line in io::stdin().lock().lines() {
+                paths.push(line?.trim_end_matches('\r').to_string());
+            }
         }
-        let content = helpers::read_file_content(path)?;
-        let object_hash;
-        if write {
-            let file_len = helpers::get_file_length(path)?;
-            return HashObjectCreator::write_object_file(content, obj_type, file_len, path_handler);
-        } else {
-            object_hash = helpers::generate_sha1_string(content.as_str());
+
+        let mut contents: Vec<Vec<u8>> = Vec::new();
+        if stdin {
+            let mut content = Vec::new();
+            io::Read::read_to_end(&mut io::stdin(), &mut content)?;
+            contents.push(content);
+        }
+        for path in paths.iter().filter(|path| !path.is_empty()) {
+            contents.push(helpers::read_file_content_to_bytes(&path_handler.get_relative_path(path))?);
+        }
+        if contents.is_empty() {
+            return Err(Box::new(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Please provide a file path or data to hash.",
+            )));
+        }
+
+        let mut hashes = Vec::new();
+        for content in contents {
+            let object_hash = HashObject::hash_object(&content, &obj_type, write, literally, path_handler)?;
             println!("{}", object_hash);
+            hashes.push(object_hash);
         }
-        Ok(object_hash)
+        Ok(hashes.join("\n"))
     }
 }
 
 pub struct Commit {
     stg_area: StagingArea,
+    signer: Option<Box<dyn Signer>>,
 }
 
 impl Default for Commit {
@@ -530,16 +1077,26 @@ impl Commit {
     pub fn new() -> Self {
         Commit {
             stg_area: StagingArea::new(),
+            signer: None,
         }
     }
 
+    /// Returns a `Commit` that signs with the given signer instead of the configured one
+    pub fn with_signer(signer: Box<dyn Signer>) -> Self {
+        Commit {
+            stg_area: StagingArea::new(),
+            signer: Some(signer),
+        }
+    }
 }
 
 impl Command for Commit {
     /// Executes the `commit` command, creating a new commit for the changes in the staging area.
     /// To achieve this, it creates a "tree" which is the index file turned into a tree object.
     /// Then it creates a commit file, which contains the tree object hash, the commit's parent
-    /// commits and the given message with the message flag.
+    /// commits and the given message with the message flag. With the sign flag, or `commit.gpgSign` unless
+    /// `--no-gpg-sign` is given, the commit is signed with the configured key or the one given as
+    /// `--gpg-sign=<key>`.
     fn execute(&self, args: Option<Vec<&str>>, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
         if helpers::get_file_length(&path_handler.get_relative_path(INDEX_FILE))? == 0 {
             return Err(Box::new(io::Error::new(
@@ -550,27 +1107,60 @@ impl Command for Commit {
 
         let mut message: Option<&str> = None;
         let mut message_flag = false;
+        let mut sign_flag = None;
+        let mut signing_key = None;
         let arg_slice = args.unwrap_or_default();
 
         for arg in arg_slice {
             match arg {
                 MESSAGE_FLAG => message_flag = true,
-                _ => {
-                    if message_flag {
-                        message = Some(arg)
+                SIGN_COMMIT_FLAG | GPG_SIGN_LONG_FLAG => sign_flag = Some(true),
+                NO_GPG_SIGN_LONG_FLAG => sign_flag = Some(false),
+                _ => match arg.strip_prefix(GPG_SIGN_LONG_FLAG).and_then(|key| key.strip_prefix('=')) {
+                    Some(key) => {
+                        sign_flag = Some(true);
+                        signing_key = Some(key);
                     }
-                }
+                    None => {
+                        if message_flag {
+                            message = Some(arg)
+                        }
+                    }
+                },
             }
         }
         message = if message_flag { message } else { None };
         let head_commit = Head::get_head_commit(path_handler)?;
+        // the branch must still point to the parent when it's moved, or not exist yet for a root commit
+        let expected_old_hash = if head_commit.is_empty() { ZERO_HASH.to_string() } else { head_commit.clone() };
         let mut parent = Vec::new();
         if !head_commit.is_empty() {
             parent.push(head_commit)
         }
-        let commit_object_hash = HashObjectCreator::create_commit_object(message, parent, path_handler)?;
-
-        let _ = helpers::update_branch_hash(&Head::get_current_branch_name(path_handler)?, &commit_object_hash, path_handler);
+        let reflog_message = if parent.is_empty() {
+            format!("commit (initial): {}", message.unwrap_or_default())
+        } else {
+            format!("commit: {}", message.unwrap_or_default())
+        };
+        let sign = signing::is_signing_enabled(sign_flag, COMMIT_SIGN_CONFIG, path_handler)?;
+        let configured_signer;
+        let signer: Option<&dyn Signer> = match &self.signer {
+            _ if !sign => None,
+            Some(signer) => Some(signer.as_ref()),
+            None => {
+                configured_signer = signing::get_signer(signing_key, path_handler)?;
+                Some(configured_signer.as_ref())
+            }
+        };
+        let commit_object_hash = HashObjectCreator::create_commit_object_with_signer(message, parent, signer, path_handler)?;
+
+        helpers::update_branch_hash(
+            &Head::get_current_branch_name(path_handler)?,
+            &commit_object_hash,
+            Some(&expected_old_hash),
+            &reflog_message,
+            path_handler,
+        )?;
 
         self.stg_area.unstage_index_file(path_handler)?;
         Ok(String::new())
@@ -593,23 +1183,358 @@ impl Rm {
             stg_area: StagingArea::new(),
         }
     }
-}
 
-impl Command for Rm {
-    /// Receives a file path and removes it from the staging area.
-    fn execute(&self, args: Option<Vec<&str>>, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
-        match args {
-            Some(args) => {
-                self.stg_area.remove_file(args[0], path_handler)?;
+    /// Returns the tracked paths matched by the pathspecs. A pathspec that only matches the files inside a
+    /// directory needs `recursive`, and one that doesn't match any tracked file is an error.
+    fn find_paths(pathspecs: &[String], recursive: bool, path_handler: &PathHandler) -> Result<Vec<String>, Box<dyn Error>> {
+        let index_entries = helpers::read_index_entries(path_handler)?;
+        let tracked_paths: Vec<&String> = index_entries
+            .iter()
+            .filter(|(_path, _hash, state, _mode)| *state != IndexFileEntryState::Deleted.to_string())
+            .map(|(path, _hash, _state, _mode)| path)
+            .collect();
+
+        let mut paths = Vec::new();
+        for pathspec in pathspecs {
+            let matched: Vec<&String> = tracked_paths
+                .iter()
+                .copied()
+                .filter(|path| helpers::matches_pathspec(pathspec, path))
+                .collect();
+            if matched.is_empty() {
+                return Err(Box::new(io::Error::new(
+                    io::ErrorKind::Other,
+                    format!("fatal: pathspec '{}' did not match any files", pathspec),
+                )));
             }
-            None => {
+            if !recursive && !matched.contains(&pathspec) {
                 return Err(Box::new(io::Error::new(
                     io::ErrorKind::Other,
-                    "Did not receive a file path to remove",
-                )))
+                    format!("fatal: not removing '{}' recursively without -r", pathspec),
+                )));
             }
-        }
-        Ok(String::new())
+            for path in matched {
+                if !paths.contains(path) {
+                    paths.push(path.clone());
+                }
+            }
+        }
+        Ok(paths)
+    }
+
+    /// Refuses to remove files whose changes would be lost: files staged with content different from both
+    /// HEAD and the working tree, and unless `cached` is set, files with staged changes or local modifications.
+    fn check_uncommitted_changes(paths: &[String], cached: bool, path_handler: &PathHandler) -> Result<(), Box<dyn Error>> {
+        let last_commit_hash = Head::get_head_commit(path_handler)?;
+        let head_files = if last_commit_hash.is_empty() {
+            HashMap::new()
+        } else {
+            helpers::get_tree_files(&helpers::get_commit_tree(&last_commit_hash, path_handler)?, "", path_handler)?
+        };
+        let index_entries = helpers::read_index_entries(path_handler)?;
+
+        let mut staged_and_modified = Vec::new();
+        let mut staged = Vec::new();
+        let mut modified = Vec::new();
+        for path in paths {
+            let index_hash = index_entries
+                .iter()
+                .find(|(index_path, _hash, _state, _mode)| index_path == path)
+                .map(|(_path, hash, _state, _mode)| hash.clone())
+                .unwrap_or_default();
+            let differs_from_head = head_files.get(path).map(|(_mode, hash)| hash) != Some(&index_hash);
+            let differs_from_working_tree = fs::symlink_metadata(path_handler.get_relative_path(path)).is_ok()
+                && helpers::hash_working_file(path, path_handler)? != index_hash;
+
+            if differs_from_head && differs_from_working_tree {
+                staged_and_modified.push(path.clone());
+            } else if !cached && differs_from_head {
+                staged.push(path.clone());
+            } else if !cached && differs_from_working_tree {
+                modified.push(path.clone());
+            }
+        }
+
+        let mut errors = Vec::new();
+        if !staged_and_modified.is_empty() {
+            errors.push(format!(
+                "error: the following files have staged content different from both the file and the HEAD:\n{}\n(use -f to force removal)",
+                staged_and_modified.join("\n")
+            ));
+        }
+        if !staged.is_empty() {
+            errors.push(format!(
+                "error: the following files have changes staged in the index:\n{}\n(use --cached to keep the file, or -f to force removal)",
+                staged.join("\n")
+            ));
+        }
+        if !modified.is_empty() {
+            errors.push(format!(
+                "error: the following files have local modifications:\n{}\n(use --cached to keep the file, or -f to force removal)",
+                modified.join("\n")
+            ));
+        }
+        if !errors.is_empty() {
+            return Err(Box::new(io::Error::new(io::ErrorKind::Other, errors.join("\n"))));
+        }
+        Ok(())
+    }
+}
+
+impl Command for Rm {
+    /// Removes the files matched by the paths from the working tree and the index.
+    /// Supports --cached to only remove them from the index, -r to remove directories recursively,
+    /// -f to skip the check for uncommitted changes, -n/--dry-run and -q/--quiet.
+    /// Returns one "rm 'path'" line per removed file.
+    fn execute(&self, args: Option<Vec<&str>>, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
+        let mut cached = false;
+        let mut recursive = false;
+        let mut force = false;
+        let mut dry_run = false;
+        let mut quiet = false;
+        let mut pathspecs = Vec::new();
+        let mut only_paths = false;
+        for arg in args.unwrap_or_default() {
+            match arg {
+                _ if only_paths => pathspecs.push(helpers::normalize_repository_path(arg, path_handler)),
+                CACHED_LONG_FLAG => cached = true,
+                RECURSE_FLAG => recursive = true,
+                FORCE_FLAG | FORCE_LONG_FLAG => force = true,
+                DRY_RUN_FLAG | DRY_RUN_LONG_FLAG => dry_run = true,
+                QUIET_FLAG | QUIET_LONG_FLAG => quiet = true,
+                PATHSPEC_SEPARATOR => only_paths = true,
+                _ => pathspecs.push(helpers::normalize_repository_path(arg, path_handler)),
+            }
+        }
+        if pathspecs.is_empty() {
+            return Err(Box::new(io::Error::new(
+                io::ErrorKind::Other,
+                "Did not receive a file path to remove",
+            )));
+        }
+
+        let paths = Rm::find_paths(&pathspecs, recursive, path_handler)?;
+        if !force {
+            Rm::check_uncommitted_changes(&paths, cached, path_handler)?;
+        }
+
+        let mut lines = Vec::new();
+        for path in paths {
+            if !dry_run {
+                self.stg_area.remove_file(&path, path_handler)?;
+                if !cached {
+                    WorkingDirectory::remove_file_and_empty_parent_directories(Path::new(&path_handler.get_relative_path(&path)))?;
+                }
+            }
+            lines.push(format!("rm '{}'", path));
+        }
+        if !quiet {
+            for line in &lines {
+                println!("{}", line);
+            }
+        }
+        Ok(lines.join("\n"))
+    }
+}
+
+pub struct Mv;
+
+impl Default for Mv {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Mv {
+    pub fn new() -> Self {
+        Mv {}
+    }
+
+    /// Returns the tracked files that are moved when the source is moved to the target, as (source, target)
+    /// pairs, or the reason why it can't be moved. Existing targets are only overwritten if `force` is set.
+    fn plan_move(
+        source: &str,
+        target: &str,
+        force: bool,
+        index_entries: &[helpers::IndexEntry],
+        path_handler: &PathHandler
+    ) -> Result<Vec<(String, String)>, String> {
+        let is_tracked = |path: &str| {
+            index_entries
+                .iter()
+                .any(|(index_path, _hash, state, _mode)| index_path == path && *state != IndexFileEntryState::Deleted.to_string())
+        };
+        let source_metadata = fs::symlink_metadata(path_handler.get_relative_path(source)).map_err(|_| "bad source".to_string())?;
+        let target_path = path_handler.get_relative_path(target);
+        let target_parent = Path::new(target).parent().map(|parent| parent.to_string_lossy().to_string()).unwrap_or_default();
+        if !target_parent.is_empty() && !Path::new(&path_handler.get_relative_path(&target_parent)).is_dir() {
+            return Err("destination directory does not exist".to_string());
+        }
+
+        if source_metadata.is_dir() {
+            if source.is_empty() || target.starts_with(&format!("{}/", source)) || source == target {
+                return Err("can not move directory into itself".to_string());
+            }
+            if fs::symlink_metadata(&target_path).is_ok() {
+                return Err("destination already exists".to_string());
+            }
+            let files: Vec<(String, String)> = index_entries
+                .iter()
+                .filter(|(path, _hash, state, _mode)| {
+                    *state != IndexFileEntryState::Deleted.to_string() && path.starts_with(&format!("{}/", source))
+                })
+                .map(|(path, _hash, _state, _mode)| (path.clone(), format!("{}{}", target, &path[source.len()..])))
+                .collect();
+            if files.is_empty() {
+                return Err("source directory is empty".to_string());
+            }
+            return Ok(files);
+        }
+
+        if !is_tracked(source) {
+            return Err("not under version control".to_string());
+        }
+        if source != target {
+            if let Ok(target_metadata) = fs::symlink_metadata(&target_path) {
+                if !force {
+                    return Err("destination exists".to_string());
+                }
+                if target_metadata.is_dir() {
+                    return Err("cannot overwrite a directory".to_string());
+                }
+            }
+        }
+        Ok(vec![(source.to_string(), target.to_string())])
+    }
+
+    /// Moves back the files and directories of the given moves, from the last to the first
+    fn undo_moves(moves: &[(String, String)], path_handler: &PathHandler) {
+        for (source, target) in moves.iter().rev() {
+            let _ = fs::rename(path_handler.get_relative_path(target), path_handler.get_relative_path(source));
+        }
+    }
+}
+
+impl Command for Mv {
+    /// Moves or renames files and directories, in the working tree and the index.
+    /// Receives a source and a destination, or several sources and an existing destination directory.
+    /// Supports -f/--force to overwrite existing files, -k to skip moves that would fail, -n/--dry-run
+    /// and -v/--verbose. Every move is checked before anything is touched, with the index locked. The new index
+    /// is written to the lock file and only replaces the index once every file was moved; if a move fails, the
+    /// files already moved are put back and the index is left as it was.
+    fn execute(&self, args: Option<Vec<&str>>, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
+        let mut force = false;
+        let mut skip_errors = false;
+        let mut dry_run = false;
+        let mut verbose = false;
+        let mut paths = Vec::new();
+        let mut only_paths = false;
+        for arg in args.unwrap_or_default() {
+            match arg {
+                _ if only_paths => paths.push(helpers::normalize_repository_path(arg, path_handler)),
+                FORCE_FLAG | FORCE_LONG_FLAG => force = true,
+                SKIP_ERRORS_FLAG => skip_errors = true,
+                DRY_RUN_FLAG | DRY_RUN_LONG_FLAG => dry_run = true,
+                VERBOSE_FLAG | VERBOSE_LONG_FLAG => verbose = true,
+                PATHSPEC_SEPARATOR => only_paths = true,
+                _ => paths.push(helpers::normalize_repository_path(arg, path_handler)),
+            }
+        }
+        let destination = match paths.pop() {
+            Some(destination) if !paths.is_empty() => destination,
+            _ => {
+                return Err(Box::new(io::Error::new(
+                    io::ErrorKind::Other,
+                    "usage: mv [<options>] <source>... <destination>",
+                )))
+            }
+        };
+        let destination_is_directory = Path::new(&path_handler.get_relative_path(&destination)).is_dir();
+        if paths.len() > 1 && !destination_is_directory {
+            return Err(Box::new(io::Error::new(
+                io::ErrorKind::Other,
+                format!("fatal: destination '{}' is not a directory", destination),
+            )));
+        }
+
+        let index_lock = helpers::IndexLock::acquire(path_handler)?;
+        let mut index_entries = helpers::read_index_entries(path_handler)?;
+        let mut moves = Vec::new();
+        let mut file_moves: Vec<(String, String)> = Vec::new();
+        for source in &paths {
+            let target = match source.rsplit('/').next() {
+                Some(name) if destination_is_directory => {
+                    if destination.is_empty() { name.to_string() } else { format!("{}/{}", destination, name) }
+                }
+                _ => destination.clone(),
+            };
+            let planned = Mv::plan_move(source, &target, force, &index_entries, path_handler).and_then(|files| {
+                let planned_targets: HashSet<&String> = moves
+                    .iter()
+                    .chain(file_moves.iter())
+                    .map(|(_source, planned_target): &(String, String)| planned_target)
+                    .collect();
+                if planned_targets.contains(&target) || files.iter().any(|(_source, file_target)| planned_targets.contains(file_target)) {
+                    return Err("multiple sources for the same target".to_string());
+                }
+                let is_nested = |path: &str, directory: &str| path.starts_with(&format!("{}/", directory));
+                if moves.iter().any(|(planned_source, _target)| is_nested(source, planned_source) || is_nested(planned_source, source)) {
+                    return Err("source is inside a moved directory".to_string());
+                }
+                Ok(files)
+            });
+            match planned {
+                Ok(files) => {
+                    file_moves.extend(files);
+                    moves.push((source.clone(), target));
+                }
+                Err(_) if skip_errors => {}
+                Err(reason) => {
+                    return Err(Box::new(io::Error::new(
+                        io::ErrorKind::Other,
+                        format!("fatal: {}, source={}, destination={}", reason, source, target),
+                    )))
+                }
+            }
+        }
+
+        let lines: Vec<String> = moves
+            .iter()
+            .map(|(source, target)| format!("Renaming {} to {}", source, target))
+            .collect();
+        if dry_run || verbose {
+            for line in &lines {
+                println!("{}", line);
+            }
+        }
+        if dry_run {
+            return Ok(lines.join("\n"));
+        }
+
+        for (source, target) in file_moves {
+            let (hash, mode) = match index_entries.iter_mut().find(|(path, _hash, _state, _mode)| *path == source) {
+                Some(entry) => {
+                    entry.2 = IndexFileEntryState::Deleted.to_string();
+                    (entry.1.clone(), entry.3.clone())
+                }
+                None => continue,
+            };
+            index_entries.retain(|(path, _hash, _state, _mode)| *path != target);
+            index_entries.push((target, hash, IndexFileEntryState::Staged.to_string(), mode));
+        }
+        index_lock.write(&index_entries)?;
+
+        for (moved, (source, target)) in moves.iter().enumerate() {
+            if let Err(error) = fs::rename(path_handler.get_relative_path(source), path_handler.get_relative_path(target)) {
+                Mv::undo_moves(&moves[..moved], path_handler);
+                return Err(Box::new(error));
+            }
+        }
+        if let Err(error) = index_lock.commit() {
+            Mv::undo_moves(&moves, path_handler);
+            return Err(error);
+        }
+        Ok(lines.join("\n"))
     }
 }
 
@@ -629,30 +1554,163 @@ impl Add {
             stg_area: StagingArea::new(),
         }
     }
+
+    /// Returns the paths that have to be added or removed from the index so it matches the working directory
+    /// for the paths matched by the pathspecs. New files are only included if `include_new` is set.
+    /// Ignored files are skipped unless `force` is set or they're already tracked, and naming an ignored
+    /// file explicitly is an error.
+    fn find_changes(
+        &self,
+        pathspecs: &[String],
+        include_new: bool,
+        force: bool,
+        path_handler: &PathHandler
+    ) -> Result<(Vec<String>, Vec<String>), Box<dyn Error>> {
+        let index_entries = helpers::read_index_entries(path_handler)?;
+        let ignore_rules = IgnoreRules::new(path_handler)?;
+        let is_ignored = |path: &str, is_directory: bool| !force && ignore_rules.is_ignored(path, is_directory);
+        let working_files = helpers::get_working_tree_files(path_handler, &|directory| {
+            is_ignored(directory, true) || !pathspecs.iter().any(|pathspec| Add::may_match_inside(pathspec, directory))
+        })?;
+
+        let mut ignored_paths = Vec::new();
+        let mut paths_to_add = Vec::new();
+        for file_path in &working_files {
+            if !helpers::matches_pathspecs(pathspecs, file_path) {
+                continue;
+            }
+            let index_entry = index_entries.iter().find(|(path, _hash, _state, _mode)| path == file_path);
+            let ignored = index_entry.is_none() && is_ignored(file_path, false);
+            match index_entry {
+                Some((_path, hash, state, mode))
+                    if *state == IndexFileEntryState::Deleted.to_string()
+                        || *hash != helpers::hash_working_file(file_path, path_handler)?
+                        || *mode != helpers::get_file_mode(file_path, path_handler)? =>
+                {
+                    paths_to_add.push(file_path.clone())
+                }
+                Some(_) => {}
+                // ignored files are only reported when they are named explicitly
+                None if ignored && pathspecs.contains(file_path) => ignored_paths.push(file_path.clone()),
+                None if ignored => {}
+                None if include_new => paths_to_add.push(file_path.clone()),
+                None => {}
+            }
+        }
+        if !ignored_paths.is_empty() {
+            return Err(Box::new(io::Error::new(
+                io::ErrorKind::Other,
+                format!(
+                    "Error: The following paths are ignored by one of your .gitignore files:\n{}\nUse -f if you really want to add them.",
+                    ignored_paths.join("\n")
+                ),
+            )));
+        }
+
+        let paths_to_remove = index_entries
+            .iter()
+            .filter(|(path, _hash, state, _mode)| {
+                *state != IndexFileEntryState::Deleted.to_string()
+                    && helpers::matches_pathspecs(pathspecs, path)
+                    && fs::symlink_metadata(path_handler.get_relative_path(path)).is_err()
+            })
+            .map(|(path, _hash, _state, _mode)| path.clone())
+            .collect();
+
+        for pathspec in pathspecs {
+            let matches_something = working_files.iter().chain(index_entries.iter().map(|(path, _, _, _)| path))
+                .any(|path| helpers::matches_pathspecs(std::slice::from_ref(pathspec), path));
+            if !pathspec.is_empty() && !matches_something && !pathspec.starts_with(':') {
+                return Err(Box::new(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("Error: pathspec '{}' did not match any files", pathspec),
+                )));
+            }
+        }
+
+        Ok((paths_to_add, paths_to_remove))
+    }
+
+    /// Returns true if a path inside the directory could be matched by the pathspec, so the directory
+    /// has to be walked
+    fn may_match_inside(pathspec: &str, directory: &str) -> bool {
+        if pathspec.starts_with(':') || pathspec.contains(['*', '?', '[']) {
+            return true;
+        }
+        helpers::matches_pathspec(pathspec, directory) || helpers::matches_pathspec(directory, pathspec)
+    }
 }
 
 impl Command for Add {
-    /// Receives a file path and adds it to the staging area.
+    /// Adds the files matched by the given pathspecs to the staging area: files, directories, globs such as
+    /// `*.rs` and exclusions such as `:!target`. New and modified files are staged, and deleted files are
+    /// removed from the index. With -A the whole working directory is used if there are no pathspecs,
+    /// with -u only tracked files are updated, with -n nothing is changed and the changes are only shown,
+    /// and with -f ignored files are added too.
     fn execute(&self, args: Option<Vec<&str>>, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
-        match args {
-            Some(args) => {
-                if (CheckIgnore::new().execute(Some(vec![args[0]]), path_handler)?).is_empty() {
-                    self.stg_area.add_file(args[0], path_handler)?;
-                } else {
-                    return Err(Box::new(io::Error::new(
-                        io::ErrorKind::Other,
-                        "Error: File is included in '.gitignore'",
-                    )));
+        let mut all_flag = false;
+        let mut update_flag = false;
+        let mut dry_run_flag = false;
+        let mut force_flag = false;
+        let mut verbose_flag = false;
+        let mut pathspecs = Vec::new();
+
+        for arg in args.unwrap_or_default() {
+            match arg {
+                ADD_ALL_FLAG | ADD_ALL_LONG_FLAG => all_flag = true,
+                UPDATE_FLAG | UPDATE_LONG_FLAG => update_flag = true,
+                DRY_RUN_FLAG | DRY_RUN_LONG_FLAG => dry_run_flag = true,
+                FORCE_FLAG | FORCE_LONG_FLAG => force_flag = true,
+                VERBOSE_FLAG | VERBOSE_LONG_FLAG => verbose_flag = true,
+                PATHSPEC_SEPARATOR => {}
+                _ => {
+                    let pathspec = match arg.find(|c| c != ':' && c != '!' && c != '^') {
+                        Some(start) if arg.starts_with(':') => {
+                            format!("{}{}", &arg[..start], helpers::normalize_repository_path(&arg[start..], path_handler))
+                        }
+                        _ => helpers::normalize_repository_path(arg, path_handler),
+                    };
+                    pathspecs.push(pathspec);
                 }
             }
-            None => {
+        }
+
+        if pathspecs.is_empty() {
+            if !all_flag && !update_flag {
                 return Err(Box::new(io::Error::new(
                     io::ErrorKind::Other,
-                    "Did not receive a file path to add",
-                )))
+                    "Nothing specified, nothing added.",
+                )));
             }
+            pathspecs.push(String::new());
         }
-        Ok(String::new())
+
+        let (paths_to_add, paths_to_remove) = self.find_changes(&pathspecs, !update_flag, force_flag, path_handler)?;
+
+        let mut output = Vec::new();
+        for path in paths_to_add {
+            if !dry_run_flag {
+                self.stg_area.add_file(&path, path_handler)?;
+            }
+            output.push(format!("add '{}'", path));
+        }
+        for path in paths_to_remove {
+            if !dry_run_flag {
+                let (_path, hash, _state, mode) = helpers::read_index_entries(path_handler)?
+                    .into_iter()
+                    .find(|(index_path, _, _, _)| *index_path == path)
+                    .unwrap_or_default();
+                helpers::update_file_with_hash(&hash, &IndexFileEntryState::Deleted.to_string(), &mode, &path, path_handler)?;
+            }
+            output.push(format!("remove '{}'", path));
+        }
+
+        if dry_run_flag || verbose_flag {
+            for line in &output {
+                println!("{}", line);
+            }
+        }
+        Ok(output.join("\n"))
     }
 }
 
@@ -676,37 +1734,76 @@ impl Command for Status {
     /// status of files in the working directory, indicating whether they are
     /// modified, staged, or unstaged.
     fn execute(&self, _args: Option<Vec<&str>>, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
-        println!("On branch {}", Head::get_current_branch_name(path_handler)?);
-        
         let last_commit_hash: String = Head::get_head_commit(path_handler)?;
-        let mut tree_content: Vec<(String, String, String)> = Vec::new();
+        let mut upstream_status = None;
+        if Head::is_detached(path_handler)? {
+            println!("HEAD detached at {}", &last_commit_hash[..last_commit_hash.len().min(ABBREVIATED_HASH_LENGTH)]);
+        } else {
+            let branch_name = Head::get_current_branch_name(path_handler)?;
+            println!("On branch {}", branch_name);
+            upstream_status = helpers::describe_upstream_status(&branch_name, path_handler)?;
+            if let Some(upstream_status) = &upstream_status {
+                println!("{}", upstream_status);
+            }
+        }
+        let mut head_files = HashMap::new();
         if !last_commit_hash.is_empty() {
             let tree_hash = helpers::get_commit_tree(&last_commit_hash, path_handler)?;
-            tree_content = helpers::read_tree_content(&tree_hash, path_handler)?;
+            head_files = helpers::get_tree_files(&tree_hash, "", path_handler)?;
         }
 
+        // staged deletions and new files are paired up as renames
+        let head_hashes: HashMap<String, String> = head_files
+            .iter()
+            .map(|(path, (_mode, hash))| (path.clone(), hash.clone()))
+            .collect();
+        let index_hashes: HashMap<String, String> = helpers::read_index_entries(path_handler)?
+            .into_iter()
+            .filter(|(_path, _hash, state, _mode)| *state != IndexFileEntryState::Deleted.to_string())
+            .map(|(path, hash, _state, _mode)| (path, hash))
+            .collect();
+        let renames: HashMap<String, String> = helpers::find_file_changes(&head_hashes, &index_hashes, Some(DEFAULT_RENAME_THRESHOLD), false, path_handler)
+            .into_iter()
+            .filter_map(|change| match change {
+                helpers::FileChange::Renamed(old_path, new_path, _) => Some((new_path, old_path)),
+                _ => None,
+            })
+            .collect();
+
         let index_file_content =
             helpers::read_file_content(&path_handler.get_relative_path(INDEX_FILE))?;
         let index_objects: Vec<String> =
             index_file_content.lines().map(|s| s.to_string()).collect();
         let mut line_result = String::new();
-        let mut line = String::new();
-        for pos in 0..(index_objects.len()) {
-            let index_file_line: Vec<&str> = index_objects[pos].split(';').collect();
-            if pos < tree_content.len() {
-                let (_, _, hash_string) = tree_content[pos].clone();
-                let current_object_content = helpers::read_file_content(index_file_line[0])?;
-                let current_object_hash = HashObjectCreator::generate_object_hash(
-                    ObjectType::Blob,
-                    get_file_length(index_file_line[0])?,
-                    &current_object_content,
-                );
+        for index_object in index_objects {
+            let mut line = String::new();
+            let index_file_line: Vec<&str> = index_object.split(';').collect();
+            if index_file_line.len() < 3 {
+                continue;
+            }
+            let file_path = path_handler.get_relative_path(index_file_line[0]);
+            if index_file_line[2] == IndexFileEntryState::Deleted.to_string() {
+                if head_files.contains_key(index_file_line[0]) && !renames.values().any(|old_path| old_path == index_file_line[0]) {
+                    line = format!("deleted: {} (Staged)", index_file_line[0]);
+                }
+            } else if fs::symlink_metadata(file_path).is_err() {
+                line = format!("deleted: {} (Unstaged)", index_file_line[0]);
+            } else if let Some((file_mode, hash_string)) = head_files.get(index_file_line[0]) {
+                let current_object_hash = helpers::hash_working_file(index_file_line[0], path_handler)?;
+                let current_file_mode = helpers::get_file_mode(index_file_line[0], path_handler)?;
+                let index_file_mode = helpers::get_index_line_mode(&index_file_line, path_handler);
 
                 if hash_string != index_file_line[1] && index_file_line[2] == "2" {
                     line = format!("modified: {} (Staged)", index_file_line[0]);
-                } else if current_object_hash != hash_string && index_file_line[2] == "0" {
+                } else if current_object_hash != *hash_string && index_file_line[2] == "0" {
                     line = format!("modified: {} (Unstaged)", index_file_line[0]);
+                } else if index_file_mode != *file_mode {
+                    line = format!("mode changed: {} ({} -> {}) (Staged)", index_file_line[0], file_mode, index_file_mode);
+                } else if current_file_mode != index_file_mode {
+                    line = format!("mode changed: {} ({} -> {}) (Unstaged)", index_file_line[0], index_file_mode, current_file_mode);
                 }
+            } else if let Some(old_path) = renames.get(index_file_line[0]) {
+                line = format!("renamed: {} -> {} (Staged)", old_path, index_file_line[0]);
             } else {
                 line = format!("new file: {} (Staged)", index_file_line[0]);
             }
@@ -717,12 +1814,31 @@ impl Command for Status {
                 line_result.push('\n');
             }
         }
+        let ignore_rules = IgnoreRules::new(path_handler)?;
+        let index_entries = helpers::read_index_entries(path_handler)?;
+        let untracked_files = helpers::get_working_tree_files(path_handler, &|directory| ignore_rules.is_ignored(directory, true))?;
+        for file_path in untracked_files {
+            let tracked = index_entries
+                .iter()
+                .any(|(path, _hash, state, _mode)| *path == file_path && *state != IndexFileEntryState::Deleted.to_string());
+            if tracked || ignore_rules.is_ignored(&file_path, false) {
+                continue;
+            }
+            let line = format!("untracked: {}", file_path);
+            println!("{}", line);
+            line_result.push_str(&line);
+            line_result.push('\n');
+        }
+
         if line_result.is_empty() {
-            line = "nothing to commit, working tree clean".to_string();
+            let line = "nothing to commit, working tree clean".to_string();
             line_result.push_str(&line);
             line_result.push('\n');
             println!("{}", line);
         }
+        if let Some(upstream_status) = upstream_status {
+            line_result.insert_str(0, &format!("{}\n", upstream_status));
+        }
         Ok(line_result)
     }
 }
@@ -740,191 +1856,366 @@ impl Remote {
         Remote {}
     }
 
-    /// Adds a new remote repository configuration to the Git configuration file.
-    fn add_new_remote(&self, remote_name: String, url: String, path_handler: &PathHandler) -> Result<(), Box<dyn Error>> {
-        let config_content =
-            helpers::read_file_content(&path_handler.get_relative_path(CONFIG_FILE))?;
+    /// Fails if no remote with the given name is configured
+    fn check_remote_exists(remote_name: &str, path_handler: &PathHandler) -> Result<(), Box<dyn Error>> {
+        if helpers::get_remote_url(remote_name, path_handler).is_err() {
+            return Err(Box::new(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("fatal: No such remote: '{}'", remote_name),
+            )));
+        }
+        Ok(())
+    }
+
+    /// Fails if a remote can't be named like that, because its remote-tracking refs couldn't be
+    fn check_remote_name(remote_name: &str) -> Result<(), Box<dyn Error>> {
+        if !refs::check_ref_format(&format!("refs/remotes/{}", remote_name)) {
+            return Err(Box::new(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("fatal: '{}' is not a valid remote name", remote_name),
+            )));
+        }
+        Ok(())
+    }
 
-        let section_header = format!("[remote '{}']", remote_name);
-        let new_config_content = format!("{}{}\nurl = {}\n", config_content, section_header, url);
+    /// Asks a remote for the refs it has, as (hash, ref name)
+    fn query_remote_refs(remote_name: &str, path_handler: &PathHandler) -> Result<Vec<(String, String)>, Box<dyn Error>> {
+        let remote_url = helpers::get_remote_url(remote_name, path_handler)?;
+        ClientProtocol::new().list_remote_refs(remote_url)
+    }
 
-        if config_content.contains(&section_header) {
+    /// Adds a new remote repository configuration to the Git configuration file, along with the refspec its
+    /// branches are fetched with.
+    fn add_new_remote(&self, remote_name: String, url: String, path_handler: &PathHandler) -> Result<(), Box<dyn Error>> {
+        let url_key = format!("remote.{}.url", remote_name);
+        if Config::load_scope(ConfigScope::Local, path_handler)?.get(&url_key).is_some() {
             //en git permite agregar mas de un remote con mismo nombre si su config o url son distintos, me parece que complejiza mucho y por ahora mejor no poder agregar dos de mismo nombre
             return Err(Box::new(io::Error::new(
                 io::ErrorKind::Other,
                 "Remote already exists in the configuration.",
             )));
         }
+        Self::check_remote_name(&remote_name)?;
 
-        let mut config_file = fs::File::create(path_handler.get_relative_path(CONFIG_FILE))?;
-        config_file.write_all(new_config_content.as_bytes())?;
+        let mut config_file = ConfigFile::open(ConfigScope::Local, path_handler)?;
+        config_file.set(&url_key, &url)?;
+        config_file.add(&format!("remote.{}.fetch", remote_name), &Refspec::default_fetch(&remote_name).to_string())?;
+        config_file.save()?;
 
         let remote_dir_path = format!("{}/{}", R_REMOTES, remote_name);
-        fs::create_dir(path_handler.get_relative_path(&remote_dir_path))?;
+        fs::create_dir_all(path_handler.get_relative_path(&remote_dir_path))?;
 
         println!("Added new remote: {}", remote_name);
 
         Ok(())
     }
 
-    /// Removes a specified remote repository configuration from the Git configuration file.
+    /// Removes a specified remote repository configuration from the Git configuration file, along with its
+    /// remote-tracking branches and the upstream configuration of the branches tracking it.
     fn remove_remote(&self, remote_name: String, path_handler: &PathHandler) -> Result<(), Box<dyn Error>> {
-        let config_content =
-            helpers::read_file_content(&path_handler.get_relative_path(CONFIG_FILE))?;
-
-        let remote_header = format!("[remote '{}']", remote_name);
-        let mut new_config_content = String::new();
-        let mut is_inside_remote_section = false;
-
-        for line in config_content.lines() {
-            if line == remote_header {
-                is_inside_remote_section = true;
-            } else if line.starts_with('[') {
-                is_inside_remote_section = false;
-            }
-            if !is_inside_remote_section {
-                new_config_content.push_str(line);
-                new_config_content.push('\n');
+        Self::check_remote_exists(&remote_name, path_handler)?;
+        let config = Config::load_scope(ConfigScope::Local, path_handler)?;
+        let mut config_file = ConfigFile::open(ConfigScope::Local, path_handler)?;
+        config_file.remove_section("remote", Some(&remote_name))?;
+        for branch_name in config.get_subsections("branch") {
+            for key in ["remote", "merge"] {
+                let name = format!("branch.{}.{}", branch_name, key);
+                let tracks_remote = config.get(&format!("branch.{}.remote", branch_name)) == Some(remote_name.clone());
+                if tracks_remote && config.get(&name).is_some() {
+                    config_file.unset(&name, true)?;
+                }
             }
         }
+        config_file.save()?;
 
-        let mut config_file = fs::File::create(path_handler.get_relative_path(CONFIG_FILE))?;
-        config_file.write_all(new_config_content.as_bytes())?;
+        let mut transaction = RefTransaction::new();
+        for (ref_name, _hash) in refs::list_refs(&format!("refs/remotes/{}/", remote_name), path_handler)? {
+            transaction.delete(&ref_name, None);
+        }
+        transaction.commit(path_handler)?;
 
+        // whatever is left of the directory of the remote are empty folders, if it was ever created
         let remote_dir = format!("{}/{}", R_REMOTES, remote_name);
-        fs::remove_dir_all(path_handler.get_relative_path(&remote_dir))?;
+        let _ = fs::remove_dir_all(path_handler.get_relative_path(&remote_dir));
 
         Ok(())
     }
 
-    /// Lists and prints the names of remote repositories configured in the Git configuration.
-    fn list_remotes(&self) -> Result<(), Box<dyn Error>> {
-        let config_content = helpers::read_file_content(CONFIG_FILE)?;
-
-        for line in config_content.lines() {
-            if line.starts_with("[remote '") {
-                let remote_name = line.trim_start_matches("[remote '").trim_end_matches("']");
-                println!("{}", remote_name);
+    /// Renames a remote. Its remote-tracking branches move along with their reflogs, and so do the refspecs
+    /// storing into them and the branches tracking it.
+    fn rename_remote(&self, old_name: &str, new_name: &str, path_handler: &PathHandler) -> Result<(), Box<dyn Error>> {
+        Self::check_remote_exists(old_name, path_handler)?;
+        if helpers::get_remote_url(new_name, path_handler).is_ok() {
+            return Err(Box::new(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("error: remote {} already exists.", new_name),
+            )));
+        }
+        Self::check_remote_name(new_name)?;
+
+        let old_refs_prefix = format!("refs/remotes/{}/", old_name);
+        let new_refs_prefix = format!("refs/remotes/{}/", new_name);
+        let config = Config::load_scope(ConfigScope::Local, path_handler)?;
+        let mut config_file = ConfigFile::open(ConfigScope::Local, path_handler)?;
+        config_file.rename_section("remote", old_name, new_name)?;
+        let refspecs = config.get_all(&format!("remote.{}.fetch", old_name));
+        if !refspecs.is_empty() {
+            let fetch_key = format!("remote.{}.fetch", new_name);
+            config_file.unset(&fetch_key, true)?;
+            for refspec in refspecs {
+                config_file.add(&fetch_key, &refspec.replace(&old_refs_prefix, &new_refs_prefix))?;
+            }
+        }
+        for branch_name in config.get_subsections("branch") {
+            let remote_key = format!("branch.{}.remote", branch_name);
+            if config.get(&remote_key).as_deref() == Some(old_name) {
+                config_file.set(&remote_key, new_name)?;
             }
         }
 
+        // the config is only saved once the remote-tracking branches were moved
+        let mut transaction = RefTransaction::new();
+        for (ref_name, hash) in refs::list_refs(&old_refs_prefix, path_handler)? {
+            let new_ref_name = format!("{}{}", new_refs_prefix, &ref_name[old_refs_prefix.len()..]);
+            let message = format!("remote: renamed {} to {}", ref_name, new_ref_name);
+            transaction.rename(&ref_name, &new_ref_name, &hash, Some(&message));
+        }
+        transaction.commit(path_handler)?;
+        config_file.save()?;
+
+        let old_remote_dir = format!("{}/{}", R_REMOTES, old_name);
+        let _ = fs::remove_dir_all(path_handler.get_relative_path(&old_remote_dir));
         Ok(())
     }
-}
 
-impl Command for Remote {
-    /// Executes Command for Remote. When no flags are received, all remotes are listed. If the add flag is received
-    /// with a name and a new url, a remote is added to the config file. If a remove flag and a name is received,
-    /// the remote with said name will be removed from the config file.
-    fn execute(&self, args: Option<Vec<&str>>, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
-        if args.is_none() {
-            self.list_remotes()?;
-            return Ok(String::new());
+    /// Changes the url of a remote, or the url it's pushed to with `push`
+    fn set_url(&self, remote_name: &str, url: &str, push: bool, path_handler: &PathHandler) -> Result<(), Box<dyn Error>> {
+        Self::check_remote_exists(remote_name, path_handler)?;
+        let key = if push { "pushurl" } else { "url" };
+        let mut config_file = ConfigFile::open(ConfigScope::Local, path_handler)?;
+        config_file.set(&format!("remote.{}.{}", remote_name, key), url)?;
+        config_file.save()
+    }
+
+    /// Lists the names of remote repositories configured in the Git configuration. If verbose, their fetch and
+    /// push urls are listed too.
+    fn list_remotes(&self, verbose: bool, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
+        let config = Config::load(path_handler)?;
+        let mut remotes = String::new();
+        for remote_name in config.get_subsections("remote") {
+            if !verbose {
+                writeln!(remotes, "{}", remote_name)?;
+                continue;
+            }
+            let url = config.get(&format!("remote.{}.url", remote_name)).unwrap_or_default();
+            let push_url = config.get(&format!("remote.{}.pushurl", remote_name)).unwrap_or(url.clone());
+            writeln!(remotes, "{}\t{} (fetch)", remote_name, url)?;
+            writeln!(remotes, "{}\t{} (push)", remote_name, push_url)?;
         }
-        let mut add_flag = false;
-        let mut remove_flag = false;
-        let mut name = None;
-        let mut url = None;
-        let arg_slice = args.unwrap_or_default();
 
-        for arg in arg_slice {
-            match arg {
-                ADD_FLAG => add_flag = true,
-                REMOVE_FLAG => remove_flag = true,
-                _ => {
-                    if name.is_none() {
-                        name = Some(arg.to_string());
-                    } else if url.is_none() {
-                        url = Some(arg.to_string());
-                    }
+        Ok(remotes)
+    }
+
+    /// Describes a remote: its urls, the branch its HEAD points to, the state of its branches and the local branches
+    /// merging them on pull. The refs of the remote are given as (hash, ref name), or None if it wasn't queried.
+    fn show_remote(&self, remote_name: &str, remote_refs: Option<&[(String, String)]>, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
+        Self::check_remote_exists(remote_name, path_handler)?;
+        let mut description = format!(
+            "* remote {}\n  Fetch URL: {}\n  Push  URL: {}\n",
+            remote_name,
+            helpers::get_remote_url(remote_name, path_handler)?,
+            helpers::get_push_url(remote_name, path_handler)?
+        );
+
+        let mut branch_states: Vec<(String, String)> = Vec::new();
+        match remote_refs {
+            None => {
+                description.push_str("  HEAD branch: (not queried)\n");
+                for (branch_name, _hash) in helpers::get_remote_branches(remote_name, path_handler)? {
+                    branch_states.push((branch_name, String::new()));
+                }
+            }
+            Some(remote_refs) => {
+                // HEAD isn't symbolic in the advertisement, so its branch is the one at the same commit
+                let head_branch = remote_refs
+                    .iter()
+                    .find(|(_hash, ref_name)| ref_name == HEAD)
+                    .and_then(|(head_hash, _)| {
+                        remote_refs
+                            .iter()
+                            .find(|(hash, ref_name)| hash == head_hash && ref_name.starts_with("refs/heads/"))
+                    })
+                    .map(|(_hash, ref_name)| helpers::shorten_ref_name(ref_name))
+                    .unwrap_or(String::from("(unknown)"));
+                writeln!(description, "  HEAD branch: {}", head_branch)?;
+
+                let refspecs = helpers::get_fetch_refspecs(remote_name, path_handler)?;
+                for (_hash, ref_name) in remote_refs {
+                    let Some(local_ref_name) = refspecs.iter().find_map(|refspec| refspec.map_to_destination(ref_name)) else {
+                        continue;
+                    };
+                    let state = if refs::ref_exists(&local_ref_name, path_handler) {
+                        String::from("tracked")
+                    } else {
+                        format!("new (next fetch will store in remotes/{})", remote_name)
+                    };
+                    branch_states.push((helpers::shorten_ref_name(ref_name), state));
+                }
+                for stale_ref in helpers::find_stale_refs(remote_name, remote_refs, path_handler)? {
+                    let source = refspecs.iter().find_map(|refspec| refspec.map_to_source(&stale_ref)).unwrap_or(stale_ref);
+                    branch_states.push((helpers::shorten_ref_name(&source), String::from("stale (use 'git remote prune' to remove)")));
                 }
             }
         }
 
-        match (add_flag, remove_flag, name, url) {
-            (true, _, Some(name), Some(url)) => self.add_new_remote(name, url, path_handler)?,
-            (_, true, Some(name), _) => self.remove_remote(name, path_handler)?,
-            _ => {
-                if add_flag {
-                    println!("To add a new remote specify remote's name and url")
-                } else if remove_flag {
-                    println!("To remove a remote specify the remote's name")
-                }
+        let plural = |count: usize, word: &str| if count == 1 { word.to_string() } else { format!("{}es", word) };
+        if !branch_states.is_empty() {
+            branch_states.sort();
+            let not_queried = if remote_refs.is_none() { " (status not queried)" } else { "" };
+            writeln!(description, "  Remote {}:{}", plural(branch_states.len(), "branch"), not_queried)?;
+            let width = branch_states.iter().map(|(name, _)| name.len()).max().unwrap_or_default();
+            for (name, state) in branch_states {
+                writeln!(description, "{}", format!("    {:width$} {}", name, state, width = width).trim_end())?;
             }
         }
-        Ok(String::new())
+
+        let mut pull_branches: Vec<(String, String)> = helpers::get_remote_tracking_branches(path_handler)?
+            .into_iter()
+            .filter(|(_branch_name, (remote, _merge))| remote == remote_name)
+            .map(|(branch_name, (_remote, merge))| (branch_name, merge))
+            .collect();
+        if !pull_branches.is_empty() {
+            pull_branches.sort();
+            writeln!(description, "  Local {} configured for 'git pull':", plural(pull_branches.len(), "branch"))?;
+            let width = pull_branches.iter().map(|(name, _)| name.len()).max().unwrap_or_default();
+            for (branch_name, merge) in pull_branches {
+                writeln!(description, "    {:width$} merges with remote {}", branch_name, merge, width = width)?;
+            }
+        }
+        Ok(description)
     }
-}
 
-pub struct PackObjects;
+    /// Deletes the remote-tracking branches of a remote whose branch no longer exists in it, given the refs of the
+    /// remote as (hash, ref name). With `dry_run` they are only reported.
+    fn prune_remote(&self, remote_name: &str, remote_refs: &[(String, String)], dry_run: bool, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
+        let stale_refs = helpers::find_stale_refs(remote_name, remote_refs, path_handler)?;
+        let mut result = String::new();
+        if stale_refs.is_empty() {
+            return Ok(result);
+        }
 
-impl Default for PackObjects {
-    fn default() -> Self {
-        Self::new()
+        writeln!(result, "Pruning {}\nURL: {}", remote_name, helpers::get_remote_url(remote_name, path_handler)?)?;
+        let mut transaction = RefTransaction::new();
+        for stale_ref in &stale_refs {
+            transaction.delete(stale_ref, None);
+            let action = if dry_run { "would prune" } else { "pruned" };
+            writeln!(result, " * [{}] {}", action, helpers::shorten_ref_name(stale_ref))?;
+        }
+        if !dry_run {
+            transaction.commit(path_handler)?;
+        }
+        Ok(result)
     }
 }
 
-impl PackObjects {
-    pub fn new() -> Self {
-        PackObjects {}
-    }
-
-    fn get_tree_objects(
-        object_set: &mut HashSet<String>,
-        tree_hash: &str,
-        path_handler: &PathHandler
-    ) -> Result<(), Box<dyn Error>> {
-        object_set.insert(tree_hash.to_string());
-        let tree_content = helpers::read_tree_content(tree_hash, path_handler)?;
-
-        for (file_mode, _file_name, object_hash) in tree_content {
-            match file_mode.as_str() {
-                TREE_FILE_MODE => {
-                    object_set.insert(object_hash.clone());
-                }
-                TREE_SUBTREE_MODE => {
-                    // object_set.insert(object_hash.clone());
-                    PackObjects::get_tree_objects(object_set, &object_hash, path_handler)?;
-                }
-                _ => {}
+impl Command for Remote {
+    /// Executes Command for Remote. When no subcommand is received, all remotes are listed, along with their urls if
+    /// `-v` is received. The subcommands are `add <name> <url>`, `rm <name>`, `rename <old> <new>`,
+    /// `set-url [--push] <name> <url>`, `show [-n] <name>` and `prune [-n] <name>`.
+    fn execute(&self, args: Option<Vec<&str>>, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
+        let args = args.unwrap_or_default();
+        let subcommand = args.first().copied();
+        let flags: Vec<&str> = args.iter().skip(1).filter(|arg| arg.starts_with('-')).copied().collect();
+        let names: Vec<&str> = args.iter().skip(1).filter(|arg| !arg.starts_with('-')).copied().collect();
+
+        let result = match (subcommand, names.as_slice()) {
+            (None, _) => self.list_remotes(false, path_handler)?,
+            (Some(VERBOSE_FLAG), _) => self.list_remotes(true, path_handler)?,
+            (Some(ADD_FLAG), [name, url]) => {
+                self.add_new_remote(name.to_string(), url.to_string(), path_handler)?;
+                String::new()
             }
-        }
-
-        Ok(())
+            (Some(REMOVE_FLAG | REMOTE_REMOVE), [name]) => {
+                self.remove_remote(name.to_string(), path_handler)?;
+                String::new()
+            }
+            (Some(REMOTE_RENAME), [old_name, new_name]) => {
+                self.rename_remote(old_name, new_name, path_handler)?;
+                String::new()
+            }
+            (Some(REMOTE_SET_URL), [name, url]) => {
+                self.set_url(name, url, flags.contains(&PUSH_LONG_FLAG), path_handler)?;
+                String::new()
+            }
+            (Some(REMOTE_SHOW), [name]) => {
+                let remote_refs = match flags.contains(&NO_QUERY_FLAG) {
+                    true => None,
+                    false => Some(Self::query_remote_refs(name, path_handler)?),
+                };
+                self.show_remote(name, remote_refs.as_deref(), path_handler)?
+            }
+            (Some(REMOTE_PRUNE), [name]) => {
+                let dry_run = flags.iter().any(|flag| *flag == DRY_RUN_FLAG || *flag == DRY_RUN_LONG_FLAG);
+                let remote_refs = Self::query_remote_refs(name, path_handler)?;
+                self.prune_remote(name, &remote_refs, dry_run, path_handler)?
+            }
+            (Some(subcommand), _) => {
+                let usage = match subcommand {
+                    ADD_FLAG => "add <name> <url>",
+                    REMOVE_FLAG | REMOTE_REMOVE => "remove <name>",
+                    REMOTE_RENAME => "rename <old> <new>",
+                    REMOTE_SET_URL => "set-url [--push] <name> <newurl>",
+                    REMOTE_SHOW => "show [-n] <name>",
+                    REMOTE_PRUNE => "prune [-n | --dry-run] <name>",
+                    _ => "[-v | add | rename | remove | set-url | show | prune]",
+                };
+                return Err(Box::new(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("usage: git remote {}", usage),
+                )));
+            }
+        };
+        print!("{}", result);
+        Ok(result)
     }
+}
 
-    fn calculate_object_header(&self, object_type: ObjectType, object_size: usize) -> Vec<u8> {
-        let mut header = Vec::new();
-
-        // Encode the object type
-        let type_byte: u8 = match object_type {
-            ObjectType::Commit => 0b0001,
-            ObjectType::Tree => 0b0010,
-            ObjectType::Blob => 0b0011,
-            ObjectType::Tag => 0b0100,
-        };
+pub struct PackObjects;
 
-        // Combine type_byte and the last 4 bits of object_size
-        let combined_byte = (type_byte << 4) | ((object_size as u8) & 0x0F);
+impl Default for PackObjects {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
-        // Encode the object size in a variable-length format if it requires more than one byte
-        let mut size = object_size >> 4;
-        if size > 0 {
-            header.push(combined_byte | 0x80); // Set the first bit to 1 in the first byte
-        } else {
-            header.push(combined_byte);
-        }
+impl PackObjects {
+    pub fn new() -> Self {
+        PackObjects {}
+    }
 
-        while size > 0 {
-            let byte = (size as u8) & 0x7F;
-            size >>= 7;
-            header.push(byte);
-        }
+    /// Builds in memory a pack with the given commits and every object reachable from them,
+    /// so the receiving side gets the full history, trees and blobs.
+    pub fn create_pack(&self, commits: Vec<&str>, path_handler: &PathHandler) -> Result<Vec<u8>, Box<dyn Error>> {
+        let (pack, _entries) = Self::build_pack_for_commits(commits, path_handler)?;
+        Ok(pack)
+    }
 
-        // Print the bit representation of the final header
-        // print_bits(&header);
+    fn build_pack_for_commits(
+        commits: Vec<&str>,
+        path_handler: &PathHandler
+    ) -> Result<(Vec<u8>, Vec<packfile::PackIndexEntry>), Box<dyn Error>> {
+        let roots: Vec<String> = commits.iter().map(|commit| commit.trim().to_string()).collect();
+        let mut object_hashes: Vec<String> = helpers::get_objects_reachable_from(roots, path_handler)?
+            .into_iter()
+            .collect();
+        object_hashes.sort();
 
-        header
+        let mut objects = Vec::new();
+        for hash in object_hashes {
+            let (object_type, content, _size) = helpers::read_object_to_bytes(hash.clone(), path_handler)?;
+            objects.push(packfile::PackedObject { hash, object_type, content });
+        }
+        packfile::build_pack(objects)
     }
 }
 
@@ -934,74 +2225,14 @@ impl Command for PackObjects {
     /// The pack file format is used to efficiently store objects and their history.
     /// It also creates an index file that helps locate objects in the pack file.
     fn execute(&self, args: Option<Vec<&str>>, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
-        let commit_list = args.unwrap_or_default(); //aca recibo hashes de commits
-        // For each hash it looks for a hash tree
-        let mut object_set: HashSet<String> = HashSet::new();
-        for commit_hash in commit_list {
-            object_set.insert(commit_hash.to_string());
-            let tree_hash = helpers::get_commit_tree(commit_hash, path_handler)?;
-            PackObjects::get_tree_objects(&mut object_set, &tree_hash, path_handler)?;
-        }
-
-        // Create an uncompressed pack file
-        let mut pack_file_content = Vec::new();
-
-        // List all objects in the .git/objects directory
-        let mut object_count: u32 = 0;
-        // Iterate through objects
-        for object_hash in object_set {
-            // going through hashes in objects_list
-            object_count += 1;
-
-            let (object_type, object_content, object_size) =
-                helpers::read_object_to_bytes(object_hash.to_string(), path_handler)?;
-
-            // Append object content to the uncompressed pack file
-            let object_size_usize: usize = object_size.parse()?;
-            let compressed_content: &Vec<u8> = &helpers::compress_bytes(&object_content)?;
-            let header = self.calculate_object_header(object_type, object_size_usize);
-            
-            pack_file_content.extend_from_slice(&header);
-            pack_file_content.extend_from_slice(compressed_content);
-        }
-
-        let mut pack_file_final = Vec::new();
-        // Generate pack header
-        let version = [0u8, 0u8, 0u8, 2u8];
-        pack_file_final.extend_from_slice(b"PACK");
-        pack_file_final.extend_from_slice(&version);
-        pack_file_final.extend_from_slice(&object_count.to_be_bytes());
-
-        pack_file_final.extend_from_slice(&pack_file_content);
-
-        let pack_checksum = calculate_sha1_hash(&pack_file_final);
-        let checksum_str = helpers::hex_string_to_bytes(&pack_checksum.clone());
-
-        let pack_file_path = format!(".git/pack/pack-{}.pack", checksum_str);
-        
-        let mut pack_file = fs::File::create(path_handler.get_relative_path(&pack_file_path))?;
+        let commit_list = args.unwrap_or_default();
+        let (pack, entries) = Self::build_pack_for_commits(commit_list, path_handler)?;
+        let pack_name = packfile::write_pack_with_index(&pack, entries, path_handler)?;
 
-        pack_file.write_all(&pack_file_final)?;
-        pack_file.write_all(&pack_checksum)?;
-
-        Ok(helpers::hex_string_to_bytes(&pack_checksum).to_string())
+        Ok(pack_name.trim_start_matches("pack-").to_string())
     }
 }
 
-fn calculate_sha1_hash(data: &[u8]) -> [u8; 20] {
-    // Create a Sha1 object
-    let mut sha1 = Sha1::new();
-
-    // Update the hash with the data
-    sha1.input(data);
-
-    // Obtain the hash result as a Vec<u8>
-    let mut hash_result: [u8; 20] = Default::default();
-    sha1.result(&mut hash_result);
-
-    hash_result
-}
-
 pub struct UnpackObjects;
 
 impl Default for UnpackObjects {
@@ -1014,266 +2245,23 @@ impl UnpackObjects {
     pub fn new() -> Self {
         UnpackObjects {}
     }
-
-    fn keep_bits(value: usize, bits: u8) -> usize {
-        value & ((1 << bits) - 1)
-    }
-
-    /// Reads a fixed number of bytes from a stream.
-    /// Rust's "const generics" make this function very useful.
-    fn read_bytes<R: Read, const N: usize>(stream: &mut R) -> io::Result<[u8; N]> {
-        let mut bytes = [0; N];
-        stream.read_exact(&mut bytes)?;
-        Ok(bytes)
-    }
-
-    fn read_hash<R: Read>(stream: &mut R) -> io::Result<String> {
-        let bytes: [u8; 20] = Self::read_bytes(stream)?;
-        Ok(String::from_utf8_lossy(&bytes).to_string())
-    }
-
-    /// Read 7 bits of data and a flag indicating whether there are more
-    fn read_varint_byte<R: Read>(stream: &mut R) -> io::Result<(u8, bool)> {
-        let [byte] = Self::read_bytes(stream)?;
-        let value = byte & !VARINT_CONTINUE_FLAG;
-        let more_bytes = byte & VARINT_CONTINUE_FLAG != 0;
-        Ok((value, more_bytes))
-    }
-
-    fn read_size_encoding<R: Read>(stream: &mut R) -> io::Result<usize> {
-        let mut value = 0;
-        let mut length = 0; // the number of bits of data read so far
-        loop {
-            let (byte_value, more_bytes) = Self::read_varint_byte(stream)?;
-            // Add in the data bits
-            value |= (byte_value as usize) << length;
-            // Stop if this is the last byte
-            if !more_bytes {
-                return Ok(value);
-            }
-
-            length += VARINT_ENCODING_BITS;
-        }
-    }
-    /// Object type and uncompressed pack data size
-    /// are stored in a "size-encoding" variable-length integer.
-    /// Bits 4 through 6 store the type and the remaining bits store the size.
-    fn read_type_and_size<R: Read>(stream: &mut R) -> Result<(u8, usize), Box<dyn Error>> {
-        let value = Self::read_size_encoding(stream)?;
-        let object_type = Self::keep_bits(value >> TYPE_BYTE_SIZE_BITS, TYPE_BITS) as u8;
-        let size = Self::keep_bits(value, TYPE_BYTE_SIZE_BITS)
-            | (value >> VARINT_ENCODING_BITS << TYPE_BYTE_SIZE_BITS);
-        Ok((object_type, size))
-    }
-
-    fn read_offset_encoding<R: Read>(stream: &mut R) -> io::Result<u64> {
-        let mut value = 0;
-        loop {
-            let (byte_value, more_bytes) = Self::read_varint_byte(stream)?;
-            // Add the new bits at the *least* significant end of the value
-            value = (value << VARINT_ENCODING_BITS) | byte_value as u64;
-            if !more_bytes {
-                return Ok(value);
-            }
-
-            // Increase the value if there are more bytes, to avoid redundant encodings
-            value += 1;
-        }
-    }
-
-    // Read an integer of up to `bytes` bytes.
-    // `present_bytes` indicates which bytes are provided. The others are 0.
-    fn read_partial_int<R: Read>(
-        stream: &mut R,
-        bytes: u8,
-        present_bytes: &mut u8,
-    ) -> io::Result<usize> {
-        let mut value = 0;
-        for byte_index in 0..bytes {
-            // Use one bit of `present_bytes` to determine if the byte exists
-            if *present_bytes & 1 != 0 {
-                let [byte] = Self::read_bytes(stream)?;
-                value |= (byte as usize) << (byte_index * 8);
-            }
-            *present_bytes >>= 1;
-        }
-        Ok(value)
-    }
-
-    // Reads a single delta instruction from a stream
-    // and appends the relevant bytes to `result`.
-    // Returns whether the delta stream still had instructions.
-    fn apply_delta_instruction<R: Read>(
-        stream: &mut R,
-        base: &[u8],
-        result: &mut Vec<u8>,
-    ) -> Result<bool, Box<dyn Error>> {
-        // Check if the stream has ended, meaning the new object is done
-        let instruction = match Self::read_bytes(stream) {
-            Ok([instruction]) => instruction,
-            Err(err) if err.kind() == ErrorKind::UnexpectedEof => return Ok(false),
-            Err(err) => return Err(Box::new(err)),
-        };
-        if instruction & COPY_INSTRUCTION_FLAG == 0 {
-            // Data instruction; the instruction byte specifies the number of data bytes
-            if instruction == 0 {
-                // Appending 0 bytes doesn't make sense, so git disallows it
-                return Err(Box::new(io::Error::new(
-                    io::ErrorKind::Other,
-                    "Error: Invalid Data Instructions",
-                )));
-            }
-
-            // Append the provided bytes
-            let mut data = vec![0; instruction as usize];
-            stream.read_exact(&mut data)?;
-            result.extend_from_slice(&data);
-        } else {
-            // Copy instruction
-            let mut nonzero_bytes = instruction;
-            let offset = Self::read_partial_int(stream, COPY_OFFSET_BYTES, &mut nonzero_bytes)?;
-            let mut size = Self::read_partial_int(stream, COPY_SIZE_BYTES, &mut nonzero_bytes)?;
-            if size == 0 {
-                // Copying 0 bytes doesn't make sense, so git assumes a different size
-                size = COPY_ZERO_SIZE;
-            }
-            // Copy bytes from the base object
-            let base_data = base.get(offset..(offset + size)).ok_or(io::Error::new(
-                io::ErrorKind::NotFound,
-                "Invalid copy instructions",
-            ))?;
-            result.extend_from_slice(base_data);
-        }
-        Ok(true)
-    }
-
-    fn apply_delta(
-        pack_file: &mut fs::File,
-        base_object_content: &[u8],
-        base_type: ObjectType,
-    ) -> Result<(ObjectType, Vec<u8>, usize), Box<dyn Error>> {
-        // let Object { object_type, contents: ref base } = *base;
-        let mut delta = Decoder::new(pack_file)?; //aca esta mal esta descompresion
-        let base_size = Self::read_size_encoding(&mut delta)?;
-        if base_object_content.len() != base_size {
-            return Err(Box::new(io::Error::new(
-                io::ErrorKind::Other,
-                "Error: Incorrect base object length",
-            )));
-        }
-
-        let result_size = Self::read_size_encoding(&mut delta)?;
-        let mut result = Vec::with_capacity(result_size);
-        while Self::apply_delta_instruction(&mut delta, base_object_content, &mut result)? {}
-        if result.len() != result_size {
-            return Err(Box::new(io::Error::new(
-                io::ErrorKind::Other,
-                "Error: Incorrect object length",
-            )));
-        }
-
-        // The object type is the same as the base object
-        Ok((base_type, result, result_size))
-    }
-
-    fn seek(file: &mut fs::File, offset: u64) -> io::Result<()> {
-        file.seek(SeekFrom::Start(offset))?;
-        Ok(())
-    }
-    fn read_pack_object(
-        pack_file: &mut fs::File,
-        offset: u64,
-        path_handler: &PathHandler
-    ) -> Result<(ObjectType, Vec<u8>, usize), Box<dyn Error>> {
-        let (object_type, size) = Self::read_type_and_size(pack_file)?;
-        let object_type = match object_type {
-            1 => PackObjectType::Base(ObjectType::Commit),
-            2 => PackObjectType::Base(ObjectType::Tree),
-            3 => PackObjectType::Base(ObjectType::Blob),
-            4 => PackObjectType::Base(ObjectType::Tag),
-            6 => PackObjectType::OffsetDelta,
-            7 => PackObjectType::HashDelta,
-            _ => {
-                return Err(Box::new(io::Error::new(
-                    io::ErrorKind::Other,
-                    "Error: Invalid Object Type",
-                )))
-            }
-        };
-        match object_type {
-            PackObjectType::Base(object_type) => {
-                // The object contents are zlib-compressed
-                let mut contents = Vec::with_capacity(size);
-                Decoder::new(pack_file)?.read_to_end(&mut contents)?;
-                if contents.len() != size {
-                    return Err(Box::new(io::Error::new(
-                        io::ErrorKind::Other,
-                        "Error: Incorrect object size",
-                    )));
-                }
-
-                Ok((object_type, contents, size))
-            }
-            PackObjectType::OffsetDelta => {
-                let delta_offset = Self::read_offset_encoding(pack_file)?;
-                let base_offset = offset.checked_sub(delta_offset).ok_or(io::Error::new(
-                    io::ErrorKind::NotFound,
-                    "Invalid OffsetDelta offset",
-                ))?;
-                let (base_object_type, base_object_content, _size) =
-                    Self::read_pack_object(pack_file, base_offset, path_handler)?;
-                Self::seek(pack_file, offset)?;
-                Self::apply_delta(pack_file, &base_object_content, base_object_type)
-            }
-            PackObjectType::HashDelta => {
-                let hash = Self::read_hash(pack_file)?; // esto lo tengo que ver como implementar yo. seria la lectura del hash del delta object
-                let (object_type, base_object_content, _) = helpers::read_object_to_string(hash, path_handler)?; // aca como hace referencia a un objecto base, ya va a tener que estar descomprimido
-                return Self::apply_delta(pack_file, base_object_content.as_bytes(), object_type);
-            }
-        }
-    
-    }
 }
 
 impl Command for UnpackObjects {
+    /// Executes the `unpack-objects` command, which reads the pack file in the given path and
+    /// writes every object in it as a loose object.
     fn execute(&self, args: Option<Vec<&str>>, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
-        // Extract arguments or default to an empty vector
         let arg_slice = args.unwrap_or_default();
-
-        // Open the specified pack file
-        let mut pack_file = fs::File::open(arg_slice[0])?;
-
-        // Retrieve the size of the pack file
-        let _pack_file_size = helpers::get_file_length(arg_slice[0])?;
-
-        // Read the header of the pack file
-        let mut header = vec![0; 12]; //Size of header is fixed
-        pack_file.read_exact(&mut header)?;
-
-        // Extract the number of objects from the header
-        let object_amount = u32::from_be_bytes(header[8..12].try_into()?);
-
-        // Initialize offset after the header
-        let mut offset: u64 = 12; //Skipping the header
-        let mut objects_unpacked = 1;
-
-        // Iterate over each object in the pack file
-        while objects_unpacked <= object_amount {
-            // Read the pack object at the specified offset
-            let (object_type, content, size) = Self::read_pack_object(&mut pack_file, offset, path_handler)?;
-
-            // Convert content to a string (not used, can be removed if unnecessary)
-            let _content_to_string = String::from_utf8_lossy(&content).to_string();
-
-            // Write the object content to a file
-            HashObjectCreator::write_object_file_bytes(&content, object_type, size, path_handler)?;
-
-            // Update offset for the next object
-            offset += size as u64;
-            objects_unpacked += 1;
+        let pack_file_path = arg_slice.first().ok_or(io::Error::new(
+            io::ErrorKind::Other,
+            "Error: No pack file provided",
+        ))?;
+        let pack = helpers::read_file_content_to_bytes(pack_file_path)?;
+
+        for (_index_entry, (object_type, content)) in packfile::read_all_pack_objects(&pack, path_handler)? {
+            HashObjectCreator::write_object_file_bytes(&content, object_type, content.len(), path_handler)?;
         }
 
-        // Return an empty string indicating successful execution
         Ok(String::new())
     }
 }
@@ -1292,32 +2280,45 @@ impl Fetch {
         Fetch {}
     }
 
+    /// Stores a ref of the remote in the local ref the first matching refspec maps it to. Tags no refspec matches
+    /// are kept as they are. Updates that aren't fast-forwards are rejected unless the refspec is forced.
     pub fn add_remote_ref(
         &self,
         ref_hash: &str,
         ref_name: &str,
-        remote_name: &str,
+        refspecs: &[Refspec],
+        path_handler: &PathHandler,
     ) -> Result<(), Box<dyn Error>> {
-        let split_ref_name: Vec<&str> = ref_name.split('/').collect(); 
-        let remote_ref_name = split_ref_name[2];
-        let mut ref_path = String::new();
-    
-        match split_ref_name[1] {
-            "heads" => {
-                let dir_path = format!("{}/{}", R_REMOTES, remote_name);
-                if !helpers::check_if_directory_exists(&dir_path.clone()) {
-                    fs::create_dir(dir_path.clone())?;
-                }
-                ref_path = format!("{}/{}", dir_path, remote_ref_name);
+        let mapped_ref = refspecs
+            .iter()
+            .find_map(|refspec| refspec.map_to_destination(ref_name).map(|local_ref_name| (local_ref_name, refspec.force)));
+        let (local_ref_name, force) = match mapped_ref {
+            Some(mapped_ref) => mapped_ref,
+            None if ref_name.starts_with("refs/tags/") => {
+                return refs::update_ref(ref_name, ref_hash, None, None, path_handler);
             }
+            None => return Ok(()),
+        };
 
-            "tags" => ref_path = format!("{}/{}", R_TAGS, remote_ref_name),
-            _ => {}
-        }
-        
-        let mut ref_file = fs::File::create(ref_path)?;
-        ref_file.write_all(ref_hash.as_bytes())?;
-        Ok(())
+        let old_hash = refs::read_ref(&local_ref_name, path_handler);
+        let message = match &old_hash {
+            None => "fetch: storing head",
+            Some(old_hash) if old_hash == ref_hash => return Ok(()),
+            Some(old_hash) => {
+                let is_fast_forward = revision::get_ancestors(&[ref_hash.to_string()], path_handler)
+                    .map(|ancestors| ancestors.contains(old_hash))
+                    .unwrap_or(false);
+                match (is_fast_forward, force) {
+                    (true, _) => "fetch: fast-forward",
+                    (false, true) => "fetch: forced-update",
+                    (false, false) => {
+                        println!(" ! [rejected] {} -> {} (non-fast-forward)", ref_name, helpers::shorten_ref_name(&local_ref_name));
+                        return Ok(());
+                    }
+                }
+            }
+        };
+        refs::update_ref(&local_ref_name, ref_hash, old_hash.as_deref(), Some(message), path_handler)
     }
 
 }
@@ -1327,7 +2328,7 @@ impl Command for Fetch {
         let remote_url;
         let mut remote_name = DEFAULT_REMOTE_REPOSITORY;
         match args {
-            Some(args) => match helpers::get_remote_url(args[0]) {
+            Some(args) => match helpers::get_remote_url(args[0], path_handler) {
                 Ok(url) => {
                     remote_url = url;
                     remote_name = args[0];
@@ -1335,16 +2336,15 @@ impl Command for Fetch {
                 Err(_) => remote_url = args[0].to_string(),
             },
             None => {
-                remote_url = helpers::get_remote_url(DEFAULT_REMOTE_REPOSITORY)?;
+                remote_url = helpers::get_remote_url(DEFAULT_REMOTE_REPOSITORY, path_handler)?;
             }
         }
 
+        let refspecs = helpers::get_fetch_refspecs(remote_name, path_handler)?;
         let refs = client::client_protocol::ClientProtocol::new()
             .fetch_from_remote_with_our_server(remote_url, path_handler)?;
-        UnpackObjects::new().execute(Some(vec![&path_handler.get_relative_path(RECEIVED_PACK_FILE)]), path_handler)?;
         for (ref_hash, ref_name) in refs {
-            
-            self.add_remote_ref(&ref_hash, &ref_name, remote_name)?;
+            self.add_remote_ref(&ref_hash, &ref_name, &refspecs, path_handler)?;
         }
 
 
@@ -1369,34 +2369,43 @@ impl Push {
 
 impl Command for Push {
     fn execute(&self, args: Option<Vec<&str>>, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
-        let mut remote_url = String::new();
         if Head::get_head_commit(path_handler)?.is_empty(){
             return Err(Box::new(io::Error::new(
                 io::ErrorKind::Other,
                 "Error: Can not push without a commit",
             )))
         }
-        let mut _remote_name = DEFAULT_REMOTE_REPOSITORY;
-        let _branch = Head::get_current_branch_name(path_handler)?;
-        if let Some(args) = args {
-            match helpers::get_remote_url(args[0]) {
-                Ok(url) => {
-                    remote_url = url;
-                    _remote_name = args[0];
-                    
-                }
-                Err(_) => {
-                    return Err(Box::new(io::Error::new(
-                        io::ErrorKind::Other,
-                        "Error: Name is not a remote",
-                    )))
-                }
+        let mut remote_name = DEFAULT_REMOTE_REPOSITORY;
+        let branch = Head::get_current_branch_name(path_handler)?;
+        let mut set_upstream_flag = false;
+        let mut remote_arg = None;
+        for arg in args.unwrap_or_default() {
+            match arg {
+                SET_UPSTREAM_FLAG | SET_UPSTREAM_LONG_FLAG => set_upstream_flag = true,
+                _ => remote_arg = remote_arg.or(Some(arg)),
+            }
+        }
+        if let Some(remote_arg) = remote_arg {
+            if helpers::get_remote_url(remote_arg, path_handler).is_err() {
+                return Err(Box::new(io::Error::new(
+                    io::ErrorKind::Other,
+                    "Error: Name is not a remote",
+                )))
             }
+            remote_name = remote_arg;
+        }
+        let remote_url = helpers::get_push_url(remote_name, path_handler)?;
+        let updated_refs = ClientProtocol::new().receive_pack(remote_url, path_handler)?;
+
+        // the remote-tracking branch now matches what was pushed, if the remote updated it
+        if updated_refs.contains(&format!("refs/heads/{}", branch)) {
+            let head_commit = Head::get_head_commit(path_handler)?;
+            refs::update_ref(&format!("refs/remotes/{}/{}", remote_name, branch), &head_commit, None, Some("update by push"), path_handler)?;
         }
-        if remote_url.is_empty() {
-            remote_url = helpers::get_remote_url(DEFAULT_REMOTE_REPOSITORY)?;
+        if set_upstream_flag {
+            helpers::set_upstream_config(&branch, remote_name, &format!("refs/heads/{}", branch), path_handler)?;
+            println!("branch '{}' set up to track '{}/{}'.", branch, remote_name, branch);
         }
-        ClientProtocol::new().receive_pack(remote_url.to_string(), path_handler)?;
 
         Ok(String::new())
     }
@@ -1417,25 +2426,53 @@ impl Pull {
 }
 
 impl Command for Pull {
+    /// Fetches from a remote and merges into the current branch its remote-tracking branch of the same name. With no
+    /// remote given, the upstream of the current branch is fetched and merged.
     fn execute(&self, args: Option<Vec<&str>>, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
-        let remote_url;
-        let mut remote_name = DEFAULT_REMOTE_REPOSITORY;
+        // a configured remote is fetched by name, so its refspecs are used
+        let fetch_source;
+        let mut remote_name = DEFAULT_REMOTE_REPOSITORY.to_string();
+        let branch_name = Head::get_current_branch_name(path_handler)?;
+        let mut merge_branch = branch_name.clone();
         match args {
-            Some(args) => match helpers::get_remote_url(args[0]) {
-                Ok(url) => {
-                    remote_url = url;
-                    remote_name = args[0];
+            Some(args) => match helpers::get_remote_url(args[0], path_handler) {
+                Ok(_) => {
+                    remote_name = args[0].to_string();
+                    fetch_source = remote_name.clone();
                 }
-                Err(_) => remote_url = args[0].to_string(),
+                Err(_) => fetch_source = args[0].to_string(),
             },
             None => {
-                remote_url = helpers::get_remote_url(DEFAULT_REMOTE_REPOSITORY)?;
+                let config = Config::load(path_handler)?;
+                let upstream = (
+                    config.get(&format!("branch.{}.remote", branch_name)),
+                    config.get(&format!("branch.{}.merge", branch_name)),
+                );
+                match upstream {
+                    // the upstream is a local branch, there's nothing to fetch
+                    (Some(remote), Some(merge)) if remote == "." => {
+                        Merge::new().execute(Some(vec![merge.trim_start_matches("refs/heads/")]), path_handler)?;
+                        return Ok(String::new());
+                    }
+                    (Some(remote), Some(merge)) => {
+                        merge_branch = merge.trim_start_matches("refs/heads/").to_string();
+                        remote_name = remote;
+                    }
+                    _ => {
+                        return Err(Box::new(io::Error::new(
+                            io::ErrorKind::Other,
+                            "Error: There is no tracking information for the current branch.",
+                        )))
+                    }
+                }
+                helpers::get_remote_url(&remote_name, path_handler)?;
+                fetch_source = remote_name.clone();
             }
         }
-        Fetch::new().execute(Some(vec![&remote_url]), path_handler)?;
-        let remote_branch = format!("{}/{}", remote_name, Head::get_current_branch_name(path_handler)?);
+        Fetch::new().execute(Some(vec![&fetch_source]), path_handler)?;
+        let remote_branch = format!("{}/{}", remote_name, merge_branch);
         
-        if !helpers::check_if_file_exists(&format!("{}/{}", R_REMOTES, remote_branch), path_handler) {
+        if !refs::ref_exists(&format!("refs/remotes/{}", remote_branch), path_handler) {
             return Err(Box::new(io::Error::new(
                 io::ErrorKind::Other,
                 "Error: There is no tracking information for the current branch.",
@@ -1470,6 +2507,9 @@ impl Command for Clone {
                 Remote::new().execute(Some(vec!["add", "origin", remote_repository[0]]), path_handler)?;
                 Fetch::new().execute(None, path_handler)?;
                 let remote_branches = helpers::get_remote_branches(DEFAULT_REMOTE_REPOSITORY, path_handler)?;
+                for (branch_name, _hash) in &remote_branches {
+                    helpers::set_upstream_config(branch_name, DEFAULT_REMOTE_REPOSITORY, &format!("refs/heads/{}", branch_name), path_handler)?;
+                }
                 helpers::update_branches(remote_branches, path_handler)?;
                 Pull::new().execute(Some(vec!["origin"]), path_handler)?;
             }
@@ -1542,6 +2582,9 @@ impl Log {
                 "Error: Invalid SHA-1. Is not a commit",
             )));
         }
+        // the signature of signed commits isn't part of the entry
+        let commit_file_content = signing::split_signature(&commit_file_content)
+            .map_or(commit_file_content, |(payload, _signature)| payload);
         
         let commit_lines: Vec<String> = commit_file_content
             .split('\n')
@@ -1570,6 +2613,26 @@ impl Log {
         
         Ok(String::new())
     }
+
+    /// Returns the diffstat of a commit against its first parent, or against an empty tree for a root commit
+    fn generate_stat(
+        commit: &str,
+        rename_threshold: Option<usize>,
+        find_copies: bool,
+        path_handler: &PathHandler
+    ) -> Result<String, Box<dyn Error>> {
+        let (_, commit_content, _) = helpers::read_object_to_string(commit.to_string(), path_handler)?;
+        let parent_files = match commit_content.lines().find_map(|line| line.strip_prefix("parent ")) {
+            Some(parent) => helpers::reconstruct_working_tree(parent.to_string(), path_handler)?,
+            None => HashMap::new(),
+        };
+        let commit_files = helpers::reconstruct_working_tree(commit.to_string(), path_handler)?;
+        let changes = helpers::find_file_changes(&parent_files, &commit_files, rename_threshold, find_copies, path_handler);
+        if changes.is_empty() {
+            return Ok(String::new());
+        }
+        Ok(helpers::format_diff_stat(&changes, &parent_files, &commit_files, path_handler))
+    }
 }
 
 impl Command for Log {
@@ -1584,12 +2647,16 @@ impl Command for Log {
     /// A `Result` containing the execution result or an error message.
     fn execute(&self, args: Option<Vec<&str>>, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
         // Extract the arguments from the provided slice or use an empty slice if none is provided
-        let empty_args = args.is_none();
         let arg_slice = args.unwrap_or_default();
 
         // Initialize vectors to store log entries (included and excluded)
         let mut log_entries = Vec::new();
         let mut log_entries_excluded = Vec::new();
+        let mut show_stat = false;
+        let mut show_signature = false;
+        let mut rename_threshold = Some(DEFAULT_RENAME_THRESHOLD);
+        let mut find_copies = false;
+        let mut has_revisions = false;
         
         // Iterate through the provided arguments
         for arg in arg_slice {
@@ -1597,21 +2664,33 @@ impl Command for Log {
             // Check the first character of each argument
             if let Some(first_char) = arg.chars().next() {
                 match first_char {
+                    _ if arg == STAT_FLAG => show_stat = true,
+                    _ if arg == SHOW_SIGNATURE_FLAG => show_signature = true,
+                    _ if arg == NO_RENAMES_FLAG => rename_threshold = None,
+                    _ if arg.starts_with(FIND_RENAMES_FLAG) || arg.starts_with(FIND_RENAMES_LONG_FLAG) => {
+                        rename_threshold = Some(helpers::parse_similarity_threshold(arg)?);
+                    }
+                    _ if arg.starts_with(FIND_COPIES_FLAG) || arg.starts_with(FIND_COPIES_LONG_FLAG) => {
+                        rename_threshold = Some(helpers::parse_similarity_threshold(arg)?);
+                        find_copies = true;
+                    }
                     EXCLUDE_LOG_ENTRY => {
                         // Generate log entries for exclusion and store them in the excluded entries vector
-                        Log::generate_log_entries(&mut log_entries_excluded, arg[1..].to_string(), path_handler)?;
-                        
+                        let excluded_commit = revision::resolve_commit(&arg[1..], path_handler)?;
+                        Log::generate_log_entries(&mut log_entries_excluded, excluded_commit, path_handler)?;
+                        has_revisions = true;
                     }
                     _ => {
                         // Generate log entries for inclusion and store them in the included entries vector
-                        Log::generate_log_entries(&mut log_entries, arg.to_string(), path_handler)?;
-                        
+                        let included_commit = revision::resolve_commit(arg, path_handler)?;
+                        Log::generate_log_entries(&mut log_entries, included_commit, path_handler)?;
+                        has_revisions = true;
                     }
                 }
             }
         }
 
-        if empty_args {
+        if !has_revisions {
             Log::generate_log_entries(&mut log_entries, HEAD.to_string(), path_handler)?;
         }
 
@@ -1622,6 +2701,26 @@ impl Command for Log {
             .cloned()
             .collect::<Vec<(String, String)>>();
 
+        if show_signature {
+            for (commit, message) in log_entries.iter_mut() {
+                let description = match signing::verify_object(commit, path_handler) {
+                    Ok(Some(check)) => check.description,
+                    Ok(None) => continue,
+                    Err(error) => error.to_string(),
+                };
+                *message = format!("{}\n{}", description, message);
+            }
+        }
+
+        if show_stat {
+            for (commit, message) in log_entries.iter_mut() {
+                let stat = Log::generate_stat(commit, rename_threshold, find_copies, path_handler)?;
+                if !stat.is_empty() {
+                    message.push_str(&format!("\n---\n{}", stat));
+                }
+            }
+        }
+
         // Display the resulting log entries
         for (commit, message) in &log_entries {
             println!(
@@ -1639,93 +2738,1401 @@ impl Command for Log {
         Ok(result)
     }
 }
-pub struct LsTree;
+pub struct Diff;
 
-impl Default for LsTree {
+impl Default for Diff {
     fn default() -> Self {
         Self::new()
     }
 }
 
-impl LsTree {
-    
+impl Diff {
     pub fn new() -> Self {
-        LsTree {}
+        Diff {}
     }
 
-    pub fn generate_tree_entries(
-        entries: &mut Vec<String>,
-        tree_hash: String,
-        direct_flag: bool,
-        recurse_flag: bool,
-        long_flag: bool,
-        path_handler: &PathHandler
-    ) -> Result<(), Box<dyn Error>> {
-        let current_hash = if tree_hash == HEAD {
-            helpers::get_commit_tree(Head::get_head_commit(path_handler)?.as_str(), path_handler)?
-        } else {
-            tree_hash
-        };
-        
-        let mut tree_content = helpers::read_tree_content(&current_hash, path_handler)?;
-        
-        if tree_content.is_empty() {
-            return Ok(());
-        }
+    /// Returns the files of a commit, keyed by path with their modes and hashes
+    fn get_commit_files(revision: &str, path_handler: &PathHandler) -> Result<HashMap<String, (String, String)>, Box<dyn Error>> {
+        let commit = revision::resolve_commit(revision, path_handler)?;
+        helpers::get_tree_files(&helpers::get_commit_tree(&commit, path_handler)?, "", path_handler)
+    }
 
+    /// Returns the files of the index that aren't marked as deleted, keyed by path with their modes and hashes
+    fn get_index_files(path_handler: &PathHandler) -> Result<HashMap<String, (String, String)>, Box<dyn Error>> {
+        Ok(helpers::read_index_entries(path_handler)?
+            .into_iter()
+            .filter(|(_path, _hash, state, _mode)| *state != IndexFileEntryState::Deleted.to_string())
+            .map(|(path, hash, _state, mode)| (path, (mode, hash)))
+            .collect())
+    }
 
-        for (file_mode, file_name, object_hash) in &mut tree_content {
+    /// Returns the tracked files that are still in the working tree, keyed by path with their modes and the hash
+    /// of their content
+    fn get_working_tree_files(path_handler: &PathHandler) -> Result<HashMap<String, (String, String)>, Box<dyn Error>> {
+        let mut files = HashMap::new();
+        for path in Diff::get_index_files(path_handler)?.into_keys() {
+            if fs::symlink_metadata(path_handler.get_relative_path(&path)).is_ok() {
+                let mode = helpers::get_file_mode(&path, path_handler)?;
+                let hash = helpers::hash_working_file(&path, path_handler)?;
+                files.insert(path, (mode, hash));
+            }
+        }
+        Ok(files)
+    }
 
-            let mut line: String = format!("{} {} {}", file_mode, object_hash, file_name);
+    /// Returns the `old mode`/`new mode` headers of the files whose mode changed, sorted by path
+    fn format_mode_changes(old_files: &HashMap<String, (String, String)>, new_files: &HashMap<String, (String, String)>) -> Vec<String> {
+        let mut mode_changes: Vec<(&String, &String, &String)> = new_files
+            .iter()
+            .filter_map(|(path, (new_mode, _hash))| match old_files.get(path) {
+                Some((old_mode, _hash)) if old_mode != new_mode => Some((path, old_mode, new_mode)),
+                _ => None,
+            })
+            .collect();
+        mode_changes.sort();
+        mode_changes
+            .into_iter()
+            .map(|(path, old_mode, new_mode)| format!("diff --git a/{0} b/{0}\nold mode {1}\nnew mode {2}", path, old_mode, new_mode))
+            .collect()
+    }
+}
 
-            if file_mode == TREE_FILE_MODE && direct_flag {
-                // don't add files to the entries if direct flag is on
-                continue;
+impl Command for Diff {
+    /// Shows the files changed between the index and the working tree, a commit and the index (--cached),
+    /// a commit and the working tree, or two commits. The default output is a diffstat, and --name-status
+    /// lists the status of each file instead. Files whose mode changed are followed by their `old mode` and
+    /// `new mode`. Renames are detected unless --no-renames is given, with
+    /// -M<n>% setting how similar files have to be and -C also detecting copies.
+    fn execute(&self, args: Option<Vec<&str>>, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
+        let mut cached = false;
+        let mut name_status = false;
+        let mut rename_threshold = Some(DEFAULT_RENAME_THRESHOLD);
+        let mut find_copies = false;
+        let mut revisions = Vec::new();
+        for arg in args.unwrap_or_default() {
+            match arg {
+                CACHED_LONG_FLAG => cached = true,
+                NAME_STATUS_FLAG => name_status = true,
+                STAT_FLAG => name_status = false,
+                NO_RENAMES_FLAG => rename_threshold = None,
+                _ if arg.starts_with(FIND_RENAMES_FLAG) || arg.starts_with(FIND_RENAMES_LONG_FLAG) => {
+                    rename_threshold = Some(helpers::parse_similarity_threshold(arg)?);
+                }
+                _ if arg.starts_with(FIND_COPIES_FLAG) || arg.starts_with(FIND_COPIES_LONG_FLAG) => {
+                    rename_threshold = Some(helpers::parse_similarity_threshold(arg)?);
+                    find_copies = true;
+                }
+                _ => revisions.push(arg),
             }
+        }
 
-            if long_flag {
-                // add size to the line
-                let (_, _object_content, object_size) =
-                    helpers::read_object_to_string(object_hash.clone(), path_handler)?;
-                line.push(' ');
-                line.push_str(object_size.as_str());
+        let (old_files, new_files) = match (revisions.as_slice(), cached) {
+            ([], false) => (Diff::get_index_files(path_handler)?, Diff::get_working_tree_files(path_handler)?),
+            ([], true) => {
+                let head_files = if Head::get_head_commit(path_handler)?.is_empty() {
+                    HashMap::new()
+                } else {
+                    Diff::get_commit_files(HEAD, path_handler)?
+                };
+                (head_files, Diff::get_index_files(path_handler)?)
             }
-
-            if file_mode == TREE_FILE_MODE {
-                // add
-                entries.push(line.clone());
-                continue;
+            ([revision], true) => (Diff::get_commit_files(revision, path_handler)?, Diff::get_index_files(path_handler)?),
+            ([revision], false) => (Diff::get_commit_files(revision, path_handler)?, Diff::get_working_tree_files(path_handler)?),
+            ([old_revision, new_revision], false) => (
+                Diff::get_commit_files(old_revision, path_handler)?,
+                Diff::get_commit_files(new_revision, path_handler)?,
+            ),
+            _ => {
+                return Err(Box::new(io::Error::new(
+                    io::ErrorKind::Other,
+                    "usage: diff [--cached] [<commit> [<commit>]]",
+                )))
             }
+        };
 
-            if file_mode == TREE_SUBTREE_MODE {
-                // if direct or not recursive add
-                if direct_flag || !recurse_flag {
-                    entries.push(line.clone());
-                }
-                // if recursive loop
-                if recurse_flag {
-                    return LsTree::generate_tree_entries(
-                        entries,
-                        object_hash.clone(),
-                        direct_flag,
-                        recurse_flag,
-                        long_flag,
-                        path_handler
-                    );
-                }
-            }
+        let mode_changes = Diff::format_mode_changes(&old_files, &new_files);
+        let old_files: HashMap<String, String> = old_files.into_iter().map(|(path, (_mode, hash))| (path, hash)).collect();
+        let new_files: HashMap<String, String> = new_files.into_iter().map(|(path, (_mode, hash))| (path, hash)).collect();
+        let changes = helpers::find_file_changes(&old_files, &new_files, rename_threshold, find_copies, path_handler);
+        if changes.is_empty() && mode_changes.is_empty() {
+            return Ok(String::new());
         }
-        Ok(())
+        let mut sections = Vec::new();
+        if !changes.is_empty() {
+            sections.push(if name_status {
+                helpers::format_name_status(&changes)
+            } else {
+                helpers::format_diff_stat(&changes, &old_files, &new_files, path_handler)
+            });
+        }
+        sections.extend(mode_changes);
+        let result = sections.join("\n");
+        println!("{}", result);
+        Ok(result)
     }
 }
 
-impl Command for LsTree {
-    /// Executes the "git ls-tree" command.
-    fn execute(&self, args: Option<Vec<&str>>, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
-        let mut direct_flag = false;
-        let mut recurse_flag = false;
-        let mut long_flag = false;
-        let mut tree_entries: Vec<String> = Vec::new();
+/// A line of a file along with the commit that introduced it
+pub struct BlameLine {
+    pub commit: String,
+    /// Path of the file in the commit that introduced the line, which differs if the file was renamed since
+    pub original_path: String,
+    pub original_line_number: usize,
+    pub final_line_number: usize,
+    pub content: String,
+    pub author: String,
+    pub author_mail: String,
+    pub author_time: i64,
+    pub author_tz: String,
+    pub summary: String,
+    /// True if the commit has no parents
+    pub boundary: bool,
+}
+
+/// A commit visited by blame, parsed once
+struct BlameCommit {
+    content: String,
+    parents: Vec<String>,
+    committer_time: i64,
+}
+
+pub struct Blame;
+
+impl Default for Blame {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Blame {
+    pub fn new() -> Self {
+        Blame {}
+    }
+
+    /// Returns the parsed commit, reading it only the first time it's needed
+    fn get_commit<'a>(
+        commit: &str,
+        commits: &'a mut HashMap<String, BlameCommit>,
+        path_handler: &PathHandler
+    ) -> Result<&'a BlameCommit, Box<dyn Error>> {
+        if !commits.contains_key(commit) {
+            let (_, content, _) = helpers::read_object_to_string(commit.to_string(), path_handler)?;
+            let parents = content
+                .lines()
+                .take_while(|line| !line.is_empty())
+                .filter_map(|line| line.strip_prefix("parent "))
+                .map(String::from)
+                .collect();
+            let committer = revision::get_header_field(&content, "committer").unwrap_or_default();
+            let (_, _, committer_time, _) = Blame::parse_identity(&committer);
+            commits.insert(commit.to_string(), BlameCommit { content, parents, committer_time });
+        }
+        Ok(&commits[commit])
+    }
+
+    /// Splits an identity line such as `author name <email> 1700000000 -0300` into
+    /// the name, email, timestamp and timezone
+    fn parse_identity(identity: &str) -> (String, String, i64, String) {
+        let (name, rest) = identity.split_once(" <").unwrap_or((identity, ""));
+        let (email, rest) = rest.split_once('>').unwrap_or((rest, ""));
+        let mut date = rest.split_whitespace();
+        let timestamp = date.next().and_then(|timestamp| timestamp.parse().ok()).unwrap_or(0);
+        let timezone = date.next().unwrap_or("+0000").to_string();
+        (name.to_string(), format!("<{}>", email), timestamp, timezone)
+    }
+
+    /// Formats a timestamp in the given timezone, such as `+0300`, as `2023-11-20 18:30:00 +0300`
+    fn format_date(timestamp: i64, timezone: &str) -> String {
+        helpers::format_timestamp(timestamp, timezone, "%Y-%m-%d %H:%M:%S %z")
+    }
+
+    /// Returns the lines of a blob
+    fn read_lines(hash: &str, path_handler: &PathHandler) -> Result<Vec<String>, Box<dyn Error>> {
+        let (_, content, _) = helpers::read_object_to_string(hash.to_string(), path_handler)?;
+        Ok(content.lines().map(String::from).collect())
+    }
+
+    /// Returns the path a file had in a parent commit, following renames, or None if the parent doesn't have it
+    fn find_path_in_parent(
+        path: &str,
+        parent_files: &HashMap<String, String>,
+        commit_files: &HashMap<String, String>,
+        path_handler: &PathHandler
+    ) -> Option<String> {
+        if parent_files.contains_key(path) {
+            return Some(path.to_string());
+        }
+        helpers::find_file_changes(parent_files, commit_files, Some(DEFAULT_RENAME_THRESHOLD), false, path_handler)
+            .into_iter()
+            .find_map(|change| match change {
+                helpers::FileChange::Renamed(old_path, new_path, _) if new_path == path => Some(old_path),
+                _ => None,
+            })
+    }
+
+    /// Attributes each line of the file at the revision to the commit that introduced it. Commits are visited
+    /// newest first, and every line a parent also has, according to a line diff, is passed on to that parent.
+    /// The lines no parent has were introduced by the commit. Every commit and its files are only read once.
+    pub fn blame_file(path: &str, revision: &str, path_handler: &PathHandler) -> Result<Vec<BlameLine>, Box<dyn Error>> {
+        let start_commit = revision::resolve_commit(revision, path_handler)?;
+        let start_files = helpers::reconstruct_working_tree(start_commit.clone(), path_handler)?;
+        let start_hash = start_files.get(path).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::Other, format!("fatal: no such path '{}' in {}", path, revision))
+        })?;
+        let line_count = Blame::read_lines(start_hash, path_handler)?.len();
+
+        let mut commits: HashMap<String, BlameCommit> = HashMap::new();
+        let mut commit_files: HashMap<String, HashMap<String, String>> = HashMap::new();
+        commit_files.insert(start_commit.clone(), start_files);
+        // lines that still have to be attributed, by commit: path of the file there and (final, current) line indexes
+        let mut pending: HashMap<String, (String, Vec<(usize, usize)>)> = HashMap::new();
+        pending.insert(start_commit, (path.to_string(), (0..line_count).map(|index| (index, index)).collect()));
+        let mut blamed: Vec<Option<BlameLine>> = (0..line_count).map(|_| None).collect();
+
+        while !pending.is_empty() {
+            let mut newest_commit = String::new();
+            let mut newest_time = i64::MIN;
+            for commit in pending.keys() {
+                let time = Blame::get_commit(commit, &mut commits, path_handler)?.committer_time;
+                if time > newest_time || (time == newest_time && *commit > newest_commit) {
+                    newest_time = time;
+                    newest_commit = commit.clone();
+                }
+            }
+            let commit = newest_commit;
+            let (commit_path, mut remaining) = pending.remove(&commit).unwrap_or_default();
+            let parents = Blame::get_commit(&commit, &mut commits, path_handler)?.parents.clone();
+            for visited in parents.iter().chain([&commit]) {
+                if !commit_files.contains_key(visited) {
+                    commit_files.insert(visited.clone(), helpers::reconstruct_working_tree(visited.clone(), path_handler)?);
+                }
+            }
+            let files = &commit_files[&commit];
+            let commit_lines = Blame::read_lines(&files[&commit_path], path_handler)?;
+
+            for parent in &parents {
+                if remaining.is_empty() {
+                    break;
+                }
+                let parent_files = &commit_files[parent];
+                let parent_path = match Blame::find_path_in_parent(&commit_path, parent_files, files, path_handler) {
+                    Some(parent_path) => parent_path,
+                    None => continue,
+                };
+                let parent_lines = Blame::read_lines(&parent_files[&parent_path], path_handler)?;
+                let kept_lines: HashMap<usize, usize> = helpers::match_lines(&parent_lines, &commit_lines)
+                    .into_iter()
+                    .map(|(parent_index, commit_index)| (commit_index, parent_index))
+                    .collect();
+
+                let (passed, not_passed): (Vec<_>, Vec<_>) =
+                    remaining.into_iter().partition(|(_, index)| kept_lines.contains_key(index));
+                remaining = not_passed;
+                if !passed.is_empty() {
+                    let parent_pending = pending.entry(parent.clone()).or_insert((parent_path, Vec::new()));
+                    parent_pending.1.extend(passed.into_iter().map(|(final_index, index)| (final_index, kept_lines[&index])));
+                }
+            }
+
+            if remaining.is_empty() {
+                continue;
+            }
+            let commit_content = &Blame::get_commit(&commit, &mut commits, path_handler)?.content;
+            let author = revision::get_header_field(commit_content, "author").unwrap_or_default();
+            let (author, author_mail, author_time, author_tz) = Blame::parse_identity(&author);
+            let summary = commit_content.split_once("\n\n").map(|(_, message)| message).unwrap_or("").lines().next().unwrap_or("").to_string();
+            for (final_index, index) in remaining {
+                blamed[final_index] = Some(BlameLine {
+                    commit: commit.clone(),
+                    original_path: commit_path.clone(),
+                    original_line_number: index + 1,
+                    final_line_number: final_index + 1,
+                    content: commit_lines.get(index).cloned().unwrap_or_default(),
+                    author: author.clone(),
+                    author_mail: author_mail.clone(),
+                    author_time,
+                    author_tz: author_tz.clone(),
+                    summary: summary.clone(),
+                    boundary: parents.is_empty(),
+                });
+            }
+        }
+        Ok(blamed.into_iter().flatten().collect())
+    }
+
+    /// Parses a `-L` range, `start,end` or `start,+count`, into the first and last line numbers.
+    /// A missing start or end means the first or last line.
+    fn parse_line_range(range: &str, line_count: usize) -> Result<(usize, usize), Box<dyn Error>> {
+        let invalid = || io::Error::new(io::ErrorKind::Other, format!("fatal: invalid line range '{}'", range));
+        let (start, end) = range.split_once(',').unwrap_or((range, ""));
+        let start = if start.is_empty() { 1 } else { start.parse::<usize>().map_err(|_| invalid())? };
+        let end = match end.strip_prefix('+') {
+            _ if end.is_empty() => line_count,
+            Some(count) => start + count.parse::<usize>().map_err(|_| invalid())?.saturating_sub(1),
+            None => end.parse::<usize>().map_err(|_| invalid())?,
+        };
+        if start == 0 || start > end || start > line_count {
+            return Err(Box::new(invalid()));
+        }
+        Ok((start, end.min(line_count)))
+    }
+
+    /// Returns the lines in the porcelain format. Each line starts with a header with the commit hash, the
+    /// original and final line numbers and the size of the group of lines from the same commit. The details
+    /// of a commit are only shown the first time it appears.
+    fn format_porcelain(lines: &[BlameLine]) -> String {
+        let mut output = Vec::new();
+        let mut shown_commits = HashSet::new();
+        for (index, line) in lines.iter().enumerate() {
+            let starts_group = index == 0 || lines[index - 1].commit != line.commit
+                || lines[index - 1].original_line_number + 1 != line.original_line_number;
+            if starts_group {
+                let group_size = lines[index..]
+                    .iter()
+                    .enumerate()
+                    .take_while(|(offset, other)| {
+                        other.commit == line.commit && other.original_line_number == line.original_line_number + offset
+                    })
+                    .count();
+                output.push(format!("{} {} {} {}", line.commit, line.original_line_number, line.final_line_number, group_size));
+            } else {
+                output.push(format!("{} {} {}", line.commit, line.original_line_number, line.final_line_number));
+            }
+            if shown_commits.insert(line.commit.clone()) {
+                output.push(format!("author {}", line.author));
+                output.push(format!("author-mail {}", line.author_mail));
+                output.push(format!("author-time {}", line.author_time));
+                output.push(format!("author-tz {}", line.author_tz));
+                output.push(format!("summary {}", line.summary));
+                if line.boundary {
+                    output.push("boundary".to_string());
+                }
+                output.push(format!("filename {}", line.original_path));
+            }
+            output.push(format!("\t{}", line.content));
+        }
+        output.join("\n")
+    }
+
+    /// Returns one line per blamed line with the short commit hash, the author, the date, the line number and the content.
+    /// The original path is also shown if any line comes from a file with a different path.
+    fn format_default(lines: &[BlameLine], path: &str) -> String {
+        let author_width = lines.iter().map(|line| line.author.chars().count()).max().unwrap_or(0);
+        let number_width = lines.iter().map(|line| line.final_line_number.to_string().len()).max().unwrap_or(0);
+        let show_paths = lines.iter().any(|line| line.original_path != path);
+        let path_width = lines.iter().map(|line| line.original_path.len()).max().unwrap_or(0);
+        lines
+            .iter()
+            .map(|line| {
+                let path = if show_paths { format!(" {:<width$}", line.original_path, width = path_width) } else { String::new() };
+                format!(
+                    "{}{}{} ({:<author_width$} {} {:>number_width$}) {}",
+                    if line.boundary { "^" } else { "" },
+                    &line.commit[..BLAME_ABBREVIATED_HASH_LENGTH],
+                    path,
+                    line.author,
+                    Blame::format_date(line.author_time, &line.author_tz),
+                    line.final_line_number,
+                    line.content,
+                    author_width = author_width,
+                    number_width = number_width
+                )
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+}
+
+impl Command for Blame {
+    /// Shows the commit that last changed each line of a file: `blame [-L <start>,<end>] [--porcelain] <file> [<rev>]`.
+    /// The file is read at HEAD unless a revision is given.
+    fn execute(&self, args: Option<Vec<&str>>, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
+        let mut porcelain = false;
+        let mut line_range = None;
+        let mut positional = Vec::new();
+        let mut args = args.unwrap_or_default().into_iter();
+        while let Some(arg) = args.next() {
+            match arg {
+                PORCELAIN_FLAG => porcelain = true,
+                LINE_RANGE_FLAG => line_range = args.next(),
+                _ if arg.starts_with(LINE_RANGE_FLAG) => line_range = Some(&arg[LINE_RANGE_FLAG.len()..]),
+                PATHSPEC_SEPARATOR => {}
+                _ => positional.push(arg),
+            }
+        }
+        let (path, revision) = match positional.as_slice() {
+            [path] => (*path, HEAD),
+            [path, revision] => (*path, *revision),
+            _ => {
+                return Err(Box::new(io::Error::new(
+                    io::ErrorKind::Other,
+                    "usage: blame [-L <start>,<end>] [--porcelain] <file> [<rev>]",
+                )))
+            }
+        };
+
+        let path = helpers::normalize_repository_path(path, path_handler);
+        let mut lines = Blame::blame_file(&path, revision, path_handler)?;
+        if let Some(line_range) = line_range {
+            let (start, end) = Blame::parse_line_range(line_range, lines.len())?;
+            lines.retain(|line| line.final_line_number >= start && line.final_line_number <= end);
+        }
+
+        let result = if porcelain {
+            Blame::format_porcelain(&lines)
+        } else {
+            Blame::format_default(&lines, &path)
+        };
+        println!("{}", result);
+        Ok(result)
+    }
+}
+
+/// Where the content of a file searched by grep comes from
+enum GrepSource {
+    WorkingFile(String),
+    Blob(String),
+}
+
+pub struct Grep;
+
+impl Default for Grep {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Grep {
+    pub fn new() -> Self {
+        Grep {}
+    }
+
+    /// Builds the regular expression for the patterns. Fixed strings are escaped, and whole-word matching
+    /// requires the match to be surrounded by word boundaries.
+    fn build_regex(patterns: &[&str], fixed_strings: bool, ignore_case: bool, word: bool) -> Result<Regex, Box<dyn Error>> {
+        let alternatives: Vec<String> = patterns
+            .iter()
+            .map(|pattern| if fixed_strings { regex::escape(pattern) } else { pattern.to_string() })
+            .collect();
+        let mut pattern = format!("(?:{})", alternatives.join("|"));
+        if word {
+            pattern = format!(r"\b{}\b", pattern);
+        }
+        RegexBuilder::new(&pattern)
+            .case_insensitive(ignore_case)
+            .build()
+            .map_err(|error| Box::new(io::Error::new(io::ErrorKind::Other, format!("fatal: invalid pattern: {}", error))) as Box<dyn Error>)
+    }
+
+    /// Returns the files to search in the working tree: the tracked files still there, even inside ignored
+    /// directories, and untracked ones that aren't ignored if `include_untracked` is set
+    fn get_working_tree_sources(
+        pathspecs: &[String],
+        include_untracked: bool,
+        path_handler: &PathHandler
+    ) -> Result<Vec<(String, GrepSource)>, Box<dyn Error>> {
+        let tracked_files: BTreeSet<String> = helpers::read_index_entries(path_handler)?
+            .into_iter()
+            .filter(|(_path, _hash, state, mode)| *state != IndexFileEntryState::Deleted.to_string() && mode != GITLINK_MODE)
+            .map(|(path, _hash, _state, _mode)| path)
+            .filter(|path| fs::symlink_metadata(path_handler.get_relative_path(path)).is_ok())
+            .collect();
+        let mut files = tracked_files.clone();
+        if include_untracked {
+            let ignore_rules = IgnoreRules::new(path_handler)?;
+            let working_files = helpers::get_working_tree_files(path_handler, &|directory| ignore_rules.is_ignored(directory, true))?;
+            files.extend(
+                working_files
+                    .into_iter()
+                    .filter(|path| !tracked_files.contains(path) && !ignore_rules.is_ignored(path, false)),
+            );
+        }
+        Ok(files
+            .into_iter()
+            .filter(|path| helpers::matches_pathspecs(pathspecs, path))
+            .map(|path| (path.clone(), GrepSource::WorkingFile(path)))
+            .collect())
+    }
+
+    /// Returns the blobs of the index to search
+    fn get_index_sources(pathspecs: &[String], path_handler: &PathHandler) -> Result<Vec<(String, GrepSource)>, Box<dyn Error>> {
+        let mut index_entries = helpers::read_index_entries(path_handler)?;
+        index_entries.sort();
+        Ok(index_entries
+            .into_iter()
+            .filter(|(path, _hash, state, _mode)| *state != IndexFileEntryState::Deleted.to_string() && helpers::matches_pathspecs(pathspecs, path))
+            .map(|(path, hash, _state, _mode)| (path, GrepSource::Blob(hash)))
+            .collect())
+    }
+
+    /// Returns the blobs of the tree of a revision to search, named `<revision>:<path>`
+    fn get_tree_sources(
+        revision: &str,
+        tree_hash: &str,
+        path_prefix: &str,
+        pathspecs: &[String],
+        path_handler: &PathHandler
+    ) -> Result<Vec<(String, GrepSource)>, Box<dyn Error>> {
+        let mut sources = Vec::new();
+        for (file_mode, file_name, file_hash) in helpers::read_tree_content(tree_hash, path_handler)? {
+            let file_path = format!("{}{}", path_prefix, file_name);
+            if helpers::is_tree_mode(&file_mode) {
+                sources.extend(Grep::get_tree_sources(revision, &file_hash, &format!("{}/", file_path), pathspecs, path_handler)?);
+            } else if file_mode != GITLINK_MODE && helpers::matches_pathspecs(pathspecs, &file_path) {
+                sources.push((format!("{}:{}", revision, file_path), GrepSource::Blob(file_hash)));
+            }
+        }
+        Ok(sources)
+    }
+
+    /// Searches a file and returns its output lines
+    fn search_file(
+        name: &str,
+        source: &GrepSource,
+        regex: &Regex,
+        options: &GrepOptions,
+        path_handler: &PathHandler
+    ) -> Result<Vec<String>, Box<dyn Error>> {
+        let content = match source {
+            GrepSource::WorkingFile(path) => helpers::read_working_file(path, path_handler)?,
+            GrepSource::Blob(hash) => helpers::read_object_to_bytes(hash.clone(), path_handler)?.1,
+        };
+        let content = String::from_utf8_lossy(&content);
+        let is_binary = content.contains('\0');
+
+        let mut lines = Vec::new();
+        let mut count = 0;
+        for (index, line) in content.lines().enumerate() {
+            if !regex.is_match(line) {
+                continue;
+            }
+            count += 1;
+            if options.files_with_matches || options.count || is_binary {
+                continue;
+            }
+            if options.line_number {
+                lines.push(format!("{}:{}:{}", name, index + 1, line));
+            } else {
+                lines.push(format!("{}:{}", name, line));
+            }
+        }
+        if count == 0 {
+            return Ok(Vec::new());
+        }
+        if options.files_with_matches {
+            lines.push(name.to_string());
+        } else if options.count {
+            lines.push(format!("{}:{}", name, count));
+        } else if is_binary {
+            lines.push(format!("Binary file {} matches", name));
+        }
+        Ok(lines)
+    }
+}
+
+/// The output options of grep
+struct GrepOptions {
+    line_number: bool,
+    files_with_matches: bool,
+    count: bool,
+}
+
+impl Command for Grep {
+    /// Prints the lines matching a pattern: `grep [<options>] <pattern> [<rev>...] [-- <paths>...]`.
+    /// Searches the tracked files of the working tree by default, the index with --cached, or the trees of the
+    /// given revisions. Patterns are regular expressions unless -F/--fixed-strings is given, and more can be given
+    /// with -e. Supports -i/--ignore-case, -w/--word-regexp, -n/--line-number, -l/--files-with-matches,
+    /// -c/--count, --untracked to also search untracked files that aren't ignored and --threads <n>.
+    fn execute(&self, args: Option<Vec<&str>>, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
+        let mut patterns = Vec::new();
+        let mut fixed_strings = false;
+        let mut ignore_case = false;
+        let mut word = false;
+        let mut cached = false;
+        let mut include_untracked = false;
+        let mut threads = thread::available_parallelism().map(|threads| threads.get()).unwrap_or(1).min(GREP_MAX_THREADS);
+        let mut options = GrepOptions { line_number: false, files_with_matches: false, count: false };
+        let mut positional = Vec::new();
+        let mut pathspecs = Vec::new();
+        let mut only_paths = false;
+        let mut args = args.unwrap_or_default().into_iter();
+        while let Some(arg) = args.next() {
+            match arg {
+                _ if only_paths => pathspecs.push(helpers::normalize_repository_path(arg, path_handler)),
+                PATTERN_FLAG => patterns.extend(args.next()),
+                FIXED_STRINGS_FLAG | FIXED_STRINGS_LONG_FLAG => fixed_strings = true,
+                EXTENDED_REGEXP_FLAG | EXTENDED_REGEXP_LONG_FLAG => fixed_strings = false,
+                IGNORE_CASE_FLAG | IGNORE_CASE_LONG_FLAG => ignore_case = true,
+                WORD_REGEXP_FLAG | WORD_REGEXP_LONG_FLAG => word = true,
+                LINE_NUMBER_FLAG | LINE_NUMBER_LONG_FLAG => options.line_number = true,
+                FILES_WITH_MATCHES_FLAG | FILES_WITH_MATCHES_LONG_FLAG => options.files_with_matches = true,
+                COUNT_FLAG | COUNT_LONG_FLAG => options.count = true,
+                CACHED_LONG_FLAG => cached = true,
+                UNTRACKED_FLAG => include_untracked = true,
+                THREADS_FLAG => {
+                    threads = args
+                        .next()
+                        .and_then(|threads| threads.parse().ok())
+                        .filter(|threads| *threads > 0)
+                        .ok_or_else(|| io::Error::new(io::ErrorKind::Other, "fatal: invalid number of threads"))?;
+                }
+                PATHSPEC_SEPARATOR => only_paths = true,
+                _ => positional.push(arg),
+            }
+        }
+        if patterns.is_empty() {
+            if positional.is_empty() {
+                return Err(Box::new(io::Error::new(io::ErrorKind::Other, "fatal: no pattern given")));
+            }
+            patterns.push(positional.remove(0));
+        }
+
+        // what follows the pattern are revisions, until something that isn't one is found
+        let mut revisions = Vec::new();
+        for arg in positional {
+            match revision::resolve_commit(arg, path_handler) {
+                Ok(commit) if pathspecs.is_empty() => revisions.push((arg, commit)),
+                _ if fs::symlink_metadata(path_handler.get_relative_path(arg)).is_ok() => {
+                    pathspecs.push(helpers::normalize_repository_path(arg, path_handler))
+                }
+                _ => {
+                    return Err(Box::new(io::Error::new(
+                        io::ErrorKind::Other,
+                        format!("fatal: ambiguous argument '{}': unknown revision or path not in the working tree", arg),
+                    )))
+                }
+            }
+        }
+        if cached && !revisions.is_empty() {
+            return Err(Box::new(io::Error::new(io::ErrorKind::Other, "fatal: --cached cannot be used with revisions")));
+        }
+
+        let regex = Grep::build_regex(&patterns, fixed_strings, ignore_case, word)?;
+        let mut sources = Vec::new();
+        if !revisions.is_empty() {
+            for (name, commit) in revisions {
+                let tree_hash = helpers::get_commit_tree(&commit, path_handler)?;
+                sources.extend(Grep::get_tree_sources(name, &tree_hash, "", &pathspecs, path_handler)?);
+            }
+        } else if cached {
+            sources = Grep::get_index_sources(&pathspecs, path_handler)?;
+        } else {
+            sources = Grep::get_working_tree_sources(&pathspecs, include_untracked, path_handler)?;
+        }
+
+        // each thread searches every n-th file, and the results are put back in order
+        let mut results: Vec<Vec<String>> = vec![Vec::new(); sources.len()];
+        thread::scope(|scope| -> Result<(), Box<dyn Error>> {
+            let handles: Vec<_> = (0..threads)
+                .map(|thread_index| {
+                    let (sources, regex, options) = (&sources, &regex, &options);
+                    scope.spawn(move || {
+                        sources
+                            .iter()
+                            .enumerate()
+                            .skip(thread_index)
+                            .step_by(threads)
+                            .map(|(index, (name, source))| {
+                                Grep::search_file(name, source, regex, options, path_handler)
+                                    .map(|lines| (index, lines))
+                                    .map_err(|error| error.to_string())
+                            })
+                            .collect::<Result<Vec<(usize, Vec<String>)>, String>>()
+                    })
+                })
+                .collect();
+            for handle in handles {
+                let thread_results = handle
+                    .join()
+                    .map_err(|_| io::Error::new(io::ErrorKind::Other, "Error: grep thread panicked"))?
+                    .map_err(|error| io::Error::new(io::ErrorKind::Other, error))?;
+                for (index, lines) in thread_results {
+                    results[index] = lines;
+                }
+            }
+            Ok(())
+        })?;
+
+        let output: Vec<String> = results.into_iter().flatten().collect();
+        for line in &output {
+            println!("{}", line);
+        }
+        Ok(output.join("\n"))
+    }
+}
+
+/// What bisect does after the good and bad commits change
+enum BisectStep {
+    /// Both a good and a bad commit are needed before starting
+    Waiting,
+    /// The commit to test next, with the number of commits left to test after it
+    Test(String, usize),
+    /// The first bad commit was found
+    Found(String),
+    /// Only skipped commits are left, so the first bad commit is any of them
+    OnlySkipped(Vec<String>),
+}
+
+pub struct Bisect;
+
+impl Default for Bisect {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Bisect {
+    pub fn new() -> Self {
+        Bisect {}
+    }
+
+    /// Returns true if a bisect session was started and not reset
+    fn is_bisecting(path_handler: &PathHandler) -> bool {
+        helpers::check_if_file_exists(BISECT_START_FILE, path_handler)
+    }
+
+    /// Returns an error saying there's no bisect session
+    fn not_bisecting() -> Box<dyn Error> {
+        Box::new(io::Error::new(io::ErrorKind::Other, "You need to start by \"git bisect start\""))
+    }
+
+    /// Returns the commits listed in a bisect state file, one per line
+    fn read_commits(file: &str, path_handler: &PathHandler) -> Vec<String> {
+        helpers::read_file_content(&path_handler.get_relative_path(file))
+            .unwrap_or_default()
+            .lines()
+            .map(String::from)
+            .collect()
+    }
+
+    /// Appends lines to a bisect state file
+    fn append_lines(file: &str, lines: &str, path_handler: &PathHandler) -> Result<(), Box<dyn Error>> {
+        let mut state_file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path_handler.get_relative_path(file))?;
+        writeln!(state_file, "{}", lines)?;
+        Ok(())
+    }
+
+    /// Starts a bisect session from the current HEAD. The first revision given is the bad commit and the
+    /// rest are good ones. A session already in progress is reset first.
+    fn start(revisions: &[&str], path_handler: &PathHandler) -> Result<(), Box<dyn Error>> {
+        if Self::is_bisecting(path_handler) {
+            Self::reset(None, path_handler)?;
+        }
+        let original_head = if Head::is_detached(path_handler)? {
+            Head::get_head_commit(path_handler)?
+        } else {
+            Head::get_current_branch_name(path_handler)?
+        };
+        let commits = revisions
+            .iter()
+            .map(|revision| revision::resolve_commit(revision, path_handler))
+            .collect::<Result<Vec<String>, Box<dyn Error>>>()?;
+
+        fs::write(path_handler.get_relative_path(BISECT_START_FILE), original_head)?;
+        fs::write(path_handler.get_relative_path(BISECT_LOG_FILE), "")?;
+        Self::append_lines(BISECT_LOG_FILE, &format!("git bisect {}", BISECT_START), path_handler)?;
+        if let Some((bad, good)) = commits.split_first() {
+            Self::mark(BISECT_BAD, std::slice::from_ref(bad), path_handler)?;
+            Self::mark(BISECT_GOOD, good, path_handler)?;
+        }
+        Ok(())
+    }
+
+    /// Marks commits as good, bad or skipped and records it in the bisect log
+    fn mark(term: &str, commits: &[String], path_handler: &PathHandler) -> Result<(), Box<dyn Error>> {
+        for commit in commits {
+            match term {
+                BISECT_BAD => fs::write(path_handler.get_relative_path(BISECT_BAD_FILE), commit)?,
+                BISECT_GOOD => Self::append_lines(BISECT_GOOD_FILE, commit, path_handler)?,
+                _ => Self::append_lines(BISECT_SKIP_FILE, commit, path_handler)?,
+            }
+            let summary = helpers::get_commit_summary(commit, path_handler)?;
+            Self::append_lines(
+                BISECT_LOG_FILE,
+                &format!("# {}: [{}] {}\ngit bisect {} {}", term, commit, summary, term, commit),
+                path_handler,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Decides the next step from the bisect state. The candidates are the ancestors of the bad commit that
+    /// aren't ancestors of a good one. The commit tested next is the one that splits them most evenly: the one
+    /// whose number of ancestors among the candidates is closest to half of them. Like git does, those numbers
+    /// are counted in a single walk from the oldest candidates, adding one to the count of the only parent;
+    /// only merge commits need a walk of their own, as their parents can share ancestors.
+    fn next_step(path_handler: &PathHandler) -> Result<BisectStep, Box<dyn Error>> {
+        let bad = match Self::read_commits(BISECT_BAD_FILE, path_handler).pop() {
+            Some(bad) => bad,
+            None => return Ok(BisectStep::Waiting),
+        };
+        let good = Self::read_commits(BISECT_GOOD_FILE, path_handler);
+        if good.is_empty() {
+            return Ok(BisectStep::Waiting);
+        }
+        let skipped: HashSet<String> = Self::read_commits(BISECT_SKIP_FILE, path_handler).into_iter().collect();
+        let good_ancestors = revision::get_ancestors(&good, path_handler)?;
+        if good_ancestors.contains(&bad) {
+            return Err(Box::new(io::Error::new(
+                io::ErrorKind::Other,
+                format!("fatal: the bad commit {} is an ancestor of a good commit", bad),
+            )));
+        }
+
+        // candidates are kept in the order they are found walking back from the bad commit, with a set to
+        // look them up
+        let mut candidates = vec![bad.clone()];
+        let mut candidate_set = HashSet::from([bad.clone()]);
+        let mut parents_of: HashMap<String, Vec<String>> = HashMap::new();
+        let mut index = 0;
+        while index < candidates.len() {
+            let parents: Vec<String> = revision::get_commit_parents(&candidates[index], path_handler)?
+                .into_iter()
+                .filter(|parent| !good_ancestors.contains(parent))
+                .collect();
+            for parent in &parents {
+                if candidate_set.insert(parent.clone()) {
+                    candidates.push(parent.clone());
+                }
+            }
+            parents_of.insert(candidates[index].clone(), parents);
+            index += 1;
+        }
+        if candidates.len() == 1 {
+            return Ok(BisectStep::Found(bad));
+        }
+
+        // the candidates ordered so that every parent comes before its children
+        let mut ordered: Vec<&String> = Vec::with_capacity(candidates.len());
+        let mut visited = HashSet::new();
+        let mut stack = vec![(&bad, false)];
+        while let Some((commit, parents_done)) = stack.pop() {
+            if parents_done {
+                ordered.push(commit);
+            } else if visited.insert(commit) {
+                stack.push((commit, true));
+                stack.extend(parents_of[commit].iter().filter(|parent| !visited.contains(parent)).map(|parent| (parent, false)));
+            }
+        }
+        let mut reached_counts: HashMap<&String, usize> = HashMap::with_capacity(ordered.len());
+        for commit in ordered {
+            let reached_count = match parents_of[commit].as_slice() {
+                [] => 1,
+                [parent] => reached_counts[parent] + 1,
+                _ => {
+                    let mut reached = HashSet::new();
+                    let mut pending = vec![commit];
+                    while let Some(commit) = pending.pop() {
+                        if reached.insert(commit) {
+                            pending.extend(parents_of[commit].iter());
+                        }
+                    }
+                    reached.len()
+                }
+            };
+            reached_counts.insert(commit, reached_count);
+        }
+
+        let total = candidates.len();
+        let mut best: Option<(usize, usize, &String)> = None;
+        for candidate in candidates.iter().filter(|candidate| **candidate != bad && !skipped.contains(*candidate)) {
+            let reached_count = reached_counts[candidate];
+            let score = reached_count.min(total - reached_count);
+            if best.is_none_or(|(best_score, _, _)| score > best_score) {
+                best = Some((score, reached_count, candidate));
+            }
+        }
+        match best {
+            Some((_score, reached, commit)) => {
+                let left = (reached - 1).max(total - reached - 1);
+                Ok(BisectStep::Test(commit.clone(), left))
+            }
+            None => Ok(BisectStep::OnlySkipped(
+                candidates.into_iter().filter(|candidate| *candidate == bad || skipped.contains(candidate)).collect(),
+            )),
+        }
+    }
+
+    /// Runs the next step: checks out the next commit to test, or describes the first bad commit when found
+    fn run_next_step(path_handler: &PathHandler) -> Result<(BisectStep, String), Box<dyn Error>> {
+        let step = Self::next_step(path_handler)?;
+        let output = match &step {
+            BisectStep::Waiting => "status: waiting for both good and bad commits".to_string(),
+            BisectStep::Test(commit, left) => {
+                Checkout::checkout_commit(commit, path_handler)?;
+                Head::detach_head(commit, path_handler)?;
+                let steps = (usize::BITS - left.leading_zeros()) as usize;
+                format!(
+                    "Bisecting: {} revisions left to test after this (roughly {} steps)\n[{}] {}",
+                    left, steps, commit, helpers::get_commit_summary(commit, path_handler)?
+                )
+            }
+            BisectStep::Found(commit) => {
+                let (_, content, _) = helpers::read_object_to_string(commit.clone(), path_handler)?;
+                let author = revision::get_header_field(&content, "author").unwrap_or_default();
+                let author = author.rsplitn(3, ' ').last().unwrap_or_default().to_string();
+                let message = content.split_once("\n\n").map(|(_headers, message)| message).unwrap_or_default();
+                let message: Vec<String> = message.lines().map(|line| format!("    {}", line)).collect();
+                let output = format!(
+                    "{} is the first bad commit\ncommit {}\nAuthor: {}\n\n{}",
+                    commit, commit, author, message.join("\n")
+                );
+                Self::append_lines(BISECT_LOG_FILE, &format!("# first bad commit: [{}] {}", commit, helpers::get_commit_summary(commit, path_handler)?), path_handler)?;
+                output
+            }
+            BisectStep::OnlySkipped(commits) => format!(
+                "There are only 'skip'ped commits left to test.\nThe first bad commit could be any of:\n{}\nWe cannot bisect more!",
+                commits.join("\n")
+            ),
+        };
+        Ok((step, output))
+    }
+
+    /// Ends the bisect session, checking out the branch or commit it was started from, or the given one.
+    /// Removes the bisect state files.
+    fn reset(commit: Option<&str>, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
+        if !Self::is_bisecting(path_handler) {
+            return Ok("We are not bisecting.".to_string());
+        }
+        let original_head = helpers::read_file_content(&path_handler.get_relative_path(BISECT_START_FILE))?;
+        let target = commit.unwrap_or(original_head.trim());
+        let output = if refs::ref_exists(&format!("refs/heads/{}", target), path_handler) {
+            let branch_commit = helpers::get_branch_last_commit(&helpers::get_branch_path(target), path_handler)?;
+            Checkout::checkout_commit(&branch_commit, path_handler)?;
+            Head::change_head_branch(target, path_handler)?;
+            format!("Switched to branch '{}'", target)
+        } else {
+            let commit = revision::resolve_commit(target, path_handler)?;
+            Checkout::checkout_commit(&commit, path_handler)?;
+            Head::detach_head(&commit, path_handler)?;
+            format!("HEAD is now at {}", &commit[..ABBREVIATED_HASH_LENGTH])
+        };
+        for file in [BISECT_START_FILE, BISECT_LOG_FILE, BISECT_BAD_FILE, BISECT_GOOD_FILE, BISECT_SKIP_FILE] {
+            let _ = fs::remove_file(path_handler.get_relative_path(file));
+        }
+        Ok(output)
+    }
+
+    /// Replays a bisect log: starts a new session and applies the marks recorded in it
+    fn replay(log_file: &str, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
+        let log = helpers::read_file_content(&path_handler.get_relative_path(log_file))?;
+        let mut started = false;
+        for line in log.lines() {
+            let words: Vec<&str> = match line.strip_prefix("git bisect ") {
+                Some(command) => command.split_whitespace().collect(),
+                None => continue,
+            };
+            match words.split_first() {
+                Some((&BISECT_START, revisions)) => {
+                    Self::start(revisions, path_handler)?;
+                    started = true;
+                }
+                Some((&term, commits)) if started && [BISECT_GOOD, BISECT_BAD, BISECT_SKIP].contains(&term) => {
+                    let commits = commits
+                        .iter()
+                        .map(|commit| revision::resolve_commit(commit, path_handler))
+                        .collect::<Result<Vec<String>, Box<dyn Error>>>()?;
+                    Self::mark(term, &commits, path_handler)?;
+                }
+                _ => {
+                    return Err(Box::new(io::Error::new(
+                        io::ErrorKind::Other,
+                        format!("fatal: ?? what are you talking about? '{}'", line),
+                    )))
+                }
+            }
+        }
+        if !started {
+            return Err(Box::new(io::Error::new(io::ErrorKind::Other, "fatal: no bisect start found in the log")));
+        }
+        Ok(Self::run_next_step(path_handler)?.1)
+    }
+
+    /// Tests commits with a command until the first bad one is found. The command is run from the root of the
+    /// repository: exit code 0 marks the commit as good, 125 skips it and any other code below 128 marks it as
+    /// bad. Higher codes or a command killed by a signal stop the session.
+    fn run(command: &[&str], path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
+        let (program, args) = command
+            .split_first()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::Other, "bisect run failed: no command provided."))?;
+        if let BisectStep::Waiting = Self::next_step(path_handler)? {
+            return Err(Box::new(io::Error::new(
+                io::ErrorKind::Other,
+                "You need to give me at least one good and one bad revision.",
+            )));
+        }
+
+        let mut output = Vec::new();
+        let last_step = loop {
+            let root = path_handler.get_relative_path("");
+            let mut process = std::process::Command::new(program);
+            process.args(args);
+            if !root.is_empty() {
+                process.current_dir(root);
+            }
+            let term = match process.status()?.code() {
+                Some(0) => BISECT_GOOD,
+                Some(BISECT_SKIP_EXIT_CODE) => BISECT_SKIP,
+                Some(code) if (1..128).contains(&code) => BISECT_BAD,
+                code => {
+                    return Err(Box::new(io::Error::new(
+                        io::ErrorKind::Other,
+                        format!("bisect run failed: exit code {:?} from '{}' is < 0 or >= 128", code, command.join(" ")),
+                    )))
+                }
+            };
+            Self::mark(term, &[Head::get_head_commit(path_handler)?], path_handler)?;
+            let (step, step_output) = Self::run_next_step(path_handler)?;
+            output.push(step_output);
+            match step {
+                BisectStep::Test(_, _) => continue,
+                step => break step,
+            }
+        };
+        output.push(match last_step {
+            BisectStep::OnlySkipped(_) => "bisect run cannot continue any more".to_string(),
+            _ => "bisect found first bad commit".to_string(),
+        });
+        Ok(output.join("\n"))
+    }
+}
+
+impl Command for Bisect {
+    /// Finds the commit that introduced a bug with a binary search over the history:
+    /// `bisect start [<bad> [<good>...]]` starts a session, `bisect good|bad|skip [<rev>...]` marks commits
+    /// (HEAD by default) and checks out the next one to test, `bisect reset [<commit>]` ends the session,
+    /// `bisect log` prints what was done, `bisect replay <file>` repeats a saved log and `bisect run <cmd>...`
+    /// tests commits automatically using the exit code of a command. The state is kept in `.git/BISECT_*` files.
+    fn execute(&self, args: Option<Vec<&str>>, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
+        let args = args.unwrap_or_default();
+        let (subcommand, rest) = args
+            .split_first()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::Other, "usage: bisect [start|good|bad|skip|reset|log|replay|run]"))?;
+        let output = match *subcommand {
+            BISECT_START => {
+                let revisions: Vec<&str> = rest.iter().copied().filter(|arg| *arg != PATHSPEC_SEPARATOR).collect();
+                Self::start(&revisions, path_handler)?;
+                Self::run_next_step(path_handler)?.1
+            }
+            BISECT_GOOD | BISECT_BAD | BISECT_SKIP => {
+                if !Self::is_bisecting(path_handler) {
+                    return Err(Self::not_bisecting());
+                }
+                let revisions = if rest.is_empty() { vec![HEAD] } else { rest.to_vec() };
+                let commits = revisions
+                    .iter()
+                    .map(|revision| revision::resolve_commit(revision, path_handler))
+                    .collect::<Result<Vec<String>, Box<dyn Error>>>()?;
+                if *subcommand == BISECT_BAD && commits.len() > 1 {
+                    return Err(Box::new(io::Error::new(io::ErrorKind::Other, "'git bisect bad' can take only one argument.")));
+                }
+                Self::mark(subcommand, &commits, path_handler)?;
+                Self::run_next_step(path_handler)?.1
+            }
+            BISECT_RESET => Self::reset(rest.first().copied(), path_handler)?,
+            BISECT_LOG => {
+                if !Self::is_bisecting(path_handler) {
+                    return Err(Self::not_bisecting());
+                }
+                helpers::read_file_content(&path_handler.get_relative_path(BISECT_LOG_FILE))?.trim_end().to_string()
+            }
+            BISECT_REPLAY => {
+                let log_file = rest
+                    .first()
+                    .ok_or_else(|| io::Error::new(io::ErrorKind::Other, "No logfile given"))?;
+                Self::replay(log_file, path_handler)?
+            }
+            BISECT_RUN => {
+                if !Self::is_bisecting(path_handler) {
+                    return Err(Self::not_bisecting());
+                }
+                Self::run(rest, path_handler)?
+            }
+            _ => {
+                return Err(Box::new(io::Error::new(
+                    io::ErrorKind::Other,
+                    format!("error: unknown bisect subcommand '{}'", subcommand),
+                )))
+            }
+        };
+        println!("{}", output);
+        Ok(output)
+    }
+}
+
+pub struct Reflog;
+
+impl Default for Reflog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Reflog {
+    pub fn new() -> Self {
+        Reflog {}
+    }
+
+    /// Returns the ref whose reflog a name refers to, or an error if there's none
+    fn find_log_ref(name: &str, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
+        reflog::find_log_ref(name, path_handler).ok_or_else(|| {
+            Box::new(io::Error::new(io::ErrorKind::Other, format!("fatal: ambiguous argument '{}': unknown revision", name)))
+                as Box<dyn Error>
+        })
+    }
+
+    /// Lists the entries of the reflog of a ref, newest first, as `<hash> <name>@{<n>}: <message>`
+    fn show(name: &str, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
+        let ref_name = Self::find_log_ref(name, path_handler)?;
+        let entries = reflog::read_entries(&ref_name, path_handler)?;
+        let lines: Vec<String> = entries
+            .iter()
+            .rev()
+            .enumerate()
+            .map(|(position, entry)| {
+                format!("{} {}@{{{}}}: {}", &entry.new_hash[..ABBREVIATED_HASH_LENGTH], name, position, entry.message)
+            })
+            .collect();
+        Ok(lines.join("\n"))
+    }
+
+    /// Returns every ref that has a reflog
+    fn get_all_log_refs(path_handler: &PathHandler) -> Result<Vec<String>, Box<dyn Error>> {
+        let logs_path = path_handler.get_relative_path(LOGS_DIR);
+        let mut log_refs: Vec<String> = helpers::get_files_in_directory_recursively(&logs_path)?
+            .into_iter()
+            .filter_map(|log_file| {
+                Path::new(&log_file)
+                    .strip_prefix(&logs_path)
+                    .ok()
+                    .map(|ref_name| ref_name.to_string_lossy().to_string())
+            })
+            .collect();
+        log_refs.sort();
+        Ok(log_refs)
+    }
+
+    /// Removes the entries older than the expiry time from the reflogs of the given refs.
+    /// Returns the entries that were removed.
+    fn expire(log_refs: &[String], expire_time: Option<i64>, dry_run: bool, path_handler: &PathHandler) -> Result<Vec<String>, Box<dyn Error>> {
+        let mut pruned = Vec::new();
+        let expire_time = match expire_time {
+            Some(expire_time) => expire_time,
+            None => return Ok(pruned),
+        };
+        for log_ref in log_refs {
+            let (expired, kept): (Vec<_>, Vec<_>) = reflog::read_entries(log_ref, path_handler)?
+                .into_iter()
+                .partition(|entry| entry.timestamp < expire_time);
+            pruned.extend(expired.iter().map(|entry| format!("would prune {}", entry.message)));
+            if !dry_run && !expired.is_empty() {
+                reflog::write_entries(log_ref, &kept, path_handler)?;
+            }
+        }
+        Ok(pruned)
+    }
+
+    /// Removes single entries given as `<ref>@{<n>}`
+    fn delete(selectors: &[&str], dry_run: bool, path_handler: &PathHandler) -> Result<Vec<String>, Box<dyn Error>> {
+        let mut positions_by_ref: HashMap<String, Vec<usize>> = HashMap::new();
+        for selector in selectors {
+            let invalid_selector =
+                || io::Error::new(io::ErrorKind::Other, format!("error: not a reflog: {}", selector));
+            let (name, position) = selector
+                .strip_suffix('}')
+                .and_then(|selector| selector.split_once("@{"))
+                .ok_or_else(invalid_selector)?;
+            let position: usize = position.parse().map_err(|_| invalid_selector())?;
+            let ref_name = Self::find_log_ref(if name.is_empty() { HEAD } else { name }, path_handler)?;
+            positions_by_ref.entry(ref_name).or_default().push(position);
+        }
+
+        let mut deleted = Vec::new();
+        for (ref_name, positions) in positions_by_ref {
+            let mut entries = reflog::read_entries(&ref_name, path_handler)?;
+            let mut indexes = positions
+                .iter()
+                .map(|position| {
+                    entries.len().checked_sub(position + 1).ok_or_else(|| {
+                        io::Error::new(io::ErrorKind::Other, format!("error: entry {}@{{{}}} doesn't exist", ref_name, position))
+                    })
+                })
+                .collect::<Result<Vec<usize>, io::Error>>()?;
+            indexes.sort_unstable();
+            indexes.dedup();
+            for index in indexes.into_iter().rev() {
+                let entry = entries.remove(index);
+                deleted.push(format!("would prune {}", entry.message));
+            }
+            if !dry_run {
+                reflog::write_entries(&ref_name, &entries, path_handler)?;
+            }
+        }
+        Ok(deleted)
+    }
+}
+
+impl Command for Reflog {
+    /// Manages the reflogs, the logs of the values each ref had:
+    /// `reflog [show] [<ref>]` lists the entries of a ref (HEAD by default), newest first.
+    /// `reflog expire [--expire <time>] [--all | <refs>...]` removes entries older than the given time,
+    /// 90 days by default. `reflog delete <ref>@{<n>}...` removes single entries. Both accept -n/--dry-run,
+    /// which returns what would be removed without removing it.
+    fn execute(&self, args: Option<Vec<&str>>, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
+        let args = args.unwrap_or_default();
+        let (subcommand, rest) = match args.split_first() {
+            Some((&REFLOG_EXPIRE, rest)) => (REFLOG_EXPIRE, rest),
+            Some((&REFLOG_DELETE, rest)) => (REFLOG_DELETE, rest),
+            Some((&REFLOG_SHOW, rest)) => (REFLOG_SHOW, rest),
+            _ => (REFLOG_SHOW, &args[..]),
+        };
+
+        let mut expire = DEFAULT_REFLOG_EXPIRE.to_string();
+        let mut all_flag = false;
+        let mut dry_run = false;
+        let mut names = Vec::new();
+        let mut rest = rest.iter();
+        while let Some(arg) = rest.next() {
+            match *arg {
+                EXPIRE_FLAG => {
+                    expire = rest
+                        .next()
+                        .ok_or_else(|| io::Error::new(io::ErrorKind::Other, "error: --expire requires a value"))?
+                        .to_string()
+                }
+                _ if arg.starts_with(&format!("{}=", EXPIRE_FLAG)) => expire = arg[EXPIRE_FLAG.len() + 1..].to_string(),
+                ALL_LONG_FLAG => all_flag = true,
+                DRY_RUN_FLAG | DRY_RUN_LONG_FLAG => dry_run = true,
+                _ => names.push(*arg),
+            }
+        }
+
+        let output = match subcommand {
+            REFLOG_EXPIRE => {
+                let log_refs = if all_flag {
+                    Self::get_all_log_refs(path_handler)?
+                } else {
+                    names
+                        .iter()
+                        .map(|name| Self::find_log_ref(name, path_handler))
+                        .collect::<Result<Vec<String>, Box<dyn Error>>>()?
+                };
+                let pruned = Self::expire(&log_refs, helpers::parse_expire_time(&expire)?, dry_run, path_handler)?;
+                if dry_run { pruned.join("\n") } else { String::new() }
+            }
+            REFLOG_DELETE => {
+                if names.is_empty() {
+                    return Err(Box::new(io::Error::new(io::ErrorKind::Other, "fatal: no reflog specified to delete")));
+                }
+                let deleted = Self::delete(&names, dry_run, path_handler)?;
+                if dry_run { deleted.join("\n") } else { String::new() }
+            }
+            _ => Self::show(names.first().copied().unwrap_or(HEAD), path_handler)?,
+        };
+        if !output.is_empty() {
+            println!("{}", output);
+        }
+        Ok(output)
+    }
+}
+
+pub struct LsTree;
+
+impl Default for LsTree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LsTree {
+    
+    pub fn new() -> Self {
+        LsTree {}
+    }
+
+    /// Adds a line for each entry of the tree to `entries`, walking the subtrees if the recurse flag is on.
+    /// Names are shown with `path_prefix` in front, which is the path of the tree being listed.
+    pub fn generate_tree_entries(
+        entries: &mut Vec<String>,
+        tree_hash: String,
+        path_prefix: &str,
+        direct_flag: bool,
+        recurse_flag: bool,
+        long_flag: bool,
+        path_handler: &PathHandler
+    ) -> Result<(), Box<dyn Error>> {
+        let current_hash = if helpers::is_object_hash(&tree_hash) {
+            tree_hash
+        } else {
+            let object_hash = revision::resolve_revision(&tree_hash, path_handler)?;
+            revision::peel_object(&object_hash, Some(ObjectType::Tree), path_handler)?
+        };
+        
+        let mut tree_content = helpers::read_tree_content(&current_hash, path_handler)?;
+        
+        if tree_content.is_empty() {
+            return Ok(());
+        }
+
+
+        for (file_mode, file_name, object_hash) in &mut tree_content {
+
+            let mut line: String = format!("{:0>6} {} {}{}", file_mode, object_hash, path_prefix, file_name);
+
+            let is_subtree = helpers::is_tree_mode(file_mode);
+            if !is_subtree && direct_flag {
+                // don't add files to the entries if direct flag is on
+                continue;
+            }
+
+            if long_flag && file_mode == GITLINK_MODE {
+                // gitlinks point to commits of another repository, so there is no size to show
+                line.push_str(" -");
+            } else if long_flag {
+                // add size to the line
+                let (_, _object_content, object_size) =
+                    helpers::read_object_to_string(object_hash.clone(), path_handler)?;
+                line.push(' ');
+                line.push_str(object_size.as_str());
+            }
+
+            if !is_subtree {
+                // add
+                entries.push(line.clone());
+                continue;
+            }
+
+            // if direct or not recursive add
+            if direct_flag || !recurse_flag {
+                entries.push(line.clone());
+            }
+            // if recursive loop
+            if recurse_flag {
+                LsTree::generate_tree_entries(
+                    entries,
+                    object_hash.clone(),
+                    &format!("{}{}/", path_prefix, file_name),
+                    direct_flag,
+                    recurse_flag,
+                    long_flag,
+                    path_handler
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Command for LsTree {
+    /// Executes the "git ls-tree" command.
+    fn execute(&self, args: Option<Vec<&str>>, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
+        let mut direct_flag = false;
+        let mut recurse_flag = false;
+        let mut long_flag = false;
+        let mut tree_entries: Vec<String> = Vec::new();
         let mut tree_hash: Option<String> = None;
         let arg_slice = args.unwrap_or_default();
 
@@ -1750,6 +4157,7 @@ impl Command for LsTree {
             LsTree::generate_tree_entries(
                 &mut tree_entries,
                 tree,
+                "",
                 direct_flag,
                 recurse_flag,
                 long_flag,
@@ -1788,6 +4196,28 @@ impl LsFiles {
             stg_area: StagingArea::new(),
         }
     }
+
+    /// Returns the untracked files of the working directory that are ignored.
+    /// Ignored directories are listed once, with a trailing '/'.
+    fn get_ignored_files(path_handler: &PathHandler) -> Result<Vec<String>, Box<dyn Error>> {
+        let ignore_rules = IgnoreRules::new(path_handler)?;
+        let index_entries = helpers::read_index_entries(path_handler).unwrap_or_default();
+        let ignored_directories = RefCell::new(Vec::new());
+        let working_files = helpers::get_working_tree_files(path_handler, &|directory| {
+            let ignored = ignore_rules.is_ignored(directory, true);
+            if ignored {
+                ignored_directories.borrow_mut().push(format!("{}/", directory));
+            }
+            ignored
+        })?;
+
+        let mut ignored_files = ignored_directories.into_inner();
+        ignored_files.extend(working_files.into_iter().filter(|file_path| {
+            !index_entries.iter().any(|(path, _hash, _state, _mode)| path == file_path)
+                && ignore_rules.is_ignored(file_path, false)
+        }));
+        Ok(ignored_files)
+    }
 }
 
 impl Command for LsFiles {
@@ -1817,7 +4247,7 @@ impl Command for LsFiles {
     /// let result = lsfiles_command.execute(&mut head, Some(vec!["-c"]));
     /// assert!(result.is_ok());
     /// ```
-    fn execute(&self, args: Option<Vec<&str>>, _path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
+    fn execute(&self, args: Option<Vec<&str>>, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
         let mut file_entries: HashSet<String> = HashSet::new();
         let whole_index_flag = args.is_none();
         let arg_slice = args.unwrap_or_default();
@@ -1839,11 +4269,8 @@ impl Command for LsFiles {
                     }
                 }
                 IGNORE_FLAG => {
-                    let file = fs::File::open(".gitignore.txt")?;
-                    let reader = io::BufReader::new(file);
-                    for line in reader.lines() {
-                        let line = line?;
-                        file_entries.insert(line);
+                    for ignored_path in LsFiles::get_ignored_files(path_handler)? {
+                        file_entries.insert(ignored_path);
                     }
                 }
                 _ => { /* ignore invalid flags */ }
@@ -1879,39 +4306,77 @@ impl CheckIgnore {
     pub fn new() -> Self {
         CheckIgnore {}
     }
+
+    /// Returns true if the file path is ignored by the .gitignore files, .git/info/exclude or core.excludesFile
+    pub fn is_ignored(file_path: &str, path_handler: &PathHandler) -> Result<bool, Box<dyn Error>> {
+        let ignore_rules = IgnoreRules::new(path_handler)?;
+        let path = helpers::normalize_repository_path(file_path, path_handler);
+        let is_directory = file_path.ends_with('/') || fs::metadata(path_handler.get_relative_path(&path)).is_ok_and(|metadata| metadata.is_dir());
+        Ok(ignore_rules.is_ignored(&path, is_directory))
+    }
 }
 
 impl Command for CheckIgnore {
-    /// Execute the command, checking for the existence of a file path in the .gitignore file.
-    /// Returns a `Result` containing a string. If the file path is found in the .gitignore file,
-    /// the path is returned; otherwise, an empty string is returned. Errors are wrapped
-    /// in the `Result` type.
-    fn execute(&self, args: Option<Vec<&str>>, _path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
-        //Checking if a .gitignore file exists
-        if fs::metadata(".gitignore.txt").is_err() {
-            return Ok(String::new());
+    /// Execute the command, checking each path against the ignore rules.
+    /// Supports -v/--verbose to show the file, line and pattern that matched, -n/--non-matching to also
+    /// show paths that didn't match and --no-index to also check paths that are already tracked.
+    /// Returns the printed lines, or an empty string if no path is ignored.
+    fn execute(&self, args: Option<Vec<&str>>, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
+        let mut verbose = false;
+        let mut non_matching = false;
+        let mut no_index = false;
+        let mut paths = Vec::new();
+        for arg in args.unwrap_or_default() {
+            match arg {
+                VERBOSE_FLAG | VERBOSE_LONG_FLAG => verbose = true,
+                NON_MATCHING_FLAG | NON_MATCHING_LONG_FLAG => non_matching = true,
+                NO_INDEX_FLAG => no_index = true,
+                PATHSPEC_SEPARATOR => {}
+                _ => paths.push(arg),
+            }
+        }
+        if non_matching && !verbose {
+            return Err(Box::new(io::Error::new(
+                io::ErrorKind::Other,
+                "Error: --non-matching is only valid with --verbose",
+            )));
         }
 
-        // Extract the arguments from the provided slice or use an empty slice if none is provided
-        let arg_slice = args.unwrap_or_default();
-        let file_path = arg_slice[0];
-
-        let file = fs::File::open(".gitignore.txt")?;
-        let reader = io::BufReader::new(file);
-
-        let line_exists = reader
-            .lines()
-            .any(|line| line.map_or(false, |l| file_path.starts_with(&l)));
-        if line_exists {
-            println!("{}", file_path);
-            return Ok(file_path.to_string());
+        let ignore_rules = IgnoreRules::new(path_handler)?;
+        let index_entries = if no_index {
+            Vec::new()
+        } else {
+            helpers::read_index_entries(path_handler).unwrap_or_default()
+        };
+        let mut lines = Vec::new();
+        for file_path in paths {
+            let path = helpers::normalize_repository_path(file_path, path_handler);
+            if index_entries.iter().any(|(index_path, _hash, _state, _mode)| *index_path == path) {
+                continue;
+            }
+            let is_directory = file_path.ends_with('/')
+                || fs::metadata(path_handler.get_relative_path(&path)).is_ok_and(|metadata| metadata.is_dir());
+            match ignore_rules.find_match(&path, is_directory) {
+                Some(ignore_match) if verbose => lines.push(format!(
+                    "{}:{}:{}\t{}",
+                    ignore_match.source, ignore_match.line_number, ignore_match.pattern, file_path
+                )),
+                Some(ignore_match) if !ignore_match.negated => lines.push(file_path.to_string()),
+                None if non_matching => lines.push(format!("::\t{}", file_path)),
+                _ => {}
+            }
         }
 
-        Ok(String::new())
+        for line in &lines {
+            println!("{}", line);
+        }
+        Ok(lines.join("\n"))
     }
 }
 
-pub struct Tag;
+pub struct Tag {
+    signer: Option<Box<dyn Signer>>,
+}
 
 impl Default for Tag {
     fn default() -> Self {
@@ -1921,73 +4386,296 @@ impl Default for Tag {
 
 impl Tag {
     pub fn new() -> Self {
-        Tag {}
+        Tag { signer: None }
     }
 
-    fn list_all_tags(&self) -> Result<(), Box<dyn Error>> {
-        // Read the contents of the directory
-        let entries = fs::read_dir(R_TAGS)?;
+    /// Returns a `Tag` that signs with the given signer instead of the configured one
+    pub fn with_signer(signer: Box<dyn Signer>) -> Self {
+        Tag { signer: Some(signer) }
+    }
 
-        for entry in entries {
-            let entry = entry?;
-            let file_name = entry.file_name();
-            let file_name_str = file_name.to_string_lossy(); // Convert to a String
+    /// Returns the names of all tags, including the ones nested in directories, sorted
+    fn get_all_tag_names(&self, path_handler: &PathHandler) -> Result<Vec<String>, Box<dyn Error>> {
+        Ok(refs::list_refs("refs/tags/", path_handler)?
+            .into_iter()
+            .filter_map(|(ref_name, _hash)| ref_name.strip_prefix("refs/tags/").map(String::from))
+            .collect())
+    }
 
-            println!("{}", file_name_str);
+    /// Returns the first lines of the message of a tag. For lightweight tags the message of the
+    /// tagged commit is used instead.
+    fn get_annotation(&self, name: &str, max_lines: usize, path_handler: &PathHandler) -> Result<Vec<String>, Box<dyn Error>> {
+        let tag_ref = format!("refs/tags/{}", name);
+        let hash = revision::read_ref(&tag_ref, path_handler).ok_or(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("Error: tag '{}' not found.", name),
+        ))?;
+        let (object_type, content, _size) = helpers::read_object_to_string(hash, path_handler)?;
+        if object_type != ObjectType::Tag && object_type != ObjectType::Commit {
+            return Ok(Vec::new());
         }
+        let message = content.split_once("\n\n").map_or("", |(_header, message)| message);
+        Ok(message.lines().take(max_lines).map(String::from).collect())
+    }
 
-        Ok(())
+    /// Lists the tags matching any of the given glob patterns, or all tags if there are none.
+    /// If `annotation_lines` is given, that many lines of each tag's message are shown next to it.
+    fn list_tags(&self, patterns: &[&str], annotation_lines: Option<usize>, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
+        let mut output = Vec::new();
+        for name in self.get_all_tag_names(path_handler)? {
+            if !patterns.is_empty() && !patterns.iter().any(|pattern| helpers::matches_glob(pattern, &name)) {
+                continue;
+            }
+            match annotation_lines {
+                Some(max_lines) => {
+                    let annotation = self.get_annotation(&name, max_lines, path_handler)?;
+                    let mut lines = annotation.iter();
+                    output.push(format!("{:<15} {}", name, lines.next().map_or("", String::as_str)));
+                    output.extend(lines.map(|line| format!("{:<15} {}", "", line)));
+                }
+                None => output.push(name),
+            }
+        }
+        for line in &output {
+            println!("{}", line);
+        }
+        Ok(output.join("\n"))
+    }
+
+    /// Writes an annotated tag object pointing to the given object, signed if there is a signer, and returns its hash
+    fn create_tag_object(
+        &self,
+        name: &str,
+        target: &str,
+        message: &str,
+        signer: Option<&dyn Signer>,
+        path_handler: &PathHandler
+    ) -> Result<String, Box<dyn Error>> {
+        let (target_type, _content, _size) = helpers::read_object_to_bytes(target.to_string(), path_handler)?;
+        let mut tag_content = format!(
+            "object {}\ntype {}\ntag {}\n{}\n\n{}\n",
+            target,
+            target_type,
+            name,
+            HashObjectCreator::generate_identity_line("tagger"),
+            message.trim_end()
+        );
+        if let Some(signer) = signer {
+            tag_content = signing::add_signature_header(&tag_content, &signer.sign(&tag_content)?);
+        }
+        HashObjectCreator::write_object_file(tag_content.clone(), ObjectType::Tag, tag_content.len() as u64, path_handler)
     }
 
-    fn add_new_lightweight_tag(&self, name: &str, path_handler: &PathHandler) -> Result<(), Box<dyn Error>> {
-        let last_commit = Head::get_head_commit(path_handler)?;
+    /// Creates a tag for the given revision. With a message it's an annotated tag, which points to a new
+    /// tag object, otherwise it's a lightweight tag pointing directly to the revision. Annotated tags are signed
+    /// if there is a signer. Existing tags are only replaced if `force` is set. Returns the hash the tag points to.
+    pub fn create_tag(
+        &self,
+        name: &str,
+        target_revision: &str,
+        message: Option<&str>,
+        signer: Option<&dyn Signer>,
+        force: bool,
+        path_handler: &PathHandler
+    ) -> Result<String, Box<dyn Error>> {
+        if name.is_empty() || name.starts_with('-') || !refs::check_ref_format(&format!("refs/tags/{}", name)) {
+            return Err(Box::new(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Error: '{}' is not a valid tag name.", name),
+            )));
+        }
+        if refs::ref_exists(&format!("refs/tags/{}", name), path_handler) && !force {
+            return Err(Box::new(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("Error: tag '{}' already exists", name),
+            )));
+        }
 
-        let tag_path = format!("{}/{}", R_TAGS, name);
-        let mut tag_file = fs::File::create(path_handler.get_relative_path(&tag_path))?;
+        let target = revision::resolve_revision(target_revision, path_handler)?;
+        let tag_hash = match message {
+            Some(message) => self.create_tag_object(name, &target, message, signer, path_handler)?,
+            None => target,
+        };
 
-        tag_file.write_all(last_commit.as_bytes())?;
+        let expected_value = if force { None } else { Some(ZERO_HASH) };
+        refs::update_ref(&format!("refs/tags/{}", name), &tag_hash, expected_value, None, path_handler)?;
 
-        Ok(())
+        Ok(tag_hash)
     }
 
-
     fn delete_tag(&self, name: &str, path_handler: &PathHandler) -> Result<(), Box<dyn Error>> {
-        let tag_path = format!("{}/{}", R_TAGS, name);
-        fs::remove_file(path_handler.get_relative_path(&tag_path))?;
-        Ok(())
+        if !refs::ref_exists(&format!("refs/tags/{}", name), path_handler) {
+            return Err(Box::new(io::Error::new(io::ErrorKind::NotFound, format!("error: tag '{}' not found.", name))));
+        }
+        refs::delete_ref(&format!("refs/tags/{}", name), None, path_handler)
     }
 }
 
 impl Command for Tag {
+    /// Executes the `tag` command. Without arguments, or with the list flag, it lists the tags matching the
+    /// given patterns, showing their messages with the annotation lines flag. With a name it creates a tag
+    /// for HEAD or the given revision, annotated if the annotate or message flags are used, and signed with the
+    /// sign flag, with the key given with the local user flag or, unless `--no-sign` is given, when `tag.gpgSign`
+    /// is set. Existing tags are replaced with the force flag,
+    /// the delete flag removes the given tags and the verify flag checks their signatures.
     fn execute(&self, args: Option<Vec<&str>>, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
-        if args.is_none() {
-            self.list_all_tags()?;
-            return Ok(String::new());
-        }
-
         let mut verify_flag = false;
+        let mut sign_flag = None;
+        let mut signing_key = None;
         let mut delete_flag = false;
         let mut list_flag = false;
-        let mut name = None;
-        let arg_slice = args.unwrap_or_default();
+        let mut annotate_flag = false;
+        let mut force_flag = false;
+        let mut annotation_lines = None;
+        let mut message = None;
+        let mut positional_args = Vec::new();
 
-        for arg in arg_slice {
+        let arg_slice = args.unwrap_or_default();
+        let mut arg_iter = arg_slice.iter();
+        while let Some(&arg) = arg_iter.next() {
             match arg {
                 LIST_FLAG => list_flag = true,
                 VERIFY_FLAG => verify_flag = true,
                 DELETE_FLAG => delete_flag = true,
-                _ => name = Some(arg),
+                ANNOTATE_FLAG => annotate_flag = true,
+                FORCE_FLAG => force_flag = true,
+                SIGN_TAG_FLAG => sign_flag = Some(true),
+                NO_SIGN_FLAG => sign_flag = Some(false),
+                MESSAGE_FLAG => {
+                    message = Some(*arg_iter.next().ok_or(io::Error::new(
+                        io::ErrorKind::Other,
+                        "Error: switch 'm' requires a value",
+                    ))?);
+                }
+                LOCAL_USER_FLAG => {
+                    sign_flag = Some(true);
+                    signing_key = Some(*arg_iter.next().ok_or(io::Error::new(
+                        io::ErrorKind::Other,
+                        "Error: switch 'u' requires a value",
+                    ))?);
+                }
+                _ => match arg.strip_prefix(ANNOTATION_LINES_FLAG) {
+                    Some("") => annotation_lines = Some(1),
+                    Some(lines) if lines.chars().all(|c| c.is_ascii_digit()) => annotation_lines = Some(lines.parse()?),
+                    _ => positional_args.push(arg),
+                },
             }
         }
 
-        match (verify_flag, delete_flag, list_flag, name) {
-            (false, false, false, Some(name)) => self.add_new_lightweight_tag(name, path_handler)?,
-            (_, true, _, Some(name)) => self.delete_tag(name, path_handler)?,
-            (_, _, true, _) => self.list_all_tags()?,
-            _ => {}
+        if verify_flag {
+            return VerifyTag::new().execute(Some(positional_args), path_handler);
+        }
+        if delete_flag {
+            for name in positional_args {
+                self.delete_tag(name, path_handler)?;
+                println!("Deleted tag '{}'", name);
+            }
+            return Ok(String::new());
+        }
+        if list_flag || annotation_lines.is_some() || positional_args.is_empty() {
+            return self.list_tags(&positional_args, annotation_lines, path_handler);
         }
 
-        Ok(String::new())
+        let sign = signing::is_signing_enabled(sign_flag, TAG_SIGN_CONFIG, path_handler)?;
+        if (annotate_flag || sign) && message.is_none() {
+            return Err(Box::new(io::Error::new(
+                io::ErrorKind::Other,
+                "Error: Annotated tags need a message (-m <message>).",
+            )));
+        }
+        let configured_signer;
+        let signer: Option<&dyn Signer> = match &self.signer {
+            _ if !sign => None,
+            Some(signer) => Some(signer.as_ref()),
+            None => {
+                configured_signer = signing::get_signer(signing_key, path_handler)?;
+                Some(configured_signer.as_ref())
+            }
+        };
+        let target_revision = positional_args.get(1).copied().unwrap_or(HEAD);
+        self.create_tag(positional_args[0], target_revision, message, signer, force_flag, path_handler)
+    }
+}
+
+pub struct VerifyCommit;
+
+impl Default for VerifyCommit {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl VerifyCommit {
+    pub fn new() -> Self {
+        VerifyCommit {}
+    }
+}
+
+impl Command for VerifyCommit {
+    /// Executes the `verify-commit` command, checking the signatures of the given commits. It fails if any of
+    /// them isn't signed or its signature isn't good.
+    fn execute(&self, args: Option<Vec<&str>>, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
+        let revisions = args.unwrap_or_default();
+        if revisions.is_empty() {
+            return Err(Box::new(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "usage: git verify-commit <commit>...",
+            )));
+        }
+        let mut output = Vec::new();
+        for revision in revisions {
+            let commit = revision::resolve_commit(revision, path_handler)?;
+            let description = signing::check_object_signature(&commit, path_handler)?;
+            println!("{}", description);
+            output.push(description);
+        }
+        Ok(output.join("\n"))
+    }
+}
+
+pub struct VerifyTag;
+
+impl Default for VerifyTag {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl VerifyTag {
+    pub fn new() -> Self {
+        VerifyTag {}
+    }
+}
+
+impl Command for VerifyTag {
+    /// Executes the `verify-tag` command, checking the signatures of the given tags. It fails if any of them is
+    /// a lightweight tag, isn't signed or its signature isn't good.
+    fn execute(&self, args: Option<Vec<&str>>, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
+        let names = args.unwrap_or_default();
+        if names.is_empty() {
+            return Err(Box::new(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "usage: git verify-tag <tag>...",
+            )));
+        }
+        let mut output = Vec::new();
+        for name in names {
+            let hash = match revision::read_ref(&format!("refs/tags/{}", name), path_handler) {
+                Some(hash) => hash,
+                None => revision::resolve_revision(name, path_handler)?,
+            };
+            let (object_type, _content, _size) = helpers::read_object_to_bytes(hash.clone(), path_handler)?;
+            if object_type != ObjectType::Tag {
+                return Err(Box::new(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("error: {}: cannot verify a non-tag object of type {}.", name, object_type),
+                )));
+            }
+            let description = signing::check_object_signature(&hash, path_handler)?;
+            println!("{}", description);
+            output.push(description);
+        }
+        Ok(output.join("\n"))
     }
 }
 
@@ -2004,38 +4692,491 @@ impl ShowRef {
         ShowRef {}
     }
 
-    fn show_refs_in_directory(
-        &self,
-        directory_entries: ReadDir,
-        partial_path: &str,
-    ) -> Result<(), Box<dyn Error>> {
-        for entry in directory_entries {
-            let entry = entry?;
-            let file_name = entry.file_name();
-            let file_name_str = file_name.to_string_lossy(); // Convert to a String
+    /// Tells whether a ref matches a pattern, which has to match whole components at the end of its name. So
+    /// `master` matches both `refs/heads/master` and `refs/remotes/origin/master`.
+    fn matches_pattern(ref_name: &str, pattern: &str) -> bool {
+        ref_name == pattern || ref_name.ends_with(&format!("/{}", pattern.trim_start_matches('/')))
+    }
+
+    /// Returns the (hash, ref) pairs of the refs, loose and packed, that match any of the patterns. With `heads` or
+    /// `tags` only branches or tags are shown.
+    fn show_refs(&self, heads: bool, tags: bool, patterns: &[&str], path_handler: &PathHandler) -> Result<Vec<(String, String)>, Box<dyn Error>> {
+        let prefixes = match (heads, tags) {
+            (false, false) => vec!["refs/"],
+            _ => heads.then_some("refs/heads/").into_iter().chain(tags.then_some("refs/tags/")).collect(),
+        };
+        let mut shown_refs = Vec::new();
+        for prefix in prefixes {
+            for (ref_name, hash) in refs::list_refs(prefix, path_handler)? {
+                if !hash.is_empty() && (patterns.is_empty() || patterns.iter().any(|pattern| Self::matches_pattern(&ref_name, pattern))) {
+                    shown_refs.push((hash, ref_name));
+                }
+            }
+        }
+        Ok(shown_refs)
+    }
+
+    /// Returns the (hash, ref) pairs of refs given by their full name, failing if any of them doesn't exist
+    fn verify_refs(&self, ref_names: &[&str], path_handler: &PathHandler) -> Result<Vec<(String, String)>, Box<dyn Error>> {
+        if ref_names.is_empty() {
+            return Err(Box::new(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "fatal: --verify requires a reference",
+            )));
+        }
+        let mut verified_refs = Vec::new();
+        for ref_name in ref_names {
+            let hash = match *ref_name {
+                HEAD => Some(Head::get_head_commit(path_handler)?).filter(|hash| !hash.is_empty()),
+                _ if ref_name.starts_with("refs/") => refs::read_ref(ref_name, path_handler),
+                _ => None,
+            };
+            match hash {
+                Some(hash) => verified_refs.push((hash, ref_name.to_string())),
+                None => {
+                    return Err(Box::new(io::Error::new(
+                        io::ErrorKind::NotFound,
+                        format!("fatal: '{}' - not a valid ref", ref_name),
+                    )))
+                }
+            }
+        }
+        Ok(verified_refs)
+    }
+}
+
+impl Command for ShowRef {
+    /// Lists refs as `<hash> <ref>` lines. Patterns select the refs whose names end with them, and `--heads` and
+    /// `--tags` restrict the listing to branches and tags. With `--verify`, the arguments are full ref names that
+    /// must all exist. `--hash[=<n>]` shows only the hashes, abbreviated to n characters if given.
+    fn execute(&self, args: Option<Vec<&str>>, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
+        let mut heads = false;
+        let mut tags = false;
+        let mut verify = false;
+        let mut hash_length = None;
+        let mut patterns = Vec::new();
+        for arg in args.unwrap_or_default() {
+            match arg {
+                HEADS_FLAG => heads = true,
+                TAGS_FLAG => tags = true,
+                VERIFY_LONG_FLAG => verify = true,
+                HASH_FLAG => hash_length = Some(HASH_LENGTH),
+                _ if arg.starts_with(&format!("{}=", HASH_FLAG)) => {
+                    let length = arg[HASH_FLAG.len() + 1..]
+                        .parse::<usize>()
+                        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, format!("fatal: invalid --hash value: {}", arg)))?;
+                    hash_length = Some(length.clamp(MIN_ABBREVIATED_HASH_LENGTH, HASH_LENGTH));
+                }
+                _ => patterns.push(arg),
+            }
+        }
+
+        let shown_refs = match verify {
+            true => self.verify_refs(&patterns, path_handler)?,
+            false => self.show_refs(heads, tags, &patterns, path_handler)?,
+        };
+        let lines: Vec<String> = shown_refs
+            .into_iter()
+            .map(|(hash, ref_name)| match hash_length {
+                Some(length) => hash[..length].to_string(),
+                None => format!("{} {}", hash, ref_name),
+            })
+            .collect();
+        for line in &lines {
+            println!("{}", line);
+        }
+        Ok(lines.join("\n"))
+    }
+}
+
+pub struct ForEachRef;
+
+impl Default for ForEachRef {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ForEachRef {
+    pub fn new() -> Self {
+        ForEachRef {}
+    }
+
+    /// Returns the value of a field of a ref, such as `refname` or `upstream:short`. Commits have a
+    /// `committerdate`, which can be given as `:unix`, `:iso` or `:short`.
+    fn get_field(field: &str, ref_name: &str, hash: &str, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
+        let (name, modifier) = match field.split_once(':') {
+            Some((name, modifier)) => (name, Some(modifier)),
+            None => (field, None),
+        };
+        let value = match name {
+            "refname" => ref_name.to_string(),
+            "objectname" => hash.to_string(),
+            "objecttype" => helpers::read_object_to_string(hash.to_string(), path_handler)?.0.to_string(),
+            "upstream" => match ref_name.strip_prefix("refs/heads/") {
+                Some(branch_name) => helpers::get_upstream_ref(branch_name, path_handler)?.unwrap_or_default(),
+                None => String::new(),
+            },
+            "committerdate" => {
+                let (object_type, content, _) = helpers::read_object_to_string(hash.to_string(), path_handler)?;
+                let committer = revision::get_header_field(&content, "committer");
+                let (Some(committer), ObjectType::Commit) = (committer, object_type) else {
+                    return Ok(String::new());
+                };
+                let (_, _, timestamp, timezone) = Blame::parse_identity(&committer);
+                let date_format = match modifier {
+                    None => "%a %b %-d %H:%M:%S %Y %z",
+                    Some("iso") => "%Y-%m-%d %H:%M:%S %z",
+                    Some("short") => "%Y-%m-%d",
+                    Some("unix") => return Ok(timestamp.to_string()),
+                    Some(modifier) => {
+                        return Err(Box::new(io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            format!("fatal: unknown date format: {}", modifier),
+                        )))
+                    }
+                };
+                return Ok(helpers::format_timestamp(timestamp, &timezone, date_format));
+            }
+            _ => {
+                return Err(Box::new(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("fatal: unknown field name: {}", name),
+                )))
+            }
+        };
+        match modifier {
+            None => Ok(value),
+            Some("short") if name == "objectname" => Ok(value[..SHORT_HASH_LENGTH].to_string()),
+            Some("short") if name != "objecttype" => Ok(helpers::shorten_ref_name(&value)),
+            Some(modifier) => Err(Box::new(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("fatal: unrecognized %({}) argument: {}", name, modifier),
+            ))),
+        }
+    }
+
+    /// Expands the `%(field)` atoms of a format for a ref. `%%` is a literal `%` and `%xx` the character with the
+    /// hexadecimal code xx, so `%09` is a tab.
+    fn format_ref(format: &str, ref_name: &str, hash: &str, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
+        let mut formatted = String::new();
+        let mut rest = format;
+        while let Some(position) = rest.find('%') {
+            formatted.push_str(&rest[..position]);
+            rest = &rest[position + 1..];
+            if let Some((field, after_field)) = rest.strip_prefix('(').and_then(|rest| rest.split_once(')')) {
+                formatted.push_str(&Self::get_field(field, ref_name, hash, path_handler)?);
+                rest = after_field;
+            } else if let Some(code) = rest.get(..2).and_then(|code| u8::from_str_radix(code, 16).ok()) {
+                formatted.push(code as char);
+                rest = &rest[2..];
+            } else {
+                formatted.push('%');
+                rest = rest.strip_prefix('%').unwrap_or(rest);
+            }
+        }
+        formatted.push_str(rest);
+        Ok(formatted)
+    }
+
+    /// Tells whether a ref matches a pattern, which is either a prefix of whole components of its name or a glob
+    fn matches_pattern(ref_name: &str, pattern: &str) -> bool {
+        let prefix = pattern.trim_end_matches('/');
+        ref_name == prefix || ref_name.starts_with(&format!("{}/", prefix)) || helpers::matches_glob(pattern, ref_name)
+    }
+
+    /// Sorts refs by a key, a field prefixed with `-` for descending order. Dates are compared as timestamps.
+    /// The sort is stable, so refs that are equal keep the order of previous sorts.
+    fn sort_refs(refs: Vec<(String, String)>, key: &str, path_handler: &PathHandler) -> Result<Vec<(String, String)>, Box<dyn Error>> {
+        let (descending, field) = match key.strip_prefix('-') {
+            Some(field) => (true, field),
+            None => (false, key),
+        };
+        let by_date = field == "committerdate";
+        let field = if by_date { "committerdate:unix" } else { field };
+        let mut keyed_refs = Vec::new();
+        for (ref_name, hash) in refs {
+            keyed_refs.push((Self::get_field(field, &ref_name, &hash, path_handler)?, (ref_name, hash)));
+        }
+        keyed_refs.sort_by(|(value, _), (other_value, _)| {
+            let ordering = match by_date {
+                true => value.parse::<i64>().unwrap_or(0).cmp(&other_value.parse::<i64>().unwrap_or(0)),
+                false => value.cmp(other_value),
+            };
+            if descending {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        });
+        Ok(keyed_refs.into_iter().map(|(_value, listed_ref)| listed_ref).collect())
+    }
+}
+
+impl Command for ForEachRef {
+    /// Lists the refs matching the given patterns, or all of them, with `--format <format>` (by default
+    /// `%(objectname) %(objecttype)\t%(refname)`). Refs are sorted by name, or by each `--sort <key>`, the last one
+    /// taking precedence, and `--count <n>` shows only the first n.
+    fn execute(&self, args: Option<Vec<&str>>, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
+        let mut format = DEFAULT_FOR_EACH_REF_FORMAT.to_string();
+        let mut sort_keys = Vec::new();
+        let mut count = None;
+        let mut patterns = Vec::new();
+        let parse_count = |count: Option<&str>| {
+            count
+                .and_then(|count| count.parse::<usize>().ok())
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "fatal: invalid --count argument"))
+        };
+        let mut args = args.unwrap_or_default().into_iter();
+        while let Some(arg) = args.next() {
+            match arg {
+                FORMAT_FLAG => format = args.next().unwrap_or_default().to_string(),
+                SORT_FLAG => sort_keys.extend(args.next()),
+                COUNT_LONG_FLAG => count = Some(parse_count(args.next())?),
+                _ if arg.starts_with(&format!("{}=", FORMAT_FLAG)) => format = arg[FORMAT_FLAG.len() + 1..].to_string(),
+                _ if arg.starts_with(&format!("{}=", SORT_FLAG)) => sort_keys.push(&arg[SORT_FLAG.len() + 1..]),
+                _ if arg.starts_with(&format!("{}=", COUNT_LONG_FLAG)) => {
+                    count = Some(parse_count(Some(&arg[COUNT_LONG_FLAG.len() + 1..]))?)
+                }
+                _ => patterns.push(arg),
+            }
+        }
+
+        let mut listed_refs: Vec<(String, String)> = refs::list_refs("refs/", path_handler)?
+            .into_iter()
+            .filter(|(ref_name, hash)| {
+                !hash.is_empty() && (patterns.is_empty() || patterns.iter().any(|pattern| Self::matches_pattern(ref_name, pattern)))
+            })
+            .collect();
+        for key in sort_keys {
+            listed_refs = Self::sort_refs(listed_refs, key, path_handler)?;
+        }
+        if let Some(count) = count {
+            listed_refs.truncate(count);
+        }
+
+        let mut lines = Vec::new();
+        for (ref_name, hash) in listed_refs {
+            lines.push(Self::format_ref(&format, &ref_name, &hash, path_handler)?);
+        }
+        for line in &lines {
+            println!("{}", line);
+        }
+        Ok(lines.join("\n"))
+    }
+}
+
+/// How the describe command names a commit: with any tag or only annotated ones, always in the long format, with
+/// hashes abbreviated to `abbrev` characters (0 shows only the tag) and falling back to the hash if no tag is found
+pub struct DescribeOptions {
+    pub all_tags: bool,
+    pub long: bool,
+    pub abbrev: usize,
+    pub always: bool,
+}
+
+impl Default for DescribeOptions {
+    fn default() -> Self {
+        DescribeOptions {
+            all_tags: false,
+            long: false,
+            abbrev: SHORT_HASH_LENGTH,
+            always: false,
+        }
+    }
+}
+
+/// A tag that can describe commits: the commit it points to, its name and whether it's annotated
+type DescribeTag = (String, String, bool);
+
+pub struct Describe;
+
+impl Default for Describe {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Describe {
+    pub fn new() -> Self {
+        Describe {}
+    }
+
+    /// Returns the tags pointing to commits
+    fn get_tags(path_handler: &PathHandler) -> Result<Vec<DescribeTag>, Box<dyn Error>> {
+        let mut tags = Vec::new();
+        for (ref_name, hash) in refs::list_refs("refs/tags/", path_handler)? {
+            if hash.is_empty() {
+                continue;
+            }
+            // tags of trees or blobs can't describe a commit, and only an annotated tag peels to another object
+            if let Ok(commit) = revision::peel_object(&hash, Some(ObjectType::Commit), path_handler) {
+                let tag_name = ref_name.trim_start_matches("refs/tags/").to_string();
+                let annotated = commit != hash;
+                tags.push((commit, tag_name, annotated));
+            }
+        }
+        Ok(tags)
+    }
+
+    /// Tells whether the index or the working tree have changes to tracked files compared to a commit
+    fn has_local_changes(commit_hash: &str, path_handler: &PathHandler) -> Result<bool, Box<dyn Error>> {
+        let commit_files = Checkout::get_commit_files(commit_hash, path_handler)?;
+        let index_entries: Vec<helpers::IndexEntry> = helpers::read_index_entries(path_handler)?
+            .into_iter()
+            .filter(|(_path, _hash, state, _mode)| *state != IndexFileEntryState::Deleted.to_string())
+            .collect();
+        if index_entries.len() != commit_files.len() {
+            return Ok(true);
+        }
+        for (path, hash, _state, mode) in &index_entries {
+            let committed_file = commit_files.get(path);
+            let working_hash = helpers::hash_working_file(path, path_handler).ok();
+            let working_mode = helpers::get_file_mode(path, path_handler).ok();
+            if committed_file != Some(&(mode.clone(), hash.clone()))
+                || working_hash.as_ref() != Some(hash)
+                || working_mode.as_ref() != Some(mode)
+            {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Names a commit after the nearest tag it can reach, as `<tag>-<distance>-g<abbreviated hash>`, where the
+    /// distance is the number of commits reachable from it that aren't reachable from the tag. A commit that is
+    /// tagged is named just by the tag, unless the long format is wanted. Of several tags, the one with the least
+    /// distance is chosen, preferring annotated ones.
+    /// Like git, the history is walked once breadth-first from the commit. Every tag found becomes a candidate that
+    /// marks the commits it reaches, and its distance grows with each walked commit it doesn't reach. The walk
+    /// stops once every commit left to walk is reached by the best candidate, as its distance can't grow anymore.
+    pub fn describe_commit(&self, commit_hash: &str, options: &DescribeOptions, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
+        let tags = Self::get_tags(path_handler)?;
+        if tags.is_empty() && !options.always {
+            return Err(Box::new(io::Error::new(
+                io::ErrorKind::NotFound,
+                "fatal: No names found, cannot describe anything.",
+            )));
+        }
+
+        let mut tags_by_commit: HashMap<&str, Vec<(&str, bool)>> = HashMap::new();
+        for (tag_commit, tag_name, annotated) in &tags {
+            tags_by_commit.entry(tag_commit).or_default().push((tag_name, *annotated));
+        }
 
-            let content = std::fs::read_to_string(entry.path())?;
+        // candidates are (distance, not annotated, tag name), and the bit of each one is its position
+        let mut candidates: Vec<(usize, bool, &str)> = Vec::new();
+        let mut reached_by: HashMap<String, u32> = HashMap::new();
+        let mut seen = HashSet::from([commit_hash.to_string()]);
+        let mut pending = VecDeque::from([commit_hash.to_string()]);
+        let mut unannotated_reachable = false;
+        let mut walked = 0;
+        while let Some(commit) = pending.pop_front() {
+            walked += 1;
+            let mut commit_reached_by = reached_by.get(&commit).copied().unwrap_or_default();
+            for (tag_name, annotated) in tags_by_commit.get(commit.as_str()).into_iter().flatten() {
+                if !annotated && !options.all_tags {
+                    unannotated_reachable = true;
+                } else if candidates.len() < DESCRIBE_MAX_CANDIDATES {
+                    // the commits walked before it aren't reached by the tag
+                    commit_reached_by |= 1 << candidates.len();
+                    candidates.push((walked - 1, !annotated, tag_name));
+                }
+            }
+            for (bit, candidate) in candidates.iter_mut().enumerate() {
+                if commit_reached_by & (1 << bit) == 0 {
+                    candidate.0 += 1;
+                }
+            }
+            for parent in revision::get_commit_parents(&commit, path_handler)? {
+                *reached_by.entry(parent.clone()).or_default() |= commit_reached_by;
+                if seen.insert(parent.clone()) {
+                    pending.push_back(parent);
+                }
+            }
+            reached_by.insert(commit, commit_reached_by);
 
-            println!("{} {}{}", content, partial_path, file_name_str);
+            if let Some(best_bit) = (0..candidates.len()).min_by_key(|bit| candidates[*bit]) {
+                if pending.iter().all(|commit| reached_by[commit] & (1 << best_bit) != 0) {
+                    break;
+                }
+            }
+        }
+        let best_tag = candidates.into_iter().min();
+
+        let abbreviated_hash = match options.abbrev {
+            0 => commit_hash,
+            abbrev => &commit_hash[..abbrev.clamp(MIN_ABBREVIATED_HASH_LENGTH, HASH_LENGTH)],
+        };
+        match best_tag {
+            Some((0, _, tag_name)) if !options.long => Ok(tag_name.to_string()),
+            Some((_, _, tag_name)) if options.abbrev == 0 => Ok(tag_name.to_string()),
+            Some((distance, _, tag_name)) => Ok(format!("{}-{}-g{}", tag_name, distance, abbreviated_hash)),
+            None if options.always => Ok(abbreviated_hash.to_string()),
+            None if unannotated_reachable => Err(Box::new(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("fatal: No annotated tags can describe '{}'.\nHowever, there were unannotated tags: try --tags.", commit_hash),
+            ))),
+            None => Err(Box::new(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("fatal: No tags can describe '{}'.\nTry --always, or create some tags.", commit_hash),
+            ))),
         }
-        Ok(())
     }
 }
 
-impl Command for ShowRef {
-    fn execute(&self, _args: Option<Vec<&str>>, _path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
-        // Read the contents of the directory
-        let branch_entries = fs::read_dir(R_HEADS)?;
-        self.show_refs_in_directory(branch_entries, "refs/heads/")?;
-
-        let tags_entries = fs::read_dir(R_TAGS)?;
-        self.show_refs_in_directory(tags_entries, "refs/tags/")?;
+impl Command for Describe {
+    /// Names a revision, HEAD by default, after the nearest tag it can reach. Supports `--tags` to use lightweight
+    /// tags too, `--long`, `--abbrev=<n>`, `--always` and `--dirty[=<mark>]`, which appends a mark (`-dirty` by
+    /// default) when the working tree has changes to tracked files.
+    fn execute(&self, args: Option<Vec<&str>>, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
+        let mut options = DescribeOptions::default();
+        let mut dirty_mark = None;
+        let mut revisions = Vec::new();
+        for arg in args.unwrap_or_default() {
+            match arg {
+                TAGS_FLAG => options.all_tags = true,
+                DESCRIBE_LONG_FLAG => options.long = true,
+                ALWAYS_FLAG => options.always = true,
+                DIRTY_FLAG => dirty_mark = Some(DEFAULT_DIRTY_MARK),
+                _ if arg.starts_with(&format!("{}=", DIRTY_FLAG)) => dirty_mark = Some(&arg[DIRTY_FLAG.len() + 1..]),
+                _ if arg.starts_with(&format!("{}=", ABBREV_FLAG)) => {
+                    options.abbrev = arg[ABBREV_FLAG.len() + 1..]
+                        .parse()
+                        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, format!("fatal: invalid --abbrev value: {}", arg)))?;
+                }
+                _ => revisions.push(arg),
+            }
+        }
+        if dirty_mark.is_some() && !revisions.is_empty() {
+            return Err(Box::new(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "fatal: option '--dirty' and commit-ishes cannot be used together",
+            )));
+        }
+        if revisions.is_empty() {
+            revisions.push(HEAD);
+        }
 
-        Ok(String::new())
+        let mut names = Vec::new();
+        for revision in revisions {
+            let commit_hash = revision::resolve_commit(revision, path_handler)?;
+            let mut name = self.describe_commit(&commit_hash, &options, path_handler)?;
+            if let Some(dirty_mark) = dirty_mark {
+                if Self::has_local_changes(&commit_hash, path_handler)? {
+                    name.push_str(dirty_mark);
+                }
+            }
+            println!("{}", name);
+            names.push(name);
+        }
+        Ok(names.join("\n"))
     }
 }
 
-pub struct Merge;
+pub struct Merge {
+    signer: Option<Box<dyn Signer>>,
+}
 
 impl Default for Merge {
     fn default() -> Self {
@@ -2045,13 +5186,46 @@ impl Default for Merge {
 
 impl Merge {
     pub fn new() -> Self {
-        Merge {}
+        Merge { signer: None }
+    }
+
+    /// Returns a `Merge` that signs with the given signer instead of the configured one
+    pub fn with_signer(signer: Box<dyn Signer>) -> Self {
+        Merge { signer: Some(signer) }
     }
 }
 
 impl Command for Merge {
+    /// Executes the `merge` command, merging the given branch into the current one, or into the second branch
+    /// given. With `--continue` the merge is committed once its conflicts are solved. The merge commit is signed
+    /// like the ones of the commit command: with the sign flag, or `commit.gpgSign` unless `--no-gpg-sign` is given.
     fn execute(&self, args: Option<Vec<&str>>, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
-        let arg_slice = args.unwrap_or_default();
+        let mut sign_flag = None;
+        let mut signing_key = None;
+        let mut arg_slice = Vec::new();
+        for arg in args.unwrap_or_default() {
+            match arg {
+                SIGN_COMMIT_FLAG | GPG_SIGN_LONG_FLAG => sign_flag = Some(true),
+                NO_GPG_SIGN_LONG_FLAG => sign_flag = Some(false),
+                _ => match arg.strip_prefix(GPG_SIGN_LONG_FLAG).and_then(|key| key.strip_prefix('=')) {
+                    Some(key) => {
+                        sign_flag = Some(true);
+                        signing_key = Some(key);
+                    }
+                    None => arg_slice.push(arg),
+                },
+            }
+        }
+        let sign = signing::is_signing_enabled(sign_flag, COMMIT_SIGN_CONFIG, path_handler)?;
+        let configured_signer;
+        let signer: Option<&dyn Signer> = match &self.signer {
+            _ if !sign => None,
+            Some(signer) => Some(signer.as_ref()),
+            None => {
+                configured_signer = signing::get_signer(signing_key, path_handler)?;
+                Some(configured_signer.as_ref())
+            }
+        };
 
         if let Some(arg) = arg_slice.first() {
             if *arg == CONTINUE_FLAG {
@@ -2068,7 +5242,7 @@ impl Command for Merge {
                 println!("merging hash: {}", merging_hash);
                 fs::remove_file(path_handler.get_relative_path(MERGE_HEAD))?;
                 
-                let new_commit_hash = helpers::create_merged_working_tree(Head::get_head_commit(path_handler)?, merging_hash, path_handler)?;
+                let new_commit_hash = helpers::create_merged_working_tree(Head::get_head_commit(path_handler)?, merging_hash, signer, path_handler)?;
                 return Ok(new_commit_hash)
             }
         }
@@ -2095,7 +5269,7 @@ impl Command for Merge {
         }
 
         let mut branch_to_merge_path = helpers::get_branch_path(branch_to_merge);
-        if !helpers::check_if_file_exists(&branch_to_merge_path, path_handler) {
+        if !refs::ref_exists(&format!("refs/heads/{}", branch_to_merge), path_handler) {
             // This means the branch is a remote branch
             branch_to_merge_path = format!("{}/{}", R_REMOTES, branch_to_merge);
         }
@@ -2107,7 +5281,7 @@ impl Command for Merge {
             return Ok(String::new())
         }
 
-        let new_commit_hash = helpers::create_merged_working_tree(head_commit, merging_commit_hash, path_handler)?;
+        let new_commit_hash = helpers::create_merged_working_tree(head_commit, merging_commit_hash, signer, path_handler)?;
 
         Ok(new_commit_hash)
     }
@@ -2151,37 +5325,506 @@ impl Command for Rebase {
                     rebasing_commit = helpers::get_branch_last_commit(&helpers::get_branch_path(&rebasing_branch_name), path_handler)?;
                 }
             }
-            None => return Err(Box::new(io::Error::new(
-                io::ErrorKind::Other,
-                "Error: No argument was provided for rebase.",
-            ))),
+            None => return Err(Box::new(io::Error::new(
+                io::ErrorKind::Other,
+                "Error: No argument was provided for rebase.",
+            ))),
+        }
+
+        let mut rebasing_commit_log: Vec<(String, String)> = Vec::new();
+        Log::generate_log_entries(&mut rebasing_commit_log, rebasing_commit, path_handler)?;
+        let mut previous_commit = Head::get_head_commit(path_handler)?;
+        for (commit, _message) in rebasing_commit_log.iter().rev() {
+            println!("{}Applying:{} {}", COLOR_RED_CODE, COLOR_RESET_CODE, commit);
+            let branch_name = format!("rebase_{}", rebasing_branch_name);
+            let _ = Branch::new().create_new_branch(&branch_name, path_handler);
+            helpers::update_branch_hash(&branch_name, &commit.clone(), None, "rebase", path_handler)?;
+
+            match helpers::determine_new_working_tree(Head::get_head_commit(path_handler)?, commit.clone(), path_handler) {
+                Ok(_) => {
+                    let _ = Branch::new().execute(Some(vec![FORCE_DELETE_FLAG, &branch_name]), path_handler)?;
+                    let new_commit = HashObjectCreator::create_commit_object(None, vec![previous_commit.clone()], path_handler)?;
+                    let new_tree = helpers::get_commit_tree(&new_commit, path_handler)?;
+                    helpers::update_branch_hash(&Head::get_current_branch_name(path_handler)?, &new_commit, Some(&previous_commit), &format!("rebase: {}", commit), path_handler)?;
+                    WorkingDirectory::update_working_directory_to(&new_tree, path_handler)?;
+                    previous_commit = new_commit;
+                }
+                Err(_) => {
+                    let mut rebase_head = fs::File::create(path_handler.get_relative_path(REBASE_HEAD))?;
+                    rebase_head.write_all(commit.as_bytes())?;
+                }
+            }
+        }
+        Ok(String::new())
+    }
+}
+
+pub struct Repack;
+
+impl Default for Repack {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Repack {
+    pub fn new() -> Self {
+        Repack {}
+    }
+
+    /// Removes a pack and its index. The index goes first so the pack is never looked up half deleted.
+    fn remove_pack(pack_name: &str, path_handler: &PathHandler) -> Result<(), Box<dyn Error>> {
+        let index_path = packfile::get_index_path(pack_name);
+        let pack_path = packfile::get_pack_path(pack_name);
+        fs::remove_file(path_handler.get_relative_path(&index_path))?;
+        fs::remove_file(path_handler.get_relative_path(&pack_path))?;
+        Ok(())
+    }
+
+    /// Removes the pack files that have no index. Their objects were already unpacked when received.
+    fn remove_unindexed_packs(path_handler: &PathHandler) -> Result<(), Box<dyn Error>> {
+        for entry in fs::read_dir(path_handler.get_relative_path(PACK))? {
+            let file_name = entry?.file_name().to_string_lossy().to_string();
+            if let Some(pack_name) = file_name.strip_suffix(PACK_EXTENSION) {
+                let index_path = packfile::get_index_path(pack_name);
+                if !helpers::check_if_file_exists(&index_path, path_handler) {
+                    fs::remove_file(path_handler.get_relative_path(&format!("{}/{}", PACK, file_name)))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the unreachable objects of the given packs as loose objects before the packs are deleted.
+    /// The loose objects keep the modification time of their pack, so prune's grace period applies to them.
+    fn loosen_unreachable_objects(
+        pack_names: &[String],
+        reachable: &HashSet<String>,
+        path_handler: &PathHandler
+    ) -> Result<(), Box<dyn Error>> {
+        for pack_name in pack_names {
+            let index_path = packfile::get_index_path(pack_name);
+            let pack_path = packfile::get_pack_path(pack_name);
+            let pack_modified = fs::metadata(path_handler.get_relative_path(&pack_path))?.modified()?;
+            let index = helpers::read_file_content_to_bytes(&path_handler.get_relative_path(&index_path))?;
+
+            for hash in packfile::read_index_hashes(&index)? {
+                let object_path = helpers::get_object_path(&hash);
+                if reachable.contains(&hash) || helpers::check_if_file_exists(&object_path, path_handler) {
+                    continue;
+                }
+                let (object_type, content, _size) = helpers::read_object_to_bytes(hash, path_handler)?;
+                HashObjectCreator::write_object_file_bytes(&content, object_type, content.len(), path_handler)?;
+                fs::File::options()
+                    .write(true)
+                    .open(path_handler.get_relative_path(&object_path))?
+                    .set_modified(pack_modified)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Command for Repack {
+    /// Executes the `repack` command, which packs the reachable loose objects into a new delta compressed
+    /// pack with its index. With the all flag every reachable object is packed, including the ones already
+    /// in packs. With the delete flag redundant packs and the loose objects that were packed are removed.
+    /// The loosen-unreachable flag works like the all flag but unreachable objects of the deleted packs are
+    /// kept as loose objects so they can be pruned later.
+    fn execute(&self, args: Option<Vec<&str>>, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
+        let mut all_flag = false;
+        let mut loosen_flag = false;
+        let mut delete_flag = false;
+
+        for arg in args.unwrap_or_default() {
+            match arg {
+                ALL_FLAG => all_flag = true,
+                LOOSEN_UNREACHABLE_FLAG => {
+                    all_flag = true;
+                    loosen_flag = true;
+                }
+                DELETE_FLAG => delete_flag = true,
+                _ => {
+                    return Err(Box::new(io::Error::new(
+                        io::ErrorKind::Other,
+                        format!("Error: Unknown option {}", arg),
+                    )))
+                }
+            }
+        }
+
+        let reachable = helpers::get_reachable_objects(path_handler)?;
+        let loose_objects = helpers::get_loose_objects(path_handler)?;
+        let previous_packs = packfile::list_indexed_packs(path_handler)?;
+
+        let mut objects_to_pack: Vec<String> = if all_flag {
+            reachable.iter().cloned().collect()
+        } else {
+            loose_objects.iter().filter(|hash| reachable.contains(*hash)).cloned().collect()
+        };
+        objects_to_pack.sort();
+
+        if objects_to_pack.is_empty() {
+            println!("Nothing new to pack.");
+            return Ok(String::new());
+        }
+
+        let mut objects = Vec::new();
+        for hash in &objects_to_pack {
+            let (object_type, content, _size) = helpers::read_object_to_bytes(hash.clone(), path_handler)?;
+            objects.push(packfile::PackedObject { hash: hash.clone(), object_type, content });
+        }
+        let (pack, entries) = packfile::build_pack(objects)?;
+        let pack_name = packfile::write_pack_with_index(&pack, entries, path_handler)?;
+        println!("{}", pack_name);
+
+        if delete_flag {
+            if all_flag {
+                let redundant_packs: Vec<String> = previous_packs
+                    .into_iter()
+                    .filter(|previous_pack| *previous_pack != pack_name)
+                    .collect();
+                if loosen_flag {
+                    Self::loosen_unreachable_objects(&redundant_packs, &reachable, path_handler)?;
+                }
+                for redundant_pack in &redundant_packs {
+                    Self::remove_pack(redundant_pack, path_handler)?;
+                }
+                Self::remove_unindexed_packs(path_handler)?;
+            }
+
+            let packed_objects: HashSet<String> = objects_to_pack.into_iter().collect();
+            for hash in loose_objects.iter().filter(|hash| packed_objects.contains(*hash)) {
+                helpers::remove_loose_object(hash, path_handler)?;
+            }
+        }
+
+        Ok(pack_name)
+    }
+}
+
+pub struct Prune;
+
+impl Default for Prune {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Prune {
+    pub fn new() -> Self {
+        Prune {}
+    }
+}
+
+impl Command for Prune {
+    /// Executes the `prune` command, which removes the loose objects that can't be reached from any ref,
+    /// HEAD, reflog or the index, along with temporary pack files left by interrupted writes.
+    /// Only files older than the expire date are removed, by default all of them.
+    /// With the dry run flag the objects are only listed.
+    fn execute(&self, args: Option<Vec<&str>>, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
+        let mut dry_run_flag = false;
+        let mut verbose_flag = false;
+        let mut expire = String::from("now");
+
+        let arg_slice = args.unwrap_or_default();
+        let mut arg_iter = arg_slice.iter();
+        while let Some(&arg) = arg_iter.next() {
+            match arg {
+                DRY_RUN_FLAG => dry_run_flag = true,
+                VERBOSE_FLAG => verbose_flag = true,
+                EXPIRE_FLAG => {
+                    expire = arg_iter.next().ok_or(io::Error::new(
+                        io::ErrorKind::Other,
+                        "Error: No date was provided for expire.",
+                    ))?.to_string();
+                }
+                _ => {
+                    return Err(Box::new(io::Error::new(
+                        io::ErrorKind::Other,
+                        format!("Error: Unknown option {}", arg),
+                    )))
+                }
+            }
+        }
+
+        let expire_time = match helpers::parse_expire_time(&expire)? {
+            Some(expire_time) => expire_time,
+            None => return Ok(String::new()),
+        };
+
+        let reachable = helpers::get_reachable_objects(path_handler)?;
+        let mut pruned_objects = Vec::new();
+        for hash in helpers::get_loose_objects(path_handler)? {
+            if reachable.contains(&hash) {
+                continue;
+            }
+            let object_path = path_handler.get_relative_path(&helpers::get_object_path(&hash));
+            if helpers::get_modification_timestamp(&object_path)? > expire_time {
+                continue;
+            }
+            if dry_run_flag || verbose_flag {
+                println!("{}", hash);
+            }
+            if !dry_run_flag {
+                helpers::remove_loose_object(&hash, path_handler)?;
+            }
+            pruned_objects.push(hash);
+        }
+
+        // temporary files of pack writes that never finished
+        for temporary_file in packfile::list_temporary_pack_files(path_handler)? {
+            if helpers::get_modification_timestamp(&temporary_file)? > expire_time {
+                continue;
+            }
+            if dry_run_flag || verbose_flag {
+                println!("{}", temporary_file);
+            }
+            if !dry_run_flag {
+                fs::remove_file(&temporary_file)?;
+            }
+        }
+
+        Ok(pruned_objects.join("\n"))
+    }
+}
+
+pub struct Gc;
+
+impl Default for Gc {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Gc {
+    pub fn new() -> Self {
+        Gc {}
+    }
+
+    /// Returns true if there are enough loose objects or packs for an automatic collection to be worth it
+    fn needs_collection(path_handler: &PathHandler) -> Result<bool, Box<dyn Error>> {
+        let loose_objects = helpers::get_loose_objects(path_handler)?.len();
+        let packs = packfile::list_indexed_packs(path_handler)?.len();
+        Ok(loose_objects > GC_AUTO_LOOSE_OBJECTS || packs > GC_AUTO_PACK_LIMIT)
+    }
+}
+
+impl Command for Gc {
+    /// Executes the `gc` command, which repacks every reachable object into a single pack and then prunes
+    /// the unreachable loose objects older than the prune date (two weeks by default). Objects referenced
+    /// by reflogs are considered reachable. With the auto flag it only runs if the repository needs it.
+    fn execute(&self, args: Option<Vec<&str>>, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
+        let mut auto_flag = false;
+        let mut prune_expire = Some(DEFAULT_PRUNE_EXPIRE.to_string());
+
+        for arg in args.unwrap_or_default() {
+            match arg {
+                AUTO_FLAG => auto_flag = true,
+                NO_PRUNE_FLAG => prune_expire = None,
+                _ => match arg.strip_prefix(PRUNE_FLAG).and_then(|rest| rest.strip_prefix('=')) {
+                    Some(expire) => prune_expire = Some(expire.to_string()),
+                    None => {
+                        return Err(Box::new(io::Error::new(
+                            io::ErrorKind::Other,
+                            format!("Error: Unknown option {}", arg),
+                        )))
+                    }
+                },
+            }
+        }
+
+        if auto_flag && !Self::needs_collection(path_handler)? {
+            return Ok(String::new());
+        }
+
+        let pack_name = Repack::new().execute(Some(vec![LOOSEN_UNREACHABLE_FLAG, DELETE_FLAG]), path_handler)?;
+        if let Some(expire) = prune_expire {
+            Prune::new().execute(Some(vec![EXPIRE_FLAG, &expire]), path_handler)?;
+        }
+
+        Ok(pack_name)
+    }
+}
+
+pub struct PackRefs;
+
+impl Default for PackRefs {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PackRefs {
+    pub fn new() -> Self {
+        PackRefs {}
+    }
+}
+
+impl Command for PackRefs {
+    /// Executes the `pack-refs` command, which moves the loose tags (every loose ref with the all flag) into
+    /// the packed-refs file, deleting the loose files unless the no-prune flag is given. Returns the names
+    /// of the packed refs.
+    fn execute(&self, args: Option<Vec<&str>>, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
+        let mut all_flag = false;
+        let mut prune = true;
+
+        for arg in args.unwrap_or_default() {
+            match arg {
+                ALL_LONG_FLAG => all_flag = true,
+                NO_PRUNE_FLAG => prune = false,
+                _ => {
+                    return Err(Box::new(io::Error::new(
+                        io::ErrorKind::Other,
+                        format!("Error: Unknown option {}", arg),
+                    )))
+                }
+            }
+        }
+
+        let packed_refs = refs::pack_refs(all_flag, prune, path_handler)?;
+        Ok(packed_refs.join("\n"))
+    }
+}
+
+/// What the config command was asked to do
+enum ConfigAction {
+    Get,
+    GetAll,
+    Set,
+    Add,
+    Unset,
+    UnsetAll,
+    List,
+}
+
+pub struct ConfigCommand;
+
+impl Default for ConfigCommand {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ConfigCommand {
+    pub fn new() -> Self {
+        ConfigCommand {}
+    }
+
+    /// Reads the config of a single scope if one was given, or every scope layered
+    fn load(scope: Option<ConfigScope>, path_handler: &PathHandler) -> Result<Config, Box<dyn Error>> {
+        match scope {
+            Some(scope) => Config::load_scope(scope, path_handler),
+            None => Config::load(path_handler),
+        }
+    }
+
+    /// Formats a value as the given type, failing if it isn't valid for it
+    fn format_typed_value(name: &str, value: Option<&str>, value_type: Option<&str>) -> Result<String, Box<dyn Error>> {
+        match value_type {
+            Some(BOOL_FLAG) => Ok(config::parse_bool(name, value)?.to_string()),
+            Some(INT_FLAG) => Ok(config::parse_int(name, value)?.to_string()),
+            _ => Ok(value.unwrap_or_default().to_string()),
+        }
+    }
+}
+
+impl Command for ConfigCommand {
+    /// Executes the `config` command. A name alone gets its value and a name and a value set it, in the repository
+    /// config unless --global or --system are given. --get-all gets every value of a multi-valued variable, --add adds
+    /// a value, --unset and --unset-all remove it and --list lists every variable. With --bool or --int values
+    /// are checked and shown in canonical form.
+    fn execute(&self, args: Option<Vec<&str>>, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
+        let mut scope = None;
+        let mut action = None;
+        let mut value_type = None;
+        let mut operands = Vec::new();
+
+        for arg in args.unwrap_or_default() {
+            match arg {
+                GLOBAL_FLAG => scope = Some(ConfigScope::Global),
+                SYSTEM_FLAG => scope = Some(ConfigScope::System),
+                LOCAL_FLAG => scope = Some(ConfigScope::Local),
+                GET_FLAG => action = Some(ConfigAction::Get),
+                GET_ALL_FLAG => action = Some(ConfigAction::GetAll),
+                ADD_LONG_FLAG => action = Some(ConfigAction::Add),
+                UNSET_FLAG => action = Some(ConfigAction::Unset),
+                UNSET_ALL_FLAG => action = Some(ConfigAction::UnsetAll),
+                LIST_FLAG | LIST_LONG_FLAG => action = Some(ConfigAction::List),
+                BOOL_FLAG | INT_FLAG => value_type = Some(arg),
+                _ if arg.starts_with('-') => {
+                    return Err(Box::new(io::Error::new(
+                        io::ErrorKind::Other,
+                        format!("error: unknown option `{}'", arg),
+                    )))
+                }
+                _ => operands.push(arg),
+            }
         }
 
-        let mut rebasing_commit_log: Vec<(String, String)> = Vec::new();
-        Log::generate_log_entries(&mut rebasing_commit_log, rebasing_commit, path_handler)?;
-        let mut previous_commit = Head::get_head_commit(path_handler)?;
-        for (commit, _message) in rebasing_commit_log.iter().rev() {
-            println!("{}Applying:{} {}", COLOR_RED_CODE, COLOR_RESET_CODE, commit);
-            let branch_name = format!("rebase_{}", rebasing_branch_name);
-            let _ = Branch::new().create_new_branch(&branch_name, path_handler);
-            helpers::update_branch_hash(&branch_name, &commit.clone(), path_handler)?;
+        let action = action.unwrap_or(if operands.len() > 1 { ConfigAction::Set } else { ConfigAction::Get });
+        let expected_operands = match action {
+            ConfigAction::List => 0,
+            ConfigAction::Get | ConfigAction::GetAll | ConfigAction::Unset | ConfigAction::UnsetAll => 1,
+            ConfigAction::Set | ConfigAction::Add => 2,
+        };
+        if operands.len() != expected_operands {
+            return Err(Box::new(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "usage: config [--global | --system | --local] [--bool | --int] [--get | --get-all | --add | --unset | --unset-all | --list] [<name> [<value>]]",
+            )));
+        }
 
-            match helpers::determine_new_working_tree(Head::get_head_commit(path_handler)?, commit.clone(), path_handler) {
-                Ok(_) => {
-                    let _ = Branch::new().execute(Some(vec![DELETE_FLAG, &branch_name]), path_handler)?;
-                    let new_commit = HashObjectCreator::create_commit_object(None, vec![previous_commit], path_handler)?;
-                    let new_tree = helpers::get_commit_tree(&new_commit, path_handler)?;
-                    helpers::update_branch_hash(&Head::get_current_branch_name(path_handler)?, &new_commit, path_handler)?;
-                    WorkingDirectory::update_working_directory_to(&new_tree, path_handler)?;
-                    previous_commit = new_commit;
+        let output = match action {
+            ConfigAction::List => Self::load(scope, path_handler)?
+                .entries()
+                .iter()
+                .map(|entry| match &entry.value {
+                    Some(value) => format!("{}={}", entry.name, value),
+                    None => entry.name.clone(),
+                })
+                .collect::<Vec<String>>()
+                .join("\n"),
+            ConfigAction::Get | ConfigAction::GetAll => {
+                let name = operands[0];
+                let config = Self::load(scope, path_handler)?;
+                let mut entries = config.get_entries(name);
+                if let ConfigAction::Get = action {
+                    entries = entries.split_off(entries.len().saturating_sub(1));
                 }
-                Err(_) => {
-                    let mut rebase_head = fs::File::create(path_handler.get_relative_path(REBASE_HEAD))?;
-                    rebase_head.write_all(commit.as_bytes())?;
+                if entries.is_empty() {
+                    return Err(Box::new(io::Error::new(
+                        io::ErrorKind::NotFound,
+                        format!("error: key '{}' is not set", name),
+                    )));
+                }
+                entries
+                    .iter()
+                    .map(|entry| Self::format_typed_value(name, entry.value.as_deref(), value_type))
+                    .collect::<Result<Vec<String>, Box<dyn Error>>>()?
+                    .join("\n")
+            }
+            ConfigAction::Set | ConfigAction::Add | ConfigAction::Unset | ConfigAction::UnsetAll => {
+                let mut config_file = ConfigFile::open(scope.unwrap_or(ConfigScope::Local), path_handler)?;
+                match action {
+                    ConfigAction::Set => {
+                        config_file.set(operands[0], &Self::format_typed_value(operands[0], Some(operands[1]), value_type)?)?
+                    }
+                    ConfigAction::Add => {
+                        config_file.add(operands[0], &Self::format_typed_value(operands[0], Some(operands[1]), value_type)?)?
+                    }
+                    ConfigAction::Unset => config_file.unset(operands[0], false)?,
+                    _ => config_file.unset(operands[0], true)?,
                 }
+                config_file.save()?;
+                String::new()
             }
+        };
+
+        if !output.is_empty() {
+            println!("{}", output);
         }
-        Ok(String::new())
+        Ok(output)
     }
 }
 
@@ -2194,7 +5837,8 @@ mod tests {
     use tempfile::tempdir;
 
     fn common_setup() -> (tempfile::TempDir, String) {
-        
+        crate::commands::config::ignore_user_config();
+
         // Create a temporary directory
         let temp_dir = tempdir().unwrap();
         let temp_path = temp_dir.path().to_str().unwrap().to_string();
@@ -2203,49 +5847,41 @@ mod tests {
 
         // Create and execute the Init command
         let init_command = Init::new();
-        
+        let result = init_command.execute(None, &PathHandler::new(temp_path.clone()));
 
         // Check if the Init command was successful
+        assert!(result.is_ok(), "Init command failed: {:?}", result);
 
         (temp_dir, temp_path)
     }
 
+    /// Writes the content to the file, adds it and commits it with the message. Returns the new HEAD commit.
+    fn commit_file(path: &str, content: &str, message: &str, path_handler: &PathHandler) -> String {
+        fs::write(path_handler.get_relative_path(path), content).unwrap();
+        Add::new().execute(Some(vec![path]), path_handler).unwrap();
+        Commit::new().execute(Some(vec!["-m", message]), path_handler).unwrap();
+        Head::get_head_commit(path_handler).unwrap()
+    }
+
     #[test]
     fn test_init_command() {
         // Create a temporary directory for testing
         let temp_dir = tempfile::tempdir().unwrap();
         let temp_path = temp_dir.path();
 
-        // Set the environment variable for relative path
-        //env::set_var(RELATIVE_PATH, temp_path);
-        let mut path_handler = PathHandler::new(&&temp_path);
+        let path_handler = PathHandler::new(temp_path.to_str().unwrap().to_string());
 
         // Create and execute the Init command
         let init_command = Init::new();
-        let result = init_command.execute(None, &mut path_handler);
+        let result = init_command.execute(None, &path_handler);
 
         // Assert that the command executed successfully
         assert!(result.is_ok());
 
         // Verify the directory structure and necessary files
-        assert!(temp_path
-            .join(path_handler.get_relative_path(R_HEADS))
-            .exists());
-        assert!(temp_path
-            .join(path_handler.get_relative_path(R_TAGS))
-            .exists());
-        assert!(temp_path
-            .join(path_handler.get_relative_path(OBJECT))
-            .exists());
-        assert!(temp_path
-            .join(path_handler.get_relative_path(PACK))
-            .exists());
-        assert!(temp_path
-            .join(path_handler.get_relative_path(R_REMOTES))
-            .exists());
-        assert!(temp_path
-            .join(path_handler.get_relative_path(CONFIG_FILE))
-            .exists());
+        for expected_path in [R_HEADS, R_TAGS, OBJECT, PACK, R_REMOTES, CONFIG_FILE] {
+            assert!(Path::new(&path_handler.get_relative_path(expected_path)).exists());
+        }
         // Add more assertions for other files and folders as needed
     }
 
@@ -2279,6 +5915,343 @@ mod tests {
         assert!(result4.is_ok());
     }
 
+    #[test]
+    fn test_branch_listing_and_safe_delete() {
+        let (_temp_dir, temp_path) = common_setup();
+        let path_handler = PathHandler::new(temp_path.to_string());
+        let branch_command = Branch::new();
+        let first_commit = commit_file("file.txt", "first", "first", &path_handler);
+        branch_command.execute(Some(vec!["feature"]), &path_handler).unwrap();
+        Checkout::new().execute(Some(vec!["feature"]), &path_handler).unwrap();
+        let feature_commit = commit_file("file.txt", "feature work", "feature work", &path_handler);
+        Checkout::new().execute(Some(vec!["master"]), &path_handler).unwrap();
+        refs::update_ref("refs/remotes/origin/master", &first_commit, None, None, &path_handler).unwrap();
+
+        let result = branch_command.execute(Some(vec!["--contains", &first_commit]), &path_handler).unwrap();
+        assert_eq!(result, "feature\n\nmaster\n\n");
+        let result = branch_command.execute(Some(vec!["--contains", &feature_commit]), &path_handler).unwrap();
+        assert_eq!(result, "feature\n\n");
+        assert_eq!(branch_command.execute(Some(vec!["--merged"]), &path_handler).unwrap(), "master\n\n");
+        assert_eq!(branch_command.execute(Some(vec!["--no-merged", "master"]), &path_handler).unwrap(), "feature\n\n");
+        assert_eq!(branch_command.execute(Some(vec!["-r"]), &path_handler).unwrap(), "origin/master\n\n");
+        let result = branch_command.execute(Some(vec!["-a"]), &path_handler).unwrap();
+        assert_eq!(result, "feature\n\nmaster\n\nremotes/origin/master\n\n");
+        let result = branch_command.execute(Some(vec!["-v"]), &path_handler).unwrap();
+        assert_eq!(result.lines().next().unwrap(), format!("  feature {} feature work", &feature_commit[..8]));
+
+        // renames and copies don't overwrite other branches unless forced
+        assert!(branch_command.execute(Some(vec!["-m", "feature", "master"]), &path_handler).is_err());
+        // not even forced ones can replace the checked out branch
+        assert!(branch_command.execute(Some(vec!["-M", "feature", "master"]), &path_handler).is_err());
+        assert!(branch_command.execute(Some(vec!["-C", "feature", "master"]), &path_handler).is_err());
+        assert_eq!(refs::read_ref("refs/heads/master", &path_handler), Some(first_commit.clone()));
+        assert_eq!(refs::read_ref("refs/heads/feature", &path_handler), Some(feature_commit.clone()));
+        branch_command.execute(Some(vec!["-c", "feature", "copy"]), &path_handler).unwrap();
+        assert_eq!(refs::read_ref("refs/heads/copy", &path_handler), Some(feature_commit.clone()));
+        assert_eq!(refs::read_ref("refs/heads/feature", &path_handler), Some(feature_commit.clone()));
+        assert!(branch_command.execute(Some(vec!["-c", "master", "copy"]), &path_handler).is_err());
+        branch_command.execute(Some(vec!["-C", "master", "copy"]), &path_handler).unwrap();
+        assert_eq!(refs::read_ref("refs/heads/copy", &path_handler), Some(first_commit.clone()));
+        branch_command.execute(Some(vec!["-M", "copy", "renamed"]), &path_handler).unwrap();
+        assert!(!refs::ref_exists("refs/heads/copy", &path_handler));
+
+        let error = branch_command.execute(Some(vec!["-d", "feature"]), &path_handler).unwrap_err();
+        assert!(error.to_string().starts_with("error: The branch 'feature' is not fully merged."));
+        branch_command.execute(Some(vec!["-d", "renamed"]), &path_handler).unwrap();
+        branch_command.execute(Some(vec!["-D", "feature"]), &path_handler).unwrap();
+        assert_eq!(branch_command.execute(None, &path_handler).unwrap(), "master\n\n");
+    }
+
+    #[test]
+    fn test_remote_command() {
+        let (_temp_dir, temp_path) = common_setup();
+        let path_handler = PathHandler::new(temp_path.to_string());
+        let remote_command = Remote::new();
+        remote_command.execute(Some(vec!["add", REMOTE_NAME, REMOTE_URL]), &path_handler).unwrap();
+        assert!(remote_command.execute(Some(vec!["add", "bad..name", REMOTE_URL]), &path_handler).is_err());
+        assert!(remote_command.execute(Some(vec!["add", REMOTE_NAME]), &path_handler).is_err());
+        let first_commit = commit_file("file.txt", "first", "first", &path_handler);
+        let second_commit = commit_file("file.txt", "second", "second", &path_handler);
+        refs::update_ref("refs/remotes/origin/master", &first_commit, None, Some("fetch: storing head"), &path_handler).unwrap();
+        refs::update_ref("refs/remotes/origin/gone", &first_commit, None, None, &path_handler).unwrap();
+        helpers::set_upstream_config("master", REMOTE_NAME, "refs/heads/master", &path_handler).unwrap();
+
+        let result = remote_command.execute(Some(vec!["-v"]), &path_handler).unwrap();
+        assert_eq!(result, format!("origin\t{} (fetch)\norigin\t{} (push)\n", REMOTE_URL, REMOTE_URL));
+        remote_command.execute(Some(vec!["set-url", "--push", REMOTE_NAME, "127.0.0.1:9420"]), &path_handler).unwrap();
+        assert_eq!(helpers::get_push_url(REMOTE_NAME, &path_handler).unwrap(), "127.0.0.1:9420");
+        assert_eq!(helpers::get_remote_url(REMOTE_NAME, &path_handler).unwrap(), REMOTE_URL);
+        assert!(remote_command.execute(Some(vec!["set-url", "missing", REMOTE_URL]), &path_handler).is_err());
+
+        // the remote has master and topic, and gone was deleted from it
+        let remote_refs = vec![
+            (second_commit.clone(), HEAD.to_string()),
+            (second_commit.clone(), String::from("refs/heads/master")),
+            (first_commit.clone(), String::from("refs/heads/topic")),
+        ];
+        let result = remote_command.show_remote(REMOTE_NAME, Some(&remote_refs), &path_handler).unwrap();
+        assert!(result.starts_with(&format!("* remote origin\n  Fetch URL: {}\n  Push  URL: 127.0.0.1:9420\n", REMOTE_URL)));
+        assert!(result.contains("  HEAD branch: master\n  Remote branches:\n"));
+        assert!(result.contains("    gone   stale (use 'git remote prune' to remove)\n"));
+        assert!(result.contains("    master tracked\n"));
+        assert!(result.contains("    topic  new (next fetch will store in remotes/origin)\n"));
+        assert!(result.ends_with("  Local branch configured for 'git pull':\n    master merges with remote master\n"));
+        let result = remote_command.show_remote(REMOTE_NAME, None, &path_handler).unwrap();
+        assert!(result.contains("  HEAD branch: (not queried)\n  Remote branches: (status not queried)\n    gone\n    master\n"));
+
+        let result = remote_command.prune_remote(REMOTE_NAME, &remote_refs, true, &path_handler).unwrap();
+        assert_eq!(result, format!("Pruning origin\nURL: {}\n * [would prune] origin/gone\n", REMOTE_URL));
+        assert!(refs::ref_exists("refs/remotes/origin/gone", &path_handler));
+        remote_command.prune_remote(REMOTE_NAME, &remote_refs, false, &path_handler).unwrap();
+        assert!(!refs::ref_exists("refs/remotes/origin/gone", &path_handler));
+        assert!(remote_command.prune_remote(REMOTE_NAME, &remote_refs, false, &path_handler).unwrap().is_empty());
+
+        // fetched refs go where the refspecs say, and only forced ones may stop being fast-forwards
+        let fetch = Fetch::new();
+        let refspecs = helpers::get_fetch_refspecs(REMOTE_NAME, &path_handler).unwrap();
+        fetch.add_remote_ref(&second_commit, "refs/heads/master", &refspecs, &path_handler).unwrap();
+        assert_eq!(refs::read_ref("refs/remotes/origin/master", &path_handler), Some(second_commit.clone()));
+        let unforced_refspecs = vec![Refspec::parse("refs/heads/*:refs/remotes/origin/*").unwrap()];
+        fetch.add_remote_ref(&first_commit, "refs/heads/master", &unforced_refspecs, &path_handler).unwrap();
+        assert_eq!(refs::read_ref("refs/remotes/origin/master", &path_handler), Some(second_commit.clone()));
+        fetch.add_remote_ref(&first_commit, "refs/heads/master", &refspecs, &path_handler).unwrap();
+        assert_eq!(refs::read_ref("refs/remotes/origin/master", &path_handler), Some(first_commit.clone()));
+        fetch.add_remote_ref(&first_commit, "refs/tags/v1.0", &refspecs, &path_handler).unwrap();
+        assert_eq!(refs::read_ref("refs/tags/v1.0", &path_handler), Some(first_commit.clone()));
+        fetch.add_remote_ref(&first_commit, "refs/notes/commits", &refspecs, &path_handler).unwrap();
+        assert!(!refs::ref_exists("refs/notes/commits", &path_handler));
+
+        remote_command.execute(Some(vec!["add", "other", "127.0.0.1:9421"]), &path_handler).unwrap();
+        assert!(remote_command.execute(Some(vec!["rename", REMOTE_NAME, "other"]), &path_handler).is_err());
+        // a rename that can't move the remote-tracking branches leaves the config as it was
+        let lock_path = path_handler.get_relative_path(".git/refs/remotes/origin/master.lock");
+        fs::write(&lock_path, "").unwrap();
+        assert!(remote_command.execute(Some(vec!["rename", REMOTE_NAME, "upstream"]), &path_handler).is_err());
+        assert_eq!(helpers::get_remote_url(REMOTE_NAME, &path_handler).unwrap(), REMOTE_URL);
+        assert_eq!(refs::read_ref("refs/remotes/origin/master", &path_handler), Some(first_commit.clone()));
+        assert_eq!(reflog::read_entries("refs/remotes/origin/master", &path_handler).unwrap().len(), 3);
+        fs::remove_file(&lock_path).unwrap();
+        remote_command.execute(Some(vec!["rename", REMOTE_NAME, "upstream"]), &path_handler).unwrap();
+        assert!(helpers::get_remote_url(REMOTE_NAME, &path_handler).is_err());
+        assert_eq!(helpers::get_remote_url("upstream", &path_handler).unwrap(), REMOTE_URL);
+        assert_eq!(helpers::get_fetch_refspecs("upstream", &path_handler).unwrap(), vec![Refspec::default_fetch("upstream")]);
+        assert!(!refs::ref_exists("refs/remotes/origin/master", &path_handler));
+        assert_eq!(refs::read_ref("refs/remotes/upstream/master", &path_handler), Some(first_commit.clone()));
+        let entries = reflog::read_entries("refs/remotes/upstream/master", &path_handler).unwrap();
+        assert_eq!(entries.len(), 4);
+        assert_eq!(helpers::get_upstream_ref("master", &path_handler).unwrap().as_deref(), Some("refs/remotes/upstream/master"));
+        assert_eq!(remote_command.execute(None, &path_handler).unwrap(), "upstream\nother\n");
+
+        remote_command.execute(Some(vec!["remove", "upstream"]), &path_handler).unwrap();
+        assert!(helpers::get_remote_url("upstream", &path_handler).is_err());
+        assert!(refs::list_refs("refs/remotes/upstream/", &path_handler).unwrap().is_empty());
+        assert_eq!(helpers::get_upstream_ref("master", &path_handler).unwrap(), None);
+        assert!(remote_command.execute(Some(vec!["rm", "upstream"]), &path_handler).is_err());
+    }
+
+    #[test]
+    fn test_show_ref_and_for_each_ref() {
+        let (_temp_dir, temp_path) = common_setup();
+        let path_handler = PathHandler::new(temp_path.to_string());
+        fs::write(path_handler.get_relative_path("file.txt"), "first").unwrap();
+        Add::new().execute(Some(vec!["file.txt"]), &path_handler).unwrap();
+        Commit::new().execute(Some(vec!["-m", "first"]), &path_handler).unwrap();
+        let first_commit = Head::get_head_commit(&path_handler).unwrap();
+        let tree_hash = helpers::get_commit_tree(&first_commit, &path_handler).unwrap();
+        let write_commit = |date: &str| {
+            let content = format!("tree {}\nauthor a <a@a> {}\ncommitter a <a@a> {}\n\nmessage\n", tree_hash, date, date);
+            fs::write(path_handler.get_relative_path("commit.txt"), content).unwrap();
+            HashObject.execute(Some(vec![WRITE_FLAG, TYPE_FLAG, "commit", "commit.txt"]), &path_handler).unwrap()
+        };
+        let old_commit = write_commit("1700000000 +0000");
+        let new_commit = write_commit("1700003600 -0300");
+        refs::update_ref("refs/heads/old", &old_commit, None, None, &path_handler).unwrap();
+        refs::update_ref("refs/heads/new", &new_commit, None, None, &path_handler).unwrap();
+        refs::update_ref("refs/remotes/origin/master", &first_commit, None, None, &path_handler).unwrap();
+        helpers::set_upstream_config("master", REMOTE_NAME, "refs/heads/master", &path_handler).unwrap();
+        Tag::new().execute(Some(vec!["-a", "v1.0", "-m", "release"]), &path_handler).unwrap();
+        let tag_hash = refs::read_ref("refs/tags/v1.0", &path_handler).unwrap();
+
+        let show_ref = ShowRef::new();
+        assert_eq!(show_ref.execute(None, &path_handler).unwrap().lines().count(), 5);
+        assert_eq!(show_ref.execute(Some(vec!["--heads"]), &path_handler).unwrap().lines().count(), 3);
+        let result = show_ref.execute(Some(vec!["master"]), &path_handler).unwrap();
+        assert_eq!(result, format!("{} refs/heads/master\n{} refs/remotes/origin/master", first_commit, first_commit));
+        assert_eq!(show_ref.execute(Some(vec!["--tags", "--hash"]), &path_handler).unwrap(), tag_hash);
+        assert_eq!(show_ref.execute(Some(vec!["--heads", "aster"]), &path_handler).unwrap(), "");
+        let result = show_ref.execute(Some(vec!["--verify", "refs/heads/old", "HEAD"]), &path_handler).unwrap();
+        assert_eq!(result, format!("{} refs/heads/old\n{} HEAD", old_commit, first_commit));
+        assert_eq!(show_ref.execute(Some(vec!["--hash=8", "--verify", "refs/heads/old"]), &path_handler).unwrap(), old_commit[..8]);
+        assert!(show_ref.execute(Some(vec!["--verify", "old"]), &path_handler).is_err());
+        assert!(show_ref.execute(Some(vec!["--verify", "refs/heads/missing"]), &path_handler).is_err());
+
+        let for_each_ref = ForEachRef::new();
+        let result = for_each_ref.execute(Some(vec!["refs/tags/v1.0"]), &path_handler).unwrap();
+        assert_eq!(result, format!("{} tag\trefs/tags/v1.0", tag_hash));
+        let result = for_each_ref
+            .execute(Some(vec!["--format=%(refname:short) %(objecttype) %(upstream:short)", "refs/heads"]), &path_handler)
+            .unwrap();
+        assert_eq!(result, "master commit origin/master\nnew commit \nold commit ");
+        let result = for_each_ref.execute(Some(vec!["--format", "%(objecttype)%09%(objectname:short)%%", "refs/tags/"]), &path_handler).unwrap();
+        assert_eq!(result, format!("tag\t{}%", &tag_hash[..7]));
+        let result = for_each_ref.execute(Some(vec!["--format=%(refname)", "refs/*/o*"]), &path_handler).unwrap();
+        assert_eq!(result, "refs/heads/old\nrefs/remotes/origin/master");
+
+        // the last sort key is the one that decides
+        let result = for_each_ref
+            .execute(Some(vec!["--sort=refname", "--sort=committerdate", "--format=%(refname:short)", "refs/heads"]), &path_handler)
+            .unwrap();
+        assert_eq!(result, "old\nnew\nmaster");
+        let result = for_each_ref
+            .execute(Some(vec!["--sort", "committerdate", "--count", "2", "--format=%(refname:short) %(committerdate:unix)", "refs/heads"]), &path_handler)
+            .unwrap();
+        assert_eq!(result, "old 1700000000\nnew 1700003600");
+        let result = for_each_ref.execute(Some(vec!["--format=%(committerdate)|%(committerdate:iso)", "refs/heads/new"]), &path_handler).unwrap();
+        assert_eq!(result, "Tue Nov 14 20:13:20 2023 -0300|2023-11-14 20:13:20 -0300");
+        assert!(for_each_ref.execute(Some(vec!["--format=%(bogus)"]), &path_handler).is_err());
+        assert!(for_each_ref.execute(Some(vec!["--count=many"]), &path_handler).is_err());
+    }
+
+    #[test]
+    fn test_describe_command() {
+        let (_temp_dir, temp_path) = common_setup();
+        let path_handler = PathHandler::new(temp_path.to_string());
+        let describe = Describe::new();
+        let first_commit = commit_file("file.txt", "first", "first", &path_handler);
+        assert!(describe.execute(None, &path_handler).is_err());
+        Tag::new().execute(Some(vec!["-a", "v1.0", "-m", "release"]), &path_handler).unwrap();
+        let second_commit = commit_file("file.txt", "second", "second", &path_handler);
+        let third_commit = commit_file("file.txt", "third", "third", &path_handler);
+        refs::update_ref("refs/tags/light", &second_commit, None, None, &path_handler).unwrap();
+
+        assert_eq!(describe.execute(None, &path_handler).unwrap(), format!("v1.0-2-g{}", &third_commit[..7]));
+        assert_eq!(describe.execute(Some(vec![&first_commit]), &path_handler).unwrap(), "v1.0");
+        let result = describe.execute(Some(vec!["--long", "--abbrev=10", &first_commit]), &path_handler).unwrap();
+        assert_eq!(result, format!("v1.0-0-g{}", &first_commit[..10]));
+        assert_eq!(describe.execute(Some(vec!["--abbrev=0"]), &path_handler).unwrap(), "v1.0");
+        assert_eq!(describe.execute(Some(vec!["--tags"]), &path_handler).unwrap(), format!("light-1-g{}", &third_commit[..7]));
+
+        // only tracked files make the working tree dirty
+        fs::write(path_handler.get_relative_path("untracked.txt"), "untracked").unwrap();
+        assert_eq!(describe.execute(Some(vec!["--dirty"]), &path_handler).unwrap(), format!("v1.0-2-g{}", &third_commit[..7]));
+        fs::write(path_handler.get_relative_path("file.txt"), "changed").unwrap();
+        assert_eq!(describe.execute(Some(vec!["--dirty"]), &path_handler).unwrap(), format!("v1.0-2-g{}-dirty", &third_commit[..7]));
+        assert_eq!(describe.execute(Some(vec!["--dirty=.mod"]), &path_handler).unwrap(), format!("v1.0-2-g{}.mod", &third_commit[..7]));
+        assert!(describe.execute(Some(vec!["--dirty", "HEAD"]), &path_handler).is_err());
+
+        refs::delete_ref("refs/tags/v1.0", None, &path_handler).unwrap();
+        let error = describe.execute(None, &path_handler).unwrap_err().to_string();
+        assert!(error.contains("try --tags"));
+        assert_eq!(describe.execute(Some(vec!["--always"]), &path_handler).unwrap(), third_commit[..7]);
+        assert!(describe.execute(Some(vec!["--tags", &first_commit]), &path_handler).is_err());
+    }
+
+    #[test]
+    fn test_commit_and_tag_signing() {
+        let (_temp_dir, temp_path) = common_setup();
+        let path_handler = PathHandler::new(temp_path.to_string());
+        let seed = [42; 32];
+        let allowed_signers_path = path_handler.get_relative_path("allowed_signers");
+        let public_key_line = signing::TestKeySigner::new(seed).public_key_line();
+        fs::write(&allowed_signers_path, format!("dev@example.com {}\n", public_key_line)).unwrap();
+        let mut config = ConfigFile::open(ConfigScope::Local, &path_handler).unwrap();
+        config.set("gpg.ssh.allowedSignersFile", &allowed_signers_path).unwrap();
+        config.save().unwrap();
+
+        fs::write(path_handler.get_relative_path("file.txt"), "first").unwrap();
+        Add::new().execute(Some(vec!["file.txt"]), &path_handler).unwrap();
+        let signed_commit = Commit::with_signer(Box::new(signing::TestKeySigner::new(seed)));
+        signed_commit.execute(Some(vec!["-S", "-m", "signed"]), &path_handler).unwrap();
+        let first_commit = Head::get_head_commit(&path_handler).unwrap();
+        let (_type, content, _size) = helpers::read_object_to_string(first_commit.clone(), &path_handler).unwrap();
+        assert!(content.contains("\ngpgsig -----BEGIN SSH SIGNATURE-----\n "));
+        assert!(content.ends_with("-----END SSH SIGNATURE-----\n\nsigned"));
+
+        let result = VerifyCommit::new().execute(Some(vec!["HEAD"]), &path_handler).unwrap();
+        assert!(result.starts_with("Good \"git\" signature for dev@example.com with ED25519 key SHA256:"));
+        let log = Log::new().execute(Some(vec!["--show-signature"]), &path_handler).unwrap();
+        assert!(log.contains(&result));
+        assert!(!Log::new().execute(None, &path_handler).unwrap().contains("gpgsig"));
+
+        // a commit signed by a key missing from the allowed signers, and an unsigned one
+        fs::write(path_handler.get_relative_path("file.txt"), "second").unwrap();
+        Add::new().execute(Some(vec!["file.txt"]), &path_handler).unwrap();
+        let other_signer = Commit::with_signer(Box::new(signing::TestKeySigner::new([7; 32])));
+        other_signer.execute(Some(vec!["--gpg-sign", "-m", "other key"]), &path_handler).unwrap();
+        let error = VerifyCommit::new().execute(Some(vec!["HEAD"]), &path_handler).unwrap_err().to_string();
+        assert!(error.contains("No principal matched."));
+        fs::write(path_handler.get_relative_path("file.txt"), "third").unwrap();
+        Add::new().execute(Some(vec!["file.txt"]), &path_handler).unwrap();
+        signed_commit.execute(Some(vec!["-m", "unsigned"]), &path_handler).unwrap();
+        let error = VerifyCommit::new().execute(Some(vec!["HEAD"]), &path_handler).unwrap_err().to_string();
+        assert_eq!(error, "error: no signature found");
+
+        // a tampered commit keeps the signature but no longer matches it
+        let tampered_content = content.replace("\n\nsigned", "\n\ntampered");
+        let tampered_commit = HashObjectCreator::write_object_file(
+            tampered_content.clone(),
+            ObjectType::Commit,
+            tampered_content.len() as u64,
+            &path_handler,
+        ).unwrap();
+        let check = signing::verify_object(&tampered_commit, &path_handler).unwrap().unwrap();
+        assert_eq!(check.status, signing::SignatureStatus::Bad);
+        assert!(VerifyCommit::new().execute(Some(vec![&tampered_commit]), &path_handler).is_err());
+
+        let signed_tag = Tag::with_signer(Box::new(signing::TestKeySigner::new(seed)));
+        assert!(signed_tag.execute(Some(vec!["-s", "v1.0"]), &path_handler).is_err());
+        signed_tag.execute(Some(vec!["-s", "v1.0", "-m", "release", &first_commit]), &path_handler).unwrap();
+        let result = VerifyTag::new().execute(Some(vec!["v1.0"]), &path_handler).unwrap();
+        assert!(result.starts_with("Good \"git\" signature for dev@example.com"));
+        assert_eq!(Tag::new().execute(Some(vec!["-v", "v1.0"]), &path_handler).unwrap(), result);
+        assert_eq!(Describe::new().execute(Some(vec![&first_commit]), &path_handler).unwrap(), "v1.0");
+
+        Tag::new().execute(Some(vec!["-a", "v2.0", "-m", "unsigned"]), &path_handler).unwrap();
+        Tag::new().execute(Some(vec!["light"]), &path_handler).unwrap();
+        assert!(VerifyTag::new().execute(Some(vec!["v2.0"]), &path_handler).is_err());
+        let error = VerifyTag::new().execute(Some(vec!["light"]), &path_handler).unwrap_err().to_string();
+        assert!(error.contains("cannot verify a non-tag object of type commit"));
+
+        // tags signed the way git does it, with the signature after the message
+        let tag_payload = format!("object {}\ntype commit\ntag v3.0\ntagger a <a> 1 +0000\n\nrelease\n", first_commit);
+        let tag_content = format!("{}{}", tag_payload, signing::TestKeySigner::new(seed).sign(&tag_payload).unwrap());
+        let tag_hash = HashObjectCreator::write_object_file(tag_content.clone(), ObjectType::Tag, tag_content.len() as u64, &path_handler).unwrap();
+        refs::update_ref("refs/tags/v3.0", &tag_hash, None, None, &path_handler).unwrap();
+        let result = VerifyTag::new().execute(Some(vec!["v3.0"]), &path_handler).unwrap();
+        assert!(result.starts_with("Good \"git\" signature for dev@example.com"));
+
+        // merge commits are signed too
+        Branch::new().execute(Some(vec!["side"]), &path_handler).unwrap();
+        Checkout::new().execute(Some(vec!["side"]), &path_handler).unwrap();
+        commit_file("side.txt", "side", "side", &path_handler);
+        Checkout::new().execute(Some(vec!["master"]), &path_handler).unwrap();
+        commit_file("main.txt", "main", "main", &path_handler);
+        let signed_merge = Merge::with_signer(Box::new(signing::TestKeySigner::new(seed)));
+        signed_merge.execute(Some(vec!["-S", "side"]), &path_handler).unwrap();
+        let (_type, content, _size) = helpers::read_object_to_string(Head::get_head_commit(&path_handler).unwrap(), &path_handler).unwrap();
+        assert_eq!(content.lines().filter(|line| line.starts_with("parent ")).count(), 2);
+        assert!(VerifyCommit::new().execute(Some(vec!["HEAD"]), &path_handler).is_ok());
+
+        // commit.gpgSign and tag.gpgSign sign without the flags, unless signing is turned off
+        let mut config = ConfigFile::open(ConfigScope::Local, &path_handler).unwrap();
+        config.set("commit.gpgSign", "true").unwrap();
+        config.set("tag.gpgSign", "true").unwrap();
+        config.save().unwrap();
+        fs::write(path_handler.get_relative_path("file.txt"), "fourth").unwrap();
+        Add::new().execute(Some(vec!["file.txt"]), &path_handler).unwrap();
+        signed_commit.execute(Some(vec!["-m", "configured"]), &path_handler).unwrap();
+        assert!(VerifyCommit::new().execute(Some(vec!["HEAD"]), &path_handler).is_ok());
+        fs::write(path_handler.get_relative_path("file.txt"), "fifth").unwrap();
+        Add::new().execute(Some(vec!["file.txt"]), &path_handler).unwrap();
+        signed_commit.execute(Some(vec!["--no-gpg-sign", "-m", "not signed"]), &path_handler).unwrap();
+        assert!(VerifyCommit::new().execute(Some(vec!["HEAD"]), &path_handler).is_err());
+        assert!(signed_tag.execute(Some(vec!["v4.0"]), &path_handler).is_err());
+        signed_tag.execute(Some(vec!["v4.0", "-m", "configured"]), &path_handler).unwrap();
+        assert!(VerifyTag::new().execute(Some(vec!["v4.0"]), &path_handler).is_ok());
+        signed_tag.execute(Some(vec!["--no-sign", "v5.0"]), &path_handler).unwrap();
+        assert_eq!(refs::read_ref("refs/tags/v5.0", &path_handler), Some(Head::get_head_commit(&path_handler).unwrap()));
+    }
+
     #[test]
     fn test_checkout_command() {
         let (_temp_dir, _temp_pathh) = common_setup();
@@ -2298,7 +6271,7 @@ mod tests {
 
         // Example 3: Attempt to checkout a non-existing branch (should result in an error)
         let args3 = Some(vec!["non_existing_branch"]);
-        let result3 = Checkout.execute(args3, panic!());
+        let result3 = Checkout.execute(args3, &path_handler);
         assert!(result3.is_err());
 
         // Example 4: No branch name provided (should result in an error)
@@ -2312,11 +6285,11 @@ mod tests {
         // Execute the HashObject command with various scenarios
         // Example 1: Calculate hash and print (no write flag)
 
-        let _file = fs::File::create("file.txt");
+        let _file = fs::File::create(path_handler.get_relative_path("file.txt"));
         let args1 = Some(vec!["file.txt"]);
 
         let result1 = HashObject.execute(args1, &path_handler);
-        assert!(result1.is_ok());
+        assert_eq!(result1.unwrap(), "e69de29bb2d1d6434b8b29ae775ad8c2e48c5391");
 
         // Example 2: Calculate hash and write to object file (with write flag)
         let args2 = Some(vec![WRITE_FLAG, "file.txt"]);
@@ -2334,12 +6307,47 @@ mod tests {
         assert!(result5.is_err());
     }
 
+    #[test]
+    fn test_hashobject_types_and_validation() {
+        let (_temp_dir, temp_path) = common_setup();
+        let path_handler = PathHandler::new(temp_path.to_string());
+
+        // Hashing alone doesn't write the object
+        let content = b"test content\n";
+        let hash = HashObject::hash_object(content, &ObjectType::Blob, false, false, &path_handler).unwrap();
+        assert_eq!(hash, "d670460b4b4aece5915caf5c68d12f560a9fe3e4");
+        assert!(!helpers::check_if_file_exists(&helpers::get_object_path(&hash), &path_handler));
+
+        // Binary content is written as is
+        let binary_content = [0u8, 159, 146, 150, 255];
+        fs::write(path_handler.get_relative_path("binary.bin"), binary_content).unwrap();
+        let hash = HashObject.execute(Some(vec![WRITE_FLAG, "binary.bin"]), &path_handler).unwrap();
+        let (_object_type, stored_content, _size) = helpers::read_object_to_bytes(hash, &path_handler).unwrap();
+        assert_eq!(stored_content, binary_content);
+
+        // Other types are validated unless --literally is used
+        fs::write(path_handler.get_relative_path("commit.txt"), "tree 1234\n\nmessage\n").unwrap();
+        assert!(HashObject.execute(Some(vec![TYPE_FLAG, "commit", "commit.txt"]), &path_handler).is_err());
+        assert!(HashObject
+            .execute(Some(vec![TYPE_FLAG, "commit", LITERALLY_FLAG, "commit.txt"]), &path_handler)
+            .is_ok());
+
+        let commit = format!(
+            "tree {}\nauthor a <a@a> 0 +0000\ncommitter a <a@a> 0 +0000\n\nmessage\n",
+            "4b825dc642cb6eb9a060e54bf8d69288fbee4904"
+        );
+        fs::write(path_handler.get_relative_path("commit.txt"), commit).unwrap();
+        assert!(HashObject.execute(Some(vec![TYPE_FLAG, "commit", "commit.txt"]), &path_handler).is_ok());
+        assert!(HashObject.execute(Some(vec![TYPE_FLAG, "tree", "commit.txt"]), &path_handler).is_err());
+        assert!(HashObject.execute(Some(vec![TYPE_FLAG, "invalid", "commit.txt"]), &path_handler).is_err());
+    }
+
     #[test]
     fn test_catfile_command() {
         let (_temp_dir, _temp_pathh) = common_setup();
         let path_handler = PathHandler::new(_temp_pathh.to_string());
 
-        let _file = fs::File::create("file.txt");
+        let _file = fs::File::create(path_handler.get_relative_path("file.txt"));
         let args1 = Some(vec![WRITE_FLAG, "file.txt"]);
 
         let hash_object = HashObject.execute(args1, &path_handler).unwrap();
@@ -2363,6 +6371,46 @@ mod tests {
         let args4 = None;
         let result4 = CatFile.execute(args4, &path_handler);
         assert!(result4.is_err());
+
+        // Example 5: An empty argument list or a flag without an object (should result in an error)
+        let error = CatFile.execute(Some(vec![]), &path_handler).unwrap_err();
+        assert_eq!(error.to_string(), "No arguments received");
+        let error = CatFile.execute(Some(vec![TYPE_FLAG]), &path_handler).unwrap_err();
+        assert_eq!(error.to_string(), "No object provided");
+    }
+
+    #[test]
+    fn test_catfile_pretty_print_and_batch() {
+        let (_temp_dir, temp_path) = common_setup();
+        let path_handler = PathHandler::new(temp_path.to_string());
+
+        fs::write(path_handler.get_relative_path("sample.txt"), "Sample file content")
+            .expect("Failed to create a sample file");
+        StagingArea::new().add_file("sample.txt", &path_handler).unwrap();
+        let tree_hash = HashObjectCreator::create_tree_object(&path_handler).unwrap();
+        let (_mode, _name, blob_hash) = helpers::read_tree_content(&tree_hash, &path_handler).unwrap().remove(0);
+
+        // Trees are listed entry by entry, blobs are shown raw, both with abbreviated hashes
+        let tree = CatFile.execute(Some(vec![PRETTY_PRINT_FLAG, &tree_hash[..7]]), &path_handler).unwrap();
+        assert_eq!(tree, format!("100644 blob {}\tsample.txt\n", blob_hash));
+        let blob = CatFile.execute(Some(vec![PRETTY_PRINT_FLAG, &blob_hash[..7]]), &path_handler).unwrap();
+        assert_eq!(blob, "Sample file content");
+
+        assert!(CatFile.execute(Some(vec![EXISTS_FLAG, &blob_hash]), &path_handler).is_ok());
+        let missing_hash = "0".repeat(40);
+        assert!(CatFile.execute(Some(vec![EXISTS_FLAG, &missing_hash]), &path_handler).is_err());
+
+        let input = format!("{}\n{}\n", &blob_hash[..7], missing_hash);
+        let mut output = Vec::new();
+        CatFile::run_batch(input.as_bytes(), &mut output, true, &path_handler).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            format!("{} blob 19\nSample file content\n{} missing\n", blob_hash, missing_hash)
+        );
+
+        let mut output = Vec::new();
+        CatFile::run_batch(tree_hash.as_bytes(), &mut output, false, &path_handler).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), format!("{} tree 38\n", tree_hash));
     }
 
     #[test]
@@ -2388,6 +6436,50 @@ mod tests {
         // Cleanup: The temporary directory will be automatically deleted when temp_dir goes out of scope
     }
 
+    #[test]
+    fn test_add_pathspecs_and_flags() {
+        let (_temp_dir, temp_path) = common_setup();
+        let path_handler = PathHandler::new(temp_path.to_string());
+        let index_paths = || -> Vec<(String, String)> {
+            helpers::read_index_entries(&path_handler)
+                .unwrap()
+                .into_iter()
+                .map(|(path, _hash, state, _mode)| (path, state))
+                .collect()
+        };
+
+        for file_path in ["src/main.rs", "src/lib.rs", "src/notes.txt", "docs/a.md", "target/out.rs", "readme"] {
+            let full_path = path_handler.get_relative_path(file_path);
+            fs::create_dir_all(Path::new(&full_path).parent().unwrap()).unwrap();
+            fs::write(&full_path, file_path).unwrap();
+        }
+
+        // Nothing is staged with a dry run
+        let output = Add::new().execute(Some(vec!["-n", "src/"]), &path_handler).unwrap();
+        assert_eq!(output, "add 'src/lib.rs'\nadd 'src/main.rs'\nadd 'src/notes.txt'");
+        assert!(index_paths().is_empty());
+
+        // Globs and exclusions, several per call
+        Add::new().execute(Some(vec!["*.rs", ":!target", "readme"]), &path_handler).unwrap();
+        let staged: Vec<String> = index_paths().into_iter().map(|(path, _state)| path).collect();
+        assert_eq!(staged, vec!["readme", "src/lib.rs", "src/main.rs"]);
+
+        assert!(Add::new().execute(Some(vec!["missing.txt"]), &path_handler).is_err());
+        assert!(Add::new().execute(None, &path_handler).is_err());
+
+        // -u only updates tracked files, including deletions
+        fs::remove_file(path_handler.get_relative_path("readme")).unwrap();
+        fs::write(path_handler.get_relative_path("src/lib.rs"), "changed").unwrap();
+        let output = Add::new().execute(Some(vec!["-u"]), &path_handler).unwrap();
+        assert_eq!(output, "add 'src/lib.rs'\nremove 'readme'");
+        assert!(index_paths().contains(&("readme".to_string(), IndexFileEntryState::Deleted.to_string())));
+
+        // -A stages everything that is left, using absolute paths too
+        let output = Add::new().execute(Some(vec!["-A", &temp_path]), &path_handler).unwrap();
+        assert_eq!(output, "add 'docs/a.md'\nadd 'src/notes.txt'\nadd 'target/out.rs'");
+        assert_eq!(Add::new().execute(Some(vec!["-A"]), &path_handler).unwrap(), "");
+    }
+
     #[test]
     fn test_commit_command() {
         // Common setup
@@ -2418,6 +6510,22 @@ mod tests {
         // Cleanup: The temporary directory will be automatically deleted when temp_dir goes out of scope
     }
 
+    #[test]
+    fn test_branch_updates_check_the_old_commit() {
+        let (_temp_dir, temp_path) = common_setup();
+        let path_handler = PathHandler::new(temp_path.to_string());
+        let first_commit = commit_file("file.txt", "first", "first", &path_handler);
+        let second_commit = commit_file("file.txt", "second", "second", &path_handler);
+
+        // a branch that moved since its old commit was read isn't overwritten
+        let error = helpers::update_branch_hash(DEFAULT_BRANCH_NAME, &first_commit, Some(ZERO_HASH), "commit", &path_handler).unwrap_err();
+        assert!(error.to_string().contains(&format!("cannot lock ref 'refs/heads/{}'", DEFAULT_BRANCH_NAME)));
+        assert!(helpers::update_branch_hash(DEFAULT_BRANCH_NAME, &first_commit, Some(&first_commit), "commit", &path_handler).is_err());
+        assert_eq!(Head::get_head_commit(&path_handler).unwrap(), second_commit);
+        helpers::update_branch_hash(DEFAULT_BRANCH_NAME, &first_commit, Some(&second_commit), "reset", &path_handler).unwrap();
+        assert_eq!(Head::get_head_commit(&path_handler).unwrap(), first_commit);
+    }
+
     #[test]
     fn test_remove_file_from_staging_area() {
         // Common setup
@@ -2438,10 +6546,444 @@ mod tests {
         let args_rm: Option<Vec<&str>> = Some(vec![&file_path]);
         let result = rm_command.execute(args_rm, &path_handler);
 
+        // The file only exists in the index, so it can't be removed without -f
+        assert!(result.is_err());
+
+        let args_rm: Option<Vec<&str>> = Some(vec!["-f", &file_path]);
+        let result = rm_command.execute(args_rm, &path_handler);
+
         // Assert that the command executed successfully
         assert!(result.is_ok(), "Rm command failed: {:?}", result);
+        assert_eq!(result.unwrap(), "rm 'sample.txt'");
+        assert!(!Path::new(&path_handler.get_relative_path("sample.txt")).exists());
+        let index_entries = helpers::read_index_entries(&path_handler).unwrap();
+        assert_eq!(index_entries[0].2, IndexFileEntryState::Deleted.to_string());
+    }
+
+    #[test]
+    fn test_rm_flags_and_safety_check() {
+        let (_temp_dir, temp_path) = common_setup();
+        let path_handler = PathHandler::new(temp_path.to_string());
+
+        fs::create_dir_all(path_handler.get_relative_path("dir/sub")).unwrap();
+        for file in ["a", "ab.txt", "dir/b.txt", "dir/sub/c.txt"] {
+            fs::write(path_handler.get_relative_path(file), file).unwrap();
+        }
+        Add::new().execute(Some(vec!["."]), &path_handler).unwrap();
+        Commit::new().execute(Some(vec!["-m", "initial"]), &path_handler).unwrap();
+        let rm_command = Rm::new();
+
+        // paths are matched exactly, so ab.txt is kept
+        assert_eq!(rm_command.execute(Some(vec!["a"]), &path_handler).unwrap(), "rm 'a'");
+        assert!(!Path::new(&path_handler.get_relative_path("a")).exists());
+        assert!(Path::new(&path_handler.get_relative_path("ab.txt")).exists());
+        let status = Status::new().execute(None, &path_handler).unwrap();
+        assert!(status.contains("deleted: a (Staged)"));
+        assert!(!status.contains("ab.txt"));
+
+        // local modifications are only dropped with -f, and --cached keeps them in the working tree
+        fs::write(path_handler.get_relative_path("ab.txt"), "changed").unwrap();
+        assert!(rm_command.execute(Some(vec!["ab.txt"]), &path_handler).is_err());
+        rm_command.execute(Some(vec!["--cached", "ab.txt"]), &path_handler).unwrap();
+        assert!(Path::new(&path_handler.get_relative_path("ab.txt")).exists());
+        assert!(Status::new().execute(None, &path_handler).unwrap().contains("untracked: ab.txt"));
+
+        // directories need -r, and a dry run doesn't remove anything
+        assert!(rm_command.execute(Some(vec!["dir"]), &path_handler).is_err());
+        let result = rm_command.execute(Some(vec!["-r", "-n", "dir"]), &path_handler).unwrap();
+        assert_eq!(result, "rm 'dir/b.txt'\nrm 'dir/sub/c.txt'");
+        assert!(Path::new(&path_handler.get_relative_path("dir/sub/c.txt")).exists());
+        rm_command.execute(Some(vec!["-r", "dir"]), &path_handler).unwrap();
+        assert!(!Path::new(&path_handler.get_relative_path("dir")).exists());
+
+        assert!(rm_command.execute(Some(vec!["missing.txt"]), &path_handler).is_err());
+    }
+
+    #[test]
+    fn test_mv_command() {
+        let (_temp_dir, temp_path) = common_setup();
+        let path_handler = PathHandler::new(temp_path.to_string());
+
+        fs::create_dir_all(path_handler.get_relative_path("dir")).unwrap();
+        for file in ["a.txt", "b.txt", "c.txt", "dir/d.txt"] {
+            fs::write(path_handler.get_relative_path(file), file).unwrap();
+        }
+        fs::write(path_handler.get_relative_path("untracked.txt"), "untracked").unwrap();
+        Add::new().execute(Some(vec!["a.txt", "b.txt", "c.txt", "dir"]), &path_handler).unwrap();
+        Commit::new().execute(Some(vec!["-m", "initial"]), &path_handler).unwrap();
+        let mv_command = Mv::new();
+        let index_state = |path: &str| {
+            helpers::read_index_entries(&path_handler)
+                .unwrap()
+                .into_iter()
+                .find(|(index_path, _hash, _state, _mode)| index_path == path)
+                .map(|(_path, _hash, state, _mode)| state)
+        };
+
+        mv_command.execute(Some(vec!["a.txt", "renamed.txt"]), &path_handler).unwrap();
+        assert!(!Path::new(&path_handler.get_relative_path("a.txt")).exists());
+        assert!(Path::new(&path_handler.get_relative_path("renamed.txt")).exists());
+        assert_eq!(index_state("a.txt"), Some(IndexFileEntryState::Deleted.to_string()));
+        assert_eq!(index_state("renamed.txt"), Some(IndexFileEntryState::Staged.to_string()));
+
+        // tracked destinations are only overwritten with -f, and untracked sources can't be moved
+        assert!(mv_command.execute(Some(vec!["b.txt", "c.txt"]), &path_handler).is_err());
+        assert!(mv_command.execute(Some(vec!["untracked.txt", "other.txt"]), &path_handler).is_err());
+        mv_command.execute(Some(vec!["-f", "b.txt", "c.txt"]), &path_handler).unwrap();
+        assert_eq!(fs::read_to_string(path_handler.get_relative_path("c.txt")).unwrap(), "b.txt");
+        assert_eq!(index_state("b.txt"), Some(IndexFileEntryState::Deleted.to_string()));
+
+        // several sources into a directory, skipping the ones that fail with -k
+        let result = mv_command
+            .execute(Some(vec!["-n", "-k", "c.txt", "untracked.txt", "dir"]), &path_handler)
+            .unwrap();
+        assert_eq!(result, "Renaming c.txt to dir/c.txt");
+        assert!(Path::new(&path_handler.get_relative_path("c.txt")).exists());
+        assert!(mv_command.execute(Some(vec!["c.txt", "untracked.txt", "dir"]), &path_handler).is_err());
+        assert!(Path::new(&path_handler.get_relative_path("c.txt")).exists());
+        mv_command.execute(Some(vec!["-k", "c.txt", "untracked.txt", "dir"]), &path_handler).unwrap();
+        assert_eq!(index_state("dir/c.txt"), Some(IndexFileEntryState::Staged.to_string()));
+
+        // whole directories are moved with every tracked file inside
+        mv_command.execute(Some(vec!["dir", "moved"]), &path_handler).unwrap();
+        assert!(Path::new(&path_handler.get_relative_path("moved/d.txt")).exists());
+        assert_eq!(index_state("moved/d.txt"), Some(IndexFileEntryState::Staged.to_string()));
+        assert_eq!(index_state("moved/c.txt"), Some(IndexFileEntryState::Staged.to_string()));
+        assert_eq!(index_state("dir/d.txt"), Some(IndexFileEntryState::Deleted.to_string()));
+
+        // nothing is moved if any of the moves would fail or the index is locked by another writer
+        assert!(mv_command.execute(Some(vec!["moved", "moved/d.txt", "renamed.txt", "dir"]), &path_handler).is_err());
+        fs::create_dir_all(path_handler.get_relative_path("dir")).unwrap();
+        assert!(mv_command.execute(Some(vec!["moved", "moved/d.txt", "dir"]), &path_handler).is_err());
+        assert!(Path::new(&path_handler.get_relative_path("moved/d.txt")).exists());
+        let lock_path = format!("{}{}", path_handler.get_relative_path(INDEX_FILE), LOCK_FILE_EXTENSION);
+        fs::write(&lock_path, "").unwrap();
+        assert!(mv_command.execute(Some(vec!["renamed.txt", "other.txt"]), &path_handler).is_err());
+        assert!(Path::new(&path_handler.get_relative_path("renamed.txt")).exists());
+        fs::remove_file(&lock_path).unwrap();
+        mv_command.execute(Some(vec!["renamed.txt", "other.txt"]), &path_handler).unwrap();
+        assert!(!Path::new(&lock_path).exists());
+        assert_eq!(index_state("other.txt"), Some(IndexFileEntryState::Staged.to_string()));
+    }
+
+    #[test]
+    fn test_rename_detection() {
+        assert_eq!(helpers::parse_similarity_threshold("-M").unwrap(), DEFAULT_RENAME_THRESHOLD);
+        assert_eq!(helpers::parse_similarity_threshold("-M90%").unwrap(), 90);
+        assert_eq!(helpers::parse_similarity_threshold("-M5").unwrap(), 50);
+        assert_eq!(helpers::parse_similarity_threshold("--find-copies=75%").unwrap(), 75);
+        assert!(helpers::parse_similarity_threshold("-M150%").is_err());
+        assert_eq!(helpers::compute_similarity(b"a\nb\nc\nd\n", b"a\nb\nc\nx\n"), 75);
+        assert_eq!(helpers::count_line_changes(b"a\nb\nc\n", b"a\nx\nc\nd\n"), (2, 1));
+
+        let (_temp_dir, temp_path) = common_setup();
+        let path_handler = PathHandler::new(temp_path.to_string());
+        let content = "line 1\nline 2\nline 3\nline 4\nline 5\nline 6\nline 7\nline 8\n";
+        fs::write(path_handler.get_relative_path("file.txt"), content).unwrap();
+        Add::new().execute(Some(vec!["file.txt"]), &path_handler).unwrap();
+        Commit::new().execute(Some(vec!["-m", "initial"]), &path_handler).unwrap();
+        let first_commit = Head::get_head_commit(&path_handler).unwrap();
+        Branch::new().execute(Some(vec!["feature"]), &path_handler).unwrap();
+
+        // a staged move that also changes the file is shown as a rename
+        Mv::new().execute(Some(vec!["file.txt", "moved.txt"]), &path_handler).unwrap();
+        fs::write(path_handler.get_relative_path("moved.txt"), content.replace("line 8", "line eight")).unwrap();
+        Add::new().execute(Some(vec!["moved.txt"]), &path_handler).unwrap();
+        let status = Status::new().execute(None, &path_handler).unwrap();
+        assert!(status.contains("renamed: file.txt -> moved.txt"));
+        assert!(!status.contains("deleted: file.txt"));
+        let diff = Diff::new().execute(Some(vec!["--cached", "--name-status"]), &path_handler).unwrap();
+        assert_eq!(diff, "R081\tfile.txt\tmoved.txt");
+        let diff = Diff::new().execute(Some(vec!["--cached", "-M90%", "--name-status"]), &path_handler).unwrap();
+        assert_eq!(diff, "D\tfile.txt\nA\tmoved.txt");
+        Commit::new().execute(Some(vec!["-m", "move"]), &path_handler).unwrap();
+        let second_commit = Head::get_head_commit(&path_handler).unwrap();
+
+        let diff = Diff::new().execute(Some(vec![&first_commit, &second_commit]), &path_handler).unwrap();
+        assert_eq!(diff, " file.txt => moved.txt | 2 +-\n 1 file changed, 1 insertion(+), 1 deletion(-)");
+        let log = Log::new().execute(Some(vec!["--stat"]), &path_handler).unwrap();
+        assert!(log.contains(" file.txt => moved.txt | 2 +-"));
+        assert!(log.contains(" file.txt | 8 ++++++++"));
+
+        // copies are only detected with -C, from files kept in the same commit
+        fs::write(path_handler.get_relative_path("copy.txt"), content).unwrap();
+        Add::new().execute(Some(vec!["copy.txt"]), &path_handler).unwrap();
+        let diff = Diff::new().execute(Some(vec!["--cached", "-C", "--name-status"]), &path_handler).unwrap();
+        assert_eq!(diff, "C081\tmoved.txt\tcopy.txt");
+        let diff = Diff::new().execute(Some(vec!["--cached", "--name-status"]), &path_handler).unwrap();
+        assert_eq!(diff, "A\tcopy.txt");
+        Rm::new().execute(Some(vec!["--cached", "copy.txt"]), &path_handler).unwrap();
+        fs::remove_file(path_handler.get_relative_path("copy.txt")).unwrap();
+
+        // a merge carries the other side's edits to the renamed file
+        Checkout::new().execute(Some(vec!["feature"]), &path_handler).unwrap();
+        fs::write(path_handler.get_relative_path("file.txt"), content.replace("line 1", "first line")).unwrap();
+        Add::new().execute(Some(vec!["file.txt"]), &path_handler).unwrap();
+        Commit::new().execute(Some(vec!["-m", "edit"]), &path_handler).unwrap();
+        Merge::new().execute(Some(vec![DEFAULT_BRANCH_NAME]), &path_handler).unwrap();
+
+        assert!(!Path::new(&path_handler.get_relative_path("file.txt")).exists());
+        let merged = fs::read_to_string(path_handler.get_relative_path("moved.txt")).unwrap();
+        assert!(merged.starts_with("first line\n"));
+        assert!(merged.contains("line eight"));
+    }
+
+    #[test]
+    fn test_blame_command() {
+        assert_eq!(helpers::match_lines(&["a", "b", "c", "d"], &["a", "x", "c", "d", "e"]), vec![(0, 0), (2, 2), (3, 3)]);
+        assert_eq!(helpers::count_common_lines(&["a", "b", "c", "d"], &["a", "x", "c", "d", "e"]), 3);
+        assert_eq!(helpers::count_common_lines(&["x", "a", "b"], &["a", "b", "y"]), 2);
+
+        let (_temp_dir, temp_path) = common_setup();
+        let path_handler = PathHandler::new(temp_path.to_string());
+
+        let first_commit = commit_file("file.txt", "one\ntwo\nthree\nfour\n", "first", &path_handler);
+        let second_commit = commit_file("file.txt", "one\n2\nthree\nfour\nfive\n", "second", &path_handler);
+        Mv::new().execute(Some(vec!["file.txt", "renamed.txt"]), &path_handler).unwrap();
+        let third_commit = commit_file("renamed.txt", "zero\none\n2\nthree\nfour\nfive\n", "third", &path_handler);
+
+        let lines = Blame::blame_file("renamed.txt", HEAD, &path_handler).unwrap();
+        let commits: Vec<&str> = lines.iter().map(|line| line.commit.as_str()).collect();
+        assert_eq!(
+            commits,
+            vec![&third_commit, &first_commit, &second_commit, &first_commit, &first_commit, &second_commit]
+        );
+        assert_eq!(lines[1].original_path, "file.txt");
+        assert_eq!(lines[1].original_line_number, 1);
+        assert_eq!(lines[1].final_line_number, 2);
+        assert!(lines[1].boundary);
+        assert_eq!(lines[5].summary, "second");
+
+        let blame_command = Blame::new();
+        let result = blame_command.execute(Some(vec!["-L", "2,3", "renamed.txt"]), &path_handler).unwrap();
+        let result_lines: Vec<&str> = result.lines().collect();
+        assert_eq!(result_lines.len(), 2);
+        assert!(result_lines[0].starts_with(&format!("^{} file.txt", &first_commit[..8])));
+        assert!(result_lines[0].ends_with(" 2) one"));
+        assert!(result_lines[1].starts_with(&second_commit[..8]));
+        assert!(result_lines[1].ends_with(" 3) 2"));
+
+        let result = blame_command.execute(Some(vec!["--porcelain", "-L3,+2", "file.txt", &second_commit]), &path_handler).unwrap();
+        let result_lines: Vec<&str> = result.lines().collect();
+        assert_eq!(result_lines[0], format!("{} 3 3 2", first_commit));
+        assert!(result_lines.contains(&"summary first"));
+        assert!(result_lines.contains(&"filename file.txt"));
+        assert_eq!(result_lines.last(), Some(&"\tfour"));
+        assert!(blame_command.execute(Some(vec!["-L", "9,10", "renamed.txt"]), &path_handler).is_err());
+    }
+
+    #[test]
+    fn test_grep_command() {
+        let (_temp_dir, temp_path) = common_setup();
+        let path_handler = PathHandler::new(temp_path.to_string());
+        fs::create_dir_all(path_handler.get_relative_path("dir")).unwrap();
+        fs::write(path_handler.get_relative_path("a.txt"), "Hello world\nfoo bar\nhello again\n").unwrap();
+        fs::write(path_handler.get_relative_path("dir/b.txt"), "worldwide\nnothing\n").unwrap();
+        Add::new().execute(Some(vec!["a.txt", "dir/b.txt"]), &path_handler).unwrap();
+        Commit::new().execute(Some(vec!["-m", "first"]), &path_handler).unwrap();
+        let first_commit = Head::get_head_commit(&path_handler).unwrap();
+
+        fs::write(path_handler.get_relative_path("a.txt"), "changed world\n").unwrap();
+        fs::write(path_handler.get_relative_path("untracked.txt"), "world\n").unwrap();
+        fs::write(path_handler.get_relative_path("ignored.log"), "world\n").unwrap();
+        fs::write(path_handler.get_relative_path(GITIGNORE_FILE), "*.log\n").unwrap();
+
+        let grep_command = Grep::new();
+        let result = grep_command.execute(Some(vec!["world"]), &path_handler).unwrap();
+        assert_eq!(result, "a.txt:changed world\ndir/b.txt:worldwide");
+        let result = grep_command.execute(Some(vec!["-w", "world", "--untracked"]), &path_handler).unwrap();
+        assert_eq!(result, "a.txt:changed world\nuntracked.txt:world");
+        let result = grep_command.execute(Some(vec!["-i", "-n", "--cached", "hello"]), &path_handler).unwrap();
+        assert_eq!(result, "a.txt:1:Hello world\na.txt:3:hello again");
+        let result = grep_command.execute(Some(vec!["-c", "-i", "hello", &first_commit]), &path_handler).unwrap();
+        assert_eq!(result, format!("{}:a.txt:2", first_commit));
+        let result = grep_command.execute(Some(vec!["-l", "--threads", "1", "o.*d", "--", "dir"]), &path_handler).unwrap();
+        assert_eq!(result, "dir/b.txt");
+        let result = grep_command.execute(Some(vec!["-F", "o.*d"]), &path_handler).unwrap();
+        assert_eq!(result, "");
+        assert!(grep_command.execute(Some(vec!["(unclosed"]), &path_handler).is_err());
+
+        // tracked files are searched even inside ignored directories
+        fs::create_dir_all(path_handler.get_relative_path("build")).unwrap();
+        fs::write(path_handler.get_relative_path("build/kept.txt"), "tracked world\n").unwrap();
+        fs::write(path_handler.get_relative_path("build/output.txt"), "untracked world\n").unwrap();
+        Add::new().execute(Some(vec!["build/kept.txt"]), &path_handler).unwrap();
+        fs::write(path_handler.get_relative_path(GITIGNORE_FILE), "*.log\nbuild/\n").unwrap();
+        let result = grep_command.execute(Some(vec!["-l", "world"]), &path_handler).unwrap();
+        assert_eq!(result, "a.txt\nbuild/kept.txt\ndir/b.txt");
+        let result = grep_command.execute(Some(vec!["-l", "--untracked", "world"]), &path_handler).unwrap();
+        assert_eq!(result, "a.txt\nbuild/kept.txt\ndir/b.txt\nuntracked.txt");
+    }
+
+    #[test]
+    fn test_bisect_command() {
+        let (_temp_dir, temp_path) = common_setup();
+        let path_handler = PathHandler::new(temp_path.to_string());
+        let mut commits = Vec::new();
+        for version in 1..=6 {
+            let content = if version >= 4 { format!("version {}\nbug\n", version) } else { format!("version {}\n", version) };
+            fs::write(path_handler.get_relative_path("file.txt"), content).unwrap();
+            Add::new().execute(Some(vec!["file.txt"]), &path_handler).unwrap();
+            Commit::new().execute(Some(vec!["-m", &format!("commit {}", version)]), &path_handler).unwrap();
+            commits.push(Head::get_head_commit(&path_handler).unwrap());
+        }
+
+        let bisect_command = Bisect::new();
+        assert!(bisect_command.execute(Some(vec!["good"]), &path_handler).is_err());
+        let result = bisect_command.execute(Some(vec!["start"]), &path_handler).unwrap();
+        assert!(result.contains("waiting for both good and bad commits"));
+        bisect_command.execute(Some(vec!["bad"]), &path_handler).unwrap();
+        let result = bisect_command.execute(Some(vec!["good", &commits[0]]), &path_handler).unwrap();
+        assert!(result.starts_with("Bisecting: 2 revisions left to test after this"));
+        assert!(Head::is_detached(&path_handler).unwrap());
+        assert_eq!(Head::get_head_commit(&path_handler).unwrap(), commits[3]);
+        assert_eq!(fs::read_to_string(path_handler.get_relative_path("file.txt")).unwrap(), "version 4\nbug\n");
+
+        // a local change to a file that differs in the next commit stops the checkout
+        fs::write(path_handler.get_relative_path("file.txt"), "local change\n").unwrap();
+        let error = bisect_command.execute(Some(vec!["bad"]), &path_handler).unwrap_err();
+        assert!(error.to_string().contains("would be overwritten by checkout:\n\tfile.txt"));
+        fs::write(path_handler.get_relative_path("file.txt"), "version 4\nbug\n").unwrap();
+
+        let result = bisect_command.execute(Some(vec!["bad"]), &path_handler).unwrap();
+        assert!(result.ends_with(&format!("[{}] commit 3", commits[2])));
+        let result = bisect_command.execute(Some(vec!["skip"]), &path_handler).unwrap();
+        assert!(result.ends_with(&format!("[{}] commit 2", commits[1])));
+        let result = bisect_command.execute(Some(vec!["good"]), &path_handler).unwrap();
+        assert!(result.contains(&format!("could be any of:\n{}\n{}", commits[3], commits[2])));
+        let result = bisect_command.execute(Some(vec!["good", &commits[2]]), &path_handler).unwrap();
+        assert!(result.starts_with(&format!("{} is the first bad commit", commits[3])));
+        let log = bisect_command.execute(Some(vec!["log"]), &path_handler).unwrap();
+        assert!(log.starts_with("git bisect start\n# bad: ["));
+        assert!(log.contains(&format!("git bisect skip {}", commits[2])));
+        fs::write(path_handler.get_relative_path("bisect.log"), &log).unwrap();
+
+        let result = bisect_command.execute(Some(vec!["reset"]), &path_handler).unwrap();
+        assert_eq!(result, "Switched to branch 'master'");
+        assert_eq!(Head::get_current_branch_name(&path_handler).unwrap(), "master");
+        assert_eq!(fs::read_to_string(path_handler.get_relative_path("file.txt")).unwrap(), "version 6\nbug\n");
+        assert!(!helpers::check_if_file_exists(BISECT_START_FILE, &path_handler));
+        assert_eq!(bisect_command.execute(Some(vec!["reset"]), &path_handler).unwrap(), "We are not bisecting.");
+
+        let result = bisect_command.execute(Some(vec!["replay", "bisect.log"]), &path_handler).unwrap();
+        assert!(result.starts_with(&format!("{} is the first bad commit", commits[3])));
+        bisect_command.execute(Some(vec!["reset"]), &path_handler).unwrap();
+
+        bisect_command.execute(Some(vec!["start", &commits[5], &commits[0]]), &path_handler).unwrap();
+        let result = bisect_command
+            .execute(Some(vec!["run", "sh", "-c", "! grep -q bug file.txt"]), &path_handler)
+            .unwrap();
+        assert!(result.contains(&format!("{} is the first bad commit", commits[3])));
+        assert!(result.ends_with("bisect found first bad commit"));
+        bisect_command.execute(Some(vec!["reset"]), &path_handler).unwrap();
+
+        bisect_command.execute(Some(vec!["start", &commits[5], &commits[0]]), &path_handler).unwrap();
+        let result = bisect_command.execute(Some(vec!["run", "sh", "-c", "exit 125"]), &path_handler).unwrap();
+        assert!(result.contains("There are only 'skip'ped commits left to test."));
+        assert!(result.ends_with("bisect run cannot continue any more"));
+        bisect_command.execute(Some(vec!["reset"]), &path_handler).unwrap();
+    }
+
+    #[test]
+    fn test_reflog_command() {
+        let (_temp_dir, temp_path) = common_setup();
+        let path_handler = PathHandler::new(temp_path.to_string());
+        let mut commits = Vec::new();
+        for message in ["first", "second", "third"] {
+            fs::write(path_handler.get_relative_path("file.txt"), message).unwrap();
+            Add::new().execute(Some(vec!["file.txt"]), &path_handler).unwrap();
+            Commit::new().execute(Some(vec!["-m", message]), &path_handler).unwrap();
+            commits.push(Head::get_head_commit(&path_handler).unwrap());
+        }
+
+        let reflog_command = Reflog::new();
+        let result = reflog_command.execute(None, &path_handler).unwrap();
+        let lines: Vec<&str> = result.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], format!("{} HEAD@{{0}}: commit: third", &commits[2][..8]));
+        assert_eq!(lines[2], format!("{} HEAD@{{2}}: commit (initial): first", &commits[0][..8]));
+        assert_eq!(revision::resolve_commit("HEAD@{1}", &path_handler).unwrap(), commits[1]);
+        assert_eq!(revision::resolve_commit("master@{2}", &path_handler).unwrap(), commits[0]);
+        assert_eq!(revision::resolve_commit("@{1}~1", &path_handler).unwrap(), commits[0]);
+        assert_eq!(revision::resolve_commit("master@{now}", &path_handler).unwrap(), commits[2]);
+        assert!(revision::resolve_commit("master@{yesterday}", &path_handler).is_err());
+        assert!(revision::resolve_commit("master@{5}", &path_handler).is_err());
+
+        Branch::new().execute(Some(vec!["feature"]), &path_handler).unwrap();
+        Checkout::new().execute(Some(vec!["feature"]), &path_handler).unwrap();
+        let result = reflog_command.execute(Some(vec!["show", "feature"]), &path_handler).unwrap();
+        assert_eq!(result, format!("{} feature@{{0}}: branch: Created from HEAD", &commits[2][..8]));
+        let result = reflog_command.execute(None, &path_handler).unwrap();
+        assert!(result.starts_with(&format!("{} HEAD@{{0}}: checkout: moving from master to feature", &commits[2][..8])));
+        Branch::new().execute(Some(vec!["-m", "feature", "topic"]), &path_handler).unwrap();
+        let result = reflog_command.execute(Some(vec!["topic"]), &path_handler).unwrap();
+        assert!(result.starts_with(&format!("{} topic@{{0}}: Branch: renamed refs/heads/feature to refs/heads/topic", &commits[2][..8])));
+        assert!(!helpers::check_if_file_exists(&reflog::get_log_path("refs/heads/feature"), &path_handler));
+
+        reflog_command.execute(Some(vec!["delete", "master@{1}"]), &path_handler).unwrap();
+        let entries = reflog::read_entries("refs/heads/master", &path_handler).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].message, "commit (initial): first");
+
+        let mut old_entries = entries.clone();
+        old_entries[0].timestamp = 1000;
+        reflog::write_entries("refs/heads/master", &old_entries, &path_handler).unwrap();
+        let result = reflog_command.execute(Some(vec!["expire", "--all", "-n"]), &path_handler).unwrap();
+        assert_eq!(result, "would prune commit (initial): first");
+        reflog_command.execute(Some(vec!["expire", "master"]), &path_handler).unwrap();
+        assert_eq!(reflog::read_entries("refs/heads/master", &path_handler).unwrap(), vec![entries[1].clone()]);
+    }
 
-        // TODO: Add assertions for the expected state after removal
+    #[test]
+    fn test_pack_refs_command() {
+        let (_temp_dir, temp_path) = common_setup();
+        let path_handler = PathHandler::new(temp_path.to_string());
+        fs::write(path_handler.get_relative_path("file.txt"), "content").unwrap();
+        Add::new().execute(Some(vec!["file.txt"]), &path_handler).unwrap();
+        Commit::new().execute(Some(vec!["-m", "first"]), &path_handler).unwrap();
+        let commit = Head::get_head_commit(&path_handler).unwrap();
+
+        Branch::new().execute(Some(vec!["feature/login"]), &path_handler).unwrap();
+        assert!(Branch::new().execute(Some(vec!["bad..name"]), &path_handler).is_err());
+        Tag::new().execute(Some(vec!["-a", "v1.0", "-m", "release"]), &path_handler).unwrap();
+        Checkout::new().execute(Some(vec!["feature/login"]), &path_handler).unwrap();
+        assert_eq!(Head::get_current_branch_name(&path_handler).unwrap(), "feature/login");
+
+        let result = PackRefs::new().execute(Some(vec!["--all"]), &path_handler).unwrap();
+        assert_eq!(result, "refs/heads/feature/login\nrefs/heads/master\nrefs/tags/v1.0");
+        assert!(!helpers::check_if_file_exists(".git/refs/heads/feature/login", &path_handler));
+        assert!(helpers::check_if_directory_exists(&path_handler.get_relative_path(R_HEADS)));
+        let packed_refs = refs::read_packed_refs(&path_handler).unwrap();
+        assert_eq!(packed_refs.len(), 3);
+        assert_eq!(packed_refs[2].peeled.as_deref(), Some(commit.as_str()));
+
+        // packed refs are still resolved and listed
+        assert_eq!(Head::get_head_commit(&path_handler).unwrap(), commit);
+        assert_eq!(revision::resolve_commit("v1.0", &path_handler).unwrap(), commit);
+        let result = ShowRef::new().execute(None, &path_handler).unwrap();
+        assert_eq!(result.lines().count(), 3);
+        assert!(result.contains(&format!("{} refs/heads/feature/login", commit)));
+
+        Checkout::new().execute(Some(vec!["master"]), &path_handler).unwrap();
+        Branch::new().execute(Some(vec!["-d", "feature/login"]), &path_handler).unwrap();
+        assert!(!refs::ref_exists("refs/heads/feature/login", &path_handler));
+        assert_eq!(refs::read_packed_refs(&path_handler).unwrap().len(), 2);
+
+        // a loose ref locked by another writer isn't pruned
+        Branch::new().execute(Some(vec!["locked"]), &path_handler).unwrap();
+        let lock_path = format!("{}{}", path_handler.get_relative_path(".git/refs/heads/locked"), LOCK_FILE_EXTENSION);
+        fs::write(&lock_path, "").unwrap();
+        PackRefs::new().execute(Some(vec!["--all"]), &path_handler).unwrap();
+        assert!(helpers::check_if_file_exists(".git/refs/heads/locked", &path_handler));
+        fs::remove_file(&lock_path).unwrap();
+        PackRefs::new().execute(Some(vec!["--all"]), &path_handler).unwrap();
+        assert!(!helpers::check_if_file_exists(".git/refs/heads/locked", &path_handler));
+        assert!(!Path::new(&lock_path).exists());
+
+        assert!(refs::check_ref_format("refs/heads/feature/login"));
+        for invalid_name in ["refs/heads/a..b", "refs/heads/a.lock", "refs/heads/.hidden", "refs/heads/a b", "refs/heads/a/", "refs/heads/a@{1}"] {
+            assert!(!refs::check_ref_format(invalid_name), "{}", invalid_name);
+        }
     }
 
     #[test]
@@ -2493,8 +7035,8 @@ mod tests {
     #[test]
     fn test_add_remote() {
         // Common setup
-        let _temp_dir = common_setup();
-        let path_handler = PathHandler::new(_temp_dir.to_string());
+        let (_temp_dir, temp_path) = common_setup();
+        let path_handler = PathHandler::new(temp_path.to_string());
 
         // Create a new Remote instance
         let remote = Remote::new();
@@ -2511,8 +7053,8 @@ mod tests {
     #[test]
     fn test_remove_remote() {
         // Common setup
-        let _temp_dir = common_setup();
-        let path_handler = PathHandler::new(_temp_dir.to_string());
+        let (_temp_dir, temp_path) = common_setup();
+        let path_handler = PathHandler::new(temp_path.to_string());
 
         // Create a new Remote instance
         let remote = Remote::new();
@@ -2532,7 +7074,100 @@ mod tests {
     }
 
     #[test]
-    fn test_add_new_lightweight_tag() {
+    fn test_config_command() {
+        let (_temp_dir, temp_path) = common_setup();
+        let path_handler = PathHandler::new(temp_path.to_string());
+        Remote::new().execute(Some(vec!["add", REMOTE_NAME, REMOTE_URL]), &path_handler).unwrap();
+        let config_content = fs::read_to_string(path_handler.get_relative_path(CONFIG_FILE)).unwrap();
+        assert_eq!(
+            config_content,
+            format!("[remote \"{}\"]\n\turl = {}\n\tfetch = +refs/heads/*:refs/remotes/{}/*\n", REMOTE_NAME, REMOTE_URL, REMOTE_NAME)
+        );
+        assert_eq!(helpers::get_remote_url(REMOTE_NAME, &path_handler).unwrap(), REMOTE_URL);
+
+        // remotes written by older versions are still read
+        let legacy_content = format!("{}[remote 'old']\nurl = 127.0.0.1:9419\n", config_content);
+        fs::write(path_handler.get_relative_path(CONFIG_FILE), legacy_content).unwrap();
+        assert_eq!(helpers::get_remote_url("old", &path_handler).unwrap(), "127.0.0.1:9419");
+
+        let config_command = ConfigCommand::new();
+        config_command.execute(Some(vec!["core.bare", "yes"]), &path_handler).unwrap();
+        assert_eq!(config_command.execute(Some(vec!["--get", "core.bare"]), &path_handler).unwrap(), "yes");
+        assert_eq!(config_command.execute(Some(vec!["--bool", "core.bare"]), &path_handler).unwrap(), "true");
+        config_command.execute(Some(vec!["--int", "pack.windowMemory", "1k"]), &path_handler).unwrap();
+        assert_eq!(config_command.execute(Some(vec!["pack.windowmemory"]), &path_handler).unwrap(), "1024");
+        assert!(config_command.execute(Some(vec!["--int", "pack.depth", "deep"]), &path_handler).is_err());
+
+        config_command.execute(Some(vec!["--add", "remote.origin.fetch", "+refs/heads/*:refs/remotes/origin/*"]), &path_handler).unwrap();
+        config_command.execute(Some(vec!["--add", "remote.origin.fetch", "+refs/tags/*:refs/tags/*"]), &path_handler).unwrap();
+        let result = config_command.execute(Some(vec!["--get-all", "remote.origin.fetch"]), &path_handler).unwrap();
+        assert_eq!(result.lines().count(), 3);
+        assert!(config_command.execute(Some(vec!["--unset", "remote.origin.fetch"]), &path_handler).is_err());
+        config_command.execute(Some(vec!["--unset-all", "remote.origin.fetch"]), &path_handler).unwrap();
+        assert!(config_command.execute(Some(vec!["remote.origin.fetch"]), &path_handler).is_err());
+
+        let result = config_command.execute(Some(vec!["--local", "--list"]), &path_handler).unwrap();
+        assert_eq!(
+            result,
+            format!("remote.origin.url={}\nremote.old.url=127.0.0.1:9419\ncore.bare=yes\npack.windowmemory=1024", REMOTE_URL)
+        );
+        assert!(config_command.execute(Some(vec!["nosection", "value"]), &path_handler).is_err());
+
+        Remote::new().execute(Some(vec!["rm", REMOTE_NAME]), &path_handler).unwrap();
+        assert!(helpers::get_remote_url(REMOTE_NAME, &path_handler).is_err());
+    }
+
+    #[test]
+    fn test_upstream_tracking() {
+        let (_temp_dir, temp_path) = common_setup();
+        let path_handler = PathHandler::new(temp_path.to_string());
+        Remote::new().execute(Some(vec!["add", REMOTE_NAME, REMOTE_URL]), &path_handler).unwrap();
+        let mut commits = Vec::new();
+        for message in ["first", "second", "third"] {
+            fs::write(path_handler.get_relative_path("file.txt"), message).unwrap();
+            Add::new().execute(Some(vec!["file.txt"]), &path_handler).unwrap();
+            Commit::new().execute(Some(vec!["-m", message]), &path_handler).unwrap();
+            commits.push(Head::get_head_commit(&path_handler).unwrap());
+        }
+        refs::update_ref("refs/remotes/origin/master", &commits[0], None, None, &path_handler).unwrap();
+
+        let branch_command = Branch::new();
+        assert!(revision::resolve_commit("@{u}", &path_handler).is_err());
+        assert!(branch_command.execute(Some(vec!["-u", "origin/missing"]), &path_handler).is_err());
+        let result = branch_command.execute(Some(vec!["--set-upstream-to=origin/master"]), &path_handler).unwrap();
+        assert_eq!(result, "branch 'master' set up to track 'origin/master'.");
+        assert_eq!(revision::resolve_commit("@{u}", &path_handler).unwrap(), commits[0]);
+        assert_eq!(revision::resolve_commit("master@{upstream}~0", &path_handler).unwrap(), commits[0]);
+
+        let status = Status::new().execute(None, &path_handler).unwrap();
+        assert!(status.starts_with("Your branch is ahead of 'origin/master' by 2 commits."));
+        let result = branch_command.execute(Some(vec!["-vv"]), &path_handler).unwrap();
+        assert_eq!(result, format!("* master {} [origin/master: ahead 2] third", &commits[2][..8]));
+        let result = branch_command.execute(Some(vec!["-v"]), &path_handler).unwrap();
+        assert_eq!(result, format!("* master {} [ahead 2] third", &commits[2][..8]));
+
+        refs::update_ref("refs/heads/master", &commits[1], None, None, &path_handler).unwrap();
+        refs::update_ref("refs/remotes/origin/master", &commits[2], None, None, &path_handler).unwrap();
+        let status = Status::new().execute(None, &path_handler).unwrap();
+        assert!(status.starts_with("Your branch is behind 'origin/master' by 1 commit, and can be fast-forwarded."));
+
+        // upstreams can be local branches and follow the branch when it's renamed
+        branch_command.execute(Some(vec!["topic"]), &path_handler).unwrap();
+        branch_command.execute(Some(vec!["-u", "master", "topic"]), &path_handler).unwrap();
+        assert_eq!(helpers::get_upstream_ref("topic", &path_handler).unwrap().as_deref(), Some("refs/heads/master"));
+        branch_command.execute(Some(vec!["-m", "topic", "renamed"]), &path_handler).unwrap();
+        assert_eq!(helpers::get_upstream_ref("renamed", &path_handler).unwrap().as_deref(), Some("refs/heads/master"));
+        assert_eq!(helpers::get_upstream_ref("topic", &path_handler).unwrap(), None);
+        branch_command.execute(Some(vec!["-d", "renamed"]), &path_handler).unwrap();
+        assert_eq!(helpers::get_upstream_ref("renamed", &path_handler).unwrap(), None);
+
+        branch_command.execute(Some(vec!["--unset-upstream"]), &path_handler).unwrap();
+        assert!(branch_command.execute(Some(vec!["--unset-upstream"]), &path_handler).is_err());
+        assert!(!Status::new().execute(None, &path_handler).unwrap().contains("Your branch"));
+    }
+
+    #[test]
+    fn test_create_lightweight_tag() {
         // Create a temporary directory
         let (_temp_dir, temp_path) = common_setup();
         let path_handler = PathHandler::new(temp_path.to_string());
@@ -2543,7 +7178,7 @@ mod tests {
 
         // Execute the Add command
         let add_command = Add::new();
-        let args_add: Option<Vec<&str>> = Some(vec![&file_path]);
+        let args_add: Option<Vec<&str>> = Some(vec!["sample.txt"]);
         let _result_add = add_command.execute(args_add, &path_handler);
 
         // Execute the Commit command
@@ -2551,17 +7186,17 @@ mod tests {
         let args_commit: Option<Vec<&str>> = Some(vec!["-m", "Initial commit"]);
         let _result_commit = commit_command.execute(args_commit, &path_handler);
 
-        let last_commit = helpers::read_file_content(&(temp_path.clone() + ".git/refs/heads/main"));
+        let last_commit = helpers::read_file_content(&path_handler.get_relative_path(".git/refs/heads/master"));
         // Create a Tag instance
         let tag = Tag::new();
 
-        // Execute add_new_lightweight_tag
+        // Create a lightweight tag for HEAD
         let _result = tag
-            .add_new_lightweight_tag("new_tag", &path_handler)
+            .create_tag("new_tag", HEAD, None, None, false, &path_handler)
             .expect("Failed to add new tag");
 
         // Read the content of the created tag file
-        let tag_content = fs::read_to_string(temp_path + ".git/refs/tags/new_tag")
+        let tag_content = fs::read_to_string(path_handler.get_relative_path(".git/refs/tags/new_tag"))
             .expect("Failed to read tag file");
 
         // Assertions based on tag content
@@ -2580,7 +7215,7 @@ mod tests {
 
         // Execute the Add command
         let add_command = Add::new();
-        let args_add: Option<Vec<&str>> = Some(vec![&file_path]);
+        let args_add: Option<Vec<&str>> = Some(vec!["sample.txt"]);
         let _result_add = add_command.execute(args_add, &path_handler);
 
         // Execute the Commit command
@@ -2588,21 +7223,57 @@ mod tests {
         let args_commit: Option<Vec<&str>> = Some(vec!["-m", "Initial commit"]);
         let _result_commit = commit_command.execute(args_commit, &path_handler);
 
-        let _last_commit =
-            helpers::read_file_content(&(temp_path.clone() + ".git/refs/heads/main"));
         // Create a Tag instance
         let tag = Tag::new();
 
-        // Execute add_new_lightweight_tag
+        // Create a lightweight tag for HEAD
         let _result = tag
-            .add_new_lightweight_tag("new_tag", &path_handler)
+            .create_tag("new_tag", HEAD, None, None, false, &path_handler)
             .expect("Failed to add new tag");
 
         // Execute delete_tag
         tag.delete_tag("new_tag", &path_handler).expect("Failed to delete tag");
 
         // Check that the tag file is deleted
-        assert!(!(Path::new(&(temp_path.clone() + "tags/new_tag"))).exists());
+        assert!(!Path::new(&path_handler.get_relative_path(".git/refs/tags/new_tag")).exists());
+    }
+
+    #[test]
+    fn test_annotated_tag_and_peeling() {
+        let (_temp_dir, temp_path) = common_setup();
+        let path_handler = PathHandler::new(temp_path.to_string());
+
+        fs::write(path_handler.get_relative_path("sample.txt"), "Sample file content")
+            .expect("Failed to create a sample file");
+        Add::new()
+            .execute(Some(vec!["sample.txt"]), &path_handler)
+            .expect("Failed to add file");
+        Commit::new()
+            .execute(Some(vec!["-m", "Initial commit"]), &path_handler)
+            .expect("Failed to commit");
+        let commit = revision::resolve_revision(HEAD, &path_handler).expect("Failed to resolve HEAD");
+
+        let tag = Tag::new();
+        let tag_hash = tag
+            .execute(Some(vec!["-a", "v1.0", "-m", "First release"]), &path_handler)
+            .expect("Failed to create annotated tag");
+        let (object_type, content, _size) =
+            helpers::read_object_to_string(tag_hash.clone(), &path_handler).expect("Failed to read tag object");
+        assert!(object_type == ObjectType::Tag);
+        assert_eq!(revision::get_header_field(&content, "object"), Some(commit.clone()));
+        assert_eq!(revision::get_header_field(&content, "type"), Some("commit".to_string()));
+        assert_eq!(revision::get_header_field(&content, "tag"), Some("v1.0".to_string()));
+
+        assert_eq!(revision::resolve_revision("v1.0", &path_handler).unwrap(), tag_hash);
+        assert_eq!(revision::resolve_revision("v1.0^{}", &path_handler).unwrap(), commit);
+        assert_eq!(revision::resolve_commit("v1.0", &path_handler).unwrap(), commit);
+
+        assert!(tag.execute(Some(vec!["v1.0"]), &path_handler).is_err());
+        assert_eq!(tag.execute(Some(vec!["-f", "v1.0"]), &path_handler).unwrap(), commit);
+
+        tag.execute(Some(vec!["v2.0"]), &path_handler).expect("Failed to create tag");
+        let listed = tag.execute(Some(vec!["-l", "v1*"]), &path_handler).unwrap();
+        assert_eq!(listed, "v1.0");
     }
 
     #[test]
@@ -2611,10 +7282,9 @@ mod tests {
         let (_temp_dir, _temp_path) = common_setup();
         let path_handler = PathHandler::new(_temp_path.to_string());
 
-        // Create a .gitignore.txt file in the temporary directory
-        let gitignore_path = ".gitignore.txt";
-        fs::write(&gitignore_path, "ignored_file.txt")
-            .expect("Failed to create .gitignore.txt file");
+        // Create a .gitignore file in the temporary directory
+        fs::write(path_handler.get_relative_path(GITIGNORE_FILE), "ignored_file.txt")
+            .expect("Failed to create .gitignore file");
 
         // Create a CheckIgnore instance
         let check_ignore = CheckIgnore::new();
@@ -2624,7 +7294,6 @@ mod tests {
 
         // Assert that the result is the provided file path
         assert_eq!(result.unwrap(), "ignored_file.txt");
-        let _ = fs::remove_file("ignored_file.txt");
     }
 
     #[test]
@@ -2636,7 +7305,7 @@ mod tests {
         // Create a CheckIgnore instance
         let check_ignore = CheckIgnore::new();
 
-        // Execute the check_ignore command with a file that does not exist in .gitignore.txt
+        // Execute the check_ignore command with a file that does not exist in .gitignore
         let result = check_ignore.execute(Some(vec!["non_existent_file.txt"]), &path_handler);
 
         // Assert that the result is an empty string
@@ -2652,10 +7321,239 @@ mod tests {
         // Create a CheckIgnore instance
         let check_ignore = CheckIgnore::new();
 
-        // Execute the check_ignore command without a .gitignore.txt file
+        // Execute the check_ignore command without a .gitignore file
         let result = check_ignore.execute(Some(vec!["some_file.txt"]), &path_handler);
 
         // Assert that the result is an empty string
         assert_eq!(result.unwrap(), "");
     }
+
+    #[test]
+    fn test_ignore_rules_in_commands() {
+        let (_temp_dir, temp_path) = common_setup();
+        let path_handler = PathHandler::new(temp_path.to_string());
+
+        fs::create_dir_all(path_handler.get_relative_path("logs/old")).unwrap();
+        fs::create_dir_all(path_handler.get_relative_path("src")).unwrap();
+        fs::create_dir_all(path_handler.get_relative_path(".git/info")).unwrap();
+        fs::write(path_handler.get_relative_path(GITIGNORE_FILE), "*.tmp\n/logs/\n").unwrap();
+        fs::write(path_handler.get_relative_path("src/.gitignore"), "# keep this one\n!keep.tmp\n").unwrap();
+        fs::write(path_handler.get_relative_path(INFO_EXCLUDE_FILE), "local.txt\n").unwrap();
+        for file in ["a.tmp", "src/keep.tmp", "src/main.rs", "logs/old/1.log", "local.txt"] {
+            fs::write(path_handler.get_relative_path(file), "content").unwrap();
+        }
+
+        let check_ignore = CheckIgnore::new();
+        let result = check_ignore
+            .execute(Some(vec!["-v", "a.tmp", "logs/old/1.log", "src/keep.tmp"]), &path_handler)
+            .unwrap();
+        assert_eq!(result, ".gitignore:1:*.tmp\ta.tmp\n.gitignore:2:/logs/\tlogs/old/1.log\nsrc/.gitignore:2:!keep.tmp\tsrc/keep.tmp");
+        let result = check_ignore.execute(Some(vec!["-v", "-n", "src/main.rs"]), &path_handler).unwrap();
+        assert_eq!(result, "::\tsrc/main.rs");
+
+        Add::new().execute(Some(vec!["."]), &path_handler).unwrap();
+        let index_paths: Vec<String> = helpers::read_index_entries(&path_handler)
+            .unwrap()
+            .into_iter()
+            .map(|(path, _hash, _state, _mode)| path)
+            .collect();
+        assert_eq!(index_paths, vec![".gitignore", "src/.gitignore", "src/keep.tmp", "src/main.rs"]);
+        assert!(Add::new().execute(Some(vec!["a.tmp"]), &path_handler).is_err());
+
+        let mut ignored_files = LsFiles::get_ignored_files(&path_handler).unwrap();
+        ignored_files.sort();
+        assert_eq!(ignored_files, vec!["a.tmp", "local.txt", "logs/"]);
+
+        fs::write(path_handler.get_relative_path("new.txt"), "content").unwrap();
+        let status = Status::new().execute(None, &path_handler).unwrap();
+        assert!(status.contains("untracked: new.txt"));
+        assert!(!status.contains("a.tmp"));
+        assert!(!status.contains("logs"));
+    }
+
+    #[test]
+    fn test_path_handler_keeps_absolute_paths() {
+        assert_eq!(PathHandler::new(String::from("/tmp/repo")).get_relative_path(HEAD_FILE), "/tmp/repo/.git/HEAD");
+        assert_eq!(PathHandler::new(String::from("repo/")).get_relative_path(HEAD_FILE), "repo/.git/HEAD");
+        assert_eq!(PathHandler::new(String::new()).get_relative_path(HEAD_FILE), HEAD_FILE);
+    }
+
+    #[test]
+    fn test_legacy_pack_directory_is_migrated() {
+        let (_temp_dir, temp_path) = common_setup();
+        let path_handler = PathHandler::new(temp_path.to_string());
+        let commit_hash = commit_file("sample.txt", "Sample file content", "Initial commit", &path_handler);
+        let tree_hash = helpers::get_commit_tree(&commit_hash, &path_handler).unwrap();
+
+        // older versions kept received packs in .git/pack, with no index
+        let pack = PackObjects::new().create_pack(vec![&commit_hash], &path_handler).unwrap();
+        fs::create_dir_all(path_handler.get_relative_path(LEGACY_PACK)).unwrap();
+        fs::write(path_handler.get_relative_path(&format!("{}/received_pack_file.pack", LEGACY_PACK)), &pack).unwrap();
+        for object_hash in helpers::get_objects_reachable_from(vec![commit_hash.clone()], &path_handler).unwrap() {
+            fs::remove_file(path_handler.get_relative_path(&helpers::get_object_path(&object_hash))).unwrap();
+        }
+
+        assert_eq!(helpers::get_commit_tree(&commit_hash, &path_handler).unwrap(), tree_hash);
+        assert!(!Path::new(&path_handler.get_relative_path(LEGACY_PACK)).exists());
+        assert_eq!(packfile::list_indexed_packs(&path_handler).unwrap().len(), 1);
+        assert_eq!(helpers::read_tree_content(&tree_hash, &path_handler).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_repack_and_prune() {
+        let (_temp_dir, temp_path) = common_setup();
+        let path_handler = PathHandler::new(temp_path.to_string());
+
+        let commit_hash = commit_file("sample.txt", "Sample file content", "Initial commit", &path_handler);
+        let tree_hash = helpers::get_commit_tree(&commit_hash, &path_handler).unwrap();
+
+        let dangling_hash =
+            HashObjectCreator::write_object_file_bytes(b"dangling", ObjectType::Blob, 8, &path_handler).unwrap();
+
+        // Reachable objects move to a single pack and can still be read from there
+        let pack_name = Repack::new().execute(Some(vec![ALL_FLAG, DELETE_FLAG]), &path_handler).unwrap();
+        assert_eq!(packfile::list_indexed_packs(&path_handler).unwrap(), vec![pack_name]);
+        assert!(!helpers::check_if_file_exists(&helpers::get_object_path(&commit_hash), &path_handler));
+        assert_eq!(helpers::get_commit_tree(&commit_hash, &path_handler).unwrap(), tree_hash);
+        assert_eq!(helpers::read_tree_content(&tree_hash, &path_handler).unwrap().len(), 1);
+
+        // Unreachable objects survive until they are older than the expire date
+        let result = Prune::new().execute(Some(vec![EXPIRE_FLAG, DEFAULT_PRUNE_EXPIRE]), &path_handler);
+        assert_eq!(result.unwrap(), "");
+        assert!(helpers::check_if_file_exists(&helpers::get_object_path(&dangling_hash), &path_handler));
+
+        let result = Prune::new().execute(Some(vec![EXPIRE_FLAG, "now"]), &path_handler);
+        assert_eq!(result.unwrap(), dangling_hash);
+        assert!(!helpers::check_if_file_exists(&helpers::get_object_path(&dangling_hash), &path_handler));
+
+        // Running gc on an already packed repository keeps everything reachable
+        Gc::new().execute(None, &path_handler).unwrap();
+        assert_eq!(packfile::list_indexed_packs(&path_handler).unwrap().len(), 1);
+        assert_eq!(helpers::get_commit_tree(&commit_hash, &path_handler).unwrap(), tree_hash);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_file_modes_roundtrip() {
+        use std::os::unix::fs::{symlink, PermissionsExt};
+
+        let (_temp_dir, temp_path) = common_setup();
+        let path_handler = PathHandler::new(temp_path.to_string());
+
+        fs::write(path_handler.get_relative_path("sample.txt"), "Sample file content").unwrap();
+        fs::write(path_handler.get_relative_path("script.sh"), "#!/bin/sh\necho hi\n").unwrap();
+        fs::set_permissions(path_handler.get_relative_path("script.sh"), fs::Permissions::from_mode(0o755)).unwrap();
+        symlink("sample.txt", path_handler.get_relative_path("link")).unwrap();
+
+        // A nested repository is stored as a gitlink to its checked out commit
+        let nested_path_handler = PathHandler::new(path_handler.get_relative_path("nested"));
+        Init::new().execute(None, &nested_path_handler).unwrap();
+        let nested_commit = commit_file("nested.txt", "nested", "nested", &nested_path_handler);
+
+        let staging_area = StagingArea::new();
+        for path in ["sample.txt", "script.sh", "link", "nested"] {
+            staging_area.add_file(path, &path_handler).unwrap();
+        }
+        let tree_hash = HashObjectCreator::create_tree_object(&path_handler).unwrap();
+        let tree_files = helpers::get_tree_files(&tree_hash, "", &path_handler).unwrap();
+        assert_eq!(tree_files["sample.txt"].0, TREE_FILE_MODE);
+        assert_eq!(tree_files["script.sh"].0, EXECUTABLE_FILE_MODE);
+        assert_eq!(tree_files["link"].0, SYMLINK_MODE);
+        assert_eq!(tree_files["nested"], (GITLINK_MODE.to_string(), nested_commit));
+        let (_type, link_content, _size) =
+            helpers::read_object_to_bytes(tree_files["link"].1.clone(), &path_handler).unwrap();
+        assert_eq!(link_content, b"sample.txt");
+
+        // Checking the tree out again restores the executable bit and the symlink
+        for path in ["sample.txt", "script.sh", "link"] {
+            fs::remove_file(path_handler.get_relative_path(path)).unwrap();
+        }
+        WorkingDirectory::update_working_directory_to(&tree_hash, &path_handler).unwrap();
+        let script_metadata = fs::metadata(path_handler.get_relative_path("script.sh")).unwrap();
+        assert_ne!(script_metadata.permissions().mode() & 0o111, 0);
+        let link_target = fs::read_link(path_handler.get_relative_path("link")).unwrap();
+        assert_eq!(link_target, Path::new("sample.txt"));
+        assert_eq!(helpers::get_file_mode("sample.txt", &path_handler).unwrap(), TREE_FILE_MODE);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_index_keeps_file_modes() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let (_temp_dir, temp_path) = common_setup();
+        let path_handler = PathHandler::new(temp_path.to_string());
+        let set_mode = |mode: u32| {
+            fs::set_permissions(path_handler.get_relative_path("script.sh"), fs::Permissions::from_mode(mode)).unwrap();
+        };
+        fs::write(path_handler.get_relative_path("script.sh"), "#!/bin/sh\necho hi\n").unwrap();
+        set_mode(0o755);
+        Add::new().execute(Some(vec!["script.sh"]), &path_handler).unwrap();
+        let index_content = helpers::read_file_content(&path_handler.get_relative_path(INDEX_FILE)).unwrap();
+        assert!(index_content.ends_with(&format!(";{}", EXECUTABLE_FILE_MODE)));
+        Commit::new().execute(Some(vec!["-m", "initial"]), &path_handler).unwrap();
+        let index_entries = helpers::read_index_entries(&path_handler).unwrap();
+        assert_eq!(index_entries[0].3, EXECUTABLE_FILE_MODE);
+
+        // the tree keeps the staged mode even if the working file changes afterwards
+        set_mode(0o644);
+        let status = Status::new().execute(None, &path_handler).unwrap();
+        assert!(status.contains("mode changed: script.sh (100755 -> 100644) (Unstaged)"));
+        let diff = Diff::new().execute(None, &path_handler).unwrap();
+        assert_eq!(diff, "diff --git a/script.sh b/script.sh\nold mode 100755\nnew mode 100644");
+        assert_eq!(HashObjectCreator::create_tree_object(&path_handler).unwrap(), "");
+
+        Add::new().execute(Some(vec!["script.sh"]), &path_handler).unwrap();
+        let status = Status::new().execute(None, &path_handler).unwrap();
+        assert!(status.contains("mode changed: script.sh (100755 -> 100644) (Staged)"));
+        let diff = Diff::new().execute(Some(vec!["--cached"]), &path_handler).unwrap();
+        assert_eq!(diff, "diff --git a/script.sh b/script.sh\nold mode 100755\nnew mode 100644");
+        Commit::new().execute(Some(vec!["-m", "not executable"]), &path_handler).unwrap();
+        let diff = Diff::new().execute(Some(vec!["HEAD~1", "HEAD"]), &path_handler).unwrap();
+        assert_eq!(diff, "diff --git a/script.sh b/script.sh\nold mode 100755\nnew mode 100644");
+    }
+
+    #[test]
+    fn test_tree_objects_are_canonical() {
+        let (_temp_dir, temp_path) = common_setup();
+        let path_handler = PathHandler::new(temp_path.to_string());
+
+        let file_paths = ["dir/sub/x", "dir/y", "z", "dir2/w", "dir/sub2/q", "a-b", "a.txt", "a/f"];
+        for file_path in file_paths {
+            let full_path = path_handler.get_relative_path(file_path);
+            fs::create_dir_all(Path::new(&full_path).parent().unwrap()).unwrap();
+            fs::write(&full_path, format!("{}\n", file_path)).unwrap();
+            StagingArea::new().add_file(file_path, &path_handler).unwrap();
+        }
+
+        // Same tree git writes for these files
+        let tree_hash = HashObjectCreator::create_tree_object(&path_handler).unwrap();
+        assert_eq!(tree_hash, "d6258ba58bcb2553e5f9aa9de09e157be6d087fa");
+
+        // The order of the index entries doesn't change the tree
+        let index_path = path_handler.get_relative_path(INDEX_FILE);
+        let mut index_lines: Vec<String> = fs::read_to_string(&index_path).unwrap().lines().map(String::from).collect();
+        for step in 1..index_lines.len() {
+            index_lines.rotate_left(step);
+            index_lines.swap(0, step);
+            fs::write(&index_path, index_lines.join("\n")).unwrap();
+            assert_eq!(HashObjectCreator::create_tree_object(&path_handler).unwrap(), tree_hash);
+        }
+
+        // Readers walk every entry, including the ones after a subtree
+        let tree_files = helpers::get_tree_files(&tree_hash, "", &path_handler).unwrap();
+        assert_eq!(tree_files.len(), file_paths.len());
+        let mut listed_entries = Vec::new();
+        LsTree::generate_tree_entries(&mut listed_entries, tree_hash.clone(), "", false, true, false, &path_handler).unwrap();
+        assert_eq!(listed_entries.len(), file_paths.len());
+
+        for file_path in file_paths {
+            fs::remove_file(path_handler.get_relative_path(file_path)).unwrap();
+        }
+        WorkingDirectory::update_working_directory_to(&tree_hash, &path_handler).unwrap();
+        for file_path in file_paths {
+            let content = fs::read_to_string(path_handler.get_relative_path(file_path)).unwrap();
+            assert_eq!(content, format!("{}\n", file_path));
+        }
+    }
 }
@@ -1,4 +1,5 @@
-use std::{collections::HashMap, error::Error, fs, io, io::Read, io::Write, path::Path, fmt};
+use std::{collections::HashMap, collections::HashSet, error::Error, fs, io, io::Read, io::Write, path::Path, fmt, time::UNIX_EPOCH};
+use chrono::{Local, NaiveDate};
 extern crate crypto;
 extern crate libflate;
 use std::env;
@@ -9,8 +10,13 @@ use crypto::sha1::Sha1;
 use libflate::zlib::{Decoder, Encoder};
 
 use super::git_commands::PathHandler;
+use super::config::{Config, ConfigFile, ConfigScope};
+use super::packfile;
+use super::refs::{self, RefTransaction, Refspec};
+use super::revision;
+use super::signing::Signer;
 use super::structs::{IndexFileEntryState, ObjectType, WorkingDirectory, StagingArea};
-use crate::constants::{CONFIG_FILE, CONFLICT_BRANCH_CHANGE, CONFLICT_END, CONFLICT_START, GIT, INDEX_FILE, OBJECT, R_HEADS, R_REMOTES, TREE_FILE_MODE, TREE_SUBTREE_MODE, ZERO_HASH};
+use crate::constants::{BASE64_ALPHABET, CONFLICT_BRANCH_CHANGE, CONFLICT_END, CONFLICT_START, DEFAULT_RENAME_THRESHOLD, DIFF_STAT_GRAPH_WIDTH, EXECUTABLE_FILE_MODE, FIND_COPIES_FLAG, FIND_COPIES_LONG_FLAG, FIND_RENAMES_FLAG, FIND_RENAMES_LONG_FLAG, GIT, GITLINK_MODE, HEAD, HEAD_FILE, INDEX_FILE, LOCK_FILE_EXTENSION, LOGS_DIR, MERGE_HEAD, OBJECT, PACKED_REFS_FILE, R_HEADS, R_REFS, REBASE_HEAD, SYMLINK_MODE, TREE_FILE_MODE, TREE_SUBTREE_MODE, ZERO_HASH};
 
 /// Returns length of a file's content
 pub fn get_file_length(path: &str) -> Result<u64, Box<dyn Error>> {
@@ -34,6 +40,67 @@ pub fn read_file_content_to_bytes(path: &str) -> Result<Vec<u8>, io::Error> {
     Ok(file_content)
 }
 
+/// Returns the mode a working directory entry is stored with in a tree. Symlinks, directories holding
+/// a nested repository (gitlinks) and executable files get their own modes.
+pub fn get_file_mode(path: &str, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
+    let full_path = path_handler.get_relative_path(path);
+    let metadata = fs::symlink_metadata(&full_path)?;
+    let file_mode = if metadata.file_type().is_symlink() {
+        SYMLINK_MODE
+    } else if metadata.is_dir() && Path::new(&full_path).join(GIT).is_dir() {
+        GITLINK_MODE
+    } else if metadata.is_dir() {
+        TREE_SUBTREE_MODE
+    } else if is_executable(&metadata) {
+        EXECUTABLE_FILE_MODE
+    } else {
+        TREE_FILE_MODE
+    };
+    Ok(file_mode.to_string())
+}
+
+#[cfg(unix)]
+fn is_executable(metadata: &fs::Metadata) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    metadata.permissions().mode() & 0o111 != 0
+}
+
+#[cfg(not(unix))]
+fn is_executable(_metadata: &fs::Metadata) -> bool {
+    false
+}
+
+/// Returns the content a working directory file is stored with: the path it points to for symlinks,
+/// its raw bytes otherwise.
+pub fn read_working_file(path: &str, path_handler: &PathHandler) -> Result<Vec<u8>, Box<dyn Error>> {
+    let full_path = path_handler.get_relative_path(path);
+    if fs::symlink_metadata(&full_path)?.file_type().is_symlink() {
+        return Ok(fs::read_link(&full_path)?.to_string_lossy().as_bytes().to_vec());
+    }
+    Ok(read_file_content_to_bytes(&full_path)?)
+}
+
+/// Returns the commit checked out in the nested repository a gitlink points to
+pub fn get_gitlink_commit(path: &str, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
+    let nested_path_handler = PathHandler::new(path_handler.get_relative_path(path));
+    revision::resolve_revision(HEAD, &nested_path_handler)
+}
+
+/// Returns the object hash a working directory entry would be stored with, without writing anything.
+/// For gitlinks it's the commit checked out in the nested repository.
+pub fn hash_working_file(path: &str, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
+    if get_file_mode(path, path_handler)? == GITLINK_MODE {
+        return get_gitlink_commit(path, path_handler);
+    }
+    let content = read_working_file(path, path_handler)?;
+    Ok(HashObjectCreator::generate_object_hash_from_bytes(&ObjectType::Blob, &content))
+}
+
+/// Returns true if the tree entry mode belongs to a subtree
+pub fn is_tree_mode(file_mode: &str) -> bool {
+    file_mode.trim_start_matches('0') == TREE_SUBTREE_MODE.trim_start_matches('0')
+}
+
 pub fn compress_content(content: &str) -> Result<Vec<u8>, io::Error> {
     let mut encoder = Encoder::new(Vec::new())?;
 
@@ -75,11 +142,12 @@ pub fn generate_sha1_string(str: &str) -> String {
     hasher.result_str()
 }
 
-/// Updates the index file with a new file path, object hash and status for a specific file.
+/// Updates the index file with a new file path, object hash, status and mode for a specific file.
 /// If the file was already contained in the index file, it replaces it.
 pub fn update_file_with_hash(
     object_hash: &str,
     new_status: &str,
+    file_mode: &str,
     file_path: &str,
     path_handler: &PathHandler
 ) -> io::Result<()> {
@@ -92,17 +160,17 @@ pub fn update_file_with_hash(
     // Search for the hash in the lines.
     let mut found = false;
     for line in &mut lines {
-        if line.starts_with(file_path) {
+        if line.split(';').next() == Some(file_path) {
             found = true;
             // Replace the existing line with the hash and "1".
-            *line = format!("{};{};{}", file_path, object_hash, new_status);
+            *line = format!("{};{};{};{}", file_path, object_hash, new_status, file_mode);
             break;
         }
     }
 
     // If the hash was not found, add a new line.
     if !found {
-        lines.push(format!("{};{};{}", file_path, object_hash, new_status));
+        lines.push(format!("{};{};{};{}", file_path, object_hash, new_status, file_mode));
     }
 
     // Join the lines back into a single string.
@@ -152,55 +220,55 @@ pub fn remove_object_from_file(file_path: &str) -> io::Result<()> {
 }
 
 pub fn get_all_branches(path_handler: &PathHandler) -> Result<Vec<String>, Box<dyn Error>> {
-    let current_branch_path = &Head::get_current_branch_path(path_handler)?;
-
-    // Extract the directory path from the file path
-    let dir_path = Path::new(&current_branch_path)
-        .parent()
-        .ok_or("Failed to get parent directory")?;
-
-    let entries = fs::read_dir(path_handler.get_relative_path(&dir_path.to_string_lossy()))?;
-
-    // Iterate over the entries
-    let mut branches: Vec<String> = Vec::new();
-    for entry in entries {
-        let entry = entry?;
-
-        let dir_path_without_git = dir_path.strip_prefix(".git/").unwrap_or(dir_path);
-
-        let file_name = dir_path_without_git
-            .join(entry.file_name())
-            .to_string_lossy()
-            .into_owned();
+    // every branch is listed, loose or packed, with its content and full ref name
+    Ok(refs::list_refs("refs/heads/", path_handler)?
+        .into_iter()
+        .map(|(ref_name, hash)| format!("{} {}\n", hash, ref_name))
+        .collect())
+}
 
-        let file_content = fs::read_to_string(entry.path())?;
+pub fn get_remote_url(name: &str, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
+    Config::load(path_handler)?
+        .get(&format!("remote.{}.url", name))
+        .ok_or_else(|| Box::new(io::Error::new(io::ErrorKind::Other, "No remote found.")) as Box<dyn Error>)
+}
 
-        // Combine content and filename
-        let branch = format!("{} {}\n", file_content, file_name);
-        branches.push(branch);
+/// Returns the url pushes to a remote go to: its `pushurl` if set, otherwise its `url`
+pub fn get_push_url(name: &str, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
+    match Config::load(path_handler)?.get(&format!("remote.{}.pushurl", name)) {
+        Some(push_url) => Ok(push_url),
+        None => get_remote_url(name, path_handler),
     }
-
-    Ok(branches)
 }
 
-pub fn get_remote_url(name: &str) -> Result<String, Box<dyn Error>> {
-    let config_content = read_file_content(CONFIG_FILE)?;
-    let current_remote_line = format!("[remote '{}']", name);
-    let mut in_remote = false;
+/// Returns the `remote.<name>.fetch` refspecs of a remote, ignoring malformed ones. A remote without any fetches
+/// every branch into `refs/remotes/<name>/`.
+pub fn get_fetch_refspecs(name: &str, path_handler: &PathHandler) -> Result<Vec<Refspec>, Box<dyn Error>> {
+    let refspecs: Vec<Refspec> = Config::load(path_handler)?
+        .get_all(&format!("remote.{}.fetch", name))
+        .iter()
+        .filter_map(|refspec| Refspec::parse(refspec))
+        .collect();
+    if refspecs.is_empty() {
+        return Ok(vec![Refspec::default_fetch(name)]);
+    }
+    Ok(refspecs)
+}
 
-    for line in config_content.lines() {
-        if line == current_remote_line.as_str() {
-            in_remote = true;
-        } else if in_remote {
-            let parts: Vec<&str> = line.split(' ').collect();
-            let url = parts.last().unwrap_or(&"");
-            return Ok(url.to_string());
+/// Returns the remote-tracking refs of a remote whose branch no longer exists in it, given the refs the remote
+/// advertises as (hash, ref name)
+pub fn find_stale_refs(name: &str, remote_refs: &[(String, String)], path_handler: &PathHandler) -> Result<Vec<String>, Box<dyn Error>> {
+    let refspecs = get_fetch_refspecs(name, path_handler)?;
+    let mut stale_refs = Vec::new();
+    for (local_ref_name, _) in refs::list_refs(&format!("refs/remotes/{}/", name), path_handler)? {
+        let Some(source) = refspecs.iter().find_map(|refspec| refspec.map_to_source(&local_ref_name)) else {
+            continue;
+        };
+        if !remote_refs.iter().any(|(_, remote_ref_name)| *remote_ref_name == source) {
+            stale_refs.push(local_ref_name);
         }
     }
-    Err(Box::new(io::Error::new(
-        io::ErrorKind::Other,
-        "No remote found.",
-    )))
+    Ok(stale_refs)
 }
 
 pub fn generate_sha1_string_from_bytes(data: &[u8]) -> String {
@@ -209,8 +277,66 @@ pub fn generate_sha1_string_from_bytes(data: &[u8]) -> String {
     hasher.result_str()
 }
 
+/// Returns the raw 20 bytes of the sha1 of the given data
+pub fn generate_sha1_bytes(data: &[u8]) -> [u8; 20] {
+    let mut hasher = Sha1::new();
+    hasher.input(data);
+    let mut hash: [u8; 20] = Default::default();
+    hasher.result(&mut hash);
+    hash
+}
+
+/// Encodes bytes as padded base64
+pub fn encode_base64(bytes: &[u8]) -> String {
+    let mut encoded = String::new();
+    for chunk in bytes.chunks(3) {
+        let group = chunk.iter().enumerate().fold(0u32, |group, (index, byte)| group | ((*byte as u32) << (16 - 8 * index)));
+        for index in 0..4 {
+            if index <= chunk.len() {
+                encoded.push(BASE64_ALPHABET[((group >> (18 - 6 * index)) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+/// Decodes base64, ignoring whitespace and padding
+pub fn decode_base64(encoded: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut decoded = Vec::new();
+    let mut group = 0u32;
+    let mut bits = 0;
+    for c in encoded.chars().filter(|c| !c.is_whitespace() && *c != '=') {
+        let value = BASE64_ALPHABET.iter().position(|symbol| *symbol as char == c).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("Error: invalid base64 character '{}'", c))
+        })?;
+        group = (group << 6) | value as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            decoded.push(((group >> bits) & 0xff) as u8);
+        }
+    }
+    Ok(decoded)
+}
+
+/// Reads an object from the repository, looking first for a loose object and then in the packs.
+/// Returns its type, content and size.
 pub fn read_object_to_bytes(hash: String, path_handler: &PathHandler) -> Result<(ObjectType, Vec<u8>, String), Box<dyn Error>> {
-    let mut file = fs::File::open(path_handler.get_relative_path(&get_object_path(&hash)))?;
+    let object_path = path_handler.get_relative_path(&get_object_path(&hash));
+    if !Path::new(&object_path).exists() {
+        let mut packed_object = packfile::read_object_from_packs(&hash, path_handler)?;
+        // packs written by older versions are moved to the pack directory the first time an object is missing
+        if packed_object.is_none() && packfile::migrate_legacy_packs(path_handler)? {
+            packed_object = packfile::read_object_from_packs(&hash, path_handler)?;
+        }
+        if let Some((object_type, content)) = packed_object {
+            let object_size = content.len().to_string();
+            return Ok((object_type, content, object_size));
+        }
+    }
+    let mut file = fs::File::open(object_path)?;
     let mut buffer = Vec::new();
     file.read_to_end(&mut buffer)?;
     let file_data = decompress_file_content_to_bytes(buffer)?;
@@ -247,72 +373,150 @@ pub fn read_object_to_string(hash: String, path_handler: &PathHandler) -> Result
 }
 
 pub fn get_remote_tracking_branches(path_handler: &PathHandler) -> Result<HashMap<String, (String, String)>, Box<dyn Error>> {
-    // Read the content of the Git configuration file
-    let config_content = read_file_content(&path_handler.get_relative_path(CONFIG_FILE))?;
+    let config = Config::load(path_handler)?;
 
-    // Initialize a HashMap to store branch names and their corresponding remotes
+    // branches with both a remote and a merge branch set are tracking a remote branch
     let mut branches_and_remotes = HashMap::new();
-
-    // Parse the INI-like configuration content manually
-    let mut lines = config_content.lines().peekable();
-    while let Some(line) = lines.next() {
-        // Check if the line represents a section header
-        if line.starts_with("[branch ") {
-            let branch_name = line.trim_start_matches("[branch ").trim_end_matches(']');
-            // Extract remote and merge values
-            let mut remote = None;
-            let mut merge = None;
-
-            for next_line in lines.by_ref() {
-                if next_line.starts_with("remote = ") {
-                    remote = Some(next_line.trim_start_matches("remote = ").to_string());
-                } else if next_line.starts_with("merge = ") {
-                    merge = Some(
-                        next_line
-                            .trim_start_matches("merge = refs/heads/")
-                            .to_string(),
-                    );
-                } else if next_line.starts_with('[') {
-                    // End of the current section
-                    break;
-                }
-            }
-
-            // If both remote and merge values are present, store in the HashMap
-            if let (Some(remote), Some(merge)) = (remote, merge) {
-                branches_and_remotes.insert(branch_name.to_string(), (remote, merge));
-            }
+    for branch_name in config.get_subsections("branch") {
+        let remote = config.get(&format!("branch.{}.remote", branch_name));
+        let merge = config.get(&format!("branch.{}.merge", branch_name));
+        if let (Some(remote), Some(merge)) = (remote, merge) {
+            let merge = merge.trim_start_matches("refs/heads/").to_string();
+            branches_and_remotes.insert(branch_name, (remote, merge));
         }
     }
     Ok(branches_and_remotes)
 }
 
-/// Reads remote branches from remotes directory and returns a tuple with (branch_name, last_commit_hash)
-pub fn get_remote_branches(remote_name: &str, path_handler: &PathHandler) -> Result<Vec<(String, String)>, Box<dyn Error>> {
-    let mut branches_to_update: Vec<(String, String)> = Vec::new();
-    
-    let remote_branches_path = format!("{}/{}", R_REMOTES, remote_name);
-    let remote_branches = fs::read_dir(path_handler.get_relative_path(&remote_branches_path))?;
+/// Returns the first line of the message of a commit
+pub fn get_commit_summary(commit_hash: &str, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
+    let (_, content, _) = read_object_to_string(commit_hash.to_string(), path_handler)?;
+    let message = content.split_once("\n\n").map(|(_headers, message)| message).unwrap_or_default();
+    Ok(message.lines().next().unwrap_or_default().to_string())
+}
 
-    for branch in remote_branches {
-        let branch = branch?;
-        let branch_name = branch.file_name().to_string_lossy().to_string();
-        let branch_path = branch.path();
+/// Returns the ref a branch tracks, from its `branch.<name>.remote` and `branch.<name>.merge` config: the
+/// remote-tracking ref of the merge branch, or the merge branch itself when the remote is `.`
+pub fn get_upstream_ref(branch_name: &str, path_handler: &PathHandler) -> Result<Option<String>, Box<dyn Error>> {
+    let config = Config::load(path_handler)?;
+    let remote = config.get(&format!("branch.{}.remote", branch_name));
+    let merge = config.get(&format!("branch.{}.merge", branch_name));
+    Ok(match (remote, merge) {
+        (Some(remote), Some(merge)) if remote == "." => Some(merge),
+        (Some(remote), Some(merge)) => {
+            let merge_branch = merge.trim_start_matches("refs/heads/");
+            Some(format!("refs/remotes/{}/{}", remote, merge_branch))
+        }
+        _ => None,
+    })
+}
 
-        let branch_hash = read_file_content(&path_handler.get_relative_path(branch_path.to_str().ok_or("")?))?;
-        branches_to_update.push((branch_name, branch_hash))
+/// Sets the branch a local branch tracks, given as a remote-tracking branch such as `origin/main` or as a local
+/// branch. Returns the short name of the upstream.
+pub fn set_upstream(branch_name: &str, upstream: &str, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
+    let config = Config::load(path_handler)?;
+    let remote_upstream = config.get_subsections("remote").into_iter().find_map(|remote| {
+        let merge_branch = upstream.strip_prefix(&format!("{}/", remote))?;
+        refs::ref_exists(&format!("refs/remotes/{}", upstream), path_handler)
+            .then(|| (remote.clone(), merge_branch.to_string()))
+    });
+    let (remote, merge_branch) = match remote_upstream {
+        Some(remote_upstream) => remote_upstream,
+        None if refs::ref_exists(&format!("refs/heads/{}", upstream), path_handler) => (String::from("."), upstream.to_string()),
+        None => {
+            return Err(Box::new(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("fatal: the requested upstream branch '{}' does not exist", upstream),
+            )))
+        }
+    };
+
+    set_upstream_config(branch_name, &remote, &format!("refs/heads/{}", merge_branch), path_handler)?;
+    Ok(upstream.to_string())
+}
+
+/// Writes the `branch.<name>.remote` and `branch.<name>.merge` config of a branch
+pub fn set_upstream_config(branch_name: &str, remote: &str, merge_ref: &str, path_handler: &PathHandler) -> Result<(), Box<dyn Error>> {
+    let mut config_file = ConfigFile::open(ConfigScope::Local, path_handler)?;
+    config_file.set(&format!("branch.{}.remote", branch_name), remote)?;
+    config_file.set(&format!("branch.{}.merge", branch_name), merge_ref)?;
+    config_file.save()
+}
+
+/// Removes the upstream of a branch, failing if it has none
+pub fn unset_upstream(branch_name: &str, path_handler: &PathHandler) -> Result<(), Box<dyn Error>> {
+    if get_upstream_ref(branch_name, path_handler)?.is_none() {
+        return Err(Box::new(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("fatal: branch '{}' has no upstream information", branch_name),
+        )));
     }
-    Ok(branches_to_update)
+    let mut config_file = ConfigFile::open(ConfigScope::Local, path_handler)?;
+    config_file.unset(&format!("branch.{}.remote", branch_name), true)?;
+    config_file.unset(&format!("branch.{}.merge", branch_name), true)?;
+    config_file.save()
+}
+
+/// Returns the short name of a ref as shown to the user: `main` for `refs/heads/main` and `origin/main` for
+/// `refs/remotes/origin/main`
+pub fn shorten_ref_name(ref_name: &str) -> String {
+    ["refs/heads/", "refs/remotes/", "refs/tags/", "refs/"]
+        .iter()
+        .find_map(|prefix| ref_name.strip_prefix(prefix))
+        .unwrap_or(ref_name)
+        .to_string()
+}
+
+/// Describes how a branch compares to its upstream, as shown by status. Returns None if it has no upstream.
+pub fn describe_upstream_status(branch_name: &str, path_handler: &PathHandler) -> Result<Option<String>, Box<dyn Error>> {
+    let Some(upstream_ref) = get_upstream_ref(branch_name, path_handler)? else {
+        return Ok(None);
+    };
+    let upstream_name = shorten_ref_name(&upstream_ref);
+    let (Some(local_commit), Some(upstream_commit)) = (
+        refs::read_ref(&format!("refs/heads/{}", branch_name), path_handler),
+        refs::read_ref(&upstream_ref, path_handler),
+    ) else {
+        return Ok(Some(format!(
+            "Your branch is based on '{}', but the upstream is gone.\n  (use \"git branch --unset-upstream\" to fixup)",
+            upstream_name
+        )));
+    };
+
+    let plural = |count: usize| if count == 1 { "commit" } else { "commits" };
+    let description = match revision::count_ahead_behind(&local_commit, &upstream_commit, path_handler)? {
+        (0, 0) => format!("Your branch is up to date with '{}'.", upstream_name),
+        (ahead, 0) => format!(
+            "Your branch is ahead of '{}' by {} {}.\n  (use \"git push\" to publish your local commits)",
+            upstream_name, ahead, plural(ahead)
+        ),
+        (0, behind) => format!(
+            "Your branch is behind '{}' by {} {}, and can be fast-forwarded.\n  (use \"git pull\" to update your local branch)",
+            upstream_name, behind, plural(behind)
+        ),
+        (ahead, behind) => format!(
+            "Your branch and '{}' have diverged,\nand have {} and {} different commits each, respectively.\n  (use \"git pull\" to merge the remote branch into yours)",
+            upstream_name, ahead, behind
+        ),
+    };
+    Ok(Some(description))
+}
+
+/// Reads remote branches from remotes directory and returns a tuple with (branch_name, last_commit_hash)
+pub fn get_remote_branches(remote_name: &str, path_handler: &PathHandler) -> Result<Vec<(String, String)>, Box<dyn Error>> {
+    let remote_prefix = format!("refs/remotes/{}/", remote_name);
+    Ok(refs::list_refs(&remote_prefix, path_handler)?
+        .into_iter()
+        .filter_map(|(ref_name, hash)| ref_name.strip_prefix(&remote_prefix).map(|branch_name| (branch_name.to_string(), hash)))
+        .collect())
 }
 
 pub fn update_branches(branches: Vec<(String, String)>, path_handler: &PathHandler) -> Result<(), Box<dyn Error>>{
+    let mut transaction = RefTransaction::new();
     for (branch_name, hash) in branches {
-        let branch_file = format!("{}/{}", R_HEADS, branch_name);
-        let mut branch_file = fs::File::create(path_handler.get_relative_path(&branch_file))?;
-        branch_file.write_all(hash.as_bytes())?;
+        transaction.update(&format!("refs/heads/{}", branch_name), &hash, None, Some("clone: from remote"));
     }
-
-    Ok(())
+    transaction.commit(path_handler)
 }
 
 pub fn update_local_branch_with_commit(
@@ -321,42 +525,35 @@ pub fn update_local_branch_with_commit(
     remote_hash: &str,
     path_handler: &PathHandler
 ) -> Result<(), Box<dyn Error>> {
-    let config_content = read_file_content(&path_handler.get_relative_path(CONFIG_FILE))?;
-
-    let branch_header = format!("[branch '{}']", branch_name);
-    let mut lines = config_content.lines().peekable();
-    while let Some(line) = lines.next() {
-        if line == branch_header {
-            let mut remote = None;
-            for next_line in lines.by_ref() {
-                if next_line.starts_with("remote = ") {
-                    remote = Some(next_line.trim_start_matches("remote = ").to_string());
-                } else if next_line.starts_with('[') {
-                    break;
-                }
-            }
-            if let Some(remote) = remote {
-                if remote == remote_name {
-                    let _ = update_branch_hash(branch_name, remote_hash, path_handler);
-                }
-            }
-        }
+    let remote = Config::load(path_handler)?.get(&format!("branch.{}.remote", branch_name));
+    if remote.as_deref() == Some(remote_name) {
+        let _ = update_branch_hash(branch_name, remote_hash, None, "pull: fast-forward", path_handler);
     }
     Ok(())
 }
 
-/// Updates the commit which the specified branch points to
-pub fn update_branch_hash(branch_name: &str, new_commit_hash: &str, path_handler: &PathHandler) -> Result<(), Box<dyn Error>> {
-    let mut file = fs::File::create(path_handler.get_relative_path(&get_branch_path(
-        branch_name,
-    )))?;
-    file.write_all(new_commit_hash.as_bytes())?;
-    Ok(())
+/// Updates the commit which the specified branch points to, recording the update with the given message in the
+/// reflog of the branch and of HEAD if it's the current branch. If an expected old commit is given, the branch is
+/// only updated if it still points to it, or doesn't exist yet if it's ZERO_HASH, so concurrent updates aren't lost.
+pub fn update_branch_hash(
+    branch_name: &str,
+    new_commit_hash: &str,
+    expected_old_hash: Option<&str>,
+    message: &str,
+    path_handler: &PathHandler
+) -> Result<(), Box<dyn Error>> {
+    refs::update_ref(&format!("refs/heads/{}", branch_name), new_commit_hash, expected_old_hash, Some(message), path_handler)
 }
 
 /// Returns the commit the specified branch points to
 pub fn get_branch_last_commit(branch_path: &str, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
-    
+    if !Path::new(&path_handler.get_relative_path(branch_path)).is_file() {
+        let ref_name = branch_path.trim_start_matches(&format!("{}/", GIT));
+        return refs::read_ref(ref_name, path_handler).ok_or_else(|| {
+            Box::new(io::Error::new(io::ErrorKind::NotFound, format!("Error: ref '{}' not found", ref_name))) as Box<dyn Error>
+        });
+    }
+
     let mut file: fs::File = fs::File::open(path_handler.get_relative_path(branch_path))?;
     let mut content = String::new();
     file.read_to_string(&mut content)?;
@@ -368,6 +565,9 @@ pub fn get_branch_path(branch_name: &str) -> String {
 }
 
 pub fn get_object_path(object_hash: &str) -> String {
+    if object_hash.len() < 2 {
+        return format!("{}/{}", OBJECT, object_hash);
+    }
     format!(
         "{}/{}/{}",
         OBJECT,
@@ -421,27 +621,16 @@ pub fn ancestor_commit_exists(
 /// Given a commit's hash it accesses its file and returns the hash of its associated
 /// tree object.
 pub fn get_commit_tree(commit_hash: &str, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
-    let get_obj_path = get_object_path(commit_hash);
-    let get_obj_path_relative = path_handler.get_relative_path(&get_obj_path);
-
-    let read_file_content_to_bytes = read_file_content_to_bytes(&get_obj_path_relative)?;
-    let decompressed_data = decompress_file_content(read_file_content_to_bytes)?;
-
-    let commit_file_content: Vec<String> =
-        decompressed_data.split('\0').map(String::from).collect();
-    let commit_file_lines: Vec<String> = commit_file_content[1]
-        .lines()
-        .map(|s| s.to_string())
-        .collect();
-
-    let tree_split_line: Vec<String> = commit_file_lines[0]
-        .split_whitespace()
-        .map(String::from)
-        .collect();
-
-    let tree_hash_trimmed = &tree_split_line[1];
-
-    Ok(tree_hash_trimmed.to_string())
+    let (_object_type, commit_content, _size) = read_object_to_string(commit_hash.to_string(), path_handler)?;
+
+    let tree_line = commit_content.lines().next().unwrap_or_default();
+    match tree_line.strip_prefix("tree ") {
+        Some(tree_hash) => Ok(tree_hash.trim().to_string()),
+        None => Err(Box::new(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Commit object has no tree",
+        ))),
+    }
 }
 
 /// Checks if the file in the given path exists and returns true or false
@@ -473,55 +662,42 @@ pub fn hex_string_to_bytes(bytes: &[u8]) -> String {
     hash
 }
 
-type TreeContent = (String, String, String);
+pub type TreeContent = (String, String, String);
 
+/// Returns the (mode, name, hash) entries of a tree object
 pub fn read_tree_content(tree_hash: &str, path_handler: &PathHandler) -> Result<Vec<TreeContent>, Box<dyn Error>> {
-    let compressed_content =
-        read_file_content_to_bytes(&path_handler.get_relative_path(&get_object_path(tree_hash)))?;
-    let tree_content = decompress_file_content_to_bytes(compressed_content)?;
-    let split_content: Vec<Vec<u8>> = tree_content
-        .splitn(2, |&c| c == 0)
-        .map(|slice| slice.to_vec())
-        .collect();
-
-    let mut divided_content = Vec::new();
-
-    let mut substrings: Vec<Vec<u8>> = split_content[1]
-        .split(|&c| c == 0)
-        .map(|slice| slice.to_vec())
-        .collect();
-
-    let tree_data: Vec<Vec<u8>> = substrings[0]
-        .split(|&c| c == 32)
-        .map(|slice| slice.to_vec())
-        .collect();
-
-    let mut file_mode = String::from_utf8_lossy(&tree_data[0]).to_string();
-    let mut file_name = String::from_utf8_lossy(&tree_data[1]).to_string();
-
-    substrings.remove(0);
-
-
-    for substring in &substrings {
-        let processed_bytes = &substring[..20];
-
-        let hash_string = hex_string_to_bytes(processed_bytes);
-
-        divided_content.push((file_mode.clone(), file_name.clone(), hash_string));
+    let (_object_type, tree_content, _size) = read_object_to_bytes(tree_hash.to_string(), path_handler)?;
+    parse_tree_entries(&tree_content)
+}
 
-        if substring.len() > 20 {
-            let tree_entry_data = String::from_utf8_lossy(&substring[20..]).to_string();
-            
-            let split_entry: Vec<String> = tree_entry_data
-                .split_whitespace()
-                .map(String::from)
-                .collect();
-            file_mode = split_entry[0].clone();
-            file_name = split_entry[1].clone();
-        }
+/// Parses the content of a tree object. Each entry is stored as "<mode> <name>\0" followed by
+/// the 20 raw bytes of the object hash.
+pub fn parse_tree_entries(tree_content: &[u8]) -> Result<Vec<TreeContent>, Box<dyn Error>> {
+    let invalid_tree = || io::Error::new(io::ErrorKind::InvalidData, "Invalid tree object");
+    let mut entries = Vec::new();
+    let mut position = 0;
+
+    while position < tree_content.len() {
+        let space = tree_content[position..]
+            .iter()
+            .position(|&byte| byte == b' ')
+            .ok_or_else(invalid_tree)?
+            + position;
+        let null = tree_content[space..]
+            .iter()
+            .position(|&byte| byte == 0)
+            .ok_or_else(invalid_tree)?
+            + space;
+        let hash_bytes = tree_content.get(null + 1..null + 21).ok_or_else(invalid_tree)?;
+
+        let file_mode = String::from_utf8_lossy(&tree_content[position..space]).to_string();
+        let file_name = String::from_utf8_lossy(&tree_content[space + 1..null]).to_string();
+        entries.push((file_mode, file_name, hex_string_to_bytes(hash_bytes)));
+
+        position = null + 21;
     }
 
-    Ok(divided_content)
+    Ok(entries)
 }
 
 pub fn convert_hash_to_decimal_bytes(hash: &str) -> Result<Vec<u8>, Box<dyn Error>> {
@@ -545,20 +721,20 @@ pub fn validate_ref_update_request(
     let branch_path = format!(".git/{}", branch_ref);
     
     if check_if_file_exists(&branch_path, path_handler) {
-        /*if prev_remote_hash == ZERO_HASH {
+        // an empty ref file is a branch without commits, which can be initialized
+        let current_hash = get_branch_last_commit(&branch_path, path_handler)?;
+        if prev_remote_hash == ZERO_HASH && !current_hash.trim().is_empty() {
             return Err(Box::new(io::Error::new(
                 io::ErrorKind::Other,
                 "Error: Trying to initialize existing ref",
             )));
         }
-        if get_branch_last_commit(&PathHandler::get_relative_path(&branch_path))?
-            != prev_remote_hash
-        {
+        if prev_remote_hash != ZERO_HASH && current_hash.trim() != prev_remote_hash {
             return Err(Box::new(io::Error::new(
                 io::ErrorKind::Other,
                 "Error: New hash is different from ref's current hash",
             )));
-        }*/
+        }
     } else if prev_remote_hash != ZERO_HASH {
         return Err(Box::new(io::Error::new(
             io::ErrorKind::Other,
@@ -572,12 +748,16 @@ pub fn validate_ref_update_request(
 pub fn update_hash_for_refs(
     refs_to_update: Vec<(String, String, String)>, path_handler: &PathHandler
 ) -> Result<(), Box<dyn Error>> {
-    for (_, new_remote_hash, branch_ref) in refs_to_update {
-        let ref_path = format!(".git/{}", branch_ref);
-        let mut file = fs::File::create(path_handler.get_relative_path(&ref_path))?;
-        file.write_all(new_remote_hash.as_bytes())?
+    // the refs of a push are updated together, and only if none of them moved since it was validated
+    let mut transaction = RefTransaction::new();
+    for (prev_remote_hash, new_remote_hash, branch_ref) in refs_to_update {
+        if new_remote_hash == ZERO_HASH {
+            transaction.delete(&branch_ref, Some(&prev_remote_hash));
+        } else {
+            transaction.update(&branch_ref, &new_remote_hash, Some(&prev_remote_hash), Some("push"));
+        }
     }
-    Ok(())
+    transaction.commit(path_handler)
 }
 
 pub fn find_modified_files(ancestor_working_tree: HashMap<String, String>, working_tree_to_compare:  HashMap<String, String>) -> HashMap<String, String> {
@@ -757,25 +937,359 @@ impl fmt::Display for LineChange {
         write!(f, "{}", string)
     }
 }
-/// This function goes through the tree object associated to a commit object and
-/// adds all of the files in its working tree into a HashMap, where the file name (path)
-/// is the key and its corresponding object hash is the value stored.
-pub fn reconstruct_working_tree(commit_hash: String, path_handler: &PathHandler) -> Result<HashMap<String, String>, Box<dyn Error>> {
-    let tree_content = read_tree_content(&get_commit_tree(&commit_hash, path_handler)?, path_handler)?;
-    let mut working_tree: HashMap<String, String> = HashMap::new();
+/// A change to a file between two versions of the repository
+#[derive(Debug, PartialEq)]
+pub enum FileChange {
+    Added(String),
+    Deleted(String),
+    Modified(String),
+    /// Source path, destination path and similarity percentage
+    Renamed(String, String, usize),
+    /// Source path, destination path and similarity percentage
+    Copied(String, String, usize),
+}
+
+impl FileChange {
+    /// Returns the path the change leaves in the repository, or the deleted path
+    pub fn get_path(&self) -> &str {
+        match self {
+            FileChange::Added(path) | FileChange::Deleted(path) | FileChange::Modified(path) => path,
+            FileChange::Renamed(_, path, _) | FileChange::Copied(_, path, _) => path,
+        }
+    }
+}
+
+/// Parses a -M/--find-renames or -C/--find-copies argument into a similarity percentage. Like git, digits followed
+/// by '%' are a percentage and digits alone are the decimal part of a fraction, so `-M5` is 50%.
+/// Returns the default threshold if the argument has no value.
+pub fn parse_similarity_threshold(arg: &str) -> Result<usize, Box<dyn Error>> {
+    let value = arg
+        .strip_prefix(&format!("{}=", FIND_RENAMES_LONG_FLAG))
+        .or_else(|| arg.strip_prefix(&format!("{}=", FIND_COPIES_LONG_FLAG)))
+        .or_else(|| arg.strip_prefix(FIND_RENAMES_FLAG))
+        .or_else(|| arg.strip_prefix(FIND_COPIES_FLAG))
+        .filter(|value| !value.starts_with('-'))
+        .unwrap_or("");
+    let invalid = || io::Error::new(io::ErrorKind::Other, format!("Error: invalid similarity threshold '{}'", arg));
+    if value.is_empty() {
+        return Ok(DEFAULT_RENAME_THRESHOLD);
+    }
+    let threshold = match value.strip_suffix('%') {
+        Some(percentage) => percentage.parse::<usize>().map_err(|_| invalid())?,
+        None => {
+            let fraction = value.parse::<usize>().map_err(|_| invalid())?;
+            fraction * 100 / 10_usize.pow(value.len() as u32)
+        }
+    };
+    if threshold > 100 {
+        return Err(Box::new(invalid()));
+    }
+    Ok(threshold)
+}
+
+/// Returns the content of a blob. Files of the working tree that were hashed but not written to the object
+/// database are read from the working tree. Anything that can't be read, like gitlinks, is empty.
+fn read_blob_for_diff(path: &str, hash: &str, path_handler: &PathHandler) -> Vec<u8> {
+    if let Ok((ObjectType::Blob, content, _)) = read_object_to_bytes(hash.to_string(), path_handler) {
+        return content;
+    }
+    match hash_working_file(path, path_handler) {
+        Ok(working_hash) if working_hash == hash => read_working_file(path, path_handler).unwrap_or_default(),
+        _ => Vec::new(),
+    }
+}
+
+/// Returns how similar two contents are, from 0 to 100, as the bytes of the lines they share over the size
+/// of the biggest one.
+pub fn compute_similarity(old_content: &[u8], new_content: &[u8]) -> usize {
+    let max_size = old_content.len().max(new_content.len());
+    if max_size == 0 {
+        return 100;
+    }
+    let mut old_lines: HashMap<&[u8], usize> = HashMap::new();
+    for line in old_content.split_inclusive(|&byte| byte == b'\n') {
+        *old_lines.entry(line).or_insert(0) += 1;
+    }
+    let mut common_bytes = 0;
+    for line in new_content.split_inclusive(|&byte| byte == b'\n') {
+        if let Some(count) = old_lines.get_mut(line).filter(|count| **count > 0) {
+            *count -= 1;
+            common_bytes += line.len();
+        }
+    }
+    common_bytes * 100 / max_size
+}
+
+/// Returns the number of lines two versions of a file share at their start and, after those, at their end
+fn count_common_ends<T: PartialEq>(old_lines: &[T], new_lines: &[T]) -> (usize, usize) {
+    let prefix = old_lines.iter().zip(new_lines).take_while(|(old, new)| old == new).count();
+    let suffix = old_lines[prefix..]
+        .iter()
+        .rev()
+        .zip(new_lines[prefix..].iter().rev())
+        .take_while(|(old, new)| old == new)
+        .count();
+    (prefix, suffix)
+}
+
+/// Returns the length of the longest common subsequence of the lines of two versions of a file. Only the
+/// previous row of the table is kept, so the memory used grows with the number of lines and not with their product.
+pub fn count_common_lines<T: PartialEq>(old_lines: &[T], new_lines: &[T]) -> usize {
+    let (prefix, suffix) = count_common_ends(old_lines, new_lines);
+    let old_middle = &old_lines[prefix..old_lines.len() - suffix];
+    let new_middle = &new_lines[prefix..new_lines.len() - suffix];
+
+    let mut previous_row = vec![0; new_middle.len() + 1];
+    let mut current_row = vec![0; new_middle.len() + 1];
+    for old_line in old_middle {
+        for (j, new_line) in new_middle.iter().enumerate() {
+            current_row[j + 1] = if old_line == new_line {
+                previous_row[j] + 1
+            } else {
+                previous_row[j + 1].max(current_row[j])
+            };
+        }
+        std::mem::swap(&mut previous_row, &mut current_row);
+    }
+    prefix + previous_row[new_middle.len()] + suffix
+}
+
+/// Returns the pairs of indexes of the lines kept between two versions of a file, as their longest common
+/// subsequence, in order. It keeps the whole table to walk it back, so `count_common_lines` should be used
+/// when only the number of lines is needed.
+pub fn match_lines<T: PartialEq>(old_lines: &[T], new_lines: &[T]) -> Vec<(usize, usize)> {
+    let (prefix, suffix) = count_common_ends(old_lines, new_lines);
+    let old_middle = &old_lines[prefix..old_lines.len() - suffix];
+    let new_middle = &new_lines[prefix..new_lines.len() - suffix];
+
+    // lengths[i][j] is the length of the longest common subsequence of old_middle[i..] and new_middle[j..]
+    let mut lengths = vec![vec![0; new_middle.len() + 1]; old_middle.len() + 1];
+    for i in (0..old_middle.len()).rev() {
+        for j in (0..new_middle.len()).rev() {
+            lengths[i][j] = if old_middle[i] == new_middle[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let mut pairs: Vec<(usize, usize)> = (0..prefix).map(|index| (index, index)).collect();
+    let (mut i, mut j) = (0, 0);
+    while i < old_middle.len() && j < new_middle.len() {
+        if old_middle[i] == new_middle[j] {
+            pairs.push((prefix + i, prefix + j));
+            i += 1;
+            j += 1;
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    let old_suffix_start = old_lines.len() - suffix;
+    let new_suffix_start = new_lines.len() - suffix;
+    pairs.extend((0..suffix).map(|index| (old_suffix_start + index, new_suffix_start + index)));
+    pairs
+}
+
+/// Returns the number of lines added and removed between two contents
+pub fn count_line_changes(old_content: &[u8], new_content: &[u8]) -> (usize, usize) {
+    let old_lines: Vec<&[u8]> = old_content.split_inclusive(|&byte| byte == b'\n').collect();
+    let new_lines: Vec<&[u8]> = new_content.split_inclusive(|&byte| byte == b'\n').collect();
+    let common_lines = count_common_lines(&old_lines, &new_lines);
+    (new_lines.len() - common_lines, old_lines.len() - common_lines)
+}
 
-    for (file_mode, file_name, file_hash) in tree_content {
-        match file_mode.as_str() {
-            TREE_FILE_MODE => {
-                working_tree.insert(file_name, file_hash);
+/// Compares two versions of the files of a repository, keyed by path with their hashes, and returns the changes
+/// sorted by path. If `rename_threshold` is set, deleted files are paired with added files with the same hash
+/// first and then with the most similar ones, as long as they're at least that similar. If `find_copies` is
+/// also set, added files left are paired with files that were kept in the same way.
+pub fn find_file_changes(
+    old_files: &HashMap<String, String>,
+    new_files: &HashMap<String, String>,
+    rename_threshold: Option<usize>,
+    find_copies: bool,
+    path_handler: &PathHandler
+) -> Vec<FileChange> {
+    let mut deleted: Vec<&String> = old_files.keys().filter(|path| !new_files.contains_key(*path)).collect();
+    let mut added: Vec<&String> = new_files.keys().filter(|path| !old_files.contains_key(*path)).collect();
+    deleted.sort();
+    added.sort();
+    let mut changes: Vec<FileChange> = new_files
+        .iter()
+        .filter(|(path, hash)| old_files.get(*path).is_some_and(|old_hash| old_hash != *hash))
+        .map(|(path, _hash)| FileChange::Modified(path.clone()))
+        .collect();
+
+    if let Some(threshold) = rename_threshold {
+        let mut copy_sources: Vec<&String> = Vec::new();
+        if find_copies {
+            copy_sources = old_files.keys().filter(|path| new_files.contains_key(*path)).collect();
+            copy_sources.sort();
+        }
+        let mut contents: HashMap<String, Vec<u8>> = HashMap::new();
+        let mut read_content = |path: &str, hash: &str| -> Vec<u8> {
+            contents
+                .entry(format!("{}:{}", path, hash))
+                .or_insert_with(|| read_blob_for_diff(path, hash, path_handler))
+                .clone()
+        };
+
+        for (sources, copying) in [(&mut deleted, false), (&mut copy_sources, true)] {
+            if copying && !find_copies {
+                continue;
+            }
+            // exact matches first, they don't need the content to be read
+            let mut candidates = Vec::new();
+            for (added_index, added_path) in added.iter().enumerate() {
+                match sources.iter().position(|source| old_files[*source] == new_files[*added_path]) {
+                    Some(source_index) => candidates.push((100, source_index, added_index)),
+                    None => {
+                        for (source_index, source) in sources.iter().enumerate() {
+                            let similarity = compute_similarity(
+                                &read_content(source, &old_files[*source]),
+                                &read_content(added_path, &new_files[*added_path]),
+                            );
+                            if similarity >= threshold {
+                                candidates.push((similarity, source_index, added_index));
+                            }
+                        }
+                    }
+                }
+            }
+            candidates.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)).then(a.2.cmp(&b.2)));
+
+            let mut used_sources = HashSet::new();
+            let mut paired_added = HashSet::new();
+            for (similarity, source_index, added_index) in candidates {
+                if paired_added.contains(&added_index) || (!copying && used_sources.contains(&source_index)) {
+                    continue;
+                }
+                used_sources.insert(source_index);
+                paired_added.insert(added_index);
+                let (source, destination) = (sources[source_index].clone(), added[added_index].clone());
+                changes.push(if copying {
+                    FileChange::Copied(source, destination, similarity)
+                } else {
+                    FileChange::Renamed(source, destination, similarity)
+                });
             }
-            TREE_SUBTREE_MODE => {
-                working_tree.extend(reconstruct_working_tree(file_hash, path_handler)?);
+            if !copying {
+                let mut index = 0;
+                sources.retain(|_| {
+                    index += 1;
+                    !used_sources.contains(&(index - 1))
+                });
             }
-            _ => {}
+            let mut index = 0;
+            added.retain(|_| {
+                index += 1;
+                !paired_added.contains(&(index - 1))
+            });
         }
     }
-    Ok(working_tree)
+
+    changes.extend(deleted.into_iter().map(|path| FileChange::Deleted(path.clone())));
+    changes.extend(added.into_iter().map(|path| FileChange::Added(path.clone())));
+    changes.sort_by(|a, b| a.get_path().cmp(b.get_path()));
+    changes
+}
+
+/// Returns the diffstat of the changes: one line per file with the lines added and removed, and a summary line
+pub fn format_diff_stat(
+    changes: &[FileChange],
+    old_files: &HashMap<String, String>,
+    new_files: &HashMap<String, String>,
+    path_handler: &PathHandler
+) -> String {
+    let mut rows = Vec::new();
+    let (mut total_insertions, mut total_deletions) = (0, 0);
+    for change in changes {
+        let (name, old_path, new_path) = match change {
+            FileChange::Added(path) => (path.clone(), None, Some(path)),
+            FileChange::Deleted(path) => (path.clone(), Some(path), None),
+            FileChange::Modified(path) => (path.clone(), Some(path), Some(path)),
+            FileChange::Renamed(old, new, _) | FileChange::Copied(old, new, _) => {
+                (format!("{} => {}", old, new), Some(old), Some(new))
+            }
+        };
+        let old_content = old_path.map(|path| read_blob_for_diff(path, &old_files[path], path_handler)).unwrap_or_default();
+        let new_content = new_path.map(|path| read_blob_for_diff(path, &new_files[path], path_handler)).unwrap_or_default();
+        let (insertions, deletions) = count_line_changes(&old_content, &new_content);
+        total_insertions += insertions;
+        total_deletions += deletions;
+        rows.push((name, insertions, deletions));
+    }
+
+    let name_width = rows.iter().map(|(name, _, _)| name.len()).max().unwrap_or(0);
+    let max_changes = rows.iter().map(|(_, insertions, deletions)| insertions + deletions).max().unwrap_or(0);
+    let mut lines = Vec::new();
+    for (name, insertions, deletions) in &rows {
+        let (mut plus, mut minus) = (*insertions, *deletions);
+        if max_changes > DIFF_STAT_GRAPH_WIDTH {
+            plus = plus * DIFF_STAT_GRAPH_WIDTH / max_changes;
+            minus = minus * DIFF_STAT_GRAPH_WIDTH / max_changes;
+        }
+        lines.push(format!(
+            " {:<width$} | {} {}{}",
+            name,
+            insertions + deletions,
+            "+".repeat(plus),
+            "-".repeat(minus),
+            width = name_width
+        ).trim_end().to_string());
+    }
+    let mut summary = format!(" {} file{} changed", rows.len(), if rows.len() == 1 { "" } else { "s" });
+    if total_insertions > 0 || total_deletions == 0 {
+        summary.push_str(&format!(", {} insertion{}(+)", total_insertions, if total_insertions == 1 { "" } else { "s" }));
+    }
+    if total_deletions > 0 {
+        summary.push_str(&format!(", {} deletion{}(-)", total_deletions, if total_deletions == 1 { "" } else { "s" }));
+    }
+    lines.push(summary);
+    lines.join("\n")
+}
+
+/// Returns the changes with one line each: a status letter, the similarity for renames and copies, and the paths
+pub fn format_name_status(changes: &[FileChange]) -> String {
+    changes
+        .iter()
+        .map(|change| match change {
+            FileChange::Added(path) => format!("A\t{}", path),
+            FileChange::Deleted(path) => format!("D\t{}", path),
+            FileChange::Modified(path) => format!("M\t{}", path),
+            FileChange::Renamed(old, new, similarity) => format!("R{:03}\t{}\t{}", similarity, old, new),
+            FileChange::Copied(old, new, similarity) => format!("C{:03}\t{}\t{}", similarity, old, new),
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Returns every non-tree entry of a tree and its subtrees keyed by its path, prefixed with
+/// `path_prefix`, along with its mode and hash.
+pub fn get_tree_files(tree_hash: &str, path_prefix: &str, path_handler: &PathHandler) -> Result<HashMap<String, (String, String)>, Box<dyn Error>> {
+    let mut files = HashMap::new();
+    for (file_mode, file_name, file_hash) in read_tree_content(tree_hash, path_handler)? {
+        let file_path = format!("{}{}", path_prefix, file_name);
+        if is_tree_mode(&file_mode) {
+            files.extend(get_tree_files(&file_hash, &format!("{}/", file_path), path_handler)?);
+        } else {
+            files.insert(file_path, (file_mode, file_hash));
+        }
+    }
+    Ok(files)
+}
+
+/// This function goes through the tree object associated to a commit object and
+/// adds all of the files in its working tree into a HashMap, where the file name (path)
+/// is the key and its corresponding object hash is the value stored.
+pub fn reconstruct_working_tree(commit_hash: String, path_handler: &PathHandler) -> Result<HashMap<String, String>, Box<dyn Error>> {
+    let tree_files = get_tree_files(&get_commit_tree(&commit_hash, path_handler)?, "", path_handler)?;
+    Ok(tree_files
+        .into_iter()
+        .map(|(file_path, (_file_mode, file_hash))| (file_path, file_hash))
+        .collect())
 }
 
 /// Receives a repo name and return a result indicating if the repo already exists or not
@@ -840,8 +1354,7 @@ pub fn get_client_current_working_repo() -> Result<String, Box<dyn Error>> {
 
 /// Receives a branch name and return a result indicating if the branch already exists or not
 pub fn check_if_branch_exists(branch_name: &str, path_handler: &PathHandler) -> Result<(), Box<dyn Error>> {
-    let branch_path = get_branch_path(branch_name);
-    if !check_if_file_exists(&branch_path, path_handler) {
+    if !refs::ref_exists(&format!("refs/heads/{}", branch_name), path_handler) {
         return Err(Box::new(io::Error::new(
             io::ErrorKind::Other,
             "Error: Specified branch does not exist.",
@@ -850,16 +1363,58 @@ pub fn check_if_branch_exists(branch_name: &str, path_handler: &PathHandler) ->
     Ok(())
 }
 
+/// Moves the files one side of a merge renamed to their new path on the other side and in the ancestor, so the
+/// changes made to the old path are merged into the renamed file. Returns the old paths of those files.
+fn follow_renames(
+    ancestor_working_tree: &mut HashMap<String, String>,
+    current_working_tree: &mut HashMap<String, String>,
+    merging_working_tree: &mut HashMap<String, String>,
+    path_handler: &PathHandler
+) -> Vec<String> {
+    let find_renames = |working_tree: &HashMap<String, String>| -> Vec<(String, String)> {
+        find_file_changes(ancestor_working_tree, working_tree, Some(DEFAULT_RENAME_THRESHOLD), false, path_handler)
+            .into_iter()
+            .filter_map(|change| match change {
+                FileChange::Renamed(old_path, new_path, _) => Some((old_path, new_path)),
+                _ => None,
+            })
+            .collect()
+    };
+    let current_renames = find_renames(current_working_tree);
+    let merging_renames = find_renames(merging_working_tree);
+
+    let mut renamed_paths = Vec::new();
+    for (renames, other_working_tree) in [(current_renames, &mut *merging_working_tree), (merging_renames, &mut *current_working_tree)] {
+        for (old_path, new_path) in renames {
+            if other_working_tree.contains_key(&new_path) {
+                continue;
+            }
+            if let Some(file_hash) = other_working_tree.remove(&old_path) {
+                other_working_tree.insert(new_path.clone(), file_hash);
+                if let Some(ancestor_hash) = ancestor_working_tree.get(&old_path).cloned() {
+                    ancestor_working_tree.insert(new_path, ancestor_hash);
+                }
+                renamed_paths.push(old_path);
+            }
+        }
+    }
+    renamed_paths
+}
+
 /// Given two commits it finds which files can be merged without generating a conflict. 
 /// It then cleans the working directory of the old and not merged files and updates the 
 /// index file to the new working tree.
 pub fn determine_new_working_tree(commit_merging_into: String, commit_to_merge: String, path_handler: &PathHandler) -> Result<(), Box<dyn Error>> {
     let ancestor_commit = find_common_ancestor_commit(&commit_merging_into, &commit_to_merge, path_handler)?;
-    let ancestor_working_tree = reconstruct_working_tree(ancestor_commit, path_handler)?;
-    let current_working_tree = reconstruct_working_tree(commit_merging_into, path_handler)?;
-    let merging_working_tree = reconstruct_working_tree(commit_to_merge.clone(), path_handler)?;
+    let mut ancestor_working_tree = reconstruct_working_tree(ancestor_commit, path_handler)?;
+    let mut current_working_tree = reconstruct_working_tree(commit_merging_into, path_handler)?;
+    let mut merging_working_tree = reconstruct_working_tree(commit_to_merge.clone(), path_handler)?;
+    let renamed_paths = follow_renames(&mut ancestor_working_tree, &mut current_working_tree, &mut merging_working_tree, path_handler);
     let _files_without_conflict = find_files_without_conflict(ancestor_working_tree, current_working_tree, merging_working_tree, path_handler)?;
 
+    for renamed_path in renamed_paths {
+        WorkingDirectory::remove_file_and_empty_parent_directories(Path::new(&path_handler.get_relative_path(&renamed_path)))?;
+    }
     WorkingDirectory::clean_working_directory(path_handler)?;
 
     Ok(())
@@ -890,11 +1445,14 @@ pub fn determine_new_working_tree(commit_merging_into: String, commit_to_merge:
 //     Ok(())
 // }
 
-pub fn create_merged_working_tree(head_commit: String, merging_commit: String, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
+/// Commits the merge of two commits, signed if there is a signer, and updates the current branch and the working
+/// directory to it
+pub fn create_merged_working_tree(head_commit: String, merging_commit: String, signer: Option<&dyn Signer>, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
     StagingArea::new().stage_index_file(path_handler)?;
-    let new_commit_hash = HashObjectCreator::create_commit_object(None, vec![head_commit, merging_commit], path_handler)?;
+    let parents = vec![head_commit.clone(), merging_commit];
+    let new_commit_hash = HashObjectCreator::create_commit_object_with_signer(None, parents, signer, path_handler)?;
     
-    update_branch_hash(&Head::get_current_branch_name(path_handler)?, &new_commit_hash, path_handler)?;
+    update_branch_hash(&Head::get_current_branch_name(path_handler)?, &new_commit_hash, Some(&head_commit), "commit (merge): Merge", path_handler)?;
 
     let commit_tree = get_commit_tree(&new_commit_hash, path_handler)?;
     WorkingDirectory::update_working_directory_to(&commit_tree, path_handler)?;
@@ -920,6 +1478,470 @@ pub fn check_if_conflict_has_been_solved(path_handler: &PathHandler) -> Result<(
 }
 
 pub const RELATIVE_PATH: &str = "RELATIVE_PATH";
+/// Returns true if the given string looks like a full object hash
+pub fn is_object_hash(hash: &str) -> bool {
+    hash.len() == 40 && hash.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Returns the paths of every file under the given directory, walking it recursively.
+/// A missing directory has no files.
+pub fn get_files_in_directory_recursively(dir_path: &str) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut files = Vec::new();
+    let entries = match fs::read_dir(dir_path) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(files),
+        Err(err) => return Err(Box::new(err)),
+    };
+    for entry in entries {
+        let entry_path = entry?.path();
+        let entry_path_str = entry_path.to_string_lossy().to_string();
+        if entry_path.is_dir() {
+            files.extend(get_files_in_directory_recursively(&entry_path_str)?);
+        } else {
+            files.push(entry_path_str);
+        }
+    }
+    Ok(files)
+}
+
+/// An entry of the index file: path, object hash, state and mode
+pub type IndexEntry = (String, String, String, String);
+
+/// Returns the mode of a split index line. Lines written before the index kept modes take the mode of the
+/// working file instead.
+pub fn get_index_line_mode(split_line: &[&str], path_handler: &PathHandler) -> String {
+    match split_line.get(3) {
+        Some(file_mode) if !file_mode.is_empty() => file_mode.to_string(),
+        _ => get_file_mode(split_line[0], path_handler).unwrap_or_else(|_| TREE_FILE_MODE.to_string()),
+    }
+}
+
+/// Returns the index line with its state replaced, keeping the rest of its fields
+pub fn set_index_line_state(line: &str, state: &str) -> String {
+    let mut split_line: Vec<&str> = line.split(';').collect();
+    if split_line.len() > 2 {
+        split_line[2] = state;
+    }
+    split_line.join(";")
+}
+
+/// Returns the entries of the index file
+pub fn read_index_entries(path_handler: &PathHandler) -> Result<Vec<IndexEntry>, Box<dyn Error>> {
+    let index_file_content = read_file_content(&path_handler.get_relative_path(INDEX_FILE))?;
+    Ok(index_file_content
+        .lines()
+        .filter_map(|line| {
+            let split_line: Vec<&str> = line.split(';').collect();
+            if split_line.len() < 3 {
+                return None;
+            }
+            Some((
+                split_line[0].to_string(),
+                split_line[1].to_string(),
+                split_line[2].to_string(),
+                get_index_line_mode(&split_line, path_handler),
+            ))
+        })
+        .collect())
+}
+
+/// Overwrites the index file with the entries, through the index lock
+pub fn write_index_entries(index_entries: &[IndexEntry], path_handler: &PathHandler) -> Result<(), Box<dyn Error>> {
+    let index_lock = IndexLock::acquire(path_handler)?;
+    index_lock.write(index_entries)?;
+    index_lock.commit()
+}
+
+/// A lock on the index file, held by creating `index.lock`, which fails if another writer holds it.
+/// The new entries are written to the lock file, which is renamed over the index when committed.
+/// Dropping the lock without committing it leaves the index untouched.
+pub struct IndexLock {
+    index_path: String,
+    lock_path: String,
+    committed: bool,
+}
+
+impl IndexLock {
+    pub fn acquire(path_handler: &PathHandler) -> Result<Self, Box<dyn Error>> {
+        let index_path = path_handler.get_relative_path(INDEX_FILE);
+        let lock_path = format!("{}{}", index_path, LOCK_FILE_EXTENSION);
+        fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&lock_path)
+            .map_err(|error| match error.kind() {
+                io::ErrorKind::AlreadyExists => io::Error::new(
+                    io::ErrorKind::Other,
+                    format!("fatal: Unable to create '{}': File exists. Another process seems to be updating the index.", lock_path),
+                ),
+                _ => error,
+            })?;
+        Ok(IndexLock { index_path, lock_path, committed: false })
+    }
+
+    /// Writes the entries to the lock file, without touching the index yet
+    pub fn write(&self, index_entries: &[IndexEntry]) -> Result<(), Box<dyn Error>> {
+        let lines: Vec<String> = index_entries
+            .iter()
+            .map(|(path, hash, state, mode)| format!("{};{};{};{}", path, hash, state, mode))
+            .collect();
+        let mut lock_file = fs::File::create(&self.lock_path)?;
+        lock_file.write_all(lines.join("\n").as_bytes())?;
+        lock_file.sync_all()?;
+        Ok(())
+    }
+
+    /// Replaces the index with the lock file
+    pub fn commit(mut self) -> Result<(), Box<dyn Error>> {
+        fs::rename(&self.lock_path, &self.index_path)?;
+        self.committed = true;
+        Ok(())
+    }
+}
+
+impl Drop for IndexLock {
+    fn drop(&mut self) {
+        if !self.committed {
+            let _ = fs::remove_file(&self.lock_path);
+        }
+    }
+}
+
+/// Returns the paths of the files in the working directory, relative to its root and sorted. The `.git`
+/// directory is left out and nested repositories are listed as a single entry, like gitlinks are stored.
+/// Directories for which `skip_directory` returns true aren't walked.
+pub fn get_working_tree_files(path_handler: &PathHandler, skip_directory: &dyn Fn(&str) -> bool) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut files = Vec::new();
+    let mut pending_directories = vec![String::new()];
+
+    while let Some(directory) = pending_directories.pop() {
+        let directory_path = if directory.is_empty() {
+            path_handler.get_relative_path(".")
+        } else {
+            path_handler.get_relative_path(&directory)
+        };
+        for entry in fs::read_dir(directory_path)? {
+            let entry = entry?;
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            if file_name == GIT {
+                continue;
+            }
+            let file_path = if directory.is_empty() {
+                file_name
+            } else {
+                format!("{}/{}", directory, file_name)
+            };
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() && !entry.path().join(GIT).is_dir() {
+                if !skip_directory(&file_path) {
+                    pending_directories.push(file_path);
+                }
+            } else {
+                files.push(file_path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Turns a path given by the user into a path relative to the repository root. Absolute paths inside the
+/// repository lose the root, and `.` components, repeated and trailing slashes are removed.
+pub fn normalize_repository_path(path: &str, path_handler: &PathHandler) -> String {
+    let root = path_handler.get_relative_path("");
+    let path = if root.is_empty() {
+        path
+    } else if path == root.trim_end_matches('/') {
+        ""
+    } else {
+        path.strip_prefix(&root).unwrap_or(path)
+    };
+    path.split('/')
+        .filter(|component| !component.is_empty() && *component != ".")
+        .collect::<Vec<&str>>()
+        .join("/")
+}
+
+/// Returns the pattern of an exclude pathspec (`:!pattern`, `:^pattern` or `:(exclude)pattern`)
+fn get_excluded_pathspec(pathspec: &str) -> Option<&str> {
+    pathspec
+        .strip_prefix(":!")
+        .or_else(|| pathspec.strip_prefix(":^"))
+        .or_else(|| pathspec.strip_prefix(":(exclude)"))
+}
+
+/// Returns true if the pathspec matches the path, both relative to the repository root. An empty pathspec
+/// matches everything, a directory matches every path inside it, and glob patterns are matched against
+/// the whole path.
+pub fn matches_pathspec(pathspec: &str, path: &str) -> bool {
+    if pathspec.is_empty() || pathspec == path {
+        return true;
+    }
+    if path.starts_with(pathspec) && path[pathspec.len()..].starts_with('/') {
+        return true;
+    }
+    pathspec.contains(['*', '?', '[']) && matches_glob(pathspec, path)
+}
+
+/// Returns true if the path is matched by any of the pathspecs, or there are only exclude pathspecs,
+/// and isn't matched by any exclude pathspec.
+pub fn matches_pathspecs(pathspecs: &[String], path: &str) -> bool {
+    let mut included = pathspecs.iter().all(|pathspec| get_excluded_pathspec(pathspec).is_some());
+    for pathspec in pathspecs {
+        match get_excluded_pathspec(pathspec) {
+            Some(excluded) if matches_pathspec(excluded, path) => return false,
+            Some(_) => {}
+            None => included = included || matches_pathspec(pathspec, path),
+        }
+    }
+    included
+}
+
+/// Returns the hashes every ref, HEAD, in-progress merge or rebase, reflog entry and index entry
+/// points to. These are the starting points when looking for reachable objects.
+fn get_reachability_roots(path_handler: &PathHandler) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut roots = Vec::new();
+
+    for ref_file in get_files_in_directory_recursively(&path_handler.get_relative_path(R_REFS))? {
+        roots.push(read_file_content(&ref_file)?.trim().to_string());
+    }
+    for file in [HEAD_FILE, MERGE_HEAD, REBASE_HEAD, PACKED_REFS_FILE] {
+        if let Ok(content) = read_file_content(&path_handler.get_relative_path(file)) {
+            for line in content.lines() {
+                let first_word = line.split_whitespace().next().unwrap_or_default();
+                roots.push(first_word.trim_start_matches('^').to_string());
+            }
+        }
+    }
+    // objects referenced by the reflogs stay alive until their entries expire
+    for log_file in get_files_in_directory_recursively(&path_handler.get_relative_path(LOGS_DIR))? {
+        for line in read_file_content(&log_file)?.lines() {
+            roots.extend(line.split_whitespace().take(2).map(String::from));
+        }
+    }
+    if let Ok(index_content) = read_file_content(&path_handler.get_relative_path(INDEX_FILE)) {
+        for line in index_content.lines() {
+            if let Some(hash) = line.split(';').nth(1) {
+                roots.push(hash.to_string());
+            }
+        }
+    }
+
+    roots.retain(|hash| is_object_hash(hash) && hash != ZERO_HASH);
+    Ok(roots)
+}
+
+/// Returns the hashes of every object reachable from refs, HEAD, reflogs and the index.
+pub fn get_reachable_objects(path_handler: &PathHandler) -> Result<HashSet<String>, Box<dyn Error>> {
+    get_objects_reachable_from(get_reachability_roots(path_handler)?, path_handler)
+}
+
+/// Returns the hashes of the given objects and every object reachable from them.
+/// Commits, tags and trees are followed down to their blobs. Submodule entries are not followed.
+pub fn get_objects_reachable_from(roots: Vec<String>, path_handler: &PathHandler) -> Result<HashSet<String>, Box<dyn Error>> {
+    let mut reachable = HashSet::new();
+    let mut pending = roots;
+
+    while let Some(hash) = pending.pop() {
+        if !reachable.insert(hash.clone()) {
+            continue;
+        }
+        let (object_type, content, _size) = read_object_to_bytes(hash, path_handler)?;
+        match object_type {
+            ObjectType::Commit | ObjectType::Tag => {
+                for line in String::from_utf8_lossy(&content).lines() {
+                    if line.is_empty() {
+                        break;
+                    }
+                    if let Some((key, value)) = line.split_once(' ') {
+                        if ["tree", "parent", "object"].contains(&key) && is_object_hash(value) {
+                            pending.push(value.to_string());
+                        }
+                    }
+                }
+            }
+            ObjectType::Tree => {
+                for (file_mode, _file_name, object_hash) in parse_tree_entries(&content)? {
+                    if file_mode != GITLINK_MODE {
+                        pending.push(object_hash);
+                    }
+                }
+            }
+            ObjectType::Blob => {}
+        }
+    }
+
+    Ok(reachable)
+}
+
+/// Returns the hashes of all the loose objects in the objects directory
+pub fn get_loose_objects(path_handler: &PathHandler) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut loose_objects = Vec::new();
+    for object_dir in fs::read_dir(path_handler.get_relative_path(OBJECT))? {
+        let object_dir = object_dir?;
+        let dir_name = object_dir.file_name().to_string_lossy().to_string();
+        if dir_name.len() != 2 || !object_dir.path().is_dir() {
+            continue;
+        }
+        for object_file in fs::read_dir(object_dir.path())? {
+            let hash = format!("{}{}", dir_name, object_file?.file_name().to_string_lossy());
+            if is_object_hash(&hash) {
+                loose_objects.push(hash);
+            }
+        }
+    }
+    loose_objects.sort();
+    Ok(loose_objects)
+}
+
+/// Removes a loose object and its directory if it was the last object in it
+pub fn remove_loose_object(hash: &str, path_handler: &PathHandler) -> Result<(), Box<dyn Error>> {
+    let object_path = path_handler.get_relative_path(&get_object_path(hash));
+    fs::remove_file(&object_path)?;
+    if let Some(object_dir) = Path::new(&object_path).parent() {
+        // fails if other objects are still in the directory, which is fine
+        let _ = fs::remove_dir(object_dir);
+    }
+    Ok(())
+}
+
+/// Parses an expiry date as accepted by `prune --expire` and `gc --prune`.
+/// Accepts "now"/"all", "never", a unix timestamp, "<n>.<unit>.ago" (or with spaces) and "YYYY-MM-DD".
+/// Returns the unix timestamp up to which objects expire, or None if they never do.
+pub fn parse_expire_time(expire: &str) -> Result<Option<i64>, Box<dyn Error>> {
+    let now = Local::now().timestamp();
+    let invalid_date = || io::Error::new(io::ErrorKind::InvalidInput, format!("Invalid expiry date: {}", expire));
+
+    match expire {
+        "never" => return Ok(None),
+        "now" | "all" => return Ok(Some(now)),
+        "yesterday" => return Ok(Some(now - 24 * 60 * 60)),
+        _ => {}
+    }
+    if let Ok(timestamp) = expire.parse::<i64>() {
+        return Ok(Some(timestamp));
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(expire, "%Y-%m-%d") {
+        let midnight = date.and_hms_opt(0, 0, 0).ok_or_else(invalid_date)?;
+        return Ok(Some(midnight.and_utc().timestamp()));
+    }
+
+    let parts: Vec<&str> = expire.split(['.', ' ']).filter(|part| !part.is_empty()).collect();
+    if parts.len() != 3 || parts[2] != "ago" {
+        return Err(Box::new(invalid_date()));
+    }
+    let amount: i64 = parts[0].parse().map_err(|_| invalid_date())?;
+    let unit_seconds = match parts[1].trim_end_matches('s') {
+        "second" => 1,
+        "minute" => 60,
+        "hour" => 60 * 60,
+        "day" => 24 * 60 * 60,
+        "week" => 7 * 24 * 60 * 60,
+        "month" => 30 * 24 * 60 * 60,
+        "year" => 365 * 24 * 60 * 60,
+        _ => return Err(Box::new(invalid_date())),
+    };
+    Ok(Some(now - amount * unit_seconds))
+}
+
+/// Returns the last modification time of a file as a unix timestamp
+pub fn get_modification_timestamp(path: &str) -> Result<i64, Box<dyn Error>> {
+    let modified = fs::metadata(path)?.modified()?;
+    let since_epoch = modified.duration_since(UNIX_EPOCH)?;
+    Ok(since_epoch.as_secs() as i64)
+}
+
+/// Formats a timestamp in the given timezone, such as `+0300`, with a chrono format string
+pub fn format_timestamp(timestamp: i64, timezone: &str, format: &str) -> String {
+    let sign = if timezone.starts_with('-') { -1 } else { 1 };
+    let hours: i32 = timezone.get(1..3).and_then(|hours| hours.parse().ok()).unwrap_or(0);
+    let minutes: i32 = timezone.get(3..5).and_then(|minutes| minutes.parse().ok()).unwrap_or(0);
+    let offset = chrono::FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+        .unwrap_or_else(|| chrono::FixedOffset::east_opt(0).expect("zero offset is valid"));
+    match chrono::DateTime::from_timestamp(timestamp, 0) {
+        Some(date) => date.with_timezone(&offset).format(format).to_string(),
+        None => String::new(),
+    }
+}
+
+/// Parses a `[...]` character class starting at `start` and tells whether `c` belongs to it.
+/// Returns None if the class is never closed, in which case '[' is a literal character.
+pub fn match_glob_class(pattern: &[char], start: usize, c: char) -> Option<(bool, usize)> {
+    let mut position = start + 1;
+    let negated = matches!(pattern.get(position), Some('!') | Some('^'));
+    if negated {
+        position += 1;
+    }
+    let mut matched = false;
+    let mut first = true;
+    while position < pattern.len() {
+        if pattern[position] == ']' && !first {
+            return Some((matched != negated, position + 1));
+        }
+        first = false;
+        if position + 2 < pattern.len() && pattern[position + 1] == '-' && pattern[position + 2] != ']' {
+            if pattern[position] <= c && c <= pattern[position + 2] {
+                matched = true;
+            }
+            position += 3;
+        } else {
+            if pattern[position] == c {
+                matched = true;
+            }
+            position += 1;
+        }
+    }
+    None
+}
+
+/// Matches a text against a shell glob. Supports `*`, `?`, `[...]` classes (with ranges and `!`/`^`
+/// negation) and backslash escapes. `*` also matches '/'.
+pub fn matches_glob(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut pattern_position, mut text_position) = (0, 0);
+    // last '*' seen and the text position it is currently matching up to
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while text_position < text.len() {
+        if pattern_position < pattern.len() {
+            let current = text[text_position];
+            let step = match pattern[pattern_position] {
+                '*' => {
+                    backtrack = Some((pattern_position, text_position));
+                    pattern_position += 1;
+                    continue;
+                }
+                '?' => Some(pattern_position + 1),
+                '[' => match match_glob_class(&pattern, pattern_position, current) {
+                    Some((true, next)) => Some(next),
+                    Some((false, _)) => None,
+                    None => (current == '[').then_some(pattern_position + 1),
+                },
+                '\\' if pattern_position + 1 < pattern.len() => {
+                    (pattern[pattern_position + 1] == current).then_some(pattern_position + 2)
+                }
+                literal => (literal == current).then_some(pattern_position + 1),
+            };
+            if let Some(next) = step {
+                pattern_position = next;
+                text_position += 1;
+                continue;
+            }
+        }
+        match backtrack {
+            Some((star_position, star_text_position)) => {
+                pattern_position = star_position + 1;
+                text_position = star_text_position + 1;
+                backtrack = Some((star_position, star_text_position + 1));
+            }
+            None => return false,
+        }
+    }
+
+    pattern[pattern_position..].iter().all(|&c| c == '*')
+}
+
 /* pub const RELATIVE_PATH: &str = "RELATIVE_PATH";
 #[cfg(test)]
 mod tests {
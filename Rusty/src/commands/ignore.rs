@@ -0,0 +1,307 @@
+use std::{cell::RefCell, collections::HashMap, error::Error};
+
+use super::config::Config;
+use super::git_commands::PathHandler;
+use super::helpers;
+use crate::constants::{GITIGNORE_FILE, INFO_EXCLUDE_FILE};
+
+/// A pattern read from an ignore file, along with where it came from
+struct IgnorePattern {
+    glob: Vec<char>,
+    negated: bool,
+    directory_only: bool,
+    /// Patterns with a '/' before the end are matched against the whole path, the rest only against the file name
+    anchored: bool,
+    /// Directory of the .gitignore file the pattern was read from, relative to the repository root
+    base_directory: String,
+    source: String,
+    line_number: usize,
+    text: String,
+}
+
+/// The pattern that decided whether a path is ignored
+#[derive(Debug, PartialEq)]
+pub struct IgnoreMatch {
+    pub source: String,
+    pub line_number: usize,
+    pub pattern: String,
+    pub negated: bool,
+}
+
+/// Decides which paths of the working directory are ignored, reading `.gitignore` files at every directory
+/// level, `.git/info/exclude` and the file set in `core.excludesFile`. Patterns in deeper `.gitignore` files
+/// take precedence, then the ones in the root `.gitignore`, `.git/info/exclude` and last `core.excludesFile`.
+/// Within a file the last matching pattern wins.
+pub struct IgnoreRules {
+    path_handler: PathHandler,
+    global_patterns: Vec<IgnorePattern>,
+    directory_patterns: RefCell<HashMap<String, Vec<IgnorePattern>>>,
+}
+
+impl IgnoreRules {
+    pub fn new(path_handler: &PathHandler) -> Result<Self, Box<dyn Error>> {
+        let mut global_patterns = Vec::new();
+        if let Some(excludes_file) = Self::get_excludes_file(path_handler) {
+            if let Ok(content) = helpers::read_file_content(&excludes_file) {
+                global_patterns.extend(Self::parse_patterns(&content, "", &excludes_file));
+            }
+        }
+        if let Ok(content) = helpers::read_file_content(&path_handler.get_relative_path(INFO_EXCLUDE_FILE)) {
+            global_patterns.extend(Self::parse_patterns(&content, "", INFO_EXCLUDE_FILE));
+        }
+
+        Ok(IgnoreRules {
+            path_handler: path_handler.clone(),
+            global_patterns,
+            directory_patterns: RefCell::new(HashMap::new()),
+        })
+    }
+
+    /// Returns the path of the file set in `core.excludesFile`, with a leading `~` expanded
+    fn get_excludes_file(path_handler: &PathHandler) -> Option<String> {
+        Config::load(path_handler).ok()?.get_path("core.excludesFile")
+    }
+
+    /// Parses the lines of an ignore file. Empty lines and comments are skipped, `\#` and `\!` escape
+    /// those characters at the start of a pattern and trailing spaces are removed unless escaped.
+    fn parse_patterns(content: &str, base_directory: &str, source: &str) -> Vec<IgnorePattern> {
+        let mut patterns = Vec::new();
+        for (index, line) in content.lines().enumerate() {
+            let line = line.trim_end_matches('\r');
+            let mut pattern = if line.ends_with("\\ ") {
+                line
+            } else {
+                line.trim_end_matches(' ')
+            };
+            if pattern.is_empty() || pattern.starts_with('#') {
+                continue;
+            }
+
+            let negated = pattern.starts_with('!');
+            if negated || pattern.starts_with("\\!") || pattern.starts_with("\\#") {
+                pattern = &pattern[1..];
+            }
+            let directory_only = pattern.ends_with('/');
+            let pattern = pattern.trim_end_matches('/');
+            let anchored = pattern.contains('/');
+            let pattern = pattern.strip_prefix('/').unwrap_or(pattern);
+            if pattern.is_empty() {
+                continue;
+            }
+
+            patterns.push(IgnorePattern {
+                glob: pattern.chars().collect(),
+                negated,
+                directory_only,
+                anchored,
+                base_directory: base_directory.to_string(),
+                source: source.to_string(),
+                line_number: index + 1,
+                text: line.to_string(),
+            });
+        }
+        patterns
+    }
+
+    /// Loads the `.gitignore` file of a directory, relative to the repository root, if it wasn't loaded yet
+    fn load_directory_patterns(&self, directory: &str) {
+        if self.directory_patterns.borrow().contains_key(directory) {
+            return;
+        }
+        let source = if directory.is_empty() {
+            GITIGNORE_FILE.to_string()
+        } else {
+            format!("{}/{}", directory, GITIGNORE_FILE)
+        };
+        let patterns = match helpers::read_file_content(&self.path_handler.get_relative_path(&source)) {
+            Ok(content) => Self::parse_patterns(&content, directory, &source),
+            Err(_) => Vec::new(),
+        };
+        self.directory_patterns.borrow_mut().insert(directory.to_string(), patterns);
+    }
+
+    /// Returns true if the pattern matches the path, relative to the repository root
+    fn pattern_matches(pattern: &IgnorePattern, path: &str, is_directory: bool) -> bool {
+        if pattern.directory_only && !is_directory {
+            return false;
+        }
+        let relative_path = if pattern.base_directory.is_empty() {
+            path
+        } else {
+            match path
+                .strip_prefix(&pattern.base_directory)
+                .and_then(|rest| rest.strip_prefix('/'))
+            {
+                Some(relative_path) => relative_path,
+                None => return false,
+            }
+        };
+        let text = if pattern.anchored {
+            relative_path
+        } else {
+            relative_path.rsplit('/').next().unwrap_or(relative_path)
+        };
+        wildmatch(&pattern.glob, &text.chars().collect::<Vec<char>>())
+    }
+
+    /// Returns the last pattern matching the path itself, without looking at its parent directories
+    fn find_own_match(&self, path: &str, is_directory: bool) -> Option<IgnoreMatch> {
+        let mut directories = vec![String::new()];
+        let components: Vec<&str> = path.split('/').collect();
+        for depth in 1..components.len() {
+            directories.push(components[..depth].join("/"));
+        }
+        for directory in &directories {
+            self.load_directory_patterns(directory);
+        }
+
+        let directory_patterns = self.directory_patterns.borrow();
+        let patterns = self.global_patterns.iter().chain(
+            directories
+                .iter()
+                .filter_map(|directory| directory_patterns.get(directory))
+                .flatten(),
+        );
+        let matching_patterns: Vec<&IgnorePattern> = patterns
+            .filter(|pattern| Self::pattern_matches(pattern, path, is_directory))
+            .collect();
+        matching_patterns
+            .last()
+            .map(|pattern| IgnoreMatch {
+                source: pattern.source.clone(),
+                line_number: pattern.line_number,
+                pattern: pattern.text.clone(),
+                negated: pattern.negated,
+            })
+    }
+
+    /// Returns the pattern that decides whether the path, relative to the repository root, is ignored.
+    /// If a parent directory is ignored, the pattern ignoring it is returned, since files inside an ignored
+    /// directory can't be included again.
+    pub fn find_match(&self, path: &str, is_directory: bool) -> Option<IgnoreMatch> {
+        let components: Vec<&str> = path.split('/').collect();
+        for depth in 1..components.len() {
+            let parent_match = self.find_own_match(&components[..depth].join("/"), true);
+            if let Some(parent_match) = parent_match.filter(|parent_match| !parent_match.negated) {
+                return Some(parent_match);
+            }
+        }
+        self.find_own_match(path, is_directory)
+    }
+
+    /// Returns true if the path, relative to the repository root, is ignored
+    pub fn is_ignored(&self, path: &str, is_directory: bool) -> bool {
+        self.find_match(path, is_directory)
+            .is_some_and(|ignore_match| !ignore_match.negated)
+    }
+}
+
+/// Matches a path against an ignore pattern. `*`, `?` and `[...]` don't match '/', while a leading or inner
+/// `**/` matches any number of directories and a trailing `/**` everything inside a directory. Any other `**`
+/// acts like `*`.
+fn wildmatch(pattern: &[char], text: &[char]) -> bool {
+    wildmatch_segment(pattern, text, true)
+}
+
+/// Matches the rest of a pattern, `segment_start` telling whether it begins right after a '/' or at the start
+/// of the pattern, which is where `**` can match across directories.
+fn wildmatch_segment(pattern: &[char], text: &[char], segment_start: bool) -> bool {
+    match pattern.first() {
+        None => text.is_empty(),
+        Some('*') if segment_start && pattern.get(1) == Some(&'*') && matches!(pattern.get(2), None | Some('/')) => {
+            let rest = &pattern[2..];
+            match rest.first() {
+                Some('/') => {
+                    wildmatch(&rest[1..], text)
+                        || (0..text.len()).any(|position| text[position] == '/' && wildmatch(&rest[1..], &text[position + 1..]))
+                }
+                _ => true,
+            }
+        }
+        Some('*') => {
+            let rest = &pattern[1..];
+            for position in 0..=text.len() {
+                if wildmatch_segment(rest, &text[position..], false) {
+                    return true;
+                }
+                if position < text.len() && text[position] == '/' {
+                    break;
+                }
+            }
+            false
+        }
+        Some('?') => text.first().is_some_and(|c| *c != '/') && wildmatch_segment(&pattern[1..], &text[1..], false),
+        Some('[') if !text.is_empty() && text[0] != '/' => match helpers::match_glob_class(pattern, 0, text[0]) {
+            Some((true, next)) => wildmatch_segment(&pattern[next..], &text[1..], false),
+            Some((false, _)) => false,
+            None => text[0] == '[' && wildmatch_segment(&pattern[1..], &text[1..], false),
+        },
+        Some('\\') if pattern.len() > 1 => {
+            text.first() == Some(&pattern[1]) && wildmatch_segment(&pattern[2..], &text[1..], false)
+        }
+        Some(literal) => text.first() == Some(literal) && wildmatch_segment(&pattern[1..], &text[1..], *literal == '/'),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(pattern: &str, path: &str) -> bool {
+        wildmatch(&pattern.chars().collect::<Vec<char>>(), &path.chars().collect::<Vec<char>>())
+    }
+
+    #[test]
+    fn test_wildmatch() {
+        assert!(matches("*.rs", "main.rs"));
+        assert!(!matches("*.rs", "src/main.rs"));
+        assert!(matches("src/*.rs", "src/main.rs"));
+        assert!(matches("**/build", "a/b/build"));
+        assert!(matches("**/build", "build"));
+        assert!(matches("logs/**", "logs/a/b.log"));
+        assert!(matches("a/**/b", "a/b"));
+        assert!(matches("a/**/b", "a/x/y/b"));
+        assert!(matches("foo**bar", "fooxbar"));
+        assert!(!matches("foo**bar", "foo/x/bar"));
+        assert!(!matches("a/**b", "a/x/b"));
+        assert!(matches("file?.[ch]", "file1.c"));
+        assert!(!matches("file?.[!ch]", "file1.c"));
+        assert!(matches("\\*literal", "*literal"));
+    }
+
+    #[test]
+    fn test_ignore_rules() {
+        crate::commands::config::ignore_user_config();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path_handler = PathHandler::new(temp_dir.path().to_string_lossy().to_string());
+        std::fs::create_dir_all(path_handler.get_relative_path(".git/info")).unwrap();
+        std::fs::create_dir_all(path_handler.get_relative_path("src/generated")).unwrap();
+        std::fs::write(
+            path_handler.get_relative_path(GITIGNORE_FILE),
+            "# comment\n*.log\n!keep.log\n/build\ntarget/\ndocs/*.tmp\n",
+        )
+        .unwrap();
+        std::fs::write(path_handler.get_relative_path("src/.gitignore"), "generated/\n!debug.log\n").unwrap();
+        std::fs::write(path_handler.get_relative_path(INFO_EXCLUDE_FILE), "secret.txt\n").unwrap();
+
+        let rules = IgnoreRules::new(&path_handler).unwrap();
+        assert!(rules.is_ignored("error.log", false));
+        assert!(rules.is_ignored("a/b/error.log", false));
+        assert!(!rules.is_ignored("keep.log", false));
+        assert!(!rules.is_ignored("src/debug.log", false));
+        assert!(rules.is_ignored("build", true));
+        assert!(!rules.is_ignored("src/build", true));
+        assert!(rules.is_ignored("target", true));
+        assert!(!rules.is_ignored("target", false));
+        assert!(rules.is_ignored("target/debug/app", false));
+        assert!(rules.is_ignored("docs/a.tmp", false));
+        assert!(!rules.is_ignored("docs/sub/a.tmp", false));
+        assert!(rules.is_ignored("src/generated/code.rs", false));
+        assert!(rules.is_ignored("src/secret.txt", false));
+
+        let ignore_match = rules.find_match("src/generated/code.rs", false).unwrap();
+        assert_eq!(ignore_match.source, "src/.gitignore");
+        assert_eq!(ignore_match.line_number, 1);
+        assert_eq!(ignore_match.pattern, "generated/");
+    }
+}
@@ -1,4 +1,11 @@
+pub mod config;
 pub mod git_commands;
 pub mod helpers;
+pub mod ignore;
+pub mod packfile;
 pub mod protocol_utils;
+pub mod reflog;
+pub mod refs;
+pub mod revision;
+pub mod signing;
 pub mod structs;
\ No newline at end of file
@@ -0,0 +1,822 @@
+use std::{cell::Cell, collections::HashMap, collections::VecDeque, error::Error, fs, io, io::Cursor, io::Read, io::Write, path::Path};
+
+use libflate::zlib::Decoder;
+
+use super::git_commands::PathHandler;
+use super::helpers;
+use super::structs::ObjectType;
+use crate::constants::{
+    COPY_INSTRUCTION_FLAG, COPY_OFFSET_BYTES, COPY_SIZE_BYTES, COPY_ZERO_SIZE, DELTA_BLOCK_SIZE,
+    DELTA_MAX_DEPTH, DELTA_WINDOW, LEGACY_PACK, PACK, PACK_EXTENSION, PACK_HEADER_SIZE, PACK_INDEX_EXTENSION,
+    PACK_INDEX_SIGNATURE, PACK_INDEX_VERSION, PACK_SIGNATURE, PACK_VERSION, TEMP_PACK_PREFIX, TYPE_BITS,
+    TYPE_BYTE_SIZE_BITS, VARINT_CONTINUE_FLAG, VARINT_ENCODING_BITS,
+};
+
+const OFS_DELTA_TYPE: u8 = 6;
+const REF_DELTA_TYPE: u8 = 7;
+const HASH_BYTES: usize = 20;
+const LARGE_OFFSET_FLAG: u32 = 1 << 31;
+const MAX_INSERT_SIZE: usize = 0x7f;
+const MAX_COPY_SIZE: usize = 0xffff;
+
+thread_local! {
+    /// Set while legacy packs are migrated, since storing them reads objects that could start another migration
+    static MIGRATING_LEGACY_PACKS: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Type and content of an object read from a pack
+pub type ObjectData = (ObjectType, Vec<u8>);
+
+/// An object ready to be written to a pack file.
+pub struct PackedObject {
+    pub hash: String,
+    pub object_type: ObjectType,
+    pub content: Vec<u8>,
+}
+
+/// An entry of a pack index: object hash, offset inside the pack and crc32 of the packed bytes.
+#[derive(Clone)]
+pub struct PackIndexEntry {
+    pub hash: String,
+    pub offset: u64,
+    pub crc: u32,
+}
+
+/// Returns the numeric type used in pack entry headers for the given object type.
+pub fn pack_type_code(object_type: &ObjectType) -> u8 {
+    match object_type {
+        ObjectType::Commit => 1,
+        ObjectType::Tree => 2,
+        ObjectType::Blob => 3,
+        ObjectType::Tag => 4,
+    }
+}
+
+fn object_type_from_code(code: u8) -> Result<ObjectType, Box<dyn Error>> {
+    match code {
+        1 => Ok(ObjectType::Commit),
+        2 => Ok(ObjectType::Tree),
+        3 => Ok(ObjectType::Blob),
+        4 => Ok(ObjectType::Tag),
+        _ => Err(Box::new(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Error: Invalid Object Type",
+        ))),
+    }
+}
+
+/// Computes the crc32 (IEEE) checksum stored in pack index files.
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xffff_ffffu32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = (!(crc & 1)).wrapping_add(1);
+            crc = (crc >> 1) ^ (0xedb8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+/// Encodes the type and uncompressed size header that precedes every pack entry.
+pub fn encode_type_and_size(type_code: u8, size: usize) -> Vec<u8> {
+    let mut header = Vec::new();
+    let mut byte = (type_code << TYPE_BYTE_SIZE_BITS) | (size & 0x0f) as u8;
+    let mut remaining = size >> TYPE_BYTE_SIZE_BITS;
+    while remaining > 0 {
+        header.push(byte | VARINT_CONTINUE_FLAG);
+        byte = (remaining & 0x7f) as u8;
+        remaining >>= VARINT_ENCODING_BITS;
+    }
+    header.push(byte);
+    header
+}
+
+/// Encodes the negative offset of an OFS_DELTA base, using git's "+1 per extra byte" rule.
+fn encode_offset(mut offset: u64) -> Vec<u8> {
+    let mut bytes = vec![(offset & 0x7f) as u8];
+    offset >>= VARINT_ENCODING_BITS;
+    while offset > 0 {
+        offset -= 1;
+        bytes.push(((offset & 0x7f) as u8) | VARINT_CONTINUE_FLAG);
+        offset >>= VARINT_ENCODING_BITS;
+    }
+    bytes.reverse();
+    bytes
+}
+
+/// Encodes the little-endian size varint used at the start of delta data.
+fn encode_delta_size(mut size: usize) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    loop {
+        let byte = (size & 0x7f) as u8;
+        size >>= VARINT_ENCODING_BITS;
+        if size == 0 {
+            bytes.push(byte);
+            return bytes;
+        }
+        bytes.push(byte | VARINT_CONTINUE_FLAG);
+    }
+}
+
+fn encode_copy_instruction(offset: usize, size: usize) -> Vec<u8> {
+    let mut instruction = vec![COPY_INSTRUCTION_FLAG];
+    for byte_index in 0..COPY_OFFSET_BYTES {
+        let byte = ((offset >> (byte_index * 8)) & 0xff) as u8;
+        if byte != 0 {
+            instruction[0] |= 1 << byte_index;
+            instruction.push(byte);
+        }
+    }
+    for byte_index in 0..COPY_SIZE_BYTES {
+        let byte = ((size >> (byte_index * 8)) & 0xff) as u8;
+        if byte != 0 {
+            instruction[0] |= 1 << (COPY_OFFSET_BYTES + byte_index);
+            instruction.push(byte);
+        }
+    }
+    instruction
+}
+
+fn flush_insert(delta: &mut Vec<u8>, pending: &mut Vec<u8>) {
+    for chunk in pending.chunks(MAX_INSERT_SIZE) {
+        delta.push(chunk.len() as u8);
+        delta.extend_from_slice(chunk);
+    }
+    pending.clear();
+}
+
+/// Creates a delta that rebuilds `target` from `base` using copy and insert instructions.
+/// Blocks of the base are indexed so matching regions of the target can be copied instead of stored.
+pub fn create_delta(base: &[u8], target: &[u8]) -> Vec<u8> {
+    let mut delta = encode_delta_size(base.len());
+    delta.extend(encode_delta_size(target.len()));
+
+    let mut blocks: HashMap<&[u8], usize> = HashMap::new();
+    let mut block_start = 0;
+    while block_start + DELTA_BLOCK_SIZE <= base.len() {
+        blocks
+            .entry(&base[block_start..block_start + DELTA_BLOCK_SIZE])
+            .or_insert(block_start);
+        block_start += DELTA_BLOCK_SIZE;
+    }
+
+    let mut pending = Vec::new();
+    let mut position = 0;
+    while position < target.len() {
+        let found = if position + DELTA_BLOCK_SIZE <= target.len() {
+            blocks
+                .get(&target[position..position + DELTA_BLOCK_SIZE])
+                .copied()
+        } else {
+            None
+        };
+
+        match found {
+            Some(base_position) => {
+                let mut length = DELTA_BLOCK_SIZE;
+                while base_position + length < base.len()
+                    && position + length < target.len()
+                    && base[base_position + length] == target[position + length]
+                {
+                    length += 1;
+                }
+                flush_insert(&mut delta, &mut pending);
+                let mut copied = 0;
+                while copied < length {
+                    let size = (length - copied).min(MAX_COPY_SIZE);
+                    delta.extend(encode_copy_instruction(base_position + copied, size));
+                    copied += size;
+                }
+                position += length;
+            }
+            None => {
+                pending.push(target[position]);
+                position += 1;
+            }
+        }
+    }
+    flush_insert(&mut delta, &mut pending);
+
+    delta
+}
+
+fn read_delta_size(delta: &[u8], position: &mut usize) -> Result<usize, Box<dyn Error>> {
+    let mut value = 0;
+    let mut shift = 0;
+    loop {
+        let byte = *delta.get(*position).ok_or(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "Error: Truncated delta",
+        ))?;
+        *position += 1;
+        value |= ((byte & !VARINT_CONTINUE_FLAG) as usize) << shift;
+        if byte & VARINT_CONTINUE_FLAG == 0 {
+            return Ok(value);
+        }
+        shift += VARINT_ENCODING_BITS;
+    }
+}
+
+/// Applies a delta to its base object and returns the reconstructed content.
+pub fn apply_delta(base: &[u8], delta: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+    let mut position = 0;
+    let base_size = read_delta_size(delta, &mut position)?;
+    if base_size != base.len() {
+        return Err(Box::new(invalid("Error: Incorrect base object length")));
+    }
+    let result_size = read_delta_size(delta, &mut position)?;
+    let mut result = Vec::with_capacity(result_size);
+
+    while position < delta.len() {
+        let instruction = delta[position];
+        position += 1;
+        if instruction & COPY_INSTRUCTION_FLAG == 0 {
+            if instruction == 0 {
+                return Err(Box::new(invalid("Error: Invalid Data Instructions")));
+            }
+            let data = delta
+                .get(position..position + instruction as usize)
+                .ok_or(invalid("Error: Truncated delta"))?;
+            result.extend_from_slice(data);
+            position += instruction as usize;
+        } else {
+            let mut values = [0usize; 2];
+            let mut bit = 0;
+            for (value, bytes) in values.iter_mut().zip([COPY_OFFSET_BYTES, COPY_SIZE_BYTES]) {
+                for byte_index in 0..bytes {
+                    if instruction & (1 << bit) != 0 {
+                        let byte = *delta.get(position).ok_or(invalid("Error: Truncated delta"))?;
+                        *value |= (byte as usize) << (byte_index * 8);
+                        position += 1;
+                    }
+                    bit += 1;
+                }
+            }
+            let (offset, mut size) = (values[0], values[1]);
+            if size == 0 {
+                size = COPY_ZERO_SIZE;
+            }
+            let base_data = base
+                .get(offset..offset + size)
+                .ok_or(invalid("Invalid copy instructions"))?;
+            result.extend_from_slice(base_data);
+        }
+    }
+
+    if result.len() != result_size {
+        return Err(Box::new(invalid("Error: Incorrect object length")));
+    }
+    Ok(result)
+}
+
+/// Builds a version 2 pack containing the given objects. Objects of the same type are compared
+/// against a small window of previously written objects and stored as OFS_DELTA when that is smaller.
+/// Returns the pack bytes (checksum included) and the entries needed to write its index.
+pub fn build_pack(mut objects: Vec<PackedObject>) -> Result<(Vec<u8>, Vec<PackIndexEntry>), Box<dyn Error>> {
+    // grouping by type and writing bigger objects first gives the window better delta bases
+    objects.sort_by(|a, b| {
+        pack_type_code(&a.object_type)
+            .cmp(&pack_type_code(&b.object_type))
+            .then(b.content.len().cmp(&a.content.len()))
+    });
+
+    let mut pack = Vec::new();
+    pack.extend_from_slice(PACK_SIGNATURE);
+    pack.extend_from_slice(&PACK_VERSION.to_be_bytes());
+    pack.extend_from_slice(&(objects.len() as u32).to_be_bytes());
+
+    let mut entries = Vec::new();
+    // (object position, offset in pack, delta depth)
+    let mut window: VecDeque<(usize, u64, usize)> = VecDeque::new();
+
+    for (object_position, object) in objects.iter().enumerate() {
+        let offset = pack.len() as u64;
+        let mut best_delta: Option<(Vec<u8>, u64, usize)> = None;
+
+        for &(base_position, base_offset, base_depth) in &window {
+            let base = &objects[base_position];
+            if base.object_type != object.object_type || base_depth >= DELTA_MAX_DEPTH {
+                continue;
+            }
+            let delta = create_delta(&base.content, &object.content);
+            let best_len = best_delta
+                .as_ref()
+                .map_or(object.content.len() / 2, |(best, _, _)| best.len());
+            if delta.len() < best_len {
+                best_delta = Some((delta, base_offset, base_depth + 1));
+            }
+        }
+
+        let mut entry = Vec::new();
+        let depth = match best_delta {
+            Some((delta, base_offset, depth)) => {
+                entry.extend(encode_type_and_size(OFS_DELTA_TYPE, delta.len()));
+                entry.extend(encode_offset(offset - base_offset));
+                entry.extend(helpers::compress_bytes(&delta)?);
+                depth
+            }
+            None => {
+                entry.extend(encode_type_and_size(
+                    pack_type_code(&object.object_type),
+                    object.content.len(),
+                ));
+                entry.extend(helpers::compress_bytes(&object.content)?);
+                0
+            }
+        };
+
+        entries.push(PackIndexEntry {
+            hash: object.hash.clone(),
+            offset,
+            crc: crc32(&entry),
+        });
+        pack.extend(entry);
+
+        window.push_back((object_position, offset, depth));
+        if window.len() > DELTA_WINDOW {
+            window.pop_front();
+        }
+    }
+
+    let checksum = helpers::generate_sha1_bytes(&pack);
+    pack.extend_from_slice(&checksum);
+
+    Ok((pack, entries))
+}
+
+/// Builds a version 2 pack index for the given entries of the pack with the given checksum.
+pub fn build_pack_index(mut entries: Vec<PackIndexEntry>, pack_checksum: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+    entries.sort_by(|a, b| a.hash.cmp(&b.hash));
+
+    let mut index = Vec::new();
+    index.extend_from_slice(&PACK_INDEX_SIGNATURE);
+    index.extend_from_slice(&PACK_INDEX_VERSION.to_be_bytes());
+
+    let mut fanout = [0u32; 256];
+    let mut hashes = Vec::new();
+    for entry in &entries {
+        let hash_bytes = helpers::convert_hash_to_decimal_bytes(&entry.hash)?;
+        for count in fanout.iter_mut().skip(hash_bytes[0] as usize) {
+            *count += 1;
+        }
+        hashes.push(hash_bytes);
+    }
+    for count in fanout {
+        index.extend_from_slice(&count.to_be_bytes());
+    }
+    for hash in hashes {
+        index.extend(hash);
+    }
+    for entry in &entries {
+        index.extend_from_slice(&entry.crc.to_be_bytes());
+    }
+
+    let mut large_offsets = Vec::new();
+    for entry in &entries {
+        if entry.offset < LARGE_OFFSET_FLAG as u64 {
+            index.extend_from_slice(&(entry.offset as u32).to_be_bytes());
+        } else {
+            let position = (large_offsets.len() / 8) as u32;
+            index.extend_from_slice(&(position | LARGE_OFFSET_FLAG).to_be_bytes());
+            large_offsets.extend_from_slice(&entry.offset.to_be_bytes());
+        }
+    }
+    index.extend(large_offsets);
+    index.extend_from_slice(pack_checksum);
+
+    let checksum = helpers::generate_sha1_bytes(&index);
+    index.extend_from_slice(&checksum);
+
+    Ok(index)
+}
+
+fn read_u32(data: &[u8], position: usize) -> Result<u32, Box<dyn Error>> {
+    let bytes = data.get(position..position + 4).ok_or(io::Error::new(
+        io::ErrorKind::UnexpectedEof,
+        "Error: Truncated pack index",
+    ))?;
+    Ok(u32::from_be_bytes(bytes.try_into()?))
+}
+
+/// Looks up a hash in a version 2 pack index and returns the offset of the object in its pack.
+pub fn find_offset_in_index(index: &[u8], hash: &str) -> Result<Option<u64>, Box<dyn Error>> {
+    if index.len() < 8 + 256 * 4 || index[..4] != PACK_INDEX_SIGNATURE {
+        return Err(Box::new(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Error: Invalid pack index",
+        )));
+    }
+    let hash_bytes = match helpers::convert_hash_to_decimal_bytes(hash) {
+        Ok(bytes) if bytes.len() == HASH_BYTES => bytes,
+        _ => return Ok(None),
+    };
+    let fanout_start = 8;
+    let object_count = read_u32(index, fanout_start + 255 * 4)? as usize;
+    let first_byte = hash_bytes[0] as usize;
+    let mut low = if first_byte == 0 {
+        0
+    } else {
+        read_u32(index, fanout_start + (first_byte - 1) * 4)? as usize
+    };
+    let mut high = read_u32(index, fanout_start + first_byte * 4)? as usize;
+
+    let hashes_start = fanout_start + 256 * 4;
+    let crc_start = hashes_start + object_count * HASH_BYTES;
+    let offsets_start = crc_start + object_count * 4;
+    let large_offsets_start = offsets_start + object_count * 4;
+
+    while low < high {
+        let middle = (low + high) / 2;
+        let start = hashes_start + middle * HASH_BYTES;
+        let candidate = index.get(start..start + HASH_BYTES).ok_or(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "Error: Truncated pack index",
+        ))?;
+        match candidate.cmp(&hash_bytes[..]) {
+            std::cmp::Ordering::Less => low = middle + 1,
+            std::cmp::Ordering::Greater => high = middle,
+            std::cmp::Ordering::Equal => {
+                let offset = read_u32(index, offsets_start + middle * 4)?;
+                if offset & LARGE_OFFSET_FLAG == 0 {
+                    return Ok(Some(offset as u64));
+                }
+                let position = large_offsets_start + ((offset & !LARGE_OFFSET_FLAG) as usize) * 8;
+                let bytes = index.get(position..position + 8).ok_or(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "Error: Truncated pack index",
+                ))?;
+                return Ok(Some(u64::from_be_bytes(bytes.try_into()?)));
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Returns every hash listed in a version 2 pack index.
+pub fn read_index_hashes(index: &[u8]) -> Result<Vec<String>, Box<dyn Error>> {
+    if index.len() < 8 + 256 * 4 || index[..4] != PACK_INDEX_SIGNATURE {
+        return Err(Box::new(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Error: Invalid pack index",
+        )));
+    }
+    let object_count = read_u32(index, 8 + 255 * 4)? as usize;
+    let hashes_start = 8 + 256 * 4;
+    let mut hashes = Vec::with_capacity(object_count);
+    for position in 0..object_count {
+        let start = hashes_start + position * HASH_BYTES;
+        let bytes = index.get(start..start + HASH_BYTES).ok_or(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "Error: Truncated pack index",
+        ))?;
+        hashes.push(helpers::hex_string_to_bytes(bytes));
+    }
+    Ok(hashes)
+}
+
+/// Decodes the type and size header of the pack entry starting at `position`.
+/// Returns the type code, the size and the position right after the header.
+fn decode_type_and_size(pack: &[u8], mut position: usize) -> Result<(u8, usize, usize), Box<dyn Error>> {
+    let truncated = || io::Error::new(io::ErrorKind::UnexpectedEof, "Error: Truncated pack entry");
+    let first = *pack.get(position).ok_or_else(truncated)?;
+    position += 1;
+    let type_code = (first >> TYPE_BYTE_SIZE_BITS) & ((1 << TYPE_BITS) - 1);
+    let mut size = (first & 0x0f) as usize;
+    let mut shift = TYPE_BYTE_SIZE_BITS;
+    let mut byte = first;
+    while byte & VARINT_CONTINUE_FLAG != 0 {
+        byte = *pack.get(position).ok_or_else(truncated)?;
+        position += 1;
+        size |= ((byte & !VARINT_CONTINUE_FLAG) as usize) << shift;
+        shift += VARINT_ENCODING_BITS;
+    }
+    Ok((type_code, size, position))
+}
+
+fn decode_offset(pack: &[u8], mut position: usize) -> Result<(u64, usize), Box<dyn Error>> {
+    let truncated = || io::Error::new(io::ErrorKind::UnexpectedEof, "Error: Truncated pack entry");
+    let mut byte = *pack.get(position).ok_or_else(truncated)?;
+    position += 1;
+    let mut value = (byte & !VARINT_CONTINUE_FLAG) as u64;
+    while byte & VARINT_CONTINUE_FLAG != 0 {
+        byte = *pack.get(position).ok_or_else(truncated)?;
+        position += 1;
+        value = ((value + 1) << VARINT_ENCODING_BITS) | (byte & !VARINT_CONTINUE_FLAG) as u64;
+    }
+    Ok((value, position))
+}
+
+/// Inflates the zlib stream starting at `position`. Returns the data and the position where it ends.
+fn inflate_at(pack: &[u8], position: usize) -> Result<(Vec<u8>, usize), Box<dyn Error>> {
+    let mut cursor = Cursor::new(pack.get(position..).unwrap_or_default());
+    let mut data = Vec::new();
+    Decoder::new(&mut cursor)?.read_to_end(&mut data)?;
+    Ok((data, position + cursor.position() as usize))
+}
+
+/// Result of reading a single pack entry: its resolved type and content, and where the entry ends.
+pub struct PackEntry {
+    pub object_type: ObjectType,
+    pub content: Vec<u8>,
+    pub end: usize,
+}
+
+/// Reads the object stored at `offset` in the pack, resolving deltas. REF_DELTA bases are looked up
+/// with `resolve_base`, which receives the base hash.
+pub fn read_pack_entry<F>(pack: &[u8], offset: usize, resolve_base: &F) -> Result<PackEntry, Box<dyn Error>>
+where
+    F: Fn(&str) -> Result<ObjectData, Box<dyn Error>>,
+{
+    let (type_code, size, position) = decode_type_and_size(pack, offset)?;
+    let (object_type, content, end) = match type_code {
+        OFS_DELTA_TYPE => {
+            let (negative_offset, position) = decode_offset(pack, position)?;
+            let base_offset = (offset as u64).checked_sub(negative_offset).ok_or(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Invalid OffsetDelta offset",
+            ))?;
+            let base = read_pack_entry(pack, base_offset as usize, resolve_base)?;
+            let (delta, end) = inflate_at(pack, position)?;
+            (base.object_type, apply_delta(&base.content, &delta)?, end)
+        }
+        REF_DELTA_TYPE => {
+            let hash_bytes = pack.get(position..position + HASH_BYTES).ok_or(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "Error: Truncated pack entry",
+            ))?;
+            let (base_type, base_content) = resolve_base(&helpers::hex_string_to_bytes(hash_bytes))?;
+            let (delta, end) = inflate_at(pack, position + HASH_BYTES)?;
+            (base_type, apply_delta(&base_content, &delta)?, end)
+        }
+        _ => {
+            let object_type = object_type_from_code(type_code)?;
+            let (content, end) = inflate_at(pack, position)?;
+            if content.len() != size {
+                return Err(Box::new(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "Error: Incorrect object size",
+                )));
+            }
+            (object_type, content, end)
+        }
+    };
+    Ok(PackEntry { object_type, content, end })
+}
+
+/// Returns the path of the pack with the given name
+pub fn get_pack_path(pack_name: &str) -> String {
+    format!("{}/{}{}", PACK, pack_name, PACK_EXTENSION)
+}
+
+/// Returns the path of the index of the pack with the given name
+pub fn get_index_path(pack_name: &str) -> String {
+    format!("{}/{}{}", PACK, pack_name, PACK_INDEX_EXTENSION)
+}
+
+/// Returns the names (without extension) of the packs in the pack directory that have an index.
+pub fn list_indexed_packs(path_handler: &PathHandler) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut packs = Vec::new();
+    let entries = match fs::read_dir(path_handler.get_relative_path(PACK)) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(packs),
+        Err(err) => return Err(Box::new(err)),
+    };
+    for entry in entries {
+        let file_name = entry?.file_name().to_string_lossy().to_string();
+        if let Some(name) = file_name.strip_suffix(PACK_INDEX_EXTENSION) {
+            let pack_path = get_pack_path(name);
+            if helpers::check_if_file_exists(&pack_path, path_handler) {
+                packs.push(name.to_string());
+            }
+        }
+    }
+    packs.sort();
+    Ok(packs)
+}
+
+/// Looks for an object in every indexed pack of the repository.
+/// Returns its type and content, or None if no pack contains it.
+pub fn read_object_from_packs(hash: &str, path_handler: &PathHandler) -> Result<Option<ObjectData>, Box<dyn Error>> {
+    for pack_name in list_indexed_packs(path_handler)? {
+        let index_path = get_index_path(&pack_name);
+        let index = helpers::read_file_content_to_bytes(&path_handler.get_relative_path(&index_path))?;
+        if let Some(offset) = find_offset_in_index(&index, hash)? {
+            let pack_path = get_pack_path(&pack_name);
+            let pack = helpers::read_file_content_to_bytes(&path_handler.get_relative_path(&pack_path))?;
+            let resolve_base = |base_hash: &str| {
+                helpers::read_object_to_bytes(base_hash.to_string(), path_handler)
+                    .map(|(object_type, content, _)| (object_type, content))
+            };
+            let entry = read_pack_entry(&pack, offset as usize, &resolve_base)?;
+            return Ok(Some((entry.object_type, entry.content)));
+        }
+    }
+    Ok(None)
+}
+
+/// Writes data to a temporary file in the pack directory, syncs it and renames it to the given path,
+/// so readers and concurrent writers never see a partially written file.
+fn write_pack_file_atomically(file_path: &str, data: &[u8], path_handler: &PathHandler) -> Result<(), Box<dyn Error>> {
+    let mut temp_file = tempfile::Builder::new()
+        .prefix(TEMP_PACK_PREFIX)
+        .tempfile_in(path_handler.get_relative_path(PACK))?;
+    temp_file.write_all(data)?;
+    temp_file.as_file().sync_all()?;
+    temp_file.persist(path_handler.get_relative_path(file_path))?;
+    Ok(())
+}
+
+/// Writes a pack and its index to the pack directory as `pack-<checksum>`.
+/// Returns the pack name.
+pub fn write_pack_with_index(pack: &[u8], entries: Vec<PackIndexEntry>, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
+    if pack.len() < PACK_HEADER_SIZE + HASH_BYTES {
+        return Err(Box::new(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Error: Invalid pack file",
+        )));
+    }
+    let checksum = &pack[pack.len() - HASH_BYTES..];
+    let pack_name = format!("pack-{}", helpers::hex_string_to_bytes(checksum));
+    let index = build_pack_index(entries, checksum)?;
+
+    fs::create_dir_all(path_handler.get_relative_path(PACK))?;
+    let pack_path = get_pack_path(&pack_name);
+    let index_path = get_index_path(&pack_name);
+    write_pack_file_atomically(&pack_path, pack, path_handler)?;
+    // the index is written last so readers never find an index without its pack
+    write_pack_file_atomically(&index_path, &index, path_handler)?;
+
+    Ok(pack_name)
+}
+
+/// Parses every entry of a pack in order, checking its header and checksum. Deltas are resolved against
+/// earlier entries of the same pack or against objects already in the repository.
+/// Returns the index entry and the data of each object.
+pub fn read_all_pack_objects(pack: &[u8], path_handler: &PathHandler) -> Result<Vec<(PackIndexEntry, ObjectData)>, Box<dyn Error>> {
+    let invalid_pack = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+    if pack.len() < PACK_HEADER_SIZE + HASH_BYTES || &pack[..4] != PACK_SIGNATURE {
+        return Err(Box::new(invalid_pack("Error: Invalid pack file")));
+    }
+    let objects_end = pack.len() - HASH_BYTES;
+    if helpers::generate_sha1_bytes(&pack[..objects_end])[..] != pack[objects_end..] {
+        return Err(Box::new(invalid_pack("Error: Pack checksum mismatch")));
+    }
+    let object_count = read_u32(pack, 8)? as usize;
+
+    let mut objects: Vec<(PackIndexEntry, ObjectData)> = Vec::with_capacity(object_count);
+    let mut objects_by_hash: HashMap<String, usize> = HashMap::new();
+    let mut offset = PACK_HEADER_SIZE;
+
+    for _ in 0..object_count {
+        let resolve_base = |base_hash: &str| match objects_by_hash.get(base_hash) {
+            Some(&position) => Ok(objects[position].1.clone()),
+            None => helpers::read_object_to_bytes(base_hash.to_string(), path_handler)
+                .map(|(object_type, content, _)| (object_type, content)),
+        };
+        let entry = read_pack_entry(pack, offset, &resolve_base)?;
+
+        let mut object_data = format!("{} {}\0", entry.object_type, entry.content.len()).into_bytes();
+        object_data.extend_from_slice(&entry.content);
+        let hash = helpers::generate_sha1_string_from_bytes(&object_data);
+
+        let index_entry = PackIndexEntry {
+            hash: hash.clone(),
+            offset: offset as u64,
+            crc: crc32(&pack[offset..entry.end]),
+        };
+        objects_by_hash.insert(hash, objects.len());
+        objects.push((index_entry, (entry.object_type, entry.content)));
+        offset = entry.end;
+    }
+
+    if offset != objects_end {
+        return Err(Box::new(invalid_pack("Error: Unexpected data at the end of the pack file")));
+    }
+    Ok(objects)
+}
+
+/// Stores a pack received from a remote. The pack is checked, its index is generated and both are
+/// written through temporary files, so two concurrent receives never write to the same file.
+/// Returns the pack name, or an empty string if the pack had no objects.
+pub fn store_received_pack(pack: &[u8], path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
+    let entries: Vec<PackIndexEntry> = read_all_pack_objects(pack, path_handler)?
+        .into_iter()
+        .map(|(index_entry, _object)| index_entry)
+        .collect();
+    if entries.is_empty() {
+        return Ok(String::new());
+    }
+    write_pack_with_index(pack, entries, path_handler)
+}
+
+/// Moves the packs that older versions kept in `.git/pack` to the pack directory, indexing them so their objects
+/// can be read. The old directory is removed once all of its packs are stored, and kept if any of them fails.
+/// Returns whether any pack was migrated.
+pub fn migrate_legacy_packs(path_handler: &PathHandler) -> Result<bool, Box<dyn Error>> {
+    let legacy_dir = path_handler.get_relative_path(LEGACY_PACK);
+    if MIGRATING_LEGACY_PACKS.with(Cell::get) || !Path::new(&legacy_dir).is_dir() {
+        return Ok(false);
+    }
+    MIGRATING_LEGACY_PACKS.with(|migrating| migrating.set(true));
+    let result = store_legacy_packs(&legacy_dir, path_handler);
+    MIGRATING_LEGACY_PACKS.with(|migrating| migrating.set(false));
+
+    let migrated = result?;
+    match fs::remove_dir_all(&legacy_dir) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(Box::new(err)),
+        _ => {}
+    }
+    Ok(migrated)
+}
+
+/// Stores every pack file of the legacy pack directory. Returns whether there was any.
+fn store_legacy_packs(legacy_dir: &str, path_handler: &PathHandler) -> Result<bool, Box<dyn Error>> {
+    let mut migrated = false;
+    for entry in fs::read_dir(legacy_dir)? {
+        let path = entry?.path();
+        if path.extension().and_then(|extension| extension.to_str()) != PACK_EXTENSION.strip_prefix('.') {
+            continue;
+        }
+        let pack = fs::read(&path)?;
+        store_received_pack(&pack, path_handler).map_err(|err| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("Error: Cannot migrate {}: {}", path.display(), err))
+        })?;
+        migrated = true;
+    }
+    Ok(migrated)
+}
+
+/// Returns the paths of the temporary files left in the pack directory by interrupted writes
+pub fn list_temporary_pack_files(path_handler: &PathHandler) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut temporary_files = Vec::new();
+    let entries = match fs::read_dir(path_handler.get_relative_path(PACK)) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(temporary_files),
+        Err(err) => return Err(Box::new(err)),
+    };
+    for entry in entries {
+        let file_name = entry?.file_name().to_string_lossy().to_string();
+        if file_name.starts_with(TEMP_PACK_PREFIX) {
+            temporary_files.push(path_handler.get_relative_path(&format!("{}/{}", PACK, file_name)));
+        }
+    }
+    Ok(temporary_files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delta_roundtrip() {
+        let base: Vec<u8> = (0..2000).map(|i| (i % 251) as u8).collect();
+        let mut target = base[100..1500].to_vec();
+        target.extend_from_slice(b"some new content in the middle");
+        target.extend_from_slice(&base[..300]);
+
+        let delta = create_delta(&base, &target);
+        assert!(delta.len() < target.len() / 2);
+        assert_eq!(apply_delta(&base, &delta).unwrap(), target);
+    }
+
+    #[test]
+    fn test_pack_and_index_roundtrip() {
+        let base = "line of text\n".repeat(200);
+        let contents = [base.clone(), format!("{}one more line\n", base), String::from("x")];
+        let objects: Vec<PackedObject> = contents
+            .iter()
+            .map(|content| PackedObject {
+                hash: helpers::generate_sha1_string(&format!("blob {}\0{}", content.len(), content)),
+                object_type: ObjectType::Blob,
+                content: content.as_bytes().to_vec(),
+            })
+            .collect();
+        let hashes: Vec<String> = objects.iter().map(|object| object.hash.clone()).collect();
+
+        let (pack, entries) = build_pack(objects).unwrap();
+        let index = build_pack_index(entries, &pack[pack.len() - HASH_BYTES..]).unwrap();
+        let no_ref_delta = |_: &str| -> Result<ObjectData, Box<dyn Error>> {
+            Err(Box::new(io::Error::new(io::ErrorKind::NotFound, "unexpected ref delta")))
+        };
+
+        for (hash, content) in hashes.iter().zip(contents.iter()) {
+            let offset = find_offset_in_index(&index, hash).unwrap().unwrap();
+            let entry = read_pack_entry(&pack, offset as usize, &no_ref_delta).unwrap();
+            assert!(entry.object_type == ObjectType::Blob);
+            assert_eq!(entry.content, content.as_bytes());
+        }
+        assert!(find_offset_in_index(&index, &"0".repeat(40)).unwrap().is_none());
+
+        // Reading the pack sequentially finds the same objects, at the offsets in the index
+        let path_handler = PathHandler::new(String::new());
+        let objects = read_all_pack_objects(&pack, &path_handler).unwrap();
+        assert_eq!(objects.len(), hashes.len());
+        for (index_entry, (_object_type, content)) in objects {
+            let position = hashes.iter().position(|hash| *hash == index_entry.hash).unwrap();
+            assert_eq!(content, contents[position].as_bytes());
+            assert_eq!(find_offset_in_index(&index, &index_entry.hash).unwrap(), Some(index_entry.offset));
+        }
+    }
+}
@@ -6,7 +6,7 @@ use std::{error::Error, io, io::Read};
 // pub const NAK_RESPONSE: &str = "NAK\n";
 // pub const WANT_REQUEST: &str = "want";
 // pub const UNPACK_CONFIRMATION: &str = "unpack ok\n";
-use crate::constants::LENGTH_BYTES;
+use crate::constants::{LENGTH_BYTES, REF_STATUS_OK, REF_STATUS_REJECTED, UNPACK_CONFIRMATION};
 
 pub fn format_line_to_send(line: String) -> String {
     format!("{:04x}{}", line.len() + 4, line)
@@ -43,6 +43,45 @@ pub fn read_until(
     Ok(requests_received)
 }
 
+/// Reads the report a server sends at the end of a push: an `unpack ok` line, then `ok <ref>` for each updated ref
+/// or `ng <ref> <reason>` for each rejected one. Returns the updated refs, or an error if the pack was not
+/// unpacked or any ref was rejected.
+pub fn parse_report_status(lines: &[String]) -> Result<Vec<String>, Box<dyn Error>> {
+    let (unpack_status, ref_statuses) = match lines.split_first() {
+        Some((unpack_status, ref_statuses)) => (unpack_status.trim_end(), ref_statuses),
+        None => return Err(Box::new(io::Error::new(io::ErrorKind::Other, "Error: The remote sent no push report"))),
+    };
+    if unpack_status != UNPACK_CONFIRMATION.trim_end() {
+        return Err(Box::new(io::Error::new(
+            io::ErrorKind::Other,
+            format!("Error: The remote failed to unpack: {}", unpack_status.trim_start_matches("unpack ")),
+        )));
+    }
+
+    let mut updated_refs = Vec::new();
+    let mut rejected_refs = Vec::new();
+    for line in ref_statuses {
+        match line.trim_end().splitn(3, ' ').collect::<Vec<&str>>().as_slice() {
+            [REF_STATUS_OK, ref_name] => updated_refs.push(ref_name.to_string()),
+            [REF_STATUS_REJECTED, ref_name, reason] => rejected_refs.push(format!(" ! [remote rejected] {} ({})", ref_name, reason)),
+            [REF_STATUS_REJECTED, ref_name] => rejected_refs.push(format!(" ! [remote rejected] {}", ref_name)),
+            _ => {
+                return Err(Box::new(io::Error::new(
+                    io::ErrorKind::Other,
+                    format!("Error: Invalid line in push report: {}", line.trim_end()),
+                )))
+            }
+        }
+    }
+    if !rejected_refs.is_empty() {
+        return Err(Box::new(io::Error::new(
+            io::ErrorKind::Other,
+            format!("{}\nError: Failed to push some refs", rejected_refs.join("\n")),
+        )));
+    }
+    Ok(updated_refs)
+}
+
 pub fn read_exact_length_to_string(
     reader: &mut dyn Read,
     message_length: usize,
@@ -68,3 +107,21 @@ pub fn get_request_length(reader: &mut dyn Read) -> Result<usize, Box<dyn std::e
     let hex_string = String::from_utf8_lossy(&message_length);
     Ok(u32::from_str_radix(&hex_string, 16)? as usize)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_report_status() {
+        let report = |lines: &[&str]| parse_report_status(&lines.iter().map(|line| line.to_string()).collect::<Vec<String>>());
+        assert_eq!(report(&["unpack ok\n", "ok refs/heads/main\n"]).unwrap(), vec!["refs/heads/main"]);
+        assert_eq!(report(&["unpack ok\n"]).unwrap(), Vec::<String>::new());
+
+        let error = report(&["unpack ok\n", "ok refs/heads/main\n", "ng refs/heads/dev stale info\n"]).unwrap_err();
+        assert!(error.to_string().contains("[remote rejected] refs/heads/dev (stale info)"));
+        assert!(report(&["unpack index-pack failed\n"]).unwrap_err().to_string().contains("index-pack failed"));
+        assert!(report(&[]).is_err());
+        assert!(report(&["unpack ok\n", "maybe refs/heads/main\n"]).is_err());
+    }
+}
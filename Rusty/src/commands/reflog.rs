@@ -0,0 +1,222 @@
+use std::{error::Error, fs, io, io::Write, path::Path};
+
+use super::git_commands::PathHandler;
+use super::helpers;
+use super::structs::HashObjectCreator;
+use crate::constants::{GIT, HEAD, HEAD_FILE, LOGS_DIR, ZERO_HASH};
+
+/// An entry of a reflog: a ref moving from one value to another
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReflogEntry {
+    pub old_hash: String,
+    pub new_hash: String,
+    pub identity: String,
+    pub timestamp: i64,
+    pub timezone: String,
+    pub message: String,
+}
+
+impl ReflogEntry {
+    /// Parses a reflog line: `<old> <new> <name> <<email>> <timestamp> <timezone>\t<message>`.
+    /// Lines whose hashes aren't full object hashes are rejected.
+    fn parse(line: &str) -> Option<Self> {
+        let (header, message) = line.split_once('\t').unwrap_or((line, ""));
+        let (old_hash, rest) = header.split_once(' ')?;
+        let (new_hash, rest) = rest.split_once(' ')?;
+        if !helpers::is_object_hash(old_hash) || !helpers::is_object_hash(new_hash) {
+            return None;
+        }
+        let mut identity_words: Vec<&str> = rest.rsplitn(3, ' ').collect();
+        if identity_words.len() != 3 {
+            return None;
+        }
+        let identity = identity_words.pop()?.to_string();
+        let timestamp = identity_words.pop()?.parse().ok()?;
+        let timezone = identity_words.pop()?.to_string();
+        Some(ReflogEntry {
+            old_hash: old_hash.to_string(),
+            new_hash: new_hash.to_string(),
+            identity,
+            timestamp,
+            timezone,
+            message: message.to_string(),
+        })
+    }
+}
+
+impl std::fmt::Display for ReflogEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} {} {} {}\t{}",
+            self.old_hash, self.new_hash, self.identity, self.timestamp, self.timezone, self.message
+        )
+    }
+}
+
+/// Returns the path of the reflog of a ref, given its name relative to the git directory (e.g. `refs/heads/master`)
+pub fn get_log_path(ref_name: &str) -> String {
+    format!("{}/{}", LOGS_DIR, ref_name)
+}
+
+/// Appends an entry to the reflog of a ref, creating it if needed. An empty old value is written as the zero hash.
+/// Nothing is logged if the ref doesn't point anywhere after the update.
+pub fn append(ref_name: &str, old_hash: &str, new_hash: &str, message: &str, path_handler: &PathHandler) -> Result<(), Box<dyn Error>> {
+    if new_hash.is_empty() {
+        return Ok(());
+    }
+    let log_path = path_handler.get_relative_path(&get_log_path(ref_name));
+    if let Some(parent) = Path::new(&log_path).parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let identity = HashObjectCreator::generate_identity_line("committer");
+    let identity = identity.trim_start_matches("committer ");
+    let old_hash = if old_hash.is_empty() { ZERO_HASH } else { old_hash };
+    // messages are kept in one line so every entry is a single line
+    let message = message.lines().next().unwrap_or_default();
+
+    let mut log_file = fs::OpenOptions::new().create(true).append(true).open(log_path)?;
+    writeln!(log_file, "{} {} {}\t{}", old_hash, new_hash, identity, message)?;
+    Ok(())
+}
+
+/// Logs an update of a ref, also logging it in the reflog of HEAD when HEAD points to that ref
+pub fn log_ref_update(ref_name: &str, old_hash: &str, new_hash: &str, message: &str, path_handler: &PathHandler) -> Result<(), Box<dyn Error>> {
+    append(ref_name, old_hash, new_hash, message, path_handler)?;
+    let head_content = helpers::read_file_content(&path_handler.get_relative_path(HEAD_FILE)).unwrap_or_default();
+    if head_content.trim().strip_prefix("ref: ") == Some(ref_name) {
+        append(HEAD, old_hash, new_hash, message, path_handler)?;
+    }
+    Ok(())
+}
+
+/// Returns the entries of the reflog of a ref, oldest first
+pub fn read_entries(ref_name: &str, path_handler: &PathHandler) -> Result<Vec<ReflogEntry>, Box<dyn Error>> {
+    let log_path = path_handler.get_relative_path(&get_log_path(ref_name));
+    if fs::metadata(&log_path).is_err() {
+        return Ok(Vec::new());
+    }
+    Ok(helpers::read_file_content(&log_path)?
+        .lines()
+        .filter_map(ReflogEntry::parse)
+        .collect())
+}
+
+/// Replaces the entries of the reflog of a ref
+pub fn write_entries(ref_name: &str, entries: &[ReflogEntry], path_handler: &PathHandler) -> Result<(), Box<dyn Error>> {
+    let content: String = entries.iter().map(|entry| format!("{}\n", entry)).collect();
+    fs::write(path_handler.get_relative_path(&get_log_path(ref_name)), content)?;
+    Ok(())
+}
+
+/// Moves the reflog of a ref when the ref is renamed
+pub fn rename(old_ref_name: &str, new_ref_name: &str, path_handler: &PathHandler) -> Result<(), Box<dyn Error>> {
+    let old_log_path = path_handler.get_relative_path(&get_log_path(old_ref_name));
+    if fs::metadata(&old_log_path).is_err() {
+        return Ok(());
+    }
+    let new_log_path = path_handler.get_relative_path(&get_log_path(new_ref_name));
+    if let Some(parent) = Path::new(&new_log_path).parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::rename(old_log_path, new_log_path)?;
+    Ok(())
+}
+
+/// Copies the reflog of a ref when the ref is copied
+pub fn copy(old_ref_name: &str, new_ref_name: &str, path_handler: &PathHandler) -> Result<(), Box<dyn Error>> {
+    let old_log_path = path_handler.get_relative_path(&get_log_path(old_ref_name));
+    if fs::metadata(&old_log_path).is_err() {
+        return Ok(());
+    }
+    let new_log_path = path_handler.get_relative_path(&get_log_path(new_ref_name));
+    if let Some(parent) = Path::new(&new_log_path).parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::copy(old_log_path, new_log_path)?;
+    Ok(())
+}
+
+/// Removes the reflog of a ref when the ref is deleted
+pub fn delete(ref_name: &str, path_handler: &PathHandler) {
+    let _ = fs::remove_file(path_handler.get_relative_path(&get_log_path(ref_name)));
+}
+
+/// Finds the ref whose reflog a name refers to, looking it up the way revisions are: `master` finds
+/// `refs/heads/master` and `origin/master` finds `refs/remotes/origin/master`
+pub fn find_log_ref(name: &str, path_handler: &PathHandler) -> Option<String> {
+    if name == HEAD {
+        return Some(HEAD.to_string());
+    }
+    let candidate_refs = [
+        name.to_string(),
+        format!("refs/{}", name),
+        format!("refs/tags/{}", name),
+        format!("refs/heads/{}", name),
+        format!("refs/remotes/{}", name),
+        format!("refs/remotes/{}/HEAD", name),
+    ];
+    candidate_refs.into_iter().find(|candidate_ref| {
+        fs::metadata(path_handler.get_relative_path(&get_log_path(candidate_ref))).is_ok()
+            || fs::metadata(path_handler.get_relative_path(&format!("{}/{}", GIT, candidate_ref))).is_ok()
+    })
+}
+
+/// Resolves a reflog selector, the `<selector>` of `<ref>@{<selector>}`. A number n gives the value the ref had
+/// n updates ago, and a date such as `yesterday` or `2.hours.ago` gives the value it had at that time.
+pub fn resolve_entry(ref_name: &str, selector: &str, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
+    let entries = read_entries(ref_name, path_handler)?;
+    let not_found = || -> Box<dyn Error> {
+        Box::new(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("fatal: log for '{}' only has {} entries", ref_name, entries.len()),
+        ))
+    };
+
+    if let Ok(position) = selector.parse::<usize>() {
+        return match entries.len().checked_sub(position + 1) {
+            Some(index) => Ok(entries[index].new_hash.clone()),
+            // the value before the oldest entry is still known if it wasn't a creation
+            None if position == entries.len() => entries
+                .first()
+                .map(|entry| entry.old_hash.clone())
+                .filter(|hash| hash != ZERO_HASH)
+                .ok_or_else(not_found),
+            None => Err(not_found()),
+        };
+    }
+
+    let time = helpers::parse_expire_time(selector)?.ok_or_else(not_found)?;
+    match entries.iter().rev().find(|entry| entry.timestamp <= time) {
+        Some(entry) => Ok(entry.new_hash.clone()),
+        None => entries
+            .first()
+            .map(|entry| entry.old_hash.clone())
+            .filter(|hash| hash != ZERO_HASH)
+            .ok_or_else(|| {
+                Box::new(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("fatal: log for '{}' doesn't go back to {}", ref_name, selector),
+                )) as Box<dyn Error>
+            }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reflog_entry_parsing() {
+        let line = format!("{} {} Jane Doe <jane@fi.uba.ar> 1700000000 -0300\tcommit: first", ZERO_HASH, "a".repeat(40));
+        let entry = ReflogEntry::parse(&line).unwrap();
+        assert_eq!(entry.old_hash, ZERO_HASH);
+        assert_eq!(entry.identity, "Jane Doe <jane@fi.uba.ar>");
+        assert_eq!(entry.timestamp, 1700000000);
+        assert_eq!(entry.timezone, "-0300");
+        assert_eq!(entry.message, "commit: first");
+        assert_eq!(entry.to_string(), line);
+        assert!(ReflogEntry::parse("garbage").is_none());
+        assert!(ReflogEntry::parse(&line.replacen(&"a".repeat(40), "abc", 1)).is_none());
+    }
+}
@@ -0,0 +1,639 @@
+use std::{collections::HashSet, error::Error, fs, io, io::Write, path::Path};
+
+use super::git_commands::PathHandler;
+use super::helpers;
+use super::reflog;
+use super::revision;
+use super::structs::ObjectType;
+use crate::constants::{GIT, LOCK_FILE_EXTENSION, PACKED_REFS_FILE, PACKED_REFS_HEADER, R_REFS, ZERO_HASH};
+
+/// A ref stored in the packed-refs file, with the object it peels to if it points to an annotated tag
+#[derive(Debug, Clone, PartialEq)]
+pub struct PackedRef {
+    pub name: String,
+    pub hash: String,
+    pub peeled: Option<String>,
+}
+
+/// A refspec such as `+refs/heads/*:refs/remotes/origin/*`, which maps the refs of a remote to local refs.
+/// The source and destination may have a single `*`, matching the same part of both names. A leading `+`
+/// allows updates that aren't fast-forwards.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Refspec {
+    pub force: bool,
+    pub source: String,
+    pub destination: String,
+}
+
+impl Refspec {
+    /// Parses a refspec, returning None if it's malformed
+    pub fn parse(refspec: &str) -> Option<Self> {
+        let (force, refspec) = match refspec.strip_prefix('+') {
+            Some(refspec) => (true, refspec),
+            None => (false, refspec),
+        };
+        let (source, destination) = refspec.split_once(':')?;
+        let source_globs = source.matches('*').count();
+        if source.is_empty() || destination.is_empty() || source_globs > 1 || source_globs != destination.matches('*').count() {
+            return None;
+        }
+        Some(Refspec {
+            force,
+            source: source.to_string(),
+            destination: destination.to_string(),
+        })
+    }
+
+    /// The refspec a remote is added with: every branch of the remote goes to `refs/remotes/<remote>/`
+    pub fn default_fetch(remote_name: &str) -> Self {
+        Refspec {
+            force: true,
+            source: String::from("refs/heads/*"),
+            destination: format!("refs/remotes/{}/*", remote_name),
+        }
+    }
+
+    /// Returns the part of a ref name matched by the `*` of a pattern, or an empty string if the pattern has no
+    /// `*` and is the name itself
+    fn match_pattern<'a>(pattern: &str, ref_name: &'a str) -> Option<&'a str> {
+        match pattern.split_once('*') {
+            Some((prefix, suffix)) => ref_name
+                .strip_prefix(prefix)?
+                .strip_suffix(suffix)
+                .filter(|matched| !matched.is_empty()),
+            None => (pattern == ref_name).then_some(""),
+        }
+    }
+
+    /// Maps a ref of the remote to the local ref it's stored in
+    pub fn map_to_destination(&self, ref_name: &str) -> Option<String> {
+        Self::match_pattern(&self.source, ref_name).map(|matched| self.destination.replacen('*', matched, 1))
+    }
+
+    /// Maps a local ref back to the ref of the remote it comes from
+    pub fn map_to_source(&self, local_ref_name: &str) -> Option<String> {
+        Self::match_pattern(&self.destination, local_ref_name).map(|matched| self.source.replacen('*', matched, 1))
+    }
+}
+
+impl std::fmt::Display for Refspec {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}{}:{}", if self.force { "+" } else { "" }, self.source, self.destination)
+    }
+}
+
+/// A change to a single ref within a transaction
+struct RefUpdate {
+    ref_name: String,
+    /// The new content of the ref, or None to delete it
+    new_value: Option<String>,
+    /// The value the ref must have before the update. The zero hash means the ref must not exist, and None
+    /// means any value is accepted.
+    expected_value: Option<String>,
+    /// The message recorded in the reflog, or None to leave the reflog untouched
+    message: Option<String>,
+    /// For a deleted ref that is being renamed, the new name its reflog moves to
+    renamed_to: Option<String>,
+}
+
+/// A ref whose lock is held while a transaction is committed
+struct LockedRef {
+    path: String,
+    lock_path: String,
+    previous_value: Option<String>,
+}
+
+/// A group of ref updates applied atomically: either all of them are applied or none is.
+/// Every ref is locked by creating `<ref>.lock`, which fails if another writer holds it. The expected old
+/// values are checked while the locks are held, the new values are written and synced to the lock files,
+/// and the lock files are then renamed over the refs.
+#[derive(Default)]
+pub struct RefTransaction {
+    updates: Vec<RefUpdate>,
+}
+
+impl RefTransaction {
+    pub fn new() -> Self {
+        RefTransaction { updates: Vec::new() }
+    }
+
+    /// Adds an update of a ref, given its name relative to the git directory (e.g. `refs/heads/master`)
+    pub fn update(&mut self, ref_name: &str, new_value: &str, expected_value: Option<&str>, message: Option<&str>) -> &mut Self {
+        self.updates.push(RefUpdate {
+            ref_name: ref_name.to_string(),
+            new_value: Some(new_value.to_string()),
+            expected_value: expected_value.map(String::from),
+            message: message.map(String::from),
+            renamed_to: None,
+        });
+        self
+    }
+
+    /// Adds the deletion of a ref. Its reflog is removed along with it.
+    pub fn delete(&mut self, ref_name: &str, expected_value: Option<&str>) -> &mut Self {
+        self.updates.push(RefUpdate {
+            ref_name: ref_name.to_string(),
+            new_value: None,
+            expected_value: expected_value.map(String::from),
+            message: None,
+            renamed_to: None,
+        });
+        self
+    }
+
+    /// Adds the rename of a ref that points to `value` to a name no ref has. Its reflog moves along with it once
+    /// the refs are renamed, and `message` is recorded in it.
+    pub fn rename(&mut self, ref_name: &str, new_ref_name: &str, value: &str, message: Option<&str>) -> &mut Self {
+        self.updates.push(RefUpdate {
+            ref_name: ref_name.to_string(),
+            new_value: None,
+            expected_value: Some(value.to_string()),
+            message: message.map(String::from),
+            renamed_to: Some(new_ref_name.to_string()),
+        });
+        self.update(new_ref_name, value, Some(ZERO_HASH), None)
+    }
+
+    /// Applies every update. If a lock can't be taken, an old value doesn't match or a write fails,
+    /// the refs that were already replaced are restored and the locks are released.
+    pub fn commit(mut self, path_handler: &PathHandler) -> Result<(), Box<dyn Error>> {
+        let mut locked_refs: Vec<LockedRef> = Vec::new();
+        let result = self
+            .lock_packed_refs(&mut locked_refs, path_handler)
+            .and_then(|_| Self::lock_and_write(&self.updates, &mut locked_refs, path_handler))
+            .and_then(|_| Self::write_reflogs(&self.updates, &locked_refs, path_handler))
+            .and_then(|_| Self::rename_locks(&self.updates, &locked_refs));
+        if let Err(error) = result {
+            for locked_ref in &locked_refs {
+                let _ = fs::remove_file(&locked_ref.lock_path);
+            }
+            return Err(error);
+        }
+
+        // the reflogs of deleted refs are only removed once the refs are gone
+        for update in &self.updates {
+            if update.new_value.is_none() && update.renamed_to.is_none() {
+                reflog::delete(&update.ref_name, path_handler);
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes the deleted refs that are packed from the packed-refs file in the same transaction. The file
+    /// is locked before it's read, so a concurrent change to it isn't lost, and its update is put first so it
+    /// lines up with its entry in `locked_refs`. The lock is released right away if no deleted ref is packed.
+    fn lock_packed_refs(&mut self, locked_refs: &mut Vec<LockedRef>, path_handler: &PathHandler) -> Result<(), Box<dyn Error>> {
+        let deleted_refs: HashSet<&String> = self
+            .updates
+            .iter()
+            .filter(|update| update.new_value.is_none())
+            .map(|update| &update.ref_name)
+            .collect();
+        if deleted_refs.is_empty() {
+            return Ok(());
+        }
+        let path = path_handler.get_relative_path(PACKED_REFS_FILE);
+        let lock_path = format!("{}{}", path, LOCK_FILE_EXTENSION);
+        let mut lock_file = Self::create_lock_file(&lock_path)?;
+        let packed_refs = match read_packed_refs(path_handler) {
+            Ok(packed_refs) => packed_refs,
+            Err(error) => {
+                let _ = fs::remove_file(&lock_path);
+                return Err(error);
+            }
+        };
+        if !packed_refs.iter().any(|packed_ref| deleted_refs.contains(&packed_ref.name)) {
+            fs::remove_file(&lock_path)?;
+            return Ok(());
+        }
+
+        let remaining_refs: Vec<PackedRef> = packed_refs
+            .into_iter()
+            .filter(|packed_ref| !deleted_refs.contains(&packed_ref.name))
+            .collect();
+        let content = format_packed_refs(&remaining_refs);
+        let previous_value = fs::read_to_string(&path).ok();
+        locked_refs.push(LockedRef { path, lock_path, previous_value });
+        lock_file.write_all(content.as_bytes())?;
+        lock_file.sync_all()?;
+        self.updates.insert(
+            0,
+            RefUpdate {
+                ref_name: PACKED_REFS_FILE.trim_start_matches(&format!("{}/", GIT)).to_string(),
+                new_value: Some(content),
+                expected_value: None,
+                message: None,
+                renamed_to: None,
+            },
+        );
+        Ok(())
+    }
+
+    /// Writes the reflog entries of the updates while their refs are still locked, so an error leaves
+    /// every ref as it was. A renamed ref takes its reflog along with it.
+    fn write_reflogs(updates: &[RefUpdate], locked_refs: &[LockedRef], path_handler: &PathHandler) -> Result<(), Box<dyn Error>> {
+        for (update, locked_ref) in updates.iter().zip(locked_refs) {
+            match (&update.new_value, &update.message) {
+                (None, message) => {
+                    if let Some(new_ref_name) = &update.renamed_to {
+                        reflog::rename(&update.ref_name, new_ref_name, path_handler)?;
+                        if let (Some(message), Some(previous_value)) = (message, &locked_ref.previous_value) {
+                            reflog::append(new_ref_name, previous_value.trim(), previous_value.trim(), message, path_handler)?;
+                        }
+                    }
+                }
+                (Some(new_value), Some(message)) => {
+                    let previous_value = locked_ref.previous_value.clone().unwrap_or_default();
+                    reflog::log_ref_update(&update.ref_name, previous_value.trim(), new_value, message, path_handler)?;
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Creates the lock file of a ref, which fails if another writer holds it
+    fn create_lock_file(lock_path: &str) -> io::Result<fs::File> {
+        fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(lock_path)
+            .map_err(|error| match error.kind() {
+                io::ErrorKind::AlreadyExists => io::Error::new(
+                    io::ErrorKind::Other,
+                    format!("fatal: Unable to create '{}': File exists. Another process seems to be updating this ref.", lock_path),
+                ),
+                _ => error,
+            })
+    }
+
+    /// Removes the loose file of a ref that was packed, if it still has the packed value. The ref is locked
+    /// while it's checked and removed, so an update that happens meanwhile isn't lost. Returns whether the
+    /// loose file was removed; a ref locked by another writer is left as it is.
+    fn prune_loose_ref(ref_name: &str, packed_value: &str, path_handler: &PathHandler) -> Result<bool, Box<dyn Error>> {
+        let path = path_handler.get_relative_path(&get_ref_path(ref_name));
+        let lock_path = format!("{}{}", path, LOCK_FILE_EXTENSION);
+        if Self::create_lock_file(&lock_path).is_err() {
+            return Ok(false);
+        }
+        let result = match fs::read_to_string(&path) {
+            Ok(content) if content.trim() == packed_value => fs::remove_file(&path).map(|_| true),
+            _ => Ok(false),
+        };
+        let _ = fs::remove_file(&lock_path);
+        if let Ok(true) = result {
+            remove_empty_ref_directories(&path);
+        }
+        Ok(result?)
+    }
+
+    /// Locks every ref not in `locked_refs` yet, checks its current value and writes its new value to the lock
+    /// file. The refs locked so far are left in `locked_refs` so they can be released.
+    fn lock_and_write(updates: &[RefUpdate], locked_refs: &mut Vec<LockedRef>, path_handler: &PathHandler) -> Result<(), Box<dyn Error>> {
+        for update in &updates[locked_refs.len()..] {
+            if updates[..locked_refs.len()].iter().any(|locked| locked.ref_name == update.ref_name) {
+                return Err(Box::new(io::Error::new(
+                    io::ErrorKind::Other,
+                    format!("fatal: multiple updates for ref '{}' not allowed", update.ref_name),
+                )));
+            }
+            let path = path_handler.get_relative_path(&get_ref_path(&update.ref_name));
+            let lock_path = format!("{}{}", path, LOCK_FILE_EXTENSION);
+            if let Some(parent) = Path::new(&path).parent() {
+                fs::create_dir_all(parent)?;
+            }
+            let mut lock_file = Self::create_lock_file(&lock_path)?;
+            let previous_value = fs::read_to_string(&path).ok().or_else(|| {
+                read_packed_refs(path_handler)
+                    .ok()?
+                    .into_iter()
+                    .find(|packed_ref| packed_ref.name == update.ref_name)
+                    .map(|packed_ref| packed_ref.hash)
+            });
+            locked_refs.push(LockedRef { path, lock_path, previous_value: previous_value.clone() });
+
+            if let Some(expected_value) = &update.expected_value {
+                let current_value = previous_value.as_deref().map(str::trim).unwrap_or_default();
+                let matches = if expected_value == ZERO_HASH || expected_value.is_empty() {
+                    current_value.is_empty()
+                } else {
+                    current_value == expected_value
+                };
+                if !matches {
+                    return Err(Box::new(io::Error::new(
+                        io::ErrorKind::Other,
+                        format!(
+                            "fatal: cannot lock ref '{}': is at '{}' but expected '{}'",
+                            update.ref_name, current_value, expected_value
+                        ),
+                    )));
+                }
+            }
+            if let Some(new_value) = &update.new_value {
+                lock_file.write_all(new_value.as_bytes())?;
+                lock_file.sync_all()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Moves every lock file over its ref, or removes the ref for deletions. If one of them fails,
+    /// the refs already replaced get their previous value back.
+    fn rename_locks(updates: &[RefUpdate], locked_refs: &[LockedRef]) -> Result<(), Box<dyn Error>> {
+        for (index, (update, locked_ref)) in updates.iter().zip(locked_refs).enumerate() {
+            let result = match update.new_value {
+                Some(_) => fs::rename(&locked_ref.lock_path, &locked_ref.path),
+                None => match fs::remove_file(&locked_ref.path) {
+                    Err(error) if error.kind() != io::ErrorKind::NotFound => Err(error),
+                    _ => {
+                        remove_empty_ref_directories(&locked_ref.path);
+                        fs::remove_file(&locked_ref.lock_path)
+                    }
+                },
+            };
+            if let Err(error) = result {
+                for restored_ref in &locked_refs[..index] {
+                    let _ = match &restored_ref.previous_value {
+                        Some(previous_value) => fs::write(&restored_ref.path, previous_value),
+                        None => fs::remove_file(&restored_ref.path),
+                    };
+                }
+                return Err(Box::new(error));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns the path of a ref, given its name relative to the git directory (e.g. `refs/heads/master`)
+pub fn get_ref_path(ref_name: &str) -> String {
+    format!("{}/{}", GIT, ref_name)
+}
+
+/// Removes the directories left empty when a nested ref such as `refs/heads/feature/login` is deleted.
+/// The directories for each kind of ref, like `refs/heads`, are kept.
+fn remove_empty_ref_directories(ref_path: &str) {
+    let mut current_dir = Path::new(ref_path).parent();
+    while let Some(directory) = current_dir {
+        let is_ref_kind_directory = directory.parent().is_some_and(|parent| parent.ends_with(R_REFS));
+        if directory.ends_with(R_REFS) || is_ref_kind_directory || fs::remove_dir(directory).is_err() {
+            break;
+        }
+        current_dir = directory.parent();
+    }
+}
+
+/// Reads the refs stored in the packed-refs file. A `^<hash>` line after a ref is the object it peels to.
+pub fn read_packed_refs(path_handler: &PathHandler) -> Result<Vec<PackedRef>, Box<dyn Error>> {
+    let content = match fs::read_to_string(path_handler.get_relative_path(PACKED_REFS_FILE)) {
+        Ok(content) => content,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(error) => return Err(Box::new(error)),
+    };
+    let mut packed_refs: Vec<PackedRef> = Vec::new();
+    for line in content.lines() {
+        if line.starts_with('#') || line.trim().is_empty() {
+            continue;
+        }
+        if let Some(peeled) = line.strip_prefix('^') {
+            if let Some(last_ref) = packed_refs.last_mut() {
+                last_ref.peeled = Some(peeled.trim().to_string());
+            }
+            continue;
+        }
+        let (hash, name) = line.split_once(' ').ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("fatal: unexpected line in packed-refs: '{}'", line))
+        })?;
+        packed_refs.push(PackedRef { name: name.trim().to_string(), hash: hash.to_string(), peeled: None });
+    }
+    Ok(packed_refs)
+}
+
+/// Returns the content of a packed-refs file with the given refs, sorted by name
+fn format_packed_refs(packed_refs: &[PackedRef]) -> String {
+    let mut sorted_refs: Vec<&PackedRef> = packed_refs.iter().collect();
+    sorted_refs.sort_by(|first, second| first.name.cmp(&second.name));
+    let mut content = format!("{}\n", PACKED_REFS_HEADER);
+    for packed_ref in sorted_refs {
+        content.push_str(&format!("{} {}\n", packed_ref.hash, packed_ref.name));
+        if let Some(peeled) = &packed_ref.peeled {
+            content.push_str(&format!("^{}\n", peeled));
+        }
+    }
+    content
+}
+
+/// Reads the content of a ref, given its name relative to the git directory. Loose refs are preferred over
+/// packed ones. Returns None if the ref doesn't exist or is a branch without commits yet.
+pub fn read_ref(ref_name: &str, path_handler: &PathHandler) -> Option<String> {
+    match fs::read_to_string(path_handler.get_relative_path(&get_ref_path(ref_name))) {
+        Ok(content) => Some(content.trim().to_string()).filter(|content| !content.is_empty()),
+        Err(_) => read_packed_refs(path_handler)
+            .ok()?
+            .into_iter()
+            .find(|packed_ref| packed_ref.name == ref_name)
+            .map(|packed_ref| packed_ref.hash),
+    }
+}
+
+/// Returns true if the ref exists, either as a loose file, even an empty one, or in the packed-refs file
+pub fn ref_exists(ref_name: &str, path_handler: &PathHandler) -> bool {
+    Path::new(&path_handler.get_relative_path(&get_ref_path(ref_name))).is_file()
+        || read_packed_refs(path_handler)
+            .map(|packed_refs| packed_refs.iter().any(|packed_ref| packed_ref.name == ref_name))
+            .unwrap_or(false)
+}
+
+/// Lists the refs whose names start with the prefix (e.g. `refs/heads/`), loose and packed, sorted by name.
+/// Loose refs are found in nested directories too, so `refs/heads/feature/login` is listed. Branches without
+/// commits are listed with an empty hash.
+pub fn list_refs(prefix: &str, path_handler: &PathHandler) -> Result<Vec<(String, String)>, Box<dyn Error>> {
+    let git_path = path_handler.get_relative_path(&format!("{}/", GIT));
+    let directory = path_handler.get_relative_path(&get_ref_path(prefix.trim_end_matches('/')));
+    let mut refs: Vec<(String, String)> = Vec::new();
+    for ref_file in helpers::get_files_in_directory_recursively(&directory)? {
+        let ref_name = match ref_file.strip_prefix(&git_path) {
+            Some(ref_name) if ref_name.starts_with(prefix) && !ref_name.ends_with(LOCK_FILE_EXTENSION) => ref_name,
+            _ => continue,
+        };
+        refs.push((ref_name.to_string(), fs::read_to_string(&ref_file)?.trim().to_string()));
+    }
+    let loose_names: HashSet<String> = refs.iter().map(|(name, _hash)| name.clone()).collect();
+    for packed_ref in read_packed_refs(path_handler)? {
+        if packed_ref.name.starts_with(prefix) && !loose_names.contains(&packed_ref.name) {
+            refs.push((packed_ref.name, packed_ref.hash));
+        }
+    }
+    refs.sort();
+    Ok(refs)
+}
+
+/// Checks a full ref name against git's `check-ref-format` rules: it has at least two components separated
+/// by '/', no component starts with '.' or ends with `.lock`, it has no `..`, `@{`, `//`, control characters,
+/// spaces or any of `~^:?*[\`, doesn't start or end with '/', doesn't end with '.' and isn't `@`.
+pub fn check_ref_format(ref_name: &str) -> bool {
+    let forbidden_char = |c: char| c.is_ascii_control() || " ~^:?*[\\".contains(c);
+    ref_name != "@"
+        && ref_name.contains('/')
+        && !ref_name.starts_with('/')
+        && !ref_name.ends_with('/')
+        && !ref_name.ends_with('.')
+        && !ref_name.contains("..")
+        && !ref_name.contains("@{")
+        && !ref_name.contains("//")
+        && !ref_name.contains(forbidden_char)
+        && ref_name
+            .split('/')
+            .all(|component| !component.starts_with('.') && !component.ends_with(LOCK_FILE_EXTENSION))
+}
+
+/// Moves loose refs into the packed-refs file: every ref with --all, or only tags otherwise. Refs that point to
+/// annotated tags get the commit they peel to. Unless `prune` is false, the loose files are removed.
+/// Returns the names of the refs that were packed.
+pub fn pack_refs(all: bool, prune: bool, path_handler: &PathHandler) -> Result<Vec<String>, Box<dyn Error>> {
+    let prefix = if all { "refs/" } else { "refs/tags/" };
+    let packed_refs_path = path_handler.get_relative_path(PACKED_REFS_FILE);
+    let lock_path = format!("{}{}", packed_refs_path, LOCK_FILE_EXTENSION);
+    let mut lock_file = fs::OpenOptions::new().write(true).create_new(true).open(&lock_path).map_err(|error| {
+        io::Error::new(io::ErrorKind::Other, format!("fatal: Unable to create '{}': {}", lock_path, error))
+    })?;
+
+    let result = (|| -> Result<Vec<(String, String)>, Box<dyn Error>> {
+        let mut packed_refs = read_packed_refs(path_handler)?;
+        let loose_refs: Vec<(String, String)> = list_refs(prefix, path_handler)?
+            .into_iter()
+            .filter(|(name, hash)| !hash.is_empty() && Path::new(&path_handler.get_relative_path(&get_ref_path(name))).is_file())
+            .collect();
+        for (name, hash) in &loose_refs {
+            let peeled = match helpers::read_object_to_string(hash.clone(), path_handler)?.0 {
+                ObjectType::Tag => Some(revision::peel_object(hash, None, path_handler)?),
+                _ => None,
+            };
+            packed_refs.retain(|packed_ref| packed_ref.name != *name);
+            packed_refs.push(PackedRef { name: name.clone(), hash: hash.clone(), peeled });
+        }
+        lock_file.write_all(format_packed_refs(&packed_refs).as_bytes())?;
+        lock_file.sync_all()?;
+        fs::rename(&lock_path, &packed_refs_path)?;
+        Ok(loose_refs)
+    })();
+    let loose_refs = match result {
+        Ok(loose_refs) => loose_refs,
+        Err(error) => {
+            let _ = fs::remove_file(&lock_path);
+            return Err(error);
+        }
+    };
+
+    if prune {
+        // a loose ref that changed while packing is newer than its packed copy, so it's kept
+        for (name, hash) in &loose_refs {
+            RefTransaction::prune_loose_ref(name, hash, path_handler)?;
+        }
+    }
+    Ok(loose_refs.into_iter().map(|(name, _hash)| name).collect())
+}
+
+/// Updates a single ref through a transaction, checking its old value if one is expected and recording the
+/// message in its reflog if there's one
+pub fn update_ref(
+    ref_name: &str,
+    new_value: &str,
+    expected_value: Option<&str>,
+    message: Option<&str>,
+    path_handler: &PathHandler
+) -> Result<(), Box<dyn Error>> {
+    let mut transaction = RefTransaction::new();
+    transaction.update(ref_name, new_value, expected_value, message);
+    transaction.commit(path_handler)
+}
+
+/// Deletes a single ref through a transaction
+pub fn delete_ref(ref_name: &str, expected_value: Option<&str>, path_handler: &PathHandler) -> Result<(), Box<dyn Error>> {
+    let mut transaction = RefTransaction::new();
+    transaction.delete(ref_name, expected_value);
+    transaction.commit(path_handler)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::git_commands::{Command, Init};
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_ref_transactions() {
+        crate::commands::config::ignore_user_config();
+        let temp_dir = tempdir().unwrap();
+        let path_handler = PathHandler::new(temp_dir.path().to_string_lossy().to_string());
+        Init::new().execute(None, &path_handler).unwrap();
+        let (first_hash, second_hash) = ("a".repeat(40), "b".repeat(40));
+        let read_ref = |ref_name: &str| fs::read_to_string(path_handler.get_relative_path(&get_ref_path(ref_name))).ok();
+
+        update_ref("refs/heads/feature", &first_hash, Some(ZERO_HASH), Some("branch: created"), &path_handler).unwrap();
+        assert_eq!(read_ref("refs/heads/feature"), Some(first_hash.clone()));
+        assert_eq!(reflog::read_entries("refs/heads/feature", &path_handler).unwrap().len(), 1);
+
+        // a wrong old value makes the whole transaction fail without touching any ref
+        let mut transaction = RefTransaction::new();
+        transaction
+            .update("refs/heads/other", &first_hash, None, None)
+            .update("refs/heads/feature", &second_hash, Some(&second_hash), None);
+        let error = transaction.commit(&path_handler).unwrap_err();
+        assert!(error.to_string().contains("cannot lock ref 'refs/heads/feature'"));
+        assert_eq!(read_ref("refs/heads/other"), None);
+        assert_eq!(read_ref("refs/heads/feature"), Some(first_hash.clone()));
+
+        // a lock held by another writer is respected
+        let lock_path = format!("{}{}", path_handler.get_relative_path(&get_ref_path("refs/heads/feature")), LOCK_FILE_EXTENSION);
+        fs::write(&lock_path, "").unwrap();
+        let error = update_ref("refs/heads/feature", &second_hash, None, None, &path_handler).unwrap_err();
+        assert!(error.to_string().contains("File exists"));
+        fs::remove_file(&lock_path).unwrap();
+
+        let mut transaction = RefTransaction::new();
+        transaction
+            .update("refs/heads/other", &second_hash, Some(ZERO_HASH), None)
+            .update("refs/heads/feature", &second_hash, Some(&first_hash), None);
+        transaction.commit(&path_handler).unwrap();
+        assert_eq!(read_ref("refs/heads/other"), Some(second_hash.clone()));
+        assert_eq!(read_ref("refs/heads/feature"), Some(second_hash.clone()));
+        assert!(fs::metadata(&lock_path).is_err());
+
+        delete_ref("refs/heads/feature", Some(&second_hash), &path_handler).unwrap();
+        assert_eq!(read_ref("refs/heads/feature"), None);
+        assert!(reflog::read_entries("refs/heads/feature", &path_handler).unwrap().is_empty());
+
+        // a packed ref is only removed from packed-refs while holding its lock
+        let packed_refs_path = path_handler.get_relative_path(PACKED_REFS_FILE);
+        fs::write(&packed_refs_path, format!("{} refs/tags/v1\n{} refs/tags/v2\n", first_hash, second_hash)).unwrap();
+        let packed_refs_lock_path = format!("{}{}", packed_refs_path, LOCK_FILE_EXTENSION);
+        fs::write(&packed_refs_lock_path, "").unwrap();
+        let error = delete_ref("refs/tags/v1", None, &path_handler).unwrap_err();
+        assert!(error.to_string().contains("File exists"));
+        assert_eq!(read_packed_refs(&path_handler).unwrap().len(), 2);
+        fs::remove_file(&packed_refs_lock_path).unwrap();
+
+        delete_ref("refs/tags/v1", None, &path_handler).unwrap();
+        let packed_refs = read_packed_refs(&path_handler).unwrap();
+        assert_eq!(packed_refs.len(), 1);
+        assert_eq!(packed_refs[0].name, "refs/tags/v2");
+        assert!(fs::metadata(&packed_refs_lock_path).is_err());
+    }
+
+    #[test]
+    fn test_refspec_mapping() {
+        let refspec = Refspec::parse("+refs/heads/*:refs/remotes/origin/*").unwrap();
+        assert_eq!(refspec, Refspec::default_fetch("origin"));
+        assert_eq!(refspec.map_to_destination("refs/heads/feature/login").as_deref(), Some("refs/remotes/origin/feature/login"));
+        assert_eq!(refspec.map_to_destination("refs/tags/v1"), None);
+        assert_eq!(refspec.map_to_source("refs/remotes/origin/main").as_deref(), Some("refs/heads/main"));
+
+        let refspec = Refspec::parse("refs/heads/main:refs/remotes/mirror/trunk").unwrap();
+        assert!(!refspec.force);
+        assert_eq!(refspec.map_to_destination("refs/heads/main").as_deref(), Some("refs/remotes/mirror/trunk"));
+        assert_eq!(refspec.to_string(), "refs/heads/main:refs/remotes/mirror/trunk");
+        assert!(Refspec::parse("refs/heads/*:refs/remotes/origin/main").is_none());
+        assert!(Refspec::parse("refs/heads/main").is_none());
+    }
+}
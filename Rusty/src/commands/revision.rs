@@ -0,0 +1,270 @@
+use std::{collections::HashSet, error::Error, io};
+
+use super::git_commands::PathHandler;
+use super::helpers;
+use super::packfile;
+use super::reflog;
+use super::refs;
+use super::structs::{Head, ObjectType};
+use crate::constants::{HEAD, HEAD_FILE, MIN_ABBREVIATED_HASH_LENGTH};
+
+/// Returns an error saying the given revision can't be resolved
+fn unknown_revision(revision: &str) -> Box<dyn Error> {
+    Box::new(io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("Error: Unknown revision '{}'", revision),
+    ))
+}
+
+/// Reads the hash a ref points to, given its name relative to the git directory (e.g. `refs/heads/master`).
+/// Returns None if the ref doesn't exist or doesn't point to anything yet.
+pub fn read_ref(ref_name: &str, path_handler: &PathHandler) -> Option<String> {
+    refs::read_ref(ref_name, path_handler).filter(|hash| helpers::is_object_hash(hash))
+}
+
+/// Returns the commit HEAD points to, following the current branch unless HEAD is detached
+fn resolve_head(path_handler: &PathHandler) -> Option<String> {
+    let head_content = helpers::read_file_content(&path_handler.get_relative_path(HEAD_FILE)).ok()?;
+    match head_content.trim().strip_prefix("ref: ") {
+        Some(ref_name) => read_ref(ref_name, path_handler),
+        None => Some(head_content.trim().to_string()).filter(|hash| helpers::is_object_hash(hash)),
+    }
+}
+
+/// Returns the only object whose hash starts with the given prefix, looking at loose and packed objects.
+/// Fails if more than one object matches.
+pub fn expand_abbreviated_hash(prefix: &str, path_handler: &PathHandler) -> Result<Option<String>, Box<dyn Error>> {
+    let prefix = prefix.to_lowercase();
+    if prefix.len() < MIN_ABBREVIATED_HASH_LENGTH
+        || prefix.len() > 40
+        || !prefix.chars().all(|c| c.is_ascii_hexdigit())
+    {
+        return Ok(None);
+    }
+
+    let mut candidates: Vec<String> = helpers::get_loose_objects(path_handler)?
+        .into_iter()
+        .filter(|hash| hash.starts_with(&prefix))
+        .collect();
+    for pack_name in packfile::list_indexed_packs(path_handler)? {
+        let index_path = path_handler.get_relative_path(&packfile::get_index_path(&pack_name));
+        let index = helpers::read_file_content_to_bytes(&index_path)?;
+        candidates.extend(
+            packfile::read_index_hashes(&index)?
+                .into_iter()
+                .filter(|hash| hash.starts_with(&prefix)),
+        );
+    }
+    candidates.sort();
+    candidates.dedup();
+
+    match candidates.len() {
+        0 => Ok(None),
+        1 => Ok(candidates.pop()),
+        _ => Err(Box::new(io::Error::new(
+            io::ErrorKind::Other,
+            format!("Error: Short object ID {} is ambiguous", prefix),
+        ))),
+    }
+}
+
+/// Resolves a name without suffixes: HEAD, a ref name (looked up the way git does it, so `v1.0`
+/// finds `refs/tags/v1.0` and `master` finds `refs/heads/master`) or a full or abbreviated hash.
+fn resolve_name(name: &str, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
+    if name == HEAD || name == "@" {
+        return resolve_head(path_handler).ok_or_else(|| unknown_revision(name));
+    }
+
+    let candidate_refs = [
+        name.to_string(),
+        format!("refs/{}", name),
+        format!("refs/tags/{}", name),
+        format!("refs/heads/{}", name),
+        format!("refs/remotes/{}", name),
+        format!("refs/remotes/{}/HEAD", name),
+    ];
+    for candidate_ref in &candidate_refs {
+        if let Some(hash) = read_ref(candidate_ref, path_handler) {
+            return Ok(hash);
+        }
+    }
+
+    if helpers::is_object_hash(name) {
+        return Ok(name.to_lowercase());
+    }
+    expand_abbreviated_hash(name, path_handler)?.ok_or_else(|| unknown_revision(name))
+}
+
+/// Resolves `<branch>@{upstream}`, or `@{u}` for short, to the commit of the branch the given one tracks. With
+/// no name, the current branch is used.
+fn resolve_upstream(name: &str, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
+    let branch_name = if name.is_empty() || name == "@" || name == HEAD {
+        Head::get_current_branch_name(path_handler)?
+    } else {
+        name.trim_start_matches("refs/heads/").to_string()
+    };
+    let upstream_ref = helpers::get_upstream_ref(&branch_name, path_handler)?.ok_or_else(|| {
+        io::Error::new(io::ErrorKind::NotFound, format!("fatal: no upstream configured for branch '{}'", branch_name))
+    })?;
+    read_ref(&upstream_ref, path_handler).ok_or_else(|| {
+        Box::new(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("fatal: upstream branch '{}' of '{}' does not exist", helpers::shorten_ref_name(&upstream_ref), branch_name),
+        )) as Box<dyn Error>
+    })
+}
+
+/// Resolves `<name>@{<selector>}` with the reflog of the named ref. With no name, the reflog of the current
+/// branch is used, or the one of HEAD if it's detached. The `u` and `upstream` selectors give the upstream branch.
+fn resolve_reflog_selector(name: &str, selector: &str, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
+    if selector.eq_ignore_ascii_case("u") || selector.eq_ignore_ascii_case("upstream") {
+        return resolve_upstream(name, path_handler);
+    }
+    let ref_name = if name.is_empty() || name == "@" {
+        let head_content = helpers::read_file_content(&path_handler.get_relative_path(HEAD_FILE))?;
+        head_content.trim().strip_prefix("ref: ").unwrap_or(HEAD).to_string()
+    } else {
+        reflog::find_log_ref(name, path_handler).ok_or_else(|| unknown_revision(name))?
+    };
+    reflog::resolve_entry(&ref_name, selector, path_handler)
+}
+
+/// Follows an object until one of the wanted type is found. Tags are followed to the object they
+/// point to and commits to their tree. With no wanted type, tags are followed until a non-tag object.
+pub fn peel_object(hash: &str, wanted_type: Option<ObjectType>, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
+    let mut current_hash = hash.to_string();
+    loop {
+        let (object_type, content, _size) = helpers::read_object_to_string(current_hash.clone(), path_handler)?;
+        match &wanted_type {
+            Some(wanted_type) if *wanted_type == object_type => return Ok(current_hash),
+            None if object_type != ObjectType::Tag => return Ok(current_hash),
+            _ => {}
+        }
+
+        let next_key = match (&object_type, &wanted_type) {
+            (ObjectType::Tag, _) => "object",
+            (ObjectType::Commit, Some(ObjectType::Tree)) => "tree",
+            _ => {
+                let wanted_name = wanted_type.map_or(String::new(), |wanted_type| wanted_type.to_string());
+                return Err(Box::new(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Error: {} {} can't be peeled to {}", object_type, current_hash, wanted_name),
+                )));
+            }
+        };
+        current_hash = get_header_field(&content, next_key).ok_or(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Error: {} {} is missing its {} line", object_type, current_hash, next_key),
+        ))?;
+    }
+}
+
+/// Returns the value of the first header line of a commit or tag object with the given key
+pub fn get_header_field(object_content: &str, key: &str) -> Option<String> {
+    object_content
+        .lines()
+        .take_while(|line| !line.is_empty())
+        .find_map(|line| line.strip_prefix(&format!("{} ", key)))
+        .map(String::from)
+}
+
+/// Returns the parents of the given commit, in order
+pub fn get_commit_parents(commit_hash: &str, path_handler: &PathHandler) -> Result<Vec<String>, Box<dyn Error>> {
+    let (_object_type, content, _size) = helpers::read_object_to_string(commit_hash.to_string(), path_handler)?;
+    Ok(content
+        .lines()
+        .take_while(|line| !line.is_empty())
+        .filter_map(|line| line.strip_prefix("parent "))
+        .map(String::from)
+        .collect())
+}
+
+/// Returns the given commits and all their ancestors
+pub fn get_ancestors(commits: &[String], path_handler: &PathHandler) -> Result<HashSet<String>, Box<dyn Error>> {
+    let mut ancestors = HashSet::new();
+    let mut pending = commits.to_vec();
+    while let Some(commit) = pending.pop() {
+        if ancestors.insert(commit.clone()) {
+            pending.extend(get_commit_parents(&commit, path_handler)?);
+        }
+    }
+    Ok(ancestors)
+}
+
+/// Counts the commits reachable from `local` but not from `upstream` and the ones reachable from `upstream` but
+/// not from `local`, that is, how far ahead and behind its upstream a branch is
+pub fn count_ahead_behind(local: &str, upstream: &str, path_handler: &PathHandler) -> Result<(usize, usize), Box<dyn Error>> {
+    let local_ancestors = get_ancestors(&[local.to_string()], path_handler)?;
+    let upstream_ancestors = get_ancestors(&[upstream.to_string()], path_handler)?;
+    Ok((
+        local_ancestors.difference(&upstream_ancestors).count(),
+        upstream_ancestors.difference(&local_ancestors).count(),
+    ))
+}
+
+/// Reads the number that follows a `^` or `~`. No number means 1.
+fn parse_suffix_number(suffix: &str) -> (usize, &str) {
+    let digits_end = suffix.find(|c: char| !c.is_ascii_digit()).unwrap_or(suffix.len());
+    let number = suffix[..digits_end].parse().unwrap_or(1);
+    (number, &suffix[digits_end..])
+}
+
+/// Resolves a revision expression to the hash of the object it names. Supports HEAD, ref names,
+/// full and abbreviated hashes, reflog entries (`<ref>@{<n>}` or `<ref>@{<date>}`) and upstreams (`<branch>@{u}`), followed by any number of `^<n>` (n-th parent), `~<n>` (n-th first-parent
+/// ancestor), `^{}` (peel tags) and `^{<type>}` (peel to commit, tree, blob or tag) suffixes.
+pub fn resolve_revision(revision: &str, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
+    let revision = revision.trim();
+    let name_end = revision.find(['^', '~']).unwrap_or(revision.len());
+    let name = &revision[..name_end];
+    let mut hash = match name.find("@{") {
+        Some(selector_start) if name.ends_with('}') => {
+            resolve_reflog_selector(&name[..selector_start], &name[selector_start + 2..name.len() - 1], path_handler)?
+        }
+        _ => resolve_name(name, path_handler)?,
+    };
+    let mut suffixes = &revision[name_end..];
+
+    while !suffixes.is_empty() {
+        if let Some(rest) = suffixes.strip_prefix("^{") {
+            let closing = rest.find('}').ok_or_else(|| unknown_revision(revision))?;
+            let wanted_type = match &rest[..closing] {
+                "" => None,
+                type_name => Some(ObjectType::new(type_name).ok_or_else(|| unknown_revision(revision))?),
+            };
+            hash = peel_object(&hash, wanted_type, path_handler)?;
+            suffixes = &rest[closing + 1..];
+        } else if let Some(rest) = suffixes.strip_prefix('^') {
+            let (parent_number, rest) = parse_suffix_number(rest);
+            let commit = peel_object(&hash, Some(ObjectType::Commit), path_handler)?;
+            hash = if parent_number == 0 {
+                commit
+            } else {
+                get_commit_parents(&commit, path_handler)?
+                    .get(parent_number.wrapping_sub(1))
+                    .cloned()
+                    .ok_or_else(|| unknown_revision(revision))?
+            };
+            suffixes = rest;
+        } else if let Some(rest) = suffixes.strip_prefix('~') {
+            let (generations, rest) = parse_suffix_number(rest);
+            hash = peel_object(&hash, Some(ObjectType::Commit), path_handler)?;
+            for _ in 0..generations {
+                hash = get_commit_parents(&hash, path_handler)?
+                    .first()
+                    .cloned()
+                    .ok_or_else(|| unknown_revision(revision))?;
+            }
+            suffixes = rest;
+        } else {
+            return Err(unknown_revision(revision));
+        }
+    }
+
+    Ok(hash)
+}
+
+/// Resolves a revision and peels it to a commit
+pub fn resolve_commit(revision: &str, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
+    let hash = resolve_revision(revision, path_handler)?;
+    peel_object(&hash, Some(ObjectType::Commit), path_handler)
+}
@@ -0,0 +1,596 @@
+use std::{error::Error, fs, io, io::Write, process::Stdio};
+
+use crypto::digest::Digest;
+use crypto::ed25519;
+use crypto::sha2::{Sha256, Sha512};
+
+use super::config::Config;
+use super::git_commands::PathHandler;
+use super::helpers;
+use super::structs::ObjectType;
+use crate::constants::{DEFAULT_GPG_PROGRAM, DEFAULT_SSH_PROGRAM, PGP_SIGNATURE_BEGIN, SIGNATURE_HEADER, SSH_SIGNATURE_BEGIN, SSH_SIGNATURE_END, SSH_SIGNATURE_NAMESPACE};
+
+const SSHSIG_MAGIC: &[u8] = b"SSHSIG";
+const SSHSIG_VERSION: u32 = 1;
+const ED25519_KEY_TYPE: &str = "ssh-ed25519";
+
+/// Something able to produce a detached, armored signature for the content of an object
+pub trait Signer {
+    fn sign(&self, payload: &str) -> Result<String, Box<dyn Error>>;
+}
+
+/// Runs a program with the given input and returns whether it succeeded, its output and its error output
+fn run_program(program: &str, args: &[&str], input: &str) -> Result<(bool, String, String), Box<dyn Error>> {
+    let mut process = std::process::Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|error| io::Error::new(io::ErrorKind::Other, format!("error: cannot run {}: {}", program, error)))?;
+    if let Some(mut stdin) = process.stdin.take() {
+        stdin.write_all(input.as_bytes())?;
+    }
+    let output = process.wait_with_output()?;
+    Ok((
+        output.status.success(),
+        String::from_utf8_lossy(&output.stdout).to_string(),
+        String::from_utf8_lossy(&output.stderr).to_string(),
+    ))
+}
+
+/// Signs with `ssh-keygen -Y sign` and the key at the given path, which may also be a public key held by an agent
+pub struct SshKeygenSigner {
+    program: String,
+    key_path: String,
+}
+
+impl SshKeygenSigner {
+    pub fn new(program: &str, key_path: &str) -> Self {
+        SshKeygenSigner {
+            program: program.to_string(),
+            key_path: key_path.to_string(),
+        }
+    }
+}
+
+impl Signer for SshKeygenSigner {
+    fn sign(&self, payload: &str) -> Result<String, Box<dyn Error>> {
+        let (success, signature, error_output) = run_program(
+            &self.program,
+            &["-Y", "sign", "-n", SSH_SIGNATURE_NAMESPACE, "-f", &self.key_path],
+            payload,
+        )?;
+        if !success || !signature.contains(SSH_SIGNATURE_BEGIN) {
+            return Err(Box::new(io::Error::new(
+                io::ErrorKind::Other,
+                format!("{}\nerror: ssh-keygen failed to sign the data", error_output.trim_end()),
+            )));
+        }
+        Ok(signature)
+    }
+}
+
+/// Signs with `gpg -bsa`, using the given key or the default one
+pub struct GpgSigner {
+    program: String,
+    key_id: Option<String>,
+}
+
+impl GpgSigner {
+    pub fn new(program: &str, key_id: Option<&str>) -> Self {
+        GpgSigner {
+            program: program.to_string(),
+            key_id: key_id.map(String::from),
+        }
+    }
+}
+
+impl Signer for GpgSigner {
+    fn sign(&self, payload: &str) -> Result<String, Box<dyn Error>> {
+        let mut args = vec!["--status-fd=2", "-bsa"];
+        if let Some(key_id) = &self.key_id {
+            args.extend(["-u", key_id.as_str()]);
+        }
+        let (success, signature, error_output) = run_program(&self.program, &args, payload)?;
+        if !success || !error_output.contains("[GNUPG:] SIG_CREATED ") {
+            return Err(Box::new(io::Error::new(
+                io::ErrorKind::Other,
+                format!("{}\nerror: gpg failed to sign the data", error_output.trim_end()),
+            )));
+        }
+        Ok(signature)
+    }
+}
+
+/// Returns the signer configured for the repository: `ssh-keygen` with the given key or `user.signingKey` if
+/// `gpg.format` is `ssh`, otherwise gpg with that key or its default one.
+pub fn get_signer(signing_key: Option<&str>, path_handler: &PathHandler) -> Result<Box<dyn Signer>, Box<dyn Error>> {
+    let config = Config::load(path_handler)?;
+    match config.get("gpg.format").as_deref() {
+        Some("ssh") => {
+            let key_path = signing_key.map(String::from).or_else(|| config.get_path("user.signingKey")).ok_or(io::Error::new(
+                io::ErrorKind::Other,
+                "error: user.signingKey needs to be set for ssh signing",
+            ))?;
+            let program = config.get("gpg.ssh.program").unwrap_or_else(|| DEFAULT_SSH_PROGRAM.to_string());
+            Ok(Box::new(SshKeygenSigner::new(&program, &key_path)))
+        }
+        None | Some("openpgp") => {
+            let program = config
+                .get("gpg.openpgp.program")
+                .or_else(|| config.get("gpg.program"))
+                .unwrap_or_else(|| DEFAULT_GPG_PROGRAM.to_string());
+            let key_id = signing_key.map(String::from).or_else(|| config.get("user.signingKey"));
+            Ok(Box::new(GpgSigner::new(&program, key_id.as_deref())))
+        }
+        Some(format) => Err(Box::new(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("error: invalid value for 'gpg.format': '{}'", format),
+        ))),
+    }
+}
+
+/// Tells whether an object is signed. The sign flags decide it when one was given, otherwise the boolean config
+/// variable does, e.g. `commit.gpgSign`.
+pub fn is_signing_enabled(sign_flag: Option<bool>, config_name: &str, path_handler: &PathHandler) -> Result<bool, Box<dyn Error>> {
+    match sign_flag {
+        Some(sign_flag) => Ok(sign_flag),
+        None => Ok(Config::load(path_handler)?.get_bool(config_name)?.unwrap_or(false)),
+    }
+}
+
+/// Signs with an ed25519 key built from a fixed seed, without any external program
+#[cfg(test)]
+pub struct TestKeySigner {
+    secret_key: [u8; 64],
+    public_key: [u8; 32],
+}
+
+#[cfg(test)]
+impl TestKeySigner {
+    const LINE_LENGTH: usize = 70;
+
+    pub fn new(seed: [u8; 32]) -> Self {
+        let (secret_key, public_key) = ed25519::keypair(&seed);
+        TestKeySigner { secret_key, public_key }
+    }
+
+    /// Returns the public key as written in an allowed signers file: `ssh-ed25519 <base64 key>`
+    pub fn public_key_line(&self) -> String {
+        format!("{} {}", ED25519_KEY_TYPE, helpers::encode_base64(&Self::ed25519_blob(&self.public_key)))
+    }
+
+    fn ed25519_blob(key: &[u8]) -> Vec<u8> {
+        let mut blob = Vec::new();
+        write_string(&mut blob, ED25519_KEY_TYPE.as_bytes());
+        write_string(&mut blob, key);
+        blob
+    }
+}
+
+#[cfg(test)]
+impl Signer for TestKeySigner {
+    fn sign(&self, payload: &str) -> Result<String, Box<dyn Error>> {
+        let signed_data = sshsig_signed_data(SSH_SIGNATURE_NAMESPACE, &[], "sha512", payload)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::Other, "error: unsupported hash algorithm"))?;
+        let signature = ed25519::signature(&signed_data, &self.secret_key);
+
+        let mut blob = SSHSIG_MAGIC.to_vec();
+        blob.extend(SSHSIG_VERSION.to_be_bytes());
+        write_string(&mut blob, &Self::ed25519_blob(&self.public_key));
+        write_string(&mut blob, SSH_SIGNATURE_NAMESPACE.as_bytes());
+        write_string(&mut blob, &[]);
+        write_string(&mut blob, b"sha512");
+        write_string(&mut blob, &Self::ed25519_blob(&signature));
+
+        let encoded = helpers::encode_base64(&blob);
+        let lines: Vec<&str> = encoded
+            .as_bytes()
+            .chunks(Self::LINE_LENGTH)
+            .map(|line| std::str::from_utf8(line).unwrap_or_default())
+            .collect();
+        Ok(format!("{}\n{}\n{}\n", SSH_SIGNATURE_BEGIN, lines.join("\n"), SSH_SIGNATURE_END))
+    }
+}
+
+/// Appends an SSH wire format string: its length as a big endian u32 followed by its bytes
+fn write_string(buffer: &mut Vec<u8>, bytes: &[u8]) {
+    buffer.extend((bytes.len() as u32).to_be_bytes());
+    buffer.extend_from_slice(bytes);
+}
+
+/// Reads an SSH wire format string from the start of the data, advancing past it
+fn read_string<'a>(data: &mut &'a [u8]) -> Result<&'a [u8], Box<dyn Error>> {
+    let invalid = || io::Error::new(io::ErrorKind::InvalidData, "error: invalid SSH signature");
+    let bytes: &'a [u8] = data;
+    if bytes.len() < 4 {
+        return Err(Box::new(invalid()));
+    }
+    let length = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize;
+    let string = bytes.get(4..4 + length).ok_or_else(invalid)?;
+    *data = &bytes[4 + length..];
+    Ok(string)
+}
+
+/// Returns the data an SSH signature actually signs: the preamble, namespace, reserved field, hash algorithm
+/// and the hash of the message. Returns None if the hash algorithm is neither sha256 nor sha512, the only ones
+/// signatures may use.
+fn sshsig_signed_data(namespace: &str, reserved: &[u8], hash_algorithm: &str, message: &str) -> Option<Vec<u8>> {
+    let mut digest: Box<dyn Digest> = match hash_algorithm {
+        "sha256" => Box::new(Sha256::new()),
+        "sha512" => Box::new(Sha512::new()),
+        _ => return None,
+    };
+    digest.input(message.as_bytes());
+    let mut hash = vec![0; digest.output_bytes()];
+    digest.result(&mut hash);
+
+    let mut data = SSHSIG_MAGIC.to_vec();
+    write_string(&mut data, namespace.as_bytes());
+    write_string(&mut data, reserved);
+    write_string(&mut data, hash_algorithm.as_bytes());
+    write_string(&mut data, &hash);
+    Some(data)
+}
+
+/// Adds the signature to the object content as a `gpgsig` header after the other headers, with the signature's
+/// lines after the first one indented by a space
+pub fn add_signature_header(content: &str, signature: &str) -> String {
+    let header = format!("{} {}\n", SIGNATURE_HEADER, signature.trim_end().replace('\n', "\n "));
+    match content.find("\n\n") {
+        Some(position) => format!("{}{}{}", &content[..=position], header, &content[position + 1..]),
+        None => format!("{}{}", content, header),
+    }
+}
+
+/// Splits a signed object into the content that was signed, which is the object without its `gpgsig` header,
+/// and the signature. Returns None for unsigned objects.
+pub fn split_signature(content: &str) -> Option<(String, String)> {
+    let mut payload = String::new();
+    let mut signature: Option<String> = None;
+    let mut in_headers = true;
+    let mut in_signature = false;
+    for line in content.split_inclusive('\n') {
+        if in_headers {
+            if in_signature {
+                if let (Some(continuation), Some(signature)) = (line.strip_prefix(' '), signature.as_mut()) {
+                    signature.push_str(continuation);
+                    continue;
+                }
+                in_signature = false;
+            }
+            if let Some(first_line) = line.strip_prefix(&format!("{} ", SIGNATURE_HEADER)) {
+                signature = Some(first_line.to_string());
+                in_signature = true;
+                continue;
+            }
+            in_headers = line != "\n";
+        }
+        payload.push_str(line);
+    }
+    signature.map(|signature| (payload, signature))
+}
+
+/// Splits a tag signed the way git signs tags, with the signature appended to its message, into the content that
+/// was signed and the signature. Returns None if the content doesn't end with a signature block.
+pub fn split_trailing_signature(content: &str) -> Option<(String, String)> {
+    let start = [PGP_SIGNATURE_BEGIN, SSH_SIGNATURE_BEGIN]
+        .iter()
+        .filter_map(|begin| content.rfind(&format!("\n{}", begin)).map(|position| position + 1))
+        .max()?;
+    let (payload, signature) = content.split_at(start);
+    Some((payload.to_string(), signature.to_string()))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SignatureStatus {
+    Good,
+    /// A valid signature made with a key that isn't trusted, e.g. an SSH key missing from the allowed signers or
+    /// an expired or revoked gpg key
+    Untrusted,
+    Bad,
+    /// The signature couldn't be checked, e.g. because the key isn't available
+    Unverifiable,
+}
+
+/// The result of checking a signature and a description of it for the user
+#[derive(Debug, Clone)]
+pub struct SignatureCheck {
+    pub status: SignatureStatus,
+    pub description: String,
+}
+
+impl SignatureCheck {
+    pub fn is_good(&self) -> bool {
+        self.status == SignatureStatus::Good
+    }
+}
+
+/// Checks the signature of an object's content, either an SSH signature against the allowed signers or a gpg one
+pub fn verify_signature(payload: &str, signature: &str, path_handler: &PathHandler) -> Result<SignatureCheck, Box<dyn Error>> {
+    if signature.starts_with(SSH_SIGNATURE_BEGIN) {
+        verify_ssh_signature(payload, signature, path_handler)
+    } else if signature.starts_with(PGP_SIGNATURE_BEGIN) {
+        verify_gpg_signature(payload, signature, path_handler)
+    } else {
+        Err(Box::new(io::Error::new(io::ErrorKind::InvalidData, "error: unknown signature format")))
+    }
+}
+
+/// Checks the signature of a commit or tag. Returns None if the object isn't signed.
+pub fn verify_object(hash: &str, path_handler: &PathHandler) -> Result<Option<SignatureCheck>, Box<dyn Error>> {
+    let (object_type, content, _size) = helpers::read_object_to_string(hash.to_string(), path_handler)?;
+    let signed_content = match split_signature(&content) {
+        None if object_type == ObjectType::Tag => split_trailing_signature(&content),
+        signed_content => signed_content,
+    };
+    match signed_content {
+        Some((payload, signature)) => Ok(Some(verify_signature(&payload, &signature, path_handler)?)),
+        None => Ok(None),
+    }
+}
+
+/// Checks the signature of an object, returning the description of a good signature. Unsigned objects and
+/// signatures that aren't good are errors.
+pub fn check_object_signature(hash: &str, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
+    match verify_object(hash, path_handler)? {
+        Some(check) if check.is_good() => Ok(check.description),
+        Some(check) => Err(Box::new(io::Error::new(io::ErrorKind::Other, check.description))),
+        None => Err(Box::new(io::Error::new(io::ErrorKind::NotFound, "error: no signature found"))),
+    }
+}
+
+/// Splits a text at the separators that aren't inside double quotes, leaving the quotes in place
+fn split_unquoted(text: &str, is_separator: impl Fn(char) -> bool) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let (mut start, mut quoted) = (0, false);
+    for (position, c) in text.char_indices() {
+        if c == '"' {
+            quoted = !quoted;
+        } else if !quoted && is_separator(c) {
+            parts.push(&text[start..position]);
+            start = position + c.len_utf8();
+        }
+    }
+    parts.push(&text[start..]);
+    parts.into_iter().filter(|part| !part.is_empty()).collect()
+}
+
+/// Tells whether the options of an allowed signers line let the key sign in a namespace. Only the
+/// `namespaces="<pattern>,..."` option restricts it, where patterns can use `*` and `?` and be negated with `!`.
+fn allows_namespace(options: &str, namespace: &str) -> bool {
+    let namespaces = split_unquoted(options, |c| c == ',').into_iter().find_map(|option| {
+        let (name, value) = option.split_once('=')?;
+        name.eq_ignore_ascii_case("namespaces").then(|| value.trim_matches('"'))
+    });
+    let Some(namespaces) = namespaces else {
+        return true;
+    };
+    let mut allowed = false;
+    for pattern in namespaces.split(',').map(str::trim) {
+        match pattern.strip_prefix('!') {
+            Some(pattern) if helpers::matches_glob(pattern, namespace) => return false,
+            Some(_) => {}
+            None => allowed |= helpers::matches_glob(pattern, namespace),
+        }
+    }
+    allowed
+}
+
+/// Returns the principals the allowed signers file gives to a public key for git signatures. Each line of the
+/// file has the comma-separated principals, optional options, and the key type and base64 key. Lines whose
+/// `namespaces` option doesn't include git are skipped.
+fn find_principal(public_key_blob: &[u8], path_handler: &PathHandler) -> Result<Option<String>, Box<dyn Error>> {
+    let allowed_signers_path = Config::load(path_handler)?.get_path("gpg.ssh.allowedSignersFile");
+    let allowed_signers = allowed_signers_path.and_then(|path| fs::read_to_string(path).ok()).ok_or(io::Error::new(
+        io::ErrorKind::NotFound,
+        "error: gpg.ssh.allowedSignersFile needs to be configured and exist for ssh signature verification",
+    ))?;
+    for line in allowed_signers.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let words = split_unquoted(line, char::is_whitespace);
+        // the key follows the key type, which follows the options if there are any
+        let key_position = (2..words.len()).find(|position| {
+            helpers::decode_base64(words[*position]).is_ok_and(|key| key == public_key_blob)
+        });
+        let options = match key_position {
+            Some(3) => words[1],
+            Some(_) => "",
+            None => continue,
+        };
+        if allows_namespace(options, SSH_SIGNATURE_NAMESPACE) {
+            return Ok(Some(words[0].trim_matches('"').to_string()));
+        }
+    }
+    Ok(None)
+}
+
+/// Checks an SSH signature in the `ssh-keygen -Y sign` format. Ed25519 signatures are checked directly and other
+/// key types with `ssh-keygen -Y check-novalidate`, then the key is looked up in the allowed signers file.
+fn verify_ssh_signature(payload: &str, signature: &str, path_handler: &PathHandler) -> Result<SignatureCheck, Box<dyn Error>> {
+    let encoded: String = signature
+        .lines()
+        .skip_while(|line| *line != SSH_SIGNATURE_BEGIN)
+        .skip(1)
+        .take_while(|line| *line != SSH_SIGNATURE_END)
+        .collect();
+    let blob = helpers::decode_base64(&encoded)?;
+    let mut data = blob.as_slice();
+    if !data.starts_with(SSHSIG_MAGIC) || data.get(6..10) != Some(&SSHSIG_VERSION.to_be_bytes()[..]) {
+        return Err(Box::new(io::Error::new(io::ErrorKind::InvalidData, "error: invalid SSH signature")));
+    }
+    data = &data[10..];
+    let public_key_blob = read_string(&mut data)?;
+    let namespace = String::from_utf8_lossy(read_string(&mut data)?).to_string();
+    let reserved = read_string(&mut data)?;
+    let hash_algorithm = String::from_utf8_lossy(read_string(&mut data)?).to_string();
+    let signature_blob = read_string(&mut data)?;
+
+    let mut key_data = public_key_blob;
+    let key_type = String::from_utf8_lossy(read_string(&mut key_data)?).to_string();
+    let key_name = key_type.trim_start_matches("ssh-").split('-').next().unwrap_or_default().to_uppercase();
+    let mut hasher = Sha256::new();
+    hasher.input(public_key_blob);
+    let mut fingerprint = [0; 32];
+    hasher.result(&mut fingerprint);
+    let key = format!("{} key SHA256:{}", key_name, helpers::encode_base64(&fingerprint).trim_end_matches('='));
+
+    let valid = namespace == SSH_SIGNATURE_NAMESPACE
+        && matches!(hash_algorithm.as_str(), "sha256" | "sha512")
+        && if key_type == ED25519_KEY_TYPE {
+            let public_key = read_string(&mut key_data)?;
+            let mut signature_data = signature_blob;
+            let _signature_type = read_string(&mut signature_data)?;
+            let signature_bytes = read_string(&mut signature_data)?;
+            match sshsig_signed_data(&namespace, reserved, &hash_algorithm, payload) {
+                Some(signed_data) => {
+                    signature_bytes.len() == 64 && public_key.len() == 32 && ed25519::verify(&signed_data, public_key, signature_bytes)
+                }
+                None => false,
+            }
+        } else {
+            let mut signature_file = tempfile::NamedTempFile::new()?;
+            signature_file.write_all(signature.as_bytes())?;
+            let signature_path = signature_file.path().to_string_lossy().to_string();
+            let program = Config::load(path_handler)?
+                .get("gpg.ssh.program")
+                .unwrap_or_else(|| DEFAULT_SSH_PROGRAM.to_string());
+            run_program(&program, &["-Y", "check-novalidate", "-n", SSH_SIGNATURE_NAMESPACE, "-s", &signature_path], payload)?.0
+        };
+    if !valid {
+        return Ok(SignatureCheck {
+            status: SignatureStatus::Bad,
+            description: format!("Bad \"{}\" signature with {}", namespace, key),
+        });
+    }
+    Ok(match find_principal(public_key_blob, path_handler)? {
+        Some(principal) => SignatureCheck {
+            status: SignatureStatus::Good,
+            description: format!("Good \"{}\" signature for {} with {}", namespace, principal, key),
+        },
+        None => SignatureCheck {
+            status: SignatureStatus::Untrusted,
+            description: format!("Good \"{}\" signature with {}\nNo principal matched.", namespace, key),
+        },
+    })
+}
+
+/// Checks a gpg signature with `gpg --verify`, using its status output to tell the result and its messages as
+/// the description. A signature is only good if gpg succeeds and reports it both good and valid; one made with
+/// an expired or revoked key isn't.
+fn verify_gpg_signature(payload: &str, signature: &str, path_handler: &PathHandler) -> Result<SignatureCheck, Box<dyn Error>> {
+    let mut signature_file = tempfile::NamedTempFile::new()?;
+    signature_file.write_all(signature.as_bytes())?;
+    let signature_path = signature_file.path().to_string_lossy().to_string();
+    let config = Config::load(path_handler)?;
+    let program = config
+        .get("gpg.openpgp.program")
+        .or_else(|| config.get("gpg.program"))
+        .unwrap_or_else(|| DEFAULT_GPG_PROGRAM.to_string());
+    let (success, status_output, messages) = run_program(&program, &["--status-fd=1", "--verify", &signature_path, "-"], payload)?;
+
+    let status_words: Vec<&str> = status_output
+        .lines()
+        .filter_map(|line| line.strip_prefix("[GNUPG:] "))
+        .filter_map(|line| line.split_whitespace().next())
+        .collect();
+    let status = if status_words.contains(&"BADSIG") {
+        SignatureStatus::Bad
+    } else if status_words.contains(&"EXPKEYSIG") || status_words.contains(&"REVKEYSIG") {
+        SignatureStatus::Untrusted
+    } else if success && status_words.contains(&"GOODSIG") && status_words.contains(&"VALIDSIG") {
+        SignatureStatus::Good
+    } else {
+        SignatureStatus::Unverifiable
+    };
+    Ok(SignatureCheck {
+        status,
+        description: messages.trim_end().to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::config::{ConfigFile, ConfigScope};
+    use crate::commands::git_commands::{Command, Init};
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_ssh_signatures() {
+        crate::commands::config::ignore_user_config();
+        let temp_dir = tempdir().unwrap();
+        let path_handler = PathHandler::new(temp_dir.path().to_string_lossy().to_string());
+        Init::new().execute(None, &path_handler).unwrap();
+        let signer = TestKeySigner::new([7; 32]);
+        let allowed_signers_path = path_handler.get_relative_path("allowed_signers");
+        fs::write(&allowed_signers_path, format!("dev@example.com {}\n", signer.public_key_line())).unwrap();
+        let mut config = ConfigFile::open(ConfigScope::Local, &path_handler).unwrap();
+        config.set("gpg.ssh.allowedSignersFile", &allowed_signers_path).unwrap();
+        config.save().unwrap();
+
+        let content = "tree 1234\nauthor a <a> 1 +0000\ncommitter a <a> 1 +0000\n\nmessage\n";
+        let signed_content = add_signature_header(content, &signer.sign(content).unwrap());
+        assert!(signed_content.ends_with("-----END SSH SIGNATURE-----\n\nmessage\n"));
+        let (payload, signature) = split_signature(&signed_content).unwrap();
+        assert_eq!(payload, content);
+        assert!(split_signature(content).is_none());
+
+        let check = verify_signature(&payload, &signature, &path_handler).unwrap();
+        assert!(check.is_good());
+        assert!(check.description.starts_with("Good \"git\" signature for dev@example.com with ED25519 key SHA256:"));
+        let check = verify_signature(&payload.replace("message", "massage"), &signature, &path_handler).unwrap();
+        assert_eq!(check.status, SignatureStatus::Bad);
+
+        let other_signer = TestKeySigner::new([8; 32]);
+        let check = verify_signature(content, &other_signer.sign(content).unwrap(), &path_handler).unwrap();
+        assert_eq!(check.status, SignatureStatus::Untrusted);
+        assert!(check.description.ends_with("No principal matched."));
+        assert!(sshsig_signed_data(SSH_SIGNATURE_NAMESPACE, &[], "sha1", content).is_none());
+
+        // keys restricted to other namespaces are skipped, and every principal of a line is reported
+        let public_key_line = signer.public_key_line();
+        let allowed_signers = format!(
+            "dev@example.com namespaces=\"file,email\" {}\n\"dev@example.com,ops@example.com\" cert-authority,namespaces=\"g?t\" {}\n",
+            public_key_line, public_key_line
+        );
+        fs::write(&allowed_signers_path, allowed_signers).unwrap();
+        let check = verify_signature(&payload, &signature, &path_handler).unwrap();
+        assert!(check.description.starts_with("Good \"git\" signature for dev@example.com,ops@example.com with ED25519 key"));
+        fs::write(&allowed_signers_path, format!("dev@example.com namespaces=\"*,!git\" {}\n", public_key_line)).unwrap();
+        let check = verify_signature(&payload, &signature, &path_handler).unwrap();
+        assert_eq!(check.status, SignatureStatus::Untrusted);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_gpg_signature_status() {
+        use std::os::unix::fs::PermissionsExt;
+
+        crate::commands::config::ignore_user_config();
+        let temp_dir = tempdir().unwrap();
+        let path_handler = PathHandler::new(temp_dir.path().to_string_lossy().to_string());
+        Init::new().execute(None, &path_handler).unwrap();
+        let program_path = path_handler.get_relative_path("fake-gpg");
+        let mut config = ConfigFile::open(ConfigScope::Local, &path_handler).unwrap();
+        config.set("gpg.program", &program_path).unwrap();
+        config.save().unwrap();
+
+        // a stand-in for gpg that prints the given status lines and exits with the given code
+        let check = |status_lines: &[&str], exit_code: i32| {
+            let status: String = status_lines.iter().map(|line| format!("echo '[GNUPG:] {}'\n", line)).collect();
+            fs::write(&program_path, format!("#!/bin/sh\ncat > /dev/null\n{}exit {}\n", status, exit_code)).unwrap();
+            fs::set_permissions(&program_path, fs::Permissions::from_mode(0o755)).unwrap();
+            verify_signature("payload", PGP_SIGNATURE_BEGIN, &path_handler).unwrap().status
+        };
+        assert_eq!(check(&["GOODSIG KEY dev", "VALIDSIG FINGERPRINT"], 0), SignatureStatus::Good);
+        assert_eq!(check(&["GOODSIG KEY dev"], 0), SignatureStatus::Unverifiable);
+        assert_eq!(check(&["GOODSIG KEY dev", "VALIDSIG FINGERPRINT"], 2), SignatureStatus::Unverifiable);
+        assert_eq!(check(&["EXPKEYSIG KEY dev", "VALIDSIG FINGERPRINT"], 0), SignatureStatus::Untrusted);
+        assert_eq!(check(&["REVKEYSIG KEY dev", "VALIDSIG FINGERPRINT"], 0), SignatureStatus::Untrusted);
+        assert_eq!(check(&["BADSIG KEY dev"], 1), SignatureStatus::Bad);
+    }
+}
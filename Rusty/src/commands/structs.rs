@@ -1,10 +1,13 @@
-use std::{collections::HashMap, error::Error, fmt, fs, io, io::Write, path::Path, path::PathBuf, env};
+use std::{collections::BTreeMap, collections::HashMap, error::Error, fmt, fs, io, io::Write, path::Path, path::PathBuf, env};
 
-use crate::constants::{OBJECT, INDEX_FILE, TREE_SUBTREE_MODE, TREE_FILE_MODE, DEFAULT_HEAD_LINE, HEAD_FILE};
+use crate::constants::{OBJECT, INDEX_FILE, TREE_FILE_MODE, TREE_SUBTREE_MODE, EXECUTABLE_FILE_MODE, SYMLINK_MODE, GITLINK_MODE, DEFAULT_HEAD_LINE, HEAD, HEAD_FILE};
 
 use crate::commands::helpers;
+use crate::commands::reflog;
+use crate::commands::refs;
+use crate::commands::signing::{self, Signer};
 use chrono::{DateTime, Local};
-use super::{git_commands::PathHandler, helpers::get_file_length};
+use super::git_commands::PathHandler;
 
 /// Struct to interact with the HEAD file in the .git directory.
 /// Allows access to information about current branch and last commit in current branch.
@@ -13,10 +16,43 @@ pub struct Head;
 impl Head {
     /// Changes the branch the HEAD file points to
     pub fn change_head_branch(branch_name: &str, path_handler: &PathHandler) -> Result<(), Box<dyn Error>> {
-        let mut head_file = fs::File::create(path_handler.get_relative_path(HEAD_FILE))?;
+        let previous_head = Self::describe_head(path_handler);
         let new_line = format!("{}{}", DEFAULT_HEAD_LINE, branch_name);
-        head_file.write_all(new_line.as_bytes())?;
-        Ok(())
+        refs::update_ref(HEAD, &new_line, None, None, path_handler)?;
+        Self::log_checkout(previous_head, branch_name, path_handler)
+    }
+
+    /// Points HEAD directly to a commit instead of a branch
+    pub fn detach_head(commit_hash: &str, path_handler: &PathHandler) -> Result<(), Box<dyn Error>> {
+        let previous_head = Self::describe_head(path_handler);
+        refs::update_ref(HEAD, commit_hash, None, None, path_handler)?;
+        Self::log_checkout(previous_head, commit_hash, path_handler)
+    }
+
+    /// Returns the name of the branch HEAD points to, or the commit if it's detached, along with the commit.
+    /// Returns None if HEAD doesn't point to a commit.
+    fn describe_head(path_handler: &PathHandler) -> Option<(String, String)> {
+        let commit = Self::get_head_commit(path_handler).ok().filter(|commit| !commit.trim().is_empty())?;
+        let name = Self::get_current_branch_name(path_handler).unwrap_or_else(|_| commit.clone());
+        Some((name, commit.trim().to_string()))
+    }
+
+    /// Records in the reflog of HEAD that it moved from its previous value to the given branch or commit
+    fn log_checkout(previous_head: Option<(String, String)>, target: &str, path_handler: &PathHandler) -> Result<(), Box<dyn Error>> {
+        let (previous_name, previous_commit) = match previous_head {
+            Some(previous_head) => previous_head,
+            None => return Ok(()),
+        };
+        let new_commit = Self::get_head_commit(path_handler).unwrap_or_default();
+        let message = format!("checkout: moving from {} to {}", previous_name, target);
+        reflog::append(HEAD, &previous_commit, new_commit.trim(), &message, path_handler)
+    }
+
+    /// Returns true if HEAD points to a commit instead of a branch
+    pub fn is_detached(path_handler: &PathHandler) -> Result<bool, Box<dyn Error>> {
+        let head_file_content =
+            helpers::read_file_content(&path_handler.get_relative_path(HEAD_FILE))?;
+        Ok(!head_file_content.starts_with("ref: "))
     }
 
     /// Returns the ref the HEAD points to
@@ -27,6 +63,9 @@ impl Head {
             .split_whitespace()
             .map(String::from)
             .collect();
+        if split_head_content.len() < 2 {
+            return Err(Box::new(io::Error::new(io::ErrorKind::Other, "fatal: HEAD is detached")));
+        }
 
         let current_ref = split_head_content[1].clone();
 
@@ -36,12 +75,9 @@ impl Head {
     /// Returns the name of the current branch
     pub fn get_current_branch_name(path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
         let current_branch_ref = Self::get_current_branch_ref(path_handler)?;
-        let split_ref_content: Vec<String> =
-            current_branch_ref.split('/').map(String::from).collect();
-
-        let branch_name = split_ref_content[2].clone();
+        let branch_name = current_branch_ref.strip_prefix("refs/heads/").unwrap_or(&current_branch_ref);
 
-        Ok(branch_name)
+        Ok(branch_name.to_string())
     }
 
     /// Returns the path of the current branch
@@ -52,9 +88,13 @@ impl Head {
 
     /// Returns the last commit of the current branch
     pub fn get_head_commit(path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
+        if Self::is_detached(path_handler)? {
+            let head_file_content =
+                helpers::read_file_content(&path_handler.get_relative_path(HEAD_FILE))?;
+            return Ok(head_file_content.trim().to_string());
+        }
         let current_branch_path = Self::get_current_branch_path(path_handler)?;
-        let commit_hash =
-            helpers::read_file_content(&path_handler.get_relative_path(&current_branch_path))?;
+        let commit_hash = helpers::get_branch_last_commit(&current_branch_path, path_handler)?;
         Ok(commit_hash)
     }
 }
@@ -129,139 +169,124 @@ impl HashObjectCreator {
         Ok(hashed_data)
     }
 
+    /// Returns the hash an object with the given type and raw content would have, without writing it
+    pub fn generate_object_hash_from_bytes(obj_type: &ObjectType, content: &[u8]) -> String {
+        let mut data = format!("{} {}\0", obj_type, content.len()).into_bytes();
+        data.extend_from_slice(content);
+        helpers::generate_sha1_string_from_bytes(&data)
+    }
+
     pub fn generate_object_hash(obj_type: ObjectType, file_len: u64, content: &str) -> String {
         let data = format!("{} {}\0{}", obj_type, file_len, content);
         // println!("data when generating object hash: {}", data);
         helpers::generate_sha1_string(data.as_str())
     }
 
+    /// Creates the tree objects for every entry of the index file, except the deleted ones, and returns the hash
+    /// of the root tree. If nothing was staged an empty string is returned, as there's nothing to commit.
     pub fn create_tree_object(path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
         let index_file_content =
             helpers::read_file_content(&path_handler.get_relative_path(INDEX_FILE))?;
-        let mut subdirectories: HashMap<String, Vec<Vec<u8>>> = HashMap::new();
-
-        let index_file_lines: Vec<&str> = index_file_content.split('\n').collect();
+        let mut tree_entries = Vec::new();
         let mut staged_files = false;
 
-        for line in index_file_lines {
+        for line in index_file_content.lines() {
             let split_line: Vec<&str> = line.split(';').collect();
-            if split_line[2] == IndexFileEntryState::Cached.to_string() {
+            if split_line.len() < 3 {
                 continue;
             }
-            staged_files = true;
-            let path = Path::new(split_line[0]);
-            let hash = split_line[1];
-
-            let mut current_dir = path.parent();
-            let mut file_directory = String::new();
-            if let Some(directory) = current_dir {
-                file_directory = directory.to_string_lossy().to_string();
+            if split_line[2] != IndexFileEntryState::Cached.to_string() {
+                staged_files = true;
             }
-
-            let _split_path: Vec<&str> = index_file_content.split('/').collect();
-            let mut file_name = String::new();
-
-            if let Some(name) = path.file_name().and_then(|name| name.to_str()) {
-                file_name = name.to_string();
-            }
-            let hash = helpers::convert_hash_to_decimal_bytes(hash)?;
-            let file_entry = format!("{} {}\0", TREE_FILE_MODE, file_name);
-            let mut final_entry = Vec::new();
-            final_entry.extend_from_slice(file_entry.as_bytes());
-            final_entry.extend_from_slice(&hash);
-
-            if let Some(_parent) = current_dir {
-                subdirectories
-                    .entry(file_directory)
-                    .or_default()
-                    .push(final_entry);
+            if split_line[2] == IndexFileEntryState::Deleted.to_string() {
+                continue;
             }
+            let file_mode = helpers::get_index_line_mode(&split_line, path_handler);
+            tree_entries.push((split_line[0].to_string(), file_mode, split_line[1].to_string()));
+        }
 
-            while let Some(parent) = current_dir {
-                current_dir = parent.parent();
-                let subdirectory_entry;
-                if let Some(directory) = current_dir {
-                    subdirectory_entry = directory.to_string_lossy().to_string();
-                    subdirectories
-                        .entry(subdirectory_entry)
-                        .or_default()
-                        .push(parent.to_string_lossy().as_bytes().to_vec());
-                }
-            }
+        if !staged_files {
+            return Ok(String::new())
         }
-        let mut super_tree_hash = String::new();
-        for (parent_directory, entries) in &subdirectories {
-            let sub_tree_content =
-                Self::process_files_and_subdirectories(&mut subdirectories.clone(), entries, path_handler)?;
-            let tree_hash = Self::write_object_file_bytes(
-                &sub_tree_content,
-                ObjectType::Tree,
-                sub_tree_content.len(),
-                path_handler
-            )?;
-            if parent_directory == "/" || parent_directory.is_empty() {
-                super_tree_hash = tree_hash;
+        Self::write_tree(&tree_entries, path_handler)
+    }
+
+    /// Writes the tree objects for the given (path, mode, hash) entries, one per directory, and returns the hash
+    /// of the root tree. Entries are sorted the way git does it, comparing names as bytes with subtree names
+    /// followed by a '/', so the same entries always give the same tree no matter their order.
+    pub fn write_tree(entries: &[(String, String, String)], path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
+        let mut files: Vec<(String, String, String)> = Vec::new();
+        let mut subdirectories: BTreeMap<String, Vec<(String, String, String)>> = BTreeMap::new();
+
+        for (path, mode, hash) in entries {
+            match path.trim_start_matches('/').split_once('/') {
+                Some((directory, rest)) => subdirectories
+                    .entry(directory.to_string())
+                    .or_default()
+                    .push((rest.to_string(), mode.clone(), hash.clone())),
+                None => files.push((path.trim_start_matches('/').to_string(), mode.clone(), hash.clone())),
             }
         }
-        if !staged_files {
-            return Ok(String::new())
+        for (directory, directory_entries) in subdirectories {
+            let tree_hash = Self::write_tree(&directory_entries, path_handler)?;
+            files.push((directory, TREE_SUBTREE_MODE.to_string(), tree_hash));
         }
-        Ok(super_tree_hash)
+
+        let tree_content = Self::build_tree_content(files)?;
+        Self::write_object_file_bytes(&tree_content, ObjectType::Tree, tree_content.len(), path_handler)
     }
 
-    fn process_files_and_subdirectories(
-        subdirectories: &mut HashMap<String, Vec<Vec<u8>>>,
-        entries: &Vec<Vec<u8>>,
-        path_handler: &PathHandler
-    ) -> Result<Vec<u8>, Box<dyn Error>> {
-        let mut sub_tree_content = Vec::new();
-        for entry in entries {
-            if !entry.starts_with(TREE_FILE_MODE.as_bytes()) {
-                if let Some(value) =
-                    subdirectories.remove(&String::from_utf8_lossy(entry).to_string())
-                {
-                    let mut directory_name = String::new();
-                    if let Some(file_name) = String::from_utf8_lossy(entry)
-                        .to_string()
-                        .rsplit('/')
-                        .next()
-                    {
-                        // println!("File name: {}", file_name);
-                        directory_name = file_name.to_string();
-                    }
-                    let tree_content =
-                        Self::process_files_and_subdirectories(subdirectories, &value, path_handler)?;
-                    let tree_hash = Self::write_object_file_bytes(
-                        &tree_content,
-                        ObjectType::Tree,
-                        tree_content.len(),
-                        path_handler
-                    )?;
-                    let hash_decimal = helpers::convert_hash_to_decimal_bytes(&tree_hash)?;
-                    let tree_entry = format!("{} {}\0", TREE_SUBTREE_MODE, directory_name);
-                    let mut final_entry = Vec::new();
-                    final_entry.extend_from_slice(tree_entry.as_bytes());
-                    final_entry.extend_from_slice(&hash_decimal);
-                    sub_tree_content.extend_from_slice(&final_entry);
-                }
-            } else {
-                sub_tree_content.extend_from_slice(entry);
+    /// Returns the content of a tree object with the given (name, mode, hash) entries in canonical order.
+    /// Fails if two entries have the same name.
+    pub fn build_tree_content(mut entries: Vec<(String, String, String)>) -> Result<Vec<u8>, Box<dyn Error>> {
+        let sort_key = |(name, mode, _hash): &(String, String, String)| {
+            let mut key = name.as_bytes().to_vec();
+            if helpers::is_tree_mode(mode) {
+                key.push(b'/');
             }
+            key
+        };
+        entries.sort_by_key(sort_key);
+
+        let mut tree_content = Vec::new();
+        for (position, (name, mode, hash)) in entries.iter().enumerate() {
+            if position > 0 && entries[position - 1].0 == *name {
+                return Err(Box::new(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Error: duplicated tree entry '{}'", name),
+                )));
+            }
+            tree_content.extend_from_slice(format!("{} {}\0", mode, name).as_bytes());
+            tree_content.extend_from_slice(&helpers::convert_hash_to_decimal_bytes(hash)?);
         }
-        Ok(sub_tree_content)
+        Ok(tree_content)
     }
 
     // aca podria hacer una funcion para crear un commit object con dos padres
     // tal vez podria pasar lo de generar contenido del comando para aca, seria
     // mejor division de tareas ahi
     pub fn create_commit_object(message: Option<&str>, parents: Vec<String>, path_handler: &PathHandler) -> Result<String, Box<dyn Error>> {
+        Self::create_commit_object_with_signer(message, parents, None, path_handler)
+    }
+
+    /// Creates a commit object like `create_commit_object`, signed by the signer if one is given. The signature
+    /// goes in a `gpgsig` header and covers the rest of the commit content.
+    pub fn create_commit_object_with_signer(
+        message: Option<&str>,
+        parents: Vec<String>,
+        signer: Option<&dyn Signer>,
+        path_handler: &PathHandler
+    ) -> Result<String, Box<dyn Error>> {
         let tree_hash = HashObjectCreator::create_tree_object(path_handler)?;
         if tree_hash.is_empty() {
             // This means there was nothing staged, so no commit should be created.
             println!("no changes added to commit (use 'git add')");
             return Ok(String::new())
         }
-        let commit_content = Self::generate_commit_content(tree_hash, message, parents)?;
+        let mut commit_content = Self::generate_commit_content(tree_hash, message, parents)?;
+        if let Some(signer) = signer {
+            commit_content = signing::add_signature_header(&commit_content, &signer.sign(&commit_content)?);
+        }
         let commit_object_hash = HashObjectCreator::write_object_file(
             commit_content.clone(),
             ObjectType::Commit,
@@ -272,28 +297,37 @@ impl HashObjectCreator {
         Ok(commit_object_hash)
     }
 
-    fn generate_commit_content(
-        tree_hash: String,
-        message: Option<&str>,
-        parents: Vec<String>
-    ) -> Result<String, Box<dyn Error>> {
-        let username = env::var("USER")?;
+    /// Returns an identity line such as `tagger name <email> 1700000000 -0300`, with the given role,
+    /// the current user and the current time and timezone.
+    pub fn generate_identity_line(role: &str) -> String {
+        let username = env::var("USER")
+            .or_else(|_| env::var("USERNAME"))
+            .unwrap_or_else(|_| String::from("unknown"));
         let current_time: DateTime<Local> = Local::now();
         let timestamp = current_time.timestamp();
 
-        let offset_minutes = current_time.offset().local_minus_utc();
-        let offset_hours = (offset_minutes / 60) / 60;
+        let offset_seconds = current_time.offset().local_minus_utc();
+        let offset_sign = if offset_seconds < 0 { '-' } else { '+' };
+        let offset_string = format!(
+            "{}{:02}{:02}",
+            offset_sign,
+            offset_seconds.abs() / 3600,
+            (offset_seconds.abs() % 3600) / 60
+        );
 
-        let offset_string = format!("{:03}{:02}", offset_hours, (offset_minutes % 60).abs());
+        format!(
+            "{} {} <{}@fi.uba.ar> {} {}",
+            role, username, username, timestamp, offset_string
+        )
+    }
 
-        let author_line = format!(
-            "author {} <{}@fi.uba.ar> {} {}",
-            username, username, timestamp, offset_string
-        );
-        let commiter_line = format!(
-            "committer {} <{}@fi.uba.ar> {} {}",
-            username, username, timestamp, offset_string
-        );
+    fn generate_commit_content(
+        tree_hash: String,
+        message: Option<&str>,
+        parents: Vec<String>
+    ) -> Result<String, Box<dyn Error>> {
+        let author_line = Self::generate_identity_line("author");
+        let commiter_line = Self::generate_identity_line("committer");
         let mut content = format!("tree {}\n", tree_hash);
         let mut parents_string = String::new();
         if !parents.is_empty() {
@@ -369,20 +403,21 @@ impl StagingArea {
         StagingArea {}
     }
 
-    /// Adds a file to the staging area. Creating a git object and saving the object's path, hash and state in the
-    /// index file, following the format: file_path;hash;state.
+    /// Adds a file to the staging area. Creating a git object and saving the object's path, hash, state and mode
+    /// in the index file, following the format: file_path;hash;state;mode.
     pub fn add_file(&self, path: &str, path_handler: &PathHandler) -> Result<(), Box<dyn Error>> {
-        let relative_path = path_handler.get_relative_path(path);
-        let file_content = helpers::read_file_content(relative_path.as_str())?;
-        let object_hash = HashObjectCreator::write_object_file(
-            file_content,
-            ObjectType::Blob,
-            get_file_length(relative_path.as_str())?,
-            path_handler
-        )?;
+        let file_mode = helpers::get_file_mode(path, path_handler)?;
+        let object_hash = if file_mode == GITLINK_MODE {
+            // gitlinks aren't stored, the index keeps the commit checked out in the nested repository
+            helpers::get_gitlink_commit(path, path_handler)?
+        } else {
+            let file_content = helpers::read_working_file(path, path_handler)?;
+            HashObjectCreator::write_object_file_bytes(&file_content, ObjectType::Blob, file_content.len(), path_handler)?
+        };
         helpers::update_file_with_hash(
             object_hash.as_str(),
             IndexFileEntryState::Staged.to_string().as_str(),
+            &file_mode,
             path,
             path_handler
         )?;
@@ -399,21 +434,13 @@ impl StagingArea {
         let mut lines: Vec<String> = file_contents.lines().map(|s| s.to_string()).collect();
 
         // Search for the hash in the lines.
-        if let Some(index) = lines.iter_mut().position(|line| line.starts_with(path)) {
-            if let Some(state_index) = lines[index].rfind(';') {
-                // Check if there is a state digit after the last ";"
-                if state_index + 1 < lines[index].len() {
-                    // Modify the state to "3".
-                    lines[index].replace_range(
-                        state_index + 1..state_index + 2,
-                        IndexFileEntryState::Deleted.to_string().as_str(),
-                    );
-                }
-            }
+        if let Some(index) = lines.iter().position(|line| line.split(';').next() == Some(path)) {
+            // Modify the state to "3".
+            lines[index] = helpers::set_index_line_state(&lines[index], &IndexFileEntryState::Deleted.to_string());
         } else {
             return Err(Box::new(io::Error::new(
                 io::ErrorKind::Other,
-                "Error: Branch name did not match any file known.",
+                format!("Error: pathspec '{}' did not match any file known.", path),
             )));
         }
 
@@ -426,6 +453,8 @@ impl StagingArea {
         Ok(())
     }
 
+    /// Marks every index entry as cached once it's been committed. Deleted entries are dropped, as the
+    /// commit no longer has them.
     pub fn unstage_index_file(&self, path_handler: &PathHandler) -> Result<(), Box<dyn Error>> {
         let index_file_content =
             helpers::read_file_content(&path_handler.get_relative_path(INDEX_FILE))?;
@@ -433,9 +462,10 @@ impl StagingArea {
         let mut new_index_file_content: Vec<String> = Vec::new();
         
         for line in lines.iter_mut() {
-            line.pop();
-            line.push_str(IndexFileEntryState::Cached.to_string().as_str());
-            new_index_file_content.push(line.to_string());
+            if line.split(';').nth(2) == Some(IndexFileEntryState::Deleted.to_string().as_str()) {
+                continue;
+            }
+            new_index_file_content.push(helpers::set_index_line_state(line, &IndexFileEntryState::Cached.to_string()));
         }
 
         let mut index_file = fs::File::create(path_handler.get_relative_path(INDEX_FILE))?;
@@ -450,9 +480,7 @@ impl StagingArea {
         let mut new_index_file_content: Vec<String> = Vec::new();
         
         for line in lines.iter_mut() {
-            line.pop();
-            line.push_str(IndexFileEntryState::Staged.to_string().as_str());
-            new_index_file_content.push(line.to_string());
+            new_index_file_content.push(helpers::set_index_line_state(line, &IndexFileEntryState::Staged.to_string()));
         }
 
         let mut index_file = fs::File::create(path_handler.get_relative_path(INDEX_FILE))?;
@@ -492,14 +520,17 @@ impl StagingArea {
     }
 
 
+    /// Replaces the index file with the given files as staged and the conflicted ones as conflicted. Their modes
+    /// are taken from the working directory.
     pub fn change_index_file(&self, working_tree: HashMap<String, String>, conflicted_file: Vec<(String, String)>, path_handler: &PathHandler) -> Result<(), Box<dyn Error>> {
         let mut new_index_lines: Vec<String> = Vec::new();
+        let file_mode = |file_name: &str| helpers::get_file_mode(file_name, path_handler).unwrap_or_else(|_| TREE_FILE_MODE.to_string());
         for (file_name, file_hash) in working_tree {
-            let new_line = format!("{};{};{}", file_name, file_hash, IndexFileEntryState::Staged);
+            let new_line = format!("{};{};{};{}", file_name, file_hash, IndexFileEntryState::Staged, file_mode(&file_name));
             new_index_lines.push(new_line);
         }
         for (file_name, file_hash) in conflicted_file {
-            let new_line = format!("{};{};{}", file_name, file_hash, IndexFileEntryState::Conflicted);
+            let new_line = format!("{};{};{};{}", file_name, file_hash, IndexFileEntryState::Conflicted, file_mode(&file_name));
             new_index_lines.push(new_line);
         }
         let new_index_content = new_index_lines.join("\n");
@@ -559,7 +590,7 @@ pub enum PackObjectType {
 pub struct WorkingDirectory;
 
 impl WorkingDirectory {
-    fn remove_file_and_empty_parent_directories(file_path: &Path) -> Result<(), Box<dyn Error>> {
+    pub fn remove_file_and_empty_parent_directories(file_path: &Path) -> Result<(), Box<dyn Error>> {
         let _ = fs::remove_file(file_path);
 
         let mut current_dir = file_path.parent();
@@ -603,6 +634,67 @@ impl WorkingDirectory {
         Ok(())
     }
 
+    /// Writes a non-tree entry into the working directory the way its mode says: files with the blob's content and
+    /// the executable bit set or cleared, symlinks pointing to the path stored in the blob and gitlinks as a
+    /// directory where the nested repository can be cloned.
+    pub fn write_tree_entry(file_path: &str, file_mode: &str, file_hash: &str, path_handler: &PathHandler) -> Result<(), Box<dyn Error>> {
+        if file_mode == GITLINK_MODE {
+            fs::create_dir_all(file_path)?;
+            return Ok(());
+        }
+
+        // an existing symlink has to be removed so the file it points to isn't written instead
+        if let Ok(metadata) = fs::symlink_metadata(file_path) {
+            if metadata.file_type().is_symlink() {
+                fs::remove_file(file_path)?;
+            }
+        }
+        let (_, object_content, _) = helpers::read_object_to_bytes(file_hash.to_string(), path_handler)?;
+        if file_mode == SYMLINK_MODE {
+            return Self::create_symlink(&String::from_utf8_lossy(&object_content), file_path);
+        }
+
+        let mut object_file = fs::File::create(file_path)?;
+        object_file.write_all(&object_content)?;
+        Self::set_executable(file_path, file_mode == EXECUTABLE_FILE_MODE)
+    }
+
+    #[cfg(unix)]
+    fn create_symlink(target: &str, file_path: &str) -> Result<(), Box<dyn Error>> {
+        std::os::unix::fs::symlink(target, file_path)?;
+        Ok(())
+    }
+
+    /// Without symlink support the link is written as a file holding its target, like git does
+    #[cfg(not(unix))]
+    fn create_symlink(target: &str, file_path: &str) -> Result<(), Box<dyn Error>> {
+        fs::write(file_path, target)?;
+        Ok(())
+    }
+
+    /// Sets the executable bits wherever the file is readable, or clears all of them
+    #[cfg(unix)]
+    fn set_executable(file_path: &str, executable: bool) -> Result<(), Box<dyn Error>> {
+        use std::os::unix::fs::PermissionsExt;
+        let mut permissions = fs::metadata(file_path)?.permissions();
+        let mode = permissions.mode();
+        let new_mode = if executable {
+            mode | ((mode & 0o444) >> 2)
+        } else {
+            mode & !0o111
+        };
+        if new_mode != mode {
+            permissions.set_mode(new_mode);
+            fs::set_permissions(file_path, permissions)?;
+        }
+        Ok(())
+    }
+
+    #[cfg(not(unix))]
+    fn set_executable(_file_path: &str, _executable: bool) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+
     fn create_files_for_directory(
         tree: &str,
         current_directory: &str,
@@ -612,23 +704,16 @@ impl WorkingDirectory {
         for (file_mode, file_name, file_hash) in tree_content {
             let relative_file_path = format!("{}{}", current_directory, file_name);
 
-            match file_mode.as_str() {
-                TREE_FILE_MODE => {
-                    let (_, object_content, _) = helpers::read_object_to_string(file_hash, path_handler)?;
-                    // println!("object to write content: {} in path: {}", object_content, relative_file_path);
-                    let mut object_file = fs::File::create(relative_file_path)?;
-                    object_file.write_all(object_content.as_bytes())?;
+            if helpers::is_tree_mode(&file_mode) {
+                if let Err(_error) = fs::metadata(relative_file_path.clone()) {
+                    // println!("creating dir: {}", relative_file_path);
+                    fs::create_dir(relative_file_path.clone())?;
                 }
-                TREE_SUBTREE_MODE => {
-                    if let Err(_error) = fs::metadata(relative_file_path.clone()) {
-                        // println!("creating dir: {}", relative_file_path);
-                        fs::create_dir(relative_file_path.clone())?;
-                    }
-                    let dir_path = format!("{}/", relative_file_path);
-                    return Self::create_files_for_directory(&file_hash, &dir_path, path_handler);
-                }
-                _ => {}
+                let dir_path = format!("{}/", relative_file_path);
+                Self::create_files_for_directory(&file_hash, &dir_path, path_handler)?;
+                continue;
             }
+            Self::write_tree_entry(&relative_file_path, &file_mode, &file_hash, path_handler)?;
         }
 
         Ok(())
@@ -1,92 +1,311 @@
-pub const GIT: &str = ".git";
-pub const OBJECT: &str = ".git/objects";
-pub const PACK: &str = ".git/pack";
-pub const PARENT: &str = "parent";
-
-pub const TREE_FILE_MODE: &str = "100644";
-pub const TREE_SUBTREE_MODE: &str = "040000";
-pub const DELETE_FLAG: &str = "-d";
-pub const RENAME_FLAG: &str = "-m";
-pub const TYPE_FLAG: &str = "-t";
-pub const WRITE_FLAG: &str = "-w";
-pub const SIZE_FLAG: &str = "-s";
-pub const MESSAGE_FLAG: &str = "-m";
-pub const VERIFY_FLAG: &str = "-v";
-pub const LIST_FLAG: &str = "-l";
-pub const CONTINUE_FLAG: &str = "--continue";
-
-// flags for ls-files. also DELETE_FLAG is being used
-pub const CACHED_FLAG: &str = "-c";
-pub const IGNORE_FLAG: &str = "-i";
-pub const STAGE_FLAG: &str = "-s";
-pub const MODIFIED_FLAG: &str = "-m";
-
-// flags for ls-tree
-pub const DIRECT_FLAG: &str = "-d";
-pub const RECURSE_FLAG: &str = "-r";
-pub const LONG_FLAG: &str = "-l";
-
-pub const EXCLUDE_LOG_ENTRY: char = '^';
-pub const HEAD: &str = "HEAD";
-pub const REBASE_HEAD: &str = ".git/REBASE_HEAD";
-pub const ADD_FLAG: &str = "add";
-pub const REMOVE_FLAG: &str = "rm";
-pub const R_HEADS: &str = ".git/refs/heads";
-pub const HEAD_FILE: &str = ".git/HEAD";
-pub const R_TAGS: &str = ".git/refs/tags";
-pub const R_REMOTES: &str = ".git/refs/remotes";
-pub const DEFAULT_BRANCH_NAME: &str = "master";
-pub const INDEX_FILE: &str = ".git/index";
-pub const CONFIG_FILE: &str = ".git/config";
-//pub const RELATIVE_PATH: &str = "RELATIVE_PATH";
-
-pub const SERVER_BASE_PATH: &str = "src/server/";
-pub const DEFAULT_REMOTE_REPOSITORY: &str = "origin";
-pub const RECEIVED_PACK_FILE: &str = ".git/pack/received_pack_file.pack";
-
-// flags for UnpackObjects
-pub const VARINT_ENCODING_BITS: u8 = 7;
-pub const VARINT_CONTINUE_FLAG: u8 = 1 << VARINT_ENCODING_BITS;
-pub const TYPE_BITS: u8 = 3;
-pub const TYPE_BYTE_SIZE_BITS: u8 = VARINT_ENCODING_BITS - TYPE_BITS;
-pub const COPY_INSTRUCTION_FLAG: u8 = 1 << 7;
-pub const COPY_OFFSET_BYTES: u8 = 4;
-pub const COPY_SIZE_BYTES: u8 = 3;
-pub const COPY_ZERO_SIZE: usize = 0x10000;
-
-//CODES FOR COLORS IN TEXT
-pub const COLOR_GREEN_CODE: &str = "\x1b[32m";
-pub const COLOR_YELLOW_CODE: &str = "\x1b[33m";
-pub const COLOR_RED_CODE: &str = "\x1b[31m";
-pub const COLOR_RESET_CODE: &str = "\x1b[0m";
-
-pub const DEFAULT_HEAD_LINE: &str = "ref: refs/heads/";
-
-// consts used for server/client protocol
-pub const ZERO_HASH: &str = "0000000000000000000000000000000000000000";
-pub const LENGTH_BYTES: usize = 4;
-pub const PULL_REQUEST_FILE: &str = "pull_requests.txt";
-pub const REQUEST_DELIMITER_DONE: &str = "done\n";
-pub const REQUEST_LENGTH_CERO: &str = "0000";
-pub const SEPARATOR_PULL_REQUEST_FILE: &str = "\n";
-pub const NAK_RESPONSE: &str = "NAK\n";
-pub const WANT_REQUEST: &str = "want";
-pub const UNPACK_CONFIRMATION: &str = "unpack ok\n";
-pub const ALL_BRANCHES_LOCK: &str = "all_branches_lock";
-pub const HTTP_VERSION: &str = "HTTP/1.1";
-pub const CONTENT_TYPE: &str = "application/json";
-pub const HTTP_RESPONSE_ERROR: &str = "HTTP/1.1 404 Not Found";
-pub const RECEIVE_PACK: &str = "git-receive-pack";
-pub const UPLOAD_PACK: &str = "git-upload-pack";
-pub const PR_MERGE_SUCCESS: &str = "Pull Request was successfully merged";
-
-// consts for marking conflicts in file
-pub const CONFLICT_START: &str = "<<<<<<< HEAD";
-pub const CONFLICT_BRANCH_CHANGE: &str = "=======";
-pub const CONFLICT_END: &str = ">>>>>>>";
-pub const MERGE_HEAD: &str = ".git/MERGE_HEAD";
-
-pub const IP_LOCALHOST: &str = "127.0.0.1";
-pub const API_PORT: &str = "8081";
-pub const OUR_GIT_PORT: &str = "9418";
-
+pub const GIT: &str = ".git";
+pub const OBJECT: &str = ".git/objects";
+pub const PACK: &str = ".git/objects/pack";
+// where older versions stored the packs they sent and received
+pub const LEGACY_PACK: &str = ".git/pack";
+pub const PARENT: &str = "parent";
+
+pub const TREE_FILE_MODE: &str = "100644";
+pub const EXECUTABLE_FILE_MODE: &str = "100755";
+pub const SYMLINK_MODE: &str = "120000";
+pub const TREE_SUBTREE_MODE: &str = "40000";
+pub const GITLINK_MODE: &str = "160000";
+pub const DELETE_FLAG: &str = "-d";
+pub const RENAME_FLAG: &str = "-m";
+pub const TYPE_FLAG: &str = "-t";
+pub const WRITE_FLAG: &str = "-w";
+pub const SIZE_FLAG: &str = "-s";
+pub const MESSAGE_FLAG: &str = "-m";
+pub const VERIFY_FLAG: &str = "-v";
+pub const LIST_FLAG: &str = "-l";
+pub const CONTINUE_FLAG: &str = "--continue";
+
+// flags for branch. also DELETE_FLAG, RENAME_FLAG, FORCE_FLAG, ALL_FLAG and VERBOSE_FLAG are being used
+pub const FORCE_DELETE_FLAG: &str = "-D";
+pub const FORCE_RENAME_FLAG: &str = "-M";
+pub const COPY_FLAG: &str = "-c";
+pub const FORCE_COPY_FLAG: &str = "-C";
+pub const REMOTES_FLAG: &str = "-r";
+pub const CONTAINS_FLAG: &str = "--contains";
+pub const MERGED_FLAG: &str = "--merged";
+pub const NO_MERGED_FLAG: &str = "--no-merged";
+pub const VERY_VERBOSE_FLAG: &str = "-vv";
+pub const SET_UPSTREAM_FLAG: &str = "-u";
+pub const SET_UPSTREAM_TO_FLAG: &str = "--set-upstream-to";
+pub const UNSET_UPSTREAM_FLAG: &str = "--unset-upstream";
+pub const SET_UPSTREAM_LONG_FLAG: &str = "--set-upstream";
+
+// flags for cat-file. also TYPE_FLAG and SIZE_FLAG are being used
+pub const PRETTY_PRINT_FLAG: &str = "-p";
+pub const EXISTS_FLAG: &str = "-e";
+pub const BATCH_FLAG: &str = "--batch";
+pub const BATCH_CHECK_FLAG: &str = "--batch-check";
+
+// flags for hash-object. also TYPE_FLAG and WRITE_FLAG are being used
+pub const STDIN_FLAG: &str = "--stdin";
+pub const STDIN_PATHS_FLAG: &str = "--stdin-paths";
+pub const LITERALLY_FLAG: &str = "--literally";
+
+// flags for tag. also LIST_FLAG, DELETE_FLAG, MESSAGE_FLAG and VERIFY_FLAG are being used
+pub const ANNOTATE_FLAG: &str = "-a";
+pub const FORCE_FLAG: &str = "-f";
+pub const ANNOTATION_LINES_FLAG: &str = "-n";
+
+// flags for add. also DRY_RUN_FLAG, FORCE_FLAG and VERBOSE_FLAG are being used
+pub const ADD_ALL_FLAG: &str = "-A";
+pub const ADD_ALL_LONG_FLAG: &str = "--all";
+pub const UPDATE_FLAG: &str = "-u";
+pub const UPDATE_LONG_FLAG: &str = "--update";
+pub const DRY_RUN_LONG_FLAG: &str = "--dry-run";
+pub const FORCE_LONG_FLAG: &str = "--force";
+pub const VERBOSE_LONG_FLAG: &str = "--verbose";
+pub const PATHSPEC_SEPARATOR: &str = "--";
+
+// flags for rm. also RECURSE_FLAG, FORCE_FLAG, FORCE_LONG_FLAG, DRY_RUN_FLAG and DRY_RUN_LONG_FLAG are being used
+pub const CACHED_LONG_FLAG: &str = "--cached";
+pub const QUIET_FLAG: &str = "-q";
+pub const QUIET_LONG_FLAG: &str = "--quiet";
+
+// flags for mv. also FORCE_FLAG, FORCE_LONG_FLAG, DRY_RUN_FLAG, DRY_RUN_LONG_FLAG, VERBOSE_FLAG and VERBOSE_LONG_FLAG are being used
+pub const SKIP_ERRORS_FLAG: &str = "-k";
+
+// flags for diff and log. also CACHED_LONG_FLAG is being used
+pub const STAT_FLAG: &str = "--stat";
+pub const NAME_STATUS_FLAG: &str = "--name-status";
+pub const FIND_RENAMES_FLAG: &str = "-M";
+pub const FIND_RENAMES_LONG_FLAG: &str = "--find-renames";
+pub const FIND_COPIES_FLAG: &str = "-C";
+pub const FIND_COPIES_LONG_FLAG: &str = "--find-copies";
+pub const NO_RENAMES_FLAG: &str = "--no-renames";
+pub const DEFAULT_RENAME_THRESHOLD: usize = 50;
+pub const DIFF_STAT_GRAPH_WIDTH: usize = 50;
+
+// flags for blame
+pub const PORCELAIN_FLAG: &str = "--porcelain";
+pub const LINE_RANGE_FLAG: &str = "-L";
+pub const BLAME_ABBREVIATED_HASH_LENGTH: usize = 8;
+
+// flags for grep. also CACHED_LONG_FLAG is being used
+pub const PATTERN_FLAG: &str = "-e";
+pub const FIXED_STRINGS_FLAG: &str = "-F";
+pub const FIXED_STRINGS_LONG_FLAG: &str = "--fixed-strings";
+pub const EXTENDED_REGEXP_FLAG: &str = "-E";
+pub const EXTENDED_REGEXP_LONG_FLAG: &str = "--extended-regexp";
+pub const IGNORE_CASE_FLAG: &str = "-i";
+pub const IGNORE_CASE_LONG_FLAG: &str = "--ignore-case";
+pub const WORD_REGEXP_FLAG: &str = "-w";
+pub const WORD_REGEXP_LONG_FLAG: &str = "--word-regexp";
+pub const LINE_NUMBER_FLAG: &str = "-n";
+pub const LINE_NUMBER_LONG_FLAG: &str = "--line-number";
+pub const FILES_WITH_MATCHES_FLAG: &str = "-l";
+pub const FILES_WITH_MATCHES_LONG_FLAG: &str = "--files-with-matches";
+pub const COUNT_FLAG: &str = "-c";
+pub const COUNT_LONG_FLAG: &str = "--count";
+pub const UNTRACKED_FLAG: &str = "--untracked";
+pub const THREADS_FLAG: &str = "--threads";
+pub const GREP_MAX_THREADS: usize = 8;
+
+// flags for check-ignore. also VERBOSE_FLAG and VERBOSE_LONG_FLAG are being used
+pub const NON_MATCHING_FLAG: &str = "-n";
+pub const NON_MATCHING_LONG_FLAG: &str = "--non-matching";
+pub const NO_INDEX_FLAG: &str = "--no-index";
+
+// flags for ls-files. also DELETE_FLAG is being used
+pub const CACHED_FLAG: &str = "-c";
+pub const IGNORE_FLAG: &str = "-i";
+pub const STAGE_FLAG: &str = "-s";
+pub const MODIFIED_FLAG: &str = "-m";
+
+// flags for ls-tree
+pub const DIRECT_FLAG: &str = "-d";
+pub const RECURSE_FLAG: &str = "-r";
+pub const LONG_FLAG: &str = "-l";
+
+pub const EXCLUDE_LOG_ENTRY: char = '^';
+pub const HEAD: &str = "HEAD";
+pub const MIN_ABBREVIATED_HASH_LENGTH: usize = 4;
+pub const ABBREVIATED_HASH_LENGTH: usize = 8;
+pub const SHORT_HASH_LENGTH: usize = 7;
+pub const HASH_LENGTH: usize = 40;
+pub const REBASE_HEAD: &str = ".git/REBASE_HEAD";
+pub const ADD_FLAG: &str = "add";
+pub const REMOVE_FLAG: &str = "rm";
+pub const R_REFS: &str = ".git/refs";
+pub const R_HEADS: &str = ".git/refs/heads";
+pub const HEAD_FILE: &str = ".git/HEAD";
+pub const R_TAGS: &str = ".git/refs/tags";
+pub const R_REMOTES: &str = ".git/refs/remotes";
+pub const DEFAULT_BRANCH_NAME: &str = "master";
+pub const INDEX_FILE: &str = ".git/index";
+pub const CONFIG_FILE: &str = ".git/config";
+pub const GITIGNORE_FILE: &str = ".gitignore";
+pub const INFO_EXCLUDE_FILE: &str = ".git/info/exclude";
+//pub const RELATIVE_PATH: &str = "RELATIVE_PATH";
+
+pub const SERVER_BASE_PATH: &str = "src/server/";
+pub const DEFAULT_REMOTE_REPOSITORY: &str = "origin";
+
+// flags for UnpackObjects
+pub const VARINT_ENCODING_BITS: u8 = 7;
+pub const VARINT_CONTINUE_FLAG: u8 = 1 << VARINT_ENCODING_BITS;
+pub const TYPE_BITS: u8 = 3;
+pub const TYPE_BYTE_SIZE_BITS: u8 = VARINT_ENCODING_BITS - TYPE_BITS;
+pub const COPY_INSTRUCTION_FLAG: u8 = 1 << 7;
+pub const COPY_OFFSET_BYTES: u8 = 4;
+pub const COPY_SIZE_BYTES: u8 = 3;
+pub const COPY_ZERO_SIZE: usize = 0x10000;
+
+// consts for pack files and their indexes
+pub const PACK_SIGNATURE: &[u8] = b"PACK";
+pub const PACK_VERSION: u32 = 2;
+pub const PACK_HEADER_SIZE: usize = 12;
+pub const PACK_INDEX_SIGNATURE: [u8; 4] = [0xff, b't', b'O', b'c'];
+pub const PACK_INDEX_VERSION: u32 = 2;
+pub const PACK_EXTENSION: &str = ".pack";
+pub const PACK_INDEX_EXTENSION: &str = ".idx";
+pub const TEMP_PACK_PREFIX: &str = "tmp_pack_";
+pub const DELTA_WINDOW: usize = 10;
+pub const DELTA_MAX_DEPTH: usize = 50;
+pub const DELTA_BLOCK_SIZE: usize = 16;
+
+// consts for gc, repack and prune
+pub const ALL_FLAG: &str = "-a";
+pub const LOOSEN_UNREACHABLE_FLAG: &str = "-A";
+pub const EXPIRE_FLAG: &str = "--expire";
+pub const DRY_RUN_FLAG: &str = "-n";
+pub const VERBOSE_FLAG: &str = "-v";
+pub const AUTO_FLAG: &str = "--auto";
+pub const PRUNE_FLAG: &str = "--prune";
+pub const NO_PRUNE_FLAG: &str = "--no-prune";
+pub const DEFAULT_PRUNE_EXPIRE: &str = "2.weeks.ago";
+pub const GC_AUTO_LOOSE_OBJECTS: usize = 6700;
+pub const GC_AUTO_PACK_LIMIT: usize = 50;
+pub const LOGS_DIR: &str = ".git/logs";
+pub const DEFAULT_REFLOG_EXPIRE: &str = "90.days.ago";
+pub const PACKED_REFS_FILE: &str = ".git/packed-refs";
+pub const LOCK_FILE_EXTENSION: &str = ".lock";
+pub const MAX_CONFIG_INCLUDE_DEPTH: usize = 10;
+pub const PACKED_REFS_HEADER: &str = "# pack-refs with: peeled fully-peeled sorted ";
+
+//CODES FOR COLORS IN TEXT
+pub const COLOR_GREEN_CODE: &str = "\x1b[32m";
+pub const COLOR_YELLOW_CODE: &str = "\x1b[33m";
+pub const COLOR_RED_CODE: &str = "\x1b[31m";
+pub const COLOR_RESET_CODE: &str = "\x1b[0m";
+
+pub const DEFAULT_HEAD_LINE: &str = "ref: refs/heads/";
+
+// consts used for server/client protocol
+pub const ZERO_HASH: &str = "0000000000000000000000000000000000000000";
+pub const LENGTH_BYTES: usize = 4;
+pub const PULL_REQUEST_FILE: &str = "pull_requests.txt";
+pub const REQUEST_DELIMITER_DONE: &str = "done\n";
+pub const REQUEST_LENGTH_CERO: &str = "0000";
+pub const SEPARATOR_PULL_REQUEST_FILE: &str = "\n";
+pub const NAK_RESPONSE: &str = "NAK\n";
+pub const WANT_REQUEST: &str = "want";
+pub const UNPACK_CONFIRMATION: &str = "unpack ok\n";
+pub const REF_STATUS_OK: &str = "ok";
+pub const REF_STATUS_REJECTED: &str = "ng";
+pub const ALL_BRANCHES_LOCK: &str = "all_branches_lock";
+pub const REPOSITORY_LOCK_TIMEOUT_SECONDS: u64 = 60;
+pub const HTTP_VERSION: &str = "HTTP/1.1";
+pub const CONTENT_TYPE: &str = "application/json";
+pub const HTTP_RESPONSE_ERROR: &str = "HTTP/1.1 404 Not Found";
+pub const RECEIVE_PACK: &str = "git-receive-pack";
+pub const UPLOAD_PACK: &str = "git-upload-pack";
+pub const PR_MERGE_SUCCESS: &str = "Pull Request was successfully merged";
+
+// consts for marking conflicts in file
+pub const CONFLICT_START: &str = "<<<<<<< HEAD";
+pub const CONFLICT_BRANCH_CHANGE: &str = "=======";
+pub const CONFLICT_END: &str = ">>>>>>>";
+pub const MERGE_HEAD: &str = ".git/MERGE_HEAD";
+
+// subcommands and flags for reflog. also EXPIRE_FLAG, DRY_RUN_FLAG and DRY_RUN_LONG_FLAG are being used
+pub const REFLOG_SHOW: &str = "show";
+pub const REFLOG_EXPIRE: &str = "expire";
+pub const REFLOG_DELETE: &str = "delete";
+pub const ALL_LONG_FLAG: &str = "--all";
+
+// flags for config. also LIST_FLAG is being used
+pub const GLOBAL_FLAG: &str = "--global";
+pub const SYSTEM_FLAG: &str = "--system";
+pub const LOCAL_FLAG: &str = "--local";
+pub const GET_FLAG: &str = "--get";
+pub const GET_ALL_FLAG: &str = "--get-all";
+pub const ADD_LONG_FLAG: &str = "--add";
+pub const UNSET_FLAG: &str = "--unset";
+pub const UNSET_ALL_FLAG: &str = "--unset-all";
+pub const LIST_LONG_FLAG: &str = "--list";
+pub const BOOL_FLAG: &str = "--bool";
+pub const INT_FLAG: &str = "--int";
+
+// subcommands and flags for remote. also ADD_FLAG, REMOVE_FLAG, VERBOSE_FLAG, DRY_RUN_FLAG and DRY_RUN_LONG_FLAG are being used
+pub const REMOTE_REMOVE: &str = "remove";
+pub const REMOTE_RENAME: &str = "rename";
+pub const REMOTE_SET_URL: &str = "set-url";
+pub const REMOTE_SHOW: &str = "show";
+pub const REMOTE_PRUNE: &str = "prune";
+pub const PUSH_LONG_FLAG: &str = "--push";
+pub const NO_QUERY_FLAG: &str = "-n";
+
+// flags for show-ref
+pub const HEADS_FLAG: &str = "--heads";
+pub const TAGS_FLAG: &str = "--tags";
+pub const VERIFY_LONG_FLAG: &str = "--verify";
+pub const HASH_FLAG: &str = "--hash";
+
+// flags for for-each-ref. also COUNT_LONG_FLAG is being used
+pub const FORMAT_FLAG: &str = "--format";
+pub const SORT_FLAG: &str = "--sort";
+pub const DEFAULT_FOR_EACH_REF_FORMAT: &str = "%(objectname) %(objecttype)\t%(refname)";
+
+// flags for describe. also TAGS_FLAG is being used
+pub const DESCRIBE_LONG_FLAG: &str = "--long";
+pub const ALWAYS_FLAG: &str = "--always";
+pub const DIRTY_FLAG: &str = "--dirty";
+pub const ABBREV_FLAG: &str = "--abbrev";
+pub const DEFAULT_DIRTY_MARK: &str = "-dirty";
+// most tags describe keeps track of while walking back from a commit, as in git
+pub const DESCRIBE_MAX_CANDIDATES: usize = 10;
+
+// flags and object fields for signing. also VERIFY_FLAG is being used
+pub const SIGN_COMMIT_FLAG: &str = "-S";
+pub const GPG_SIGN_LONG_FLAG: &str = "--gpg-sign";
+pub const NO_GPG_SIGN_LONG_FLAG: &str = "--no-gpg-sign";
+pub const SIGN_TAG_FLAG: &str = "-s";
+pub const NO_SIGN_FLAG: &str = "--no-sign";
+pub const LOCAL_USER_FLAG: &str = "-u";
+pub const SHOW_SIGNATURE_FLAG: &str = "--show-signature";
+pub const SIGNATURE_HEADER: &str = "gpgsig";
+pub const SSH_SIGNATURE_NAMESPACE: &str = "git";
+pub const SSH_SIGNATURE_BEGIN: &str = "-----BEGIN SSH SIGNATURE-----";
+pub const SSH_SIGNATURE_END: &str = "-----END SSH SIGNATURE-----";
+pub const PGP_SIGNATURE_BEGIN: &str = "-----BEGIN PGP SIGNATURE-----";
+pub const DEFAULT_GPG_PROGRAM: &str = "gpg";
+pub const DEFAULT_SSH_PROGRAM: &str = "ssh-keygen";
+pub const COMMIT_SIGN_CONFIG: &str = "commit.gpgSign";
+pub const TAG_SIGN_CONFIG: &str = "tag.gpgSign";
+pub const BASE64_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+// files and subcommands for bisect
+pub const BISECT_START_FILE: &str = ".git/BISECT_START";
+pub const BISECT_LOG_FILE: &str = ".git/BISECT_LOG";
+pub const BISECT_BAD_FILE: &str = ".git/BISECT_BAD";
+pub const BISECT_GOOD_FILE: &str = ".git/BISECT_GOOD";
+pub const BISECT_SKIP_FILE: &str = ".git/BISECT_SKIP";
+pub const BISECT_START: &str = "start";
+pub const BISECT_GOOD: &str = "good";
+pub const BISECT_BAD: &str = "bad";
+pub const BISECT_SKIP: &str = "skip";
+pub const BISECT_RESET: &str = "reset";
+pub const BISECT_LOG: &str = "log";
+pub const BISECT_REPLAY: &str = "replay";
+pub const BISECT_RUN: &str = "run";
+pub const BISECT_SKIP_EXIT_CODE: i32 = 125;
+
+pub const IP_LOCALHOST: &str = "127.0.0.1";
+pub const API_PORT: &str = "8081";
+pub const OUR_GIT_PORT: &str = "9418";
+
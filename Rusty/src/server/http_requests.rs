@@ -1,7 +1,7 @@
 use crate::server::server_protocol::ServerProtocol;
-use crate::commands::git_commands::{Command, Log, Merge, PathHandler};
+use crate::commands::git_commands::{Blame, Command, Log, Merge, PathHandler};
 use crate::commands::helpers;
-use crate::constants::{ALL_BRANCHES_LOCK, API_PORT, CONTENT_TYPE, DEFAULT_BRANCH_NAME, HTTP_VERSION, IP_LOCALHOST, PR_MERGE_SUCCESS, PULL_REQUEST_FILE, SEPARATOR_PULL_REQUEST_FILE, SERVER_BASE_PATH};
+use crate::constants::{ALL_BRANCHES_LOCK, API_PORT, CONTENT_TYPE, DEFAULT_BRANCH_NAME, HEAD, HTTP_VERSION, IP_LOCALHOST, PR_MERGE_SUCCESS, PULL_REQUEST_FILE, SEPARATOR_PULL_REQUEST_FILE, SERVER_BASE_PATH};
 use std::fmt;
 use chrono::{Utc, DateTime};
 use std::{error::Error, fs::File, io::Read, io::Write, net::TcpStream, borrow::Cow, fs::OpenOptions};
@@ -75,6 +75,16 @@ pub struct CommitResponse {
     tree: String,
 }
 
+#[derive(Debug, Serialize,Deserialize)]
+pub struct BlameLineResponse {
+    sha: String,
+    path: String,
+    original_line: usize,
+    line: usize,
+    content: String,
+    author: UserResponse,
+}
+
 #[derive(Debug, Serialize,Deserialize)]
 pub struct ResponseType {
     url: String,
@@ -175,6 +185,19 @@ impl CommitResponse {
     }
 }
 
+impl BlameLineResponse {
+    pub fn new(sha: String, path: String, original_line: usize, line: usize, content: String, author: UserResponse) -> Self {
+        BlameLineResponse {
+            sha,
+            path,
+            original_line,
+            line,
+            content,
+            author,
+        }
+    }
+}
+
 impl UserResponse {
     pub fn new(name: String, email: String, date: String) -> Self {
         UserResponse {
@@ -608,10 +631,67 @@ impl HttpRequestHandler {
     Ok(log_responses)
     }
 
+    /// Decodes a percent-encoded query parameter value, where `+` stands for a space.
+    /// Returns None if an escape isn't followed by two hex digits or the result isn't valid UTF-8.
+    fn decode_query_value(value: &str) -> Option<String> {
+        let bytes = value.as_bytes();
+        let mut decoded = Vec::with_capacity(bytes.len());
+        let mut index = 0;
+        while index < bytes.len() {
+            match bytes[index] {
+                b'%' => {
+                    let hex_digits = std::str::from_utf8(bytes.get(index + 1..index + 3)?).ok()?;
+                    decoded.push(u8::from_str_radix(hex_digits, 16).ok()?);
+                    index += 3;
+                }
+                b'+' => {
+                    decoded.push(b' ');
+                    index += 1;
+                }
+                byte => {
+                    decoded.push(byte);
+                    index += 1;
+                }
+            }
+        }
+        String::from_utf8(decoded).ok()
+    }
+
+    /// Returns the commit that introduced each line of a file, read at the revision given by the `ref`
+    /// query parameter or at HEAD. The revision is percent-decoded, so branch names with '/' or '%' can be given.
+    pub fn get_blame(file_path: &str, query: Option<&str>, path_handler: &PathHandler) -> Result<SuccessResponse, ResponseStatusCode> {
+        let revision = match query.unwrap_or("").split('&').find_map(|param| param.strip_prefix("ref=")) {
+            Some(value) => HttpRequestHandler::decode_query_value(value).ok_or(ResponseStatusCode::BadRequest)?,
+            None => HEAD.to_string(),
+        };
+        let blamed_lines = match Blame::blame_file(file_path, &revision, path_handler) {
+            Ok(blamed_lines) => blamed_lines,
+            Err(_) => return Err(ResponseStatusCode::NotFound),
+        };
+
+        let blame_response: Vec<BlameLineResponse> = blamed_lines
+            .into_iter()
+            .map(|line| {
+                let author = UserResponse::new(line.author, line.author_mail, line.author_time.to_string());
+                BlameLineResponse::new(line.commit, line.original_path, line.original_line_number, line.final_line_number, line.content, author)
+            })
+            .collect();
+        SuccessResponse::new(&blame_response, SuccessResponseStatusCode::Success)
+    }
+
     pub fn handle_get_request(_request: Cow<str>, pull_request_path: &str, request_url: &str, path_handler: &PathHandler, repo_name: String) -> Result<SuccessResponse, ResponseStatusCode> {
+        let (request_url, query) = match request_url.split_once('?') {
+            Some((url, query)) => (url, Some(query)),
+            None => (request_url, None),
+        };
         // Split the string by "/"
         let params: Vec<&str> = request_url.split('/').collect();
 
+        // GET /repos/{repo}/blame/{path}
+        if params.len() > 4 && params[3] == "blame" {
+            return HttpRequestHandler::get_blame(&params[4..].join("/"), query, path_handler);
+        }
+
         match params.len() {
             4..=6 => {
                 let pull_request = if params.len() > 4 { Some(params[4]) } else { None };
@@ -1,5 +1,5 @@
-use std::{sync::{Mutex, Arc}, io, error::Error, collections::HashSet, };
-use crate::constants::ALL_BRANCHES_LOCK;
+use std::{sync::{Mutex, Arc}, io, error::Error, collections::HashSet, time::{Duration, Instant}};
+use crate::constants::{ALL_BRANCHES_LOCK, REPOSITORY_LOCK_TIMEOUT_SECONDS};
 /// Manages the locking and unlocking of branches within a server protocol.
 pub struct LockedBranches<'a> {
     locked_branches: &'a Arc<(Mutex<HashSet<String>>, std::sync::Condvar)>,
@@ -58,6 +58,51 @@ impl<'a> LockedBranches<'a> {
         Ok(())
     }
 
+    /// Locks all branches and then waits until no single branch is locked either, so nothing else reads or
+    /// writes the repository until the locks are released. If the branches aren't released within
+    /// `REPOSITORY_LOCK_TIMEOUT_SECONDS`, the lock of all branches is dropped again.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or an error if the repository could not be locked.
+    pub fn lock_repository(&mut self) -> Result<(), Box<dyn Error>> {
+        self.lock_branch(ALL_BRANCHES_LOCK, false)?;
+
+        let result = self.wait_for_locked_branches();
+        if result.is_err() {
+            self.unlock_branch(ALL_BRANCHES_LOCK)?;
+        }
+        result
+    }
+
+    /// Waits until no branch other than `ALL_BRANCHES_LOCK` is locked, or fails once the timeout is reached.
+    fn wait_for_locked_branches(&self) -> Result<(), Box<dyn Error>> {
+        let (lock, cvar) = &**self.locked_branches;
+        let mut locked_branches = lock
+            .lock()
+            .map_err(|err| io::Error::new(io::ErrorKind::Other, err.to_string()))?;
+
+        // Branches locked before all branches were stay locked until their pack is sent or stored
+        let deadline = Instant::now() + Duration::from_secs(REPOSITORY_LOCK_TIMEOUT_SECONDS);
+        if locked_branches.iter().any(|branch| branch != ALL_BRANCHES_LOCK) {
+            eprintln!("Waiting for locked branches to be released before locking the repository");
+        }
+        while locked_branches.iter().any(|branch| branch != ALL_BRANCHES_LOCK) {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Err(Box::new(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    "Error: Timed out waiting for locked branches to be released",
+                )));
+            }
+            locked_branches = cvar
+                .wait_timeout(locked_branches, remaining)
+                .map_err(|err| io::Error::new(io::ErrorKind::Other, err.to_string()))?
+                .0;
+        }
+        Ok(())
+    }
+
     fn _lock_branch(&mut self, branch: &str, all_branches_locked_by_me: bool) -> Result<(), Box<dyn Error>> {// Extract the Mutex and Condvar from the Arc
         // Extract the Mutex and Condvar from the Arc
         let (lock, cvar) = &**self.locked_branches;
@@ -1,10 +1,11 @@
-use crate::commands::git_commands::{Command, PackObjects, PathHandler, UnpackObjects};
+use crate::commands::git_commands::{Command, Gc, PackObjects, PathHandler};
 use crate::commands::helpers;
+use crate::commands::packfile;
 use crate::commands::protocol_utils;
 use crate::server::locked_branches_manager::*;
-use std::{collections::HashSet, sync::{Mutex, Arc, Condvar}};
-use crate::constants::{REQUEST_LENGTH_CERO, REQUEST_DELIMITER_DONE, WANT_REQUEST, NAK_RESPONSE, UNPACK_CONFIRMATION, ALL_BRANCHES_LOCK, RECEIVE_PACK, UPLOAD_PACK};
-use std::{error::Error, fs::File, io, io::Read, io::Write, net::TcpListener, net::TcpStream};
+use std::{collections::HashSet, sync::{Mutex, Arc, Condvar}, thread};
+use crate::constants::{REQUEST_LENGTH_CERO, REQUEST_DELIMITER_DONE, WANT_REQUEST, NAK_RESPONSE, UNPACK_CONFIRMATION, REF_STATUS_OK, REF_STATUS_REJECTED, ALL_BRANCHES_LOCK, RECEIVE_PACK, UPLOAD_PACK, AUTO_FLAG};
+use std::{error::Error, io, io::Read, io::Write, net::TcpListener, net::TcpStream};
 
 pub struct ServerProtocol;
 
@@ -145,14 +146,9 @@ impl ServerProtocol {
         }
 
         let commits_str: Vec<&str> = commits.iter().map(|s| s.as_str()).collect();
-        let checksum = PackObjects::new().execute(Some(commits_str.clone()), path_handler)?;
-        let pack_file_path = format!(".git/pack/pack-{}.pack", checksum);
-        let mut pack_file = File::open(path_handler.get_relative_path(&pack_file_path))?;
-        let mut buffer = Vec::new();
-
-        pack_file.read_to_end(&mut buffer)?;
+        let pack = PackObjects::new().create_pack(commits_str, path_handler)?;
 
-        stream.write_all(&buffer)?;
+        stream.write_all(&pack)?;
 
         drop(locked_branches_lifetime);
 
@@ -211,6 +207,7 @@ impl ServerProtocol {
         let requests_received: Vec<String> =
             protocol_utils::read_until(&mut reader, REQUEST_LENGTH_CERO, true)?;
         let mut refs_to_update: Vec<(String, String, String)> = Vec::new();
+        let mut rejected_refs: Vec<(String, String)> = Vec::new();
         // let mut branches_used: HashSet<String> = HashSet::new();
         for request_received in requests_received {
             if let [prev_remote_hash, new_remote_hash, branch_name] = request_received
@@ -221,12 +218,15 @@ impl ServerProtocol {
 
                 locked_branches_lifetime.lock_branch(branch_name, true)?;
 
-                helpers::validate_ref_update_request(
+                if let Err(err) = helpers::validate_ref_update_request(
                     prev_remote_hash,
                     new_remote_hash,
                     branch_name,
                     path_handler
-                )?;
+                ) {
+                    rejected_refs.push((branch_name.to_string(), err.to_string()));
+                    continue;
+                }
                 refs_to_update.push((
                     prev_remote_hash.to_string(),
                     new_remote_hash.to_string(),
@@ -241,29 +241,61 @@ impl ServerProtocol {
 
         let mut buffer = Vec::new();
         stream.read_to_end(&mut buffer)?;
-        let mut file = File::create(path_handler.get_relative_path(
-            ".git/pack/received_pack_file.pack",
-        ))?;
-
-        file.write_all(&buffer)?;
-
-        if UnpackObjects::new()
-            .execute(Some(vec![&path_handler.get_relative_path(
-                ".git/pack/received_pack_file.pack",
-            )]), path_handler)
-            .is_ok()
-        {
-            println!("packfile unpacked");
-            let unpack_confirmation = protocol_utils::format_line_to_send(
-                UNPACK_CONFIRMATION.to_string(),
-            );
-            println!("unpack confirmation: {}", unpack_confirmation);
-            stream.write_all(unpack_confirmation.as_bytes())?;
+        if let Err(err) = packfile::store_received_pack(&buffer, path_handler) {
+            let unpack_error = protocol_utils::format_line_to_send(format!("unpack {}\n", err));
+            let _ = stream.write_all(unpack_error.as_bytes());
+            let _ = stream.write_all(REQUEST_LENGTH_CERO.as_bytes());
+            return Err(Box::new(io::Error::new(
+                io::ErrorKind::Other,
+                format!("Error: Invalid pack file received: {}", err),
+            )));
+        }
+        println!("packfile stored");
+        let unpack_confirmation = protocol_utils::format_line_to_send(
+            UNPACK_CONFIRMATION.to_string(),
+        );
+        println!("unpack confirmation: {}", unpack_confirmation);
+        stream.write_all(unpack_confirmation.as_bytes())?;
+
+        // the refs are updated together, so they are all reported ok or all rejected with the same reason
+        let updated_ref_names: Vec<String> = refs_to_update.iter().map(|(_, _, branch_name)| branch_name.clone()).collect();
+        match helpers::update_hash_for_refs(refs_to_update, path_handler) {
+            Ok(()) => {
+                for branch_name in updated_ref_names {
+                    let status = protocol_utils::format_line_to_send(format!("{} {}\n", REF_STATUS_OK, branch_name));
+                    stream.write_all(status.as_bytes())?;
+                }
+            }
+            Err(err) => rejected_refs.extend(updated_ref_names.into_iter().map(|branch_name| (branch_name, err.to_string()))),
         }
-        helpers::update_hash_for_refs(refs_to_update, path_handler)?;
+        for (branch_name, reason) in rejected_refs {
+            let status = protocol_utils::format_line_to_send(format!("{} {} {}\n", REF_STATUS_REJECTED, branch_name, reason));
+            stream.write_all(status.as_bytes())?;
+        }
+        stream.write_all(REQUEST_LENGTH_CERO.as_bytes())?;
 
         drop(locked_branches_lifetime);
 
+        ServerProtocol::run_gc_in_background(path_handler, locked_branches);
+
         Ok(())
     }
+
+    /// Runs `gc --auto` on the repository in a separate thread, so pushes don't have to wait for it.
+    /// It waits until no fetch or push holds a branch and locks the whole repository while it runs, since
+    /// objects are moved between packs and loose files, and pruned.
+    pub fn run_gc_in_background(path_handler: &PathHandler, locked_branches: &Arc<(Mutex<HashSet<String>>, Condvar)>) -> thread::JoinHandle<()> {
+        let path_handler = path_handler.clone();
+        let locked_branches = Arc::clone(locked_branches);
+        thread::spawn(move || {
+            let mut locked_branches_lifetime = LockedBranches::new(&locked_branches);
+            if let Err(err) = locked_branches_lifetime.lock_repository() {
+                eprintln!("Error locking branches for gc: {}", err);
+                return;
+            }
+            if let Err(err) = Gc::new().execute(Some(vec![AUTO_FLAG]), &path_handler) {
+                eprintln!("Error running gc: {}", err);
+            }
+        })
+    }
 }
\ No newline at end of file
@@ -13,6 +13,7 @@ const CHECKOUT_COMMAND_NAME: &str = "Checkout";
 const LOG_COMMAND_NAME: &str = "Log";
 const BRANCH_COMMAND_NAME: &str = "Branch";
 const STATUS_COMMAND_NAME: &str = "Status";
+const BLAME_COMMAND_NAME: &str = "Blame";
 
 const HEIGHT_BUTTON: i32 = 68;
 const WIDTH_BUTTON: i32 = 108;
@@ -29,7 +30,7 @@ fn main() {
                 .application(app)
                 .title("RUSTY")
                 .default_width(1111)
-                .default_height(HEIGHT_BUTTON * 9)
+                .default_height(HEIGHT_BUTTON * 10)
                 .build();
 
         // Load CSS file for styling
@@ -229,6 +230,24 @@ fn main() {
             }
         });
 
+        let blame_button = Button::with_label(BLAME_COMMAND_NAME);
+        blame_button.set_size_request(WIDTH_BUTTON, HEIGHT_BUTTON);
+
+        blame_button.connect_clicked({
+            let button_right_ref = Rc::clone(&button_right_ref);
+            let actual_command_ref = Rc::clone(&actual_command_ref);
+            let text_input_ref = Rc::clone(&text_input_ref);
+            let output_label_ref = Rc::clone(&output_label_ref);
+            move |_| {
+                text_input_ref.borrow_mut().set_text("");
+                text_input_ref.borrow_mut().show();
+                output_label_ref.borrow_mut().set_text("");
+                let mut actual_command_mut = actual_command_ref.borrow_mut();
+                *actual_command_mut = BLAME_COMMAND_NAME;
+                button_right_ref.borrow_mut().set_label(BLAME_COMMAND_NAME);
+            }
+        });
+
         button_right.connect_clicked({
             let actual_command_ref = Rc::clone(&actual_command_ref);
             let output_label_ref = Rc::clone(&output_label_ref);
@@ -259,6 +278,10 @@ fn main() {
                     }
                     BRANCH_COMMAND_NAME => Branch::new().execute(None, &path_handler),
                     STATUS_COMMAND_NAME => Status::new().execute(None, &path_handler),
+                    BLAME_COMMAND_NAME => {
+                        let blame_vec: Vec<&str> = text.split_whitespace().collect();
+                        Blame::new().execute(Some(blame_vec), &path_handler)
+                    }
                     PULL_COMMAND_NAME => Pull::new().execute(None, &path_handler),
                     PUSH_COMMAND_NAME => Push::new().execute(None, &path_handler),
                     _ => return,
@@ -311,6 +334,7 @@ fn main() {
         vbox2.pack_start(&log_button, false, false, 0);
         vbox2.pack_start(&branch_button, false, false, 0);
         vbox2.pack_start(&status_button, false, false, 0);
+        vbox2.pack_start(&blame_button, false, false, 0);
 
         paned.pack1(&vbox2, false, false);
         paned.pack2(&vbox, false, false);